@@ -7,4 +7,12 @@ return {
   frontend = nil,
   copy_on_project_save = true,
   capabilities = { "audio", "stereo", "parameter-automation" },
+  parameters = {
+    { id = 0, name = "Gain", min = -60.0, max = 12.0, default = 0.0, type = "Float", unit = "dB" },
+  },
+  presets = {
+    { name = "Unity", tags = { "utility" }, params = { { id = 0, value = 0.0 } } },
+    { name = "Quiet", tags = { "utility", "soft" }, description = "-12 dB trim", params = { { id = 0, value = -12.0 } } },
+    { name = "Boost", tags = { "utility", "loud" }, description = "+6 dB make-up gain", params = { { id = 0, value = 6.0 } } },
+  },
 }
@@ -10,4 +10,7 @@ return {
     },
     -- copy plugin into project when saving a project that uses it
     copy_on_project_save = true,
+    parameters = {
+        { id = 0, name = "Frequency", min = 20.0, max = 2000.0, default = 440.0, type = "Float", unit = "Hz", taper = "log" },
+    },
 }
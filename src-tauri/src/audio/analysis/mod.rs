@@ -0,0 +1,3 @@
+pub mod scope;
+pub mod spectrum;
+pub mod tap;
@@ -0,0 +1,17 @@
+// 示波器：把一段样本降采样为固定数量的点，每个点保留所在区间的最小值与最大值，
+// 这样降采样后仍能看到瞬态与削波。
+
+/// 把 `samples` 均分为 `points` 段，返回每段的 [最小值, 最大值]；样本少于点数时每个样本一点
+pub fn waveform_snapshot(samples: &[f32], points: usize) -> Vec<[f32; 2]> {
+        if samples.is_empty() || points == 0 {
+                return Vec::new();
+        }
+        let points = points.min(samples.len());
+        (0..points)
+                .map(|i| {
+                        let segment = &samples[i * samples.len() / points..(i + 1) * samples.len() / points];
+                        segment.iter()
+                                .fold([f32::MAX, f32::MIN], |[lo, hi], &s| [lo.min(s), hi.max(s)])
+                })
+                .collect()
+}
@@ -0,0 +1,170 @@
+use std::f64::consts::PI;
+
+// 频谱分析：对抽头最近的样本加 Hann 窗做 FFT，功率谱按指数平均平滑，
+// 再按对数频率合并为固定数量的频带供显示（低频的 FFT 分辨率不足时在相邻频点之间插值）。
+
+/// 支持的 FFT 尺寸范围（2 的幂）
+pub const MIN_FFT_SIZE: usize = 256;
+pub const MAX_FFT_SIZE: usize = 16384;
+
+// 显示的最低电平（dBFS）
+const FLOOR_DB: f32 = -150.0;
+
+/// 基 2 复数 FFT（原地计算），尺寸为 2 的幂
+pub struct Fft {
+        size: usize,
+        // exp(-2πik / size)，k < size / 2
+        twiddles: Vec<(f32, f32)>,
+        bit_reverse: Vec<usize>,
+}
+
+impl Fft {
+        pub fn new(size: usize) -> Self {
+                let size = size.max(2).next_power_of_two();
+                let bits = size.trailing_zeros();
+                Self {
+                        size,
+                        twiddles: (0..size / 2)
+                                .map(|k| {
+                                        let angle = -2.0 * PI * k as f64 / size as f64;
+                                        (angle.cos() as f32, angle.sin() as f32)
+                                })
+                                .collect(),
+                        bit_reverse: (0..size).map(|i| i.reverse_bits() >> (usize::BITS - bits)).collect(),
+                }
+        }
+
+        pub fn size(&self) -> usize {
+                self.size
+        }
+
+        /// 对实部 `re` 与虚部 `im`（长度均为 `size`）做正向变换
+        pub fn process(&self, re: &mut [f32], im: &mut [f32]) {
+                let n = self.size;
+                for i in 0..n {
+                        let j = self.bit_reverse[i];
+                        if j > i {
+                                re.swap(i, j);
+                                im.swap(i, j);
+                        }
+                }
+                let mut len = 2;
+                while len <= n {
+                        let half = len / 2;
+                        let step = n / len;
+                        for start in (0..n).step_by(len) {
+                                for k in 0..half {
+                                        let (wr, wi) = self.twiddles[k * step];
+                                        let (a, b) = (start + k, start + k + half);
+                                        let tr = re[b] * wr - im[b] * wi;
+                                        let ti = re[b] * wi + im[b] * wr;
+                                        re[b] = re[a] - tr;
+                                        im[b] = im[a] - ti;
+                                        re[a] += tr;
+                                        im[a] += ti;
+                                }
+                        }
+                        len *= 2;
+                }
+        }
+}
+
+pub struct SpectrumAnalyzer {
+        fft: Fft,
+        window: Vec<f32>,
+        // 窗函数之和，用于把幅度归一化为满幅正弦 = 0 dBFS
+        window_sum: f32,
+        re: Vec<f32>,
+        im: Vec<f32>,
+        // 平均后的功率谱（size / 2 + 1 个频点）
+        power: Vec<f32>,
+        // 指数平均系数：0 不平均，越接近 1 越平滑
+        averaging: f32,
+        primed: bool,
+}
+
+impl SpectrumAnalyzer {
+        /// `size` 限制在 MIN_FFT_SIZE..=MAX_FFT_SIZE 并取 2 的幂
+        pub fn new(size: usize, averaging: f32) -> Self {
+                let fft = Fft::new(size.clamp(MIN_FFT_SIZE, MAX_FFT_SIZE));
+                let size = fft.size();
+                let window: Vec<f32> = (0..size)
+                        .map(|i| (0.5 - 0.5 * (2.0 * PI * i as f64 / size as f64).cos()) as f32)
+                        .collect();
+                Self {
+                        window_sum: window.iter().sum(),
+                        window,
+                        re: vec![0.0; size],
+                        im: vec![0.0; size],
+                        power: vec![0.0; size / 2 + 1],
+                        averaging: averaging.clamp(0.0, 0.99),
+                        primed: false,
+                        fft,
+                }
+        }
+
+        pub fn size(&self) -> usize {
+                self.fft.size()
+        }
+
+        pub fn set_averaging(&mut self, averaging: f32) {
+                self.averaging = averaging.clamp(0.0, 0.99);
+        }
+
+        /// 分析 `samples` 的最后 `size` 个样本（不足时在前面补零），并与之前的结果平均
+        pub fn analyze(&mut self, samples: &[f32]) {
+                let size = self.size();
+                let tail = &samples[samples.len().saturating_sub(size)..];
+                let offset = size - tail.len();
+                self.re[..offset].fill(0.0);
+                for ((re, s), w) in self.re[offset..].iter_mut().zip(tail).zip(&self.window[offset..]) {
+                        *re = s * w;
+                }
+                self.im.fill(0.0);
+                self.fft.process(&mut self.re, &mut self.im);
+
+                let keep = if self.primed { self.averaging } else { 0.0 };
+                for (k, power) in self.power.iter_mut().enumerate() {
+                        // 单边谱：直流与奈奎斯特频点之外的幅度加倍
+                        let scale = if k == 0 || k == size / 2 { 1.0 } else { 2.0 } / self.window_sum;
+                        let value = (self.re[k] * self.re[k] + self.im[k] * self.im[k]) * scale * scale;
+                        *power = *power * keep + value * (1.0 - keep);
+                }
+                self.primed = true;
+        }
+
+        // 频率 `frequency` 处的功率（在相邻频点之间线性插值）
+        fn power_at(&self, frequency: f32, bin_width: f32) -> f32 {
+                let x = (frequency / bin_width).max(0.0);
+                let i = (x as usize).min(self.power.len() - 1);
+                let next = (i + 1).min(self.power.len() - 1);
+                let t = x - i as f32;
+                self.power[i] + (self.power[next] - self.power[i]) * t.min(1.0)
+        }
+
+        /// 把 `min_frequency` 到奈奎斯特频率按对数均分为 `bands` 个频带，返回各频带的
+        /// 中心频率（Hz）与电平（dBFS，频带内频点的最大值）
+        pub fn bands(&self, sample_rate: f32, min_frequency: f32, bands: usize) -> (Vec<f32>, Vec<f32>) {
+                let nyquist = sample_rate / 2.0;
+                let bin_width = sample_rate / self.size() as f32;
+                let low = min_frequency.clamp(1.0, nyquist / 2.0);
+                let ratio = (nyquist / low).powf(1.0 / bands.max(1) as f32);
+                let mut frequencies = Vec::with_capacity(bands);
+                let mut levels = Vec::with_capacity(bands);
+                for band in 0..bands {
+                        let start = low * ratio.powi(band as i32);
+                        let end = start * ratio;
+                        let center = (start * end).sqrt();
+                        let first = (start / bin_width).ceil() as usize;
+                        let last = ((end / bin_width).ceil() as usize).min(self.power.len());
+                        let power = if first < last {
+                                self.power[first..last].iter().fold(0.0f32, |m, p| m.max(*p))
+                        } else {
+                                self.power_at(center, bin_width)
+                        };
+                        frequencies.push(center);
+                        levels.push((10.0 * power.log10()).max(FLOOR_DB));
+                }
+                (frequencies, levels)
+        }
+}
@@ -0,0 +1,171 @@
+use crate::audio::core::plugin::AudioBuffer;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering, fence};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use uuid::Uuid;
+
+// 分析抽头：音频线程把轨道 / 插件输出（各通道平均后的单声道）写入无锁环形缓冲，
+// 非实时线程按需读取并计算频谱与波形。环形缓冲在第一个读取者开始时创建、最后一个读取者离开时释放，
+// 没有读取者时音频线程直接跳过。
+
+// 每个抽头的环形缓冲容量（样本数），足够最大 FFT 尺寸加上分析线程的读取间隔
+const TAP_CAPACITY: usize = 1 << 15;
+
+/// 单写者、多读者的无锁环形缓冲。写满后覆盖最旧的样本，读者发现被覆盖时跳过丢失的部分。
+/// 样本以 f32 位模式存放在原子变量中，因此不需要 unsafe
+pub struct SampleRing {
+        samples: Box<[AtomicU32]>,
+        mask: usize,
+        // 已写入的样本总数（单调递增）
+        written: AtomicUsize,
+        // 写者即将写到的位置：覆盖旧样本之前先发布，读者据此判断读到的样本是否已被覆盖
+        reserved: AtomicUsize,
+}
+
+impl SampleRing {
+        /// 容量向上取整为 2 的幂
+        pub fn new(capacity: usize) -> Self {
+                let capacity = capacity.max(1).next_power_of_two();
+                Self {
+                        samples: (0..capacity).map(|_| AtomicU32::new(0)).collect(),
+                        mask: capacity - 1,
+                        written: AtomicUsize::new(0),
+                        reserved: AtomicUsize::new(0),
+                }
+        }
+
+        pub fn capacity(&self) -> usize {
+                self.samples.len()
+        }
+
+        /// 已写入的样本总数，作为读取的起始位置
+        pub fn written(&self) -> usize {
+                self.written.load(Ordering::Acquire)
+        }
+
+        /// 追加样本（只能由一个线程调用）
+        pub fn push(&self, samples: impl IntoIterator<Item = f32>) {
+                let samples = samples.into_iter();
+                let start = self.written.load(Ordering::Relaxed);
+                // 长度未知时按整个容量预留（读者最多多丢弃一些样本）
+                let reserve = samples.size_hint().1.unwrap_or(self.capacity());
+                self.reserved.store(start + reserve, Ordering::Relaxed);
+                fence(Ordering::Release);
+                let mut position = start;
+                for sample in samples {
+                        self.samples[position & self.mask].store(sample.to_bits(), Ordering::Relaxed);
+                        position += 1;
+                }
+                self.written.store(position, Ordering::Release);
+        }
+
+        /// 把位置 `from` 之后写入的样本追加到 `out`，返回新的读取位置。
+        /// 落后超过容量或读取期间被覆盖的样本会被跳过
+        pub fn read(&self, from: usize, out: &mut Vec<f32>) -> usize {
+                let end = self.written();
+                let start = from.max(end.saturating_sub(self.capacity())).min(end);
+                let base = out.len();
+                out.extend((start..end).map(|i| f32::from_bits(self.samples[i & self.mask].load(Ordering::Relaxed))));
+                // 读取期间写者可能已经覆盖了开头的一部分：样本读取之后再读预留位置
+                fence(Ordering::Acquire);
+                let valid = self.reserved.load(Ordering::Relaxed).saturating_sub(self.capacity());
+                if valid > start {
+                        let lost = (valid - start).min(end - start);
+                        out.drain(base..base + lost);
+                }
+                end
+        }
+}
+
+/// 附着在一个节点输出上的抽头
+pub struct AnalysisTap {
+        // 有读取者时存在。音频线程只在 try_lock 成功时借用，从不持有引用计数，
+        // 因此环形缓冲总是在非实时线程上释放
+        ring: Mutex<Option<Arc<SampleRing>>>,
+        sample_rate: AtomicU32,
+        // 正在读取的分析会话数
+        listeners: AtomicUsize,
+}
+
+impl Default for AnalysisTap {
+        fn default() -> Self {
+                Self {
+                        ring: Mutex::new(None),
+                        sample_rate: AtomicU32::new(48000.0f32.to_bits()),
+                        listeners: AtomicUsize::new(0),
+                }
+        }
+}
+
+impl AnalysisTap {
+        pub fn has_listeners(&self) -> bool {
+                self.listeners.load(Ordering::Relaxed) > 0
+        }
+
+        /// 最近一次写入时的采样率
+        pub fn sample_rate(&self) -> f32 {
+                f32::from_bits(self.sample_rate.load(Ordering::Relaxed))
+        }
+
+        /// 开始读取：第一个读取者创建环形缓冲。返回的缓冲在 `remove_listener` 之后仍可安全读取
+        pub fn add_listener(&self) -> Result<Arc<SampleRing>, String> {
+                let mut ring = self.ring.lock().map_err(|_| "Failed to lock analysis tap")?;
+                let ring = ring
+                        .get_or_insert_with(|| Arc::new(SampleRing::new(TAP_CAPACITY)))
+                        .clone();
+                self.listeners.fetch_add(1, Ordering::Relaxed);
+                Ok(ring)
+        }
+
+        /// 结束读取：最后一个读取者离开时释放环形缓冲
+        pub fn remove_listener(&self) -> Result<(), String> {
+                let mut ring = self.ring.lock().map_err(|_| "Failed to lock analysis tap")?;
+                let remaining = self
+                        .listeners
+                        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+                        .map_or(0, |n| n - 1);
+                if remaining == 0 {
+                        *ring = None;
+                }
+                Ok(())
+        }
+
+        /// 音频线程调用：有读取者时把平面缓冲的各通道平均后写入环形缓冲
+        pub fn write(&self, buffer: &AudioBuffer) {
+                if !self.has_listeners() {
+                        return;
+                }
+                // 读取者正在加入 / 离开时跳过本块，不等待
+                let Ok(ring) = self.ring.try_lock() else {
+                        return;
+                };
+                let Some(ring) = ring.as_ref() else {
+                        return;
+                };
+                self.sample_rate.store(buffer.sample_rate.to_bits(), Ordering::Relaxed);
+                let channels = buffer.channels.max(1);
+                let frames = buffer.frames();
+                let scale = 1.0 / channels as f32;
+                ring.push((0..frames)
+                        .map(|frame| (0..channels).map(|c| buffer.samples[c * frames + frame]).sum::<f32>() * scale));
+        }
+}
+
+// 节点 ID -> 抽头。构建音频图时为每个可分析的节点取得抽头，重建后沿用同一个抽头，
+// 因此分析会话不受重建影响。只保存弱引用：音频图与分析会话都不再使用的抽头随之移除
+static TAPS: OnceLock<Mutex<HashMap<Uuid, Weak<AnalysisTap>>>> = OnceLock::new();
+
+/// 取得（必要时创建）节点 `node` 的抽头。会加锁，不要在音频线程调用
+pub fn tap_point(node: Uuid) -> Result<Arc<AnalysisTap>, String> {
+        let mut taps = TAPS
+                .get_or_init(|| Mutex::new(HashMap::new()))
+                .lock()
+                .map_err(|_| "Failed to lock analysis taps")?;
+        taps.retain(|_, tap| tap.strong_count() > 0);
+        if let Some(tap) = taps.get(&node).and_then(Weak::upgrade) {
+                return Ok(tap);
+        }
+        let tap = Arc::new(AnalysisTap::default());
+        taps.insert(node, Arc::downgrade(&tap));
+        Ok(tap)
+}
@@ -0,0 +1,40 @@
+use crate::audio::core::plugin::ParamAddress;
+use serde::{Deserialize, Serialize};
+
+/// 自动化曲线上的一个点（歌曲时间，秒）
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct AutomationPoint {
+        pub time: f64,
+        pub value: f32,
+}
+
+/// 参数自动化曲线：按 `ParamAddress`（节点 UUID + 参数 ID）寻址，与 Tauri 命令和事件队列使用同一地址，
+/// 轨道 / 乐器顺序变化或重建音频图后仍指向同一参数。由音序器在播放时转换为 `NodeParameter` 事件
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationLane {
+        pub address: ParamAddress,
+        /// 按时间排序的点
+        #[serde(default)]
+        pub points: Vec<AutomationPoint>,
+}
+
+impl AutomationLane {
+        /// 歌曲时间 `time` 处的值：点之间线性插值，曲线两端之外保持端点值；没有点时为 None
+        pub fn value_at(&self, time: f64) -> Option<f32> {
+                let mut previous: Option<&AutomationPoint> = None;
+                for point in &self.points {
+                        if point.time >= time {
+                                return Some(match previous {
+                                        Some(prev) if point.time > prev.time => {
+                                                let t = ((time - prev.time) / (point.time - prev.time)) as f32;
+                                                prev.value + (point.value - prev.value) * t
+                                        }
+                                        _ => point.value,
+                                });
+                        }
+                        previous = Some(point);
+                }
+                previous.map(|p| p.value)
+        }
+}
@@ -0,0 +1,107 @@
+use crate::audio::core::plugin::AudioBuffer;
+
+// 平面（非交错）多通道缓冲：引擎内部的处理格式，每个通道的样本连续存放，
+// 通道 c 位于 [c * frames, (c + 1) * frames)。只在设备边界（交错的设备回调缓冲）
+// 以及仍使用交错格式的旧式 FFI 插件处与交错样本互相转换。
+
+/// 可复用的平面缓冲：调整尺寸时只在容量不足时分配
+#[derive(Debug, Default, Clone)]
+pub struct PlanarBuffer {
+        data: Vec<f32>,
+        channels: usize,
+        frames: usize,
+}
+
+impl PlanarBuffer {
+        pub fn new(channels: usize, frames: usize) -> Self {
+                Self {
+                        data: vec![0.0; channels * frames],
+                        channels,
+                        frames,
+                }
+        }
+
+        /// 调整为 `channels` 通道、`frames` 帧并清零
+        pub fn resize(&mut self, channels: usize, frames: usize) {
+                self.data.clear();
+                self.data.resize(channels * frames, 0.0);
+                self.channels = channels;
+                self.frames = frames;
+        }
+
+        pub fn channels(&self) -> usize {
+                self.channels
+        }
+
+        pub fn frames(&self) -> usize {
+                self.frames
+        }
+
+        /// 不重新分配即可容纳的样本数
+        pub fn capacity(&self) -> usize {
+                self.data.capacity()
+        }
+
+        pub fn channel(&self, index: usize) -> &[f32] {
+                &self.data[index * self.frames..(index + 1) * self.frames]
+        }
+
+        pub fn channel_mut(&mut self, index: usize) -> &mut [f32] {
+                &mut self.data[index * self.frames..(index + 1) * self.frames]
+        }
+
+        /// 全部样本（按通道依次排列）
+        pub fn as_slice(&self) -> &[f32] {
+                &self.data
+        }
+
+        pub fn as_mut_slice(&mut self) -> &mut [f32] {
+                &mut self.data
+        }
+
+        /// 借出为插件处理用的 `AudioBuffer`
+        pub fn as_audio_buffer(&mut self, sample_rate: f32) -> AudioBuffer<'_> {
+                AudioBuffer {
+                        samples: &mut self.data,
+                        channels: self.channels,
+                        sample_rate,
+                }
+        }
+
+        /// 从 `channels` 通道的交错样本读入（尺寸随之调整）
+        pub fn read_interleaved(&mut self, input: &[f32], channels: usize) {
+                self.resize(channels, input.len() / channels.max(1));
+                deinterleave(input, &mut self.data, channels);
+        }
+
+        /// 写出为交错样本
+        pub fn write_interleaved(&self, output: &mut [f32]) {
+                interleave(&self.data, output, self.channels);
+        }
+}
+
+/// 交错 -> 平面：两者都按 `channels` 通道划分，处理帧数较少的部分
+pub fn deinterleave(interleaved: &[f32], planar: &mut [f32], channels: usize) {
+        if channels == 0 {
+                return;
+        }
+        let frames = planar.len() / channels;
+        for (c, channel) in planar.chunks_exact_mut(frames.max(1)).take(channels).enumerate() {
+                for (sample, frame) in channel.iter_mut().zip(interleaved.chunks_exact(channels)) {
+                        *sample = frame[c];
+                }
+        }
+}
+
+/// 平面 -> 交错：两者都按 `channels` 通道划分，处理帧数较少的部分
+pub fn interleave(planar: &[f32], interleaved: &mut [f32], channels: usize) {
+        if channels == 0 {
+                return;
+        }
+        let frames = planar.len() / channels;
+        for (c, channel) in planar.chunks_exact(frames.max(1)).take(channels).enumerate() {
+                for (sample, frame) in channel.iter().zip(interleaved.chunks_exact_mut(channels)) {
+                        frame[c] = *sample;
+                }
+        }
+}
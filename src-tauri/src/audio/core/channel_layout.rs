@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+
+// 通道布局：轨道与总线的声道格式（各通道对应的扬声器），
+// 以及布局不一致的节点之间的上混 / 下混矩阵和环绕声像计算。
+
+/// 支持的最大通道数（7.1）
+pub const MAX_CHANNELS: usize = 8;
+
+/// 混音矩阵：`matrix[输出通道][输入通道]`
+pub type MixMatrix = [[f32; MAX_CHANNELS]; MAX_CHANNELS];
+
+const MINUS_3DB: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// 扬声器位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Speaker {
+        Left,
+        Right,
+        Center,
+        Lfe,
+        /// 环绕左 / 右（5.1 的 Ls / Rs、7.1 的侧环绕、四声道的后方一对）
+        SurroundLeft,
+        SurroundRight,
+        /// 7.1 的后环绕
+        RearLeft,
+        RearRight,
+}
+
+impl Speaker {
+        /// 水平方位角（度）：0 为正前方，负值在左；LFE 没有方位
+        pub fn azimuth(self, layout: ChannelLayout) -> Option<f32> {
+                let quad = layout == ChannelLayout::Quad;
+                match self {
+                        Speaker::Left => Some(if quad { -45.0 } else { -30.0 }),
+                        Speaker::Right => Some(if quad { 45.0 } else { 30.0 }),
+                        Speaker::Center => Some(0.0),
+                        Speaker::Lfe => None,
+                        Speaker::SurroundLeft => Some(match layout {
+                                ChannelLayout::Quad => -135.0,
+                                ChannelLayout::Surround71 => -90.0,
+                                _ => -110.0,
+                        }),
+                        Speaker::SurroundRight => Some(match layout {
+                                ChannelLayout::Quad => 135.0,
+                                ChannelLayout::Surround71 => 90.0,
+                                _ => 110.0,
+                        }),
+                        Speaker::RearLeft => Some(-150.0),
+                        Speaker::RearRight => Some(150.0),
+                }
+        }
+
+        // 目标布局中没有该扬声器时的替代去向（扬声器, 增益），最多两个
+        fn fallback(self, target: ChannelLayout) -> [(Speaker, f32); 2] {
+                const NONE: (Speaker, f32) = (Speaker::Lfe, 0.0);
+                let has = |s: Speaker| target.index_of(s).is_some();
+                match self {
+                        // 中置按 -3 dB 分配到左右（单声道源上混为立体声时同样适用）
+                        Speaker::Center => [(Speaker::Left, MINUS_3DB), (Speaker::Right, MINUS_3DB)],
+                        // 下混时丢弃 LFE（ITU-R BS.775）
+                        Speaker::Lfe => [NONE; 2],
+                        Speaker::SurroundLeft if has(Speaker::RearLeft) => [(Speaker::RearLeft, 1.0), NONE],
+                        Speaker::SurroundRight if has(Speaker::RearRight) => [(Speaker::RearRight, 1.0), NONE],
+                        Speaker::RearLeft if has(Speaker::SurroundLeft) => [(Speaker::SurroundLeft, MINUS_3DB), NONE],
+                        Speaker::RearRight if has(Speaker::SurroundRight) => {
+                                [(Speaker::SurroundRight, MINUS_3DB), NONE]
+                        }
+                        Speaker::SurroundLeft | Speaker::RearLeft => [(Speaker::Left, MINUS_3DB), NONE],
+                        Speaker::SurroundRight | Speaker::RearRight => [(Speaker::Right, MINUS_3DB), NONE],
+                        // 下混为单声道：左右各 -6 dB 进入中置
+                        Speaker::Left | Speaker::Right => [(Speaker::Center, 0.5), NONE],
+                }
+        }
+}
+
+/// 轨道 / 总线的通道布局（通道顺序与 WAVE / SMPTE 一致）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ChannelLayout {
+        #[serde(rename = "mono")]
+        Mono,
+        #[default]
+        #[serde(rename = "stereo")]
+        Stereo,
+        /// L R Ls Rs
+        #[serde(rename = "quad")]
+        Quad,
+        /// L R C LFE Ls Rs
+        #[serde(rename = "5.1")]
+        Surround51,
+        /// L R C LFE Lb Rb Ls Rs
+        #[serde(rename = "7.1")]
+        Surround71,
+}
+
+impl ChannelLayout {
+        pub const ALL: [ChannelLayout; 5] = [
+                ChannelLayout::Mono,
+                ChannelLayout::Stereo,
+                ChannelLayout::Quad,
+                ChannelLayout::Surround51,
+                ChannelLayout::Surround71,
+        ];
+
+        pub fn speakers(self) -> &'static [Speaker] {
+                use Speaker::*;
+                match self {
+                        ChannelLayout::Mono => &[Center],
+                        ChannelLayout::Stereo => &[Left, Right],
+                        ChannelLayout::Quad => &[Left, Right, SurroundLeft, SurroundRight],
+                        ChannelLayout::Surround51 => &[Left, Right, Center, Lfe, SurroundLeft, SurroundRight],
+                        ChannelLayout::Surround71 => &[
+                                Left,
+                                Right,
+                                Center,
+                                Lfe,
+                                RearLeft,
+                                RearRight,
+                                SurroundLeft,
+                                SurroundRight,
+                        ],
+                }
+        }
+
+        pub fn channels(self) -> usize {
+                self.speakers().len()
+        }
+
+        /// 多于两个方位的布局使用环绕声像
+        pub fn is_surround(self) -> bool {
+                self.channels() > 2
+        }
+
+        /// 按通道数推断布局；没有对应布局时取通道数不超过 `channels` 的最大布局（多出的通道保持静音）
+        pub fn from_channels(channels: usize) -> Self {
+                match channels {
+                        0 | 1 => ChannelLayout::Mono,
+                        2 | 3 => ChannelLayout::Stereo,
+                        4 | 5 => ChannelLayout::Quad,
+                        6 | 7 => ChannelLayout::Surround51,
+                        _ => ChannelLayout::Surround71,
+                }
+        }
+
+        fn index_of(self, speaker: Speaker) -> Option<usize> {
+                self.speakers().iter().position(|s| *s == speaker)
+        }
+
+        // 把 `speaker` 的信号以 `gain` 加到矩阵的第 `input` 列；目标中没有该扬声器时按替代规则展开
+        fn route(self, matrix: &mut MixMatrix, input: usize, speaker: Speaker, gain: f32, depth: u32) {
+                if gain == 0.0 || depth > 3 {
+                        return;
+                }
+                if let Some(output) = self.index_of(speaker) {
+                        matrix[output][input] += gain;
+                        return;
+                }
+                for (target, g) in speaker.fallback(self) {
+                        self.route(matrix, input, target, gain * g, depth + 1);
+                }
+        }
+
+        /// 从 `from` 到本布局的混音矩阵：同名扬声器直通，缺失的扬声器按 ITU 风格下混 / 上混
+        pub fn mix_matrix_from(self, from: ChannelLayout) -> MixMatrix {
+                let mut matrix = [[0.0; MAX_CHANNELS]; MAX_CHANNELS];
+                for (input, speaker) in from.speakers().iter().enumerate() {
+                        self.route(&mut matrix, input, *speaker, 1.0, 0);
+                }
+                matrix
+        }
+}
+
+/// 按矩阵把平面的 `input`（`in_channels` 通道）叠加到平面的 `output`（`out_channels` 通道），
+/// 处理两者帧数中较少的部分；矩阵之外的输出通道不受影响
+pub fn mix_planar(input: &[f32], in_channels: usize, output: &mut [f32], out_channels: usize, matrix: &MixMatrix) {
+        if in_channels == 0 || out_channels == 0 {
+                return;
+        }
+        let (in_frames, out_frames) = (input.len() / in_channels, output.len() / out_channels);
+        let frames = in_frames.min(out_frames);
+        for (o, row) in matrix.iter().enumerate().take(out_channels.min(MAX_CHANNELS)) {
+                let dst = &mut output[o * out_frames..o * out_frames + frames];
+                for (i, gain) in row.iter().enumerate().take(in_channels.min(MAX_CHANNELS)) {
+                        if *gain == 0.0 {
+                                continue;
+                        }
+                        let src = &input[i * in_frames..i * in_frames + frames];
+                        for (d, s) in dst.iter_mut().zip(src) {
+                                *d += gain * s;
+                        }
+                }
+        }
+}
+
+/// 把 `from` 布局的平面样本混合（叠加）到 `to` 布局的平面输出
+pub fn remix(input: &[f32], from: ChannelLayout, output: &mut [f32], to: ChannelLayout) {
+        let matrix = to.mix_matrix_from(from);
+        mix_planar(input, from.channels(), output, to.channels(), &matrix);
+}
+
+/// 环绕声像：声像位置 (`x`, `y`) 处的单个点声源在 `layout` 各扬声器上的增益。
+/// `x` 为 -1 左 .. 1 右，`y` 为 -1 后 .. 1 前；位置到中心的距离小于 1 时按等功率向所有扬声器扩散，
+/// 位于中心 (0, 0) 时均匀分布。`offset` 为叠加到声像方位上的角度（度），用于展开立体声源。
+/// 相邻扬声器之间按等功率（正弦 / 余弦）分配，LFE 不参与。
+pub fn pan_gains(layout: ChannelLayout, x: f32, y: f32, offset: f32) -> [f32; MAX_CHANNELS] {
+        let mut gains = [0.0; MAX_CHANNELS];
+        // 有方位的扬声器（通道下标, 方位），按方位排序
+        let mut speakers = [(0usize, 0.0f32); MAX_CHANNELS];
+        let mut count = 0;
+        for (index, speaker) in layout.speakers().iter().enumerate() {
+                if let Some(azimuth) = speaker.azimuth(layout) {
+                        speakers[count] = (index, azimuth);
+                        count += 1;
+                }
+        }
+        let speakers = &mut speakers[..count];
+        if count == 1 {
+                gains[speakers[0].0] = 1.0;
+                return gains;
+        }
+        speakers.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        let distance = (x * x + y * y).sqrt().min(1.0);
+        let azimuth = if distance > 0.0 {
+                x.atan2(y).to_degrees() + offset
+        } else {
+                offset
+        };
+
+        // 声像方位两侧相邻的扬声器（首尾相接，最后一段跨过正后方）
+        let mut pair = (count - 1, 0);
+        for i in 0..count {
+                let next = (i + 1) % count;
+                let span = (speakers[next].1 - speakers[i].1).rem_euclid(360.0);
+                if (azimuth - speakers[i].1).rem_euclid(360.0) <= span {
+                        pair = (i, next);
+                        break;
+                }
+        }
+        let (a, b) = (speakers[pair.0], speakers[pair.1]);
+        let span = (b.1 - a.1).rem_euclid(360.0);
+        let fraction = if span > 0.0 {
+                (azimuth - a.1).rem_euclid(360.0) / span
+        } else {
+                0.0
+        };
+        let angle = fraction.clamp(0.0, 1.0) * std::f32::consts::FRAC_PI_2;
+
+        let spread = (1.0 - distance) / count as f32;
+        for (index, _) in speakers.iter() {
+                let mut power = spread;
+                if *index == a.0 {
+                        power += distance * angle.cos().powi(2);
+                }
+                if *index == b.0 {
+                        power += distance * angle.sin().powi(2);
+                }
+                gains[*index] = power.sqrt();
+        }
+        gains
+}
@@ -1,8 +1,20 @@
+use crate::audio::core::plugin::{NoteEvent, NoteExpressionKind};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// 单音符表情曲线上的点：时间相对于音符起点（秒），同一类型的点之间线性插值
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExpressionPoint {
+        pub kind: NoteExpressionKind,
+        pub time: f64,
+        pub value: f32,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Note {
+        // 音符 ID（在音频图内唯一），随 NoteOn / NoteOff / 表情事件发送
+        #[serde(default)]
+        pub id: u32,
         // 片段内的相对起始时间（秒）
         pub relative_start: f64,
         // 音符持续时长（秒）
@@ -11,6 +23,91 @@ pub struct Note {
         pub note: u8,
         // 力度，范围通常为 0.0 - 1.0
         pub velocity: f32,
+        // MIDI 通道（0-15）
+        #[serde(default)]
+        pub channel: u8,
+        // 单音符表情曲线（音高 / 压力 / 明亮度），按时间排序
+        #[serde(default)]
+        pub expressions: Vec<ExpressionPoint>,
+}
+
+impl Note {
+        /// 某类表情曲线的起止时间（相对于音符起点）；没有该类型的点时为 None
+        pub fn expression_span(&self, kind: NoteExpressionKind) -> Option<(f64, f64)> {
+                let first = self.expressions.iter().find(|p| p.kind == kind)?;
+                let last = self.expressions.iter().rfind(|p| p.kind == kind)?;
+                Some((first.time, last.time))
+        }
+
+        /// 相对时间 `time` 处的表情值：点之间线性插值，曲线两端之外保持端点值
+        pub fn expression_at(&self, kind: NoteExpressionKind, time: f64) -> Option<f32> {
+                let mut previous: Option<&ExpressionPoint> = None;
+                for point in self.expressions.iter().filter(|p| p.kind == kind) {
+                        if point.time >= time {
+                                return Some(match previous {
+                                        Some(prev) if point.time > prev.time => {
+                                                let t = ((time - prev.time) / (point.time - prev.time)) as f32;
+                                                prev.value + (point.value - prev.value) * t
+                                        }
+                                        _ => point.value,
+                                });
+                        }
+                        previous = Some(point);
+                }
+                previous.map(|p| p.value)
+        }
+}
+
+/// 片段中控制器事件的类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ControllerKind {
+        ControlChange { controller: u8 },
+        PitchBend,
+        ChannelPressure,
+        PolyPressure { note: u8 },
+        ProgramChange,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ControllerEvent {
+        // 片段内的相对时间（秒）
+        pub relative_time: f64,
+        // MIDI 通道（0-15）
+        pub channel: u8,
+        pub kind: ControllerKind,
+        // CC / 触后为 0.0 - 1.0，弯音为 -1.0 - 1.0，音色切换为音色号（0-127）
+        pub value: f32,
+}
+
+impl ControllerEvent {
+        pub fn to_note_event(&self) -> NoteEvent {
+                let channel = self.channel;
+                match self.kind {
+                        ControllerKind::ControlChange { controller } => NoteEvent::ControlChange {
+                                channel,
+                                controller,
+                                value: self.value,
+                        },
+                        ControllerKind::PitchBend => NoteEvent::PitchBend {
+                                channel,
+                                value: self.value,
+                        },
+                        ControllerKind::ChannelPressure => NoteEvent::ChannelPressure {
+                                channel,
+                                pressure: self.value,
+                        },
+                        ControllerKind::PolyPressure { note } => NoteEvent::PolyPressure {
+                                channel,
+                                note,
+                                pressure: self.value,
+                        },
+                        ControllerKind::ProgramChange => NoteEvent::ProgramChange {
+                                channel,
+                                program: self.value.clamp(0.0, 127.0) as u8,
+                        },
+                }
+        }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -27,4 +124,7 @@ pub struct Clip {
         pub instrument_routes: HashMap<usize, Vec<usize>>,
         // 片段内的音符事件
         pub notes: Vec<Note>,
+        // 片段内的控制器事件（CC、弯音、触后、音色切换），按时间排序
+        #[serde(default)]
+        pub controllers: Vec<ControllerEvent>,
 }
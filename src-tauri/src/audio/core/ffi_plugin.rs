@@ -1,7 +1,10 @@
-use crate::audio::core::plugin::{AudioBuffer, Plugin, PluginEvent, PluginInfo};
+use crate::audio::core::buffer::{deinterleave, interleave};
+use crate::audio::core::channel_layout::MAX_CHANNELS;
+use crate::audio::core::plugin::{AudioBuffer, IOConfig, ParameterTaper, Plugin, PluginEvent, PluginInfo};
+use crate::audio::midi::mpe::MpeEncoder;
 use libc;
 use libloading::Library;
-use std::ffi::{CStr, c_void};
+use std::ffi::{CStr, CString, c_void};
 use std::os::raw::c_char;
 use std::ptr::NonNull;
 
@@ -16,6 +19,13 @@ type FreeStrFn = unsafe extern "C" fn(*mut c_char);
 type StateGetFn = unsafe extern "C" fn(*mut c_void, *mut usize) -> *mut u8;
 type StateFreeFn = unsafe extern "C" fn(*mut u8, usize);
 type StateSetFn = unsafe extern "C" fn(*mut c_void, *const u8, usize);
+type LatencyFn = unsafe extern "C" fn(*mut c_void) -> u32;
+type ValueToTextFn = unsafe extern "C" fn(*mut c_void, u32, f32, *mut c_char, usize) -> bool;
+type TextToValueFn = unsafe extern "C" fn(*mut c_void, u32, *const c_char, *mut f32) -> bool;
+type MidiEventFn = unsafe extern "C" fn(*mut c_void, *const u8, usize);
+
+// 交错缓冲预留的帧数（与 CLAP 插件激活时的最大帧数一致）；更长的块输出静音
+const MAX_BLOCK_FRAMES: usize = 4096;
 
 #[allow(dead_code)]
 pub struct FFIPlugin {
@@ -25,7 +35,7 @@ pub struct FFIPlugin {
         inst: Option<NonNull<c_void>>,
         // 插件销毁函数（必须）：在 Drop 时调用以释放实例
         destroy_fn: DestroyFn,
-        // 插件处理回调（必须）：对传入样本缓冲区就地处理
+        // 插件处理回调（必须）：对传入的交错样本缓冲区就地处理
         process_fn: ProcessFn,
         // 可选参数设置函数
         set_param_fn: Option<SetParamFn>,
@@ -41,6 +51,21 @@ pub struct FFIPlugin {
         state_free_fn: Option<StateFreeFn>,
         // 可选：将序列化状态写回插件实例的函数
         state_set_fn: Option<StateSetFn>,
+        // 可选：返回插件处理延迟（采样数）的函数
+        latency_fn: Option<LatencyFn>,
+        // 可选：把参数值格式化为文本（写入宿主提供的缓冲区）
+        value_to_text_fn: Option<ValueToTextFn>,
+        // 可选：把文本解析为参数值
+        text_to_value_fn: Option<TextToValueFn>,
+        // 可选：接收一条 MIDI 1.0 消息（在本块 process 之前调用）
+        midi_event_fn: Option<MidiEventFn>,
+        // 由 plugin_info_json 的 `inputs`/`outputs` 字段得到的 I/O 配置（缺省为立体声效果器）
+        io_config: IOConfig,
+        // plugin_info_json 中 `mpe = true` 时以 MPE（每音符一个通道）发送音符与单音符表情
+        mpe: Option<MpeEncoder>,
+        // 旧式 C 接口使用交错样本：处理前后与引擎的平面缓冲互相转换。
+        // 加载插件时按 MAX_BLOCK_FRAMES × 最大通道数分配，音频线程中只取前缀
+        interleaved: Vec<f32>,
 }
 
 // 注意：FFIPlugin 持有指向 C 插件实例的裸指针与动态库句柄。
@@ -82,6 +107,22 @@ impl FFIPlugin {
                         Ok(s) => Some(*s),
                         Err(_) => None,
                 };
+                let latency_sym = match unsafe { lib.get::<LatencyFn>(b"plugin_get_latency") } {
+                        Ok(s) => Some(*s),
+                        Err(_) => None,
+                };
+                let value_to_text_sym = match unsafe { lib.get::<ValueToTextFn>(b"plugin_param_value_to_text") } {
+                        Ok(s) => Some(*s),
+                        Err(_) => None,
+                };
+                let text_to_value_sym = match unsafe { lib.get::<TextToValueFn>(b"plugin_param_text_to_value") } {
+                        Ok(s) => Some(*s),
+                        Err(_) => None,
+                };
+                let midi_event_sym = match unsafe { lib.get::<MidiEventFn>(b"plugin_midi_event") } {
+                        Ok(s) => Some(*s),
+                        Err(_) => None,
+                };
 
                 // 把函数指针复制出来，symbol 可被丢弃，而 Library 被保存在结构体中以保证库仍然加载
                 let create_fn: CreateFn = *create_sym;
@@ -95,12 +136,16 @@ impl FFIPlugin {
                 let state_get_fn = state_get_sym;
                 let state_free_fn = state_free_sym;
                 let state_set_fn = state_set_sym;
+                let latency_fn = latency_sym;
+                let value_to_text_fn = value_to_text_sym;
+                let text_to_value_fn = text_to_value_sym;
+                let midi_event_fn = midi_event_sym;
 
                 // 调用插件创建实例（unsafe 调用外部函数）并用 NonNull 封装
                 let raw_inst = unsafe { create_fn(sample_rate) };
                 let inst = NonNull::new(raw_inst).map(|p| p.cast());
 
-                Ok(Self {
+                let mut plugin = Self {
                         lib,
                         inst,
                         destroy_fn,
@@ -112,7 +157,21 @@ impl FFIPlugin {
                         state_get_fn,
                         state_free_fn,
                         state_set_fn,
-                })
+                        latency_fn,
+                        value_to_text_fn,
+                        text_to_value_fn,
+                        midi_event_fn,
+                        io_config: IOConfig::default(),
+                        mpe: None,
+                        interleaved: Vec::new(),
+                };
+                plugin.io_config = plugin.parse_io_config();
+                let channels = plugin.io_config.inputs.max(plugin.io_config.outputs).max(MAX_CHANNELS);
+                plugin.interleaved = vec![0.0; MAX_BLOCK_FRAMES * channels];
+                if plugin.supports_mpe() {
+                        plugin.mpe = Some(MpeEncoder::new());
+                }
+                Ok(plugin)
         }
         // 小型安全包装器：返回 info 字符串（若插件返回 null 则返回 None）
         fn info_string(&self) -> Option<String> {
@@ -127,6 +186,54 @@ impl FFIPlugin {
                 }
         }
 
+        // 解析 info JSON 中的通道数；`inputs = 0` 表示乐器/发生器
+        fn parse_io_config(&self) -> IOConfig {
+                let defaults = IOConfig::default();
+                let Some(v) = self
+                        .info_string()
+                        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+                else {
+                        return defaults;
+                };
+                let inputs = v.get("inputs").and_then(|x| x.as_u64()).map(|x| x as usize);
+                let outputs = v.get("outputs").and_then(|x| x.as_u64()).map(|x| x as usize);
+                IOConfig::simple(
+                        inputs.unwrap_or(defaults.inputs),
+                        outputs.unwrap_or(defaults.outputs),
+                )
+        }
+
+        fn supports_mpe(&self) -> bool {
+                self.info_string()
+                        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+                        .and_then(|v| v.get("mpe").and_then(|x| x.as_bool()))
+                        .unwrap_or(false)
+        }
+
+        // 把块内的 MIDI 事件逐条送给插件（需要插件导出 plugin_midi_event）
+        fn send_midi(&mut self, events: &[PluginEvent]) {
+                let (Some(midi_fn), Some(inst)) = (self.midi_event_fn, self.inst) else {
+                        return;
+                };
+                let send = |bytes: [u8; 3]| {
+                        // 音色切换与通道触后只有一个数据字节
+                        let len = match bytes[0] & 0xf0 {
+                                0xc0 | 0xd0 => 2,
+                                _ => 3,
+                        };
+                        unsafe { midi_fn(inst.as_ptr(), bytes.as_ptr(), len) }
+                };
+                // FFI 接口没有块内时间，带帧偏移的事件同样在块起点送达
+                for event in events {
+                        if let Some((_, midi)) = event.as_midi() {
+                                match self.mpe.as_mut() {
+                                        Some(mpe) => mpe.encode(midi, send),
+                                        None => send(midi.to_midi_bytes()),
+                                }
+                        }
+                }
+        }
+
         fn get_info(&self) -> PluginInfo {
                 if let Some(json) = self.info_string() {
                         if let Ok(v) = serde_json::from_str::<serde_json::Value>(&json) {
@@ -231,14 +338,29 @@ impl Plugin for FFIPlugin {
                                                                 ParameterType::Float
                                                         };
 
-                                                out.push(crate::audio::core::plugin::PluginParameter {
+                                                let mut param = crate::audio::core::plugin::PluginParameter::new(
                                                         id,
-                                                        name,
+                                                        &name,
                                                         min_value,
                                                         max_value,
                                                         default_value,
                                                         value_type,
-                                                });
+                                                );
+                                                // 可选显示元数据：单位、曲线、步进、标志与分组
+                                                let str_field = |key: &str| item.get(key).and_then(|x| x.as_str());
+                                                let bool_field = |key: &str| item.get(key).and_then(|x| x.as_bool());
+                                                param.unit = str_field("unit").unwrap_or("").to_string();
+                                                param.taper = ParameterTaper::parse(
+                                                        str_field("taper"),
+                                                        item.get("skew").and_then(|x| x.as_f64()).map(|x| x as f32),
+                                                );
+                                                param.step_count =
+                                                        item.get("steps").and_then(|x| x.as_u64()).unwrap_or(0) as u32;
+                                                param.flags.automatable = bool_field("automatable").unwrap_or(true);
+                                                param.flags.read_only = bool_field("read_only").unwrap_or(false);
+                                                param.flags.hidden = bool_field("hidden").unwrap_or(false);
+                                                param.group = str_field("group").unwrap_or("").to_string();
+                                                out.push(param);
                                         }
                                         return out;
                                 }
@@ -284,28 +406,44 @@ impl Plugin for FFIPlugin {
                 }
         }
 
-        fn get_io_config(&self) -> crate::audio::core::plugin::IOConfig {
-                crate::audio::core::plugin::IOConfig::default()
+        // MPE 编码按 note_id 为每个音符分配成员通道
+        fn supports_note_ids(&self) -> bool {
+                self.mpe.is_some()
         }
 
-        // 将音频缓冲区交给插件处理（就地修改 samples）
-        fn process(
-                &mut self,
-                buffer: &mut AudioBuffer,
-                _events: &[PluginEvent],
-                _output_events: &mut Vec<PluginEvent>,
-        ) {
-                let frames = buffer.samples.len() / buffer.channels;
-                if let Some(inst) = self.inst {
-                        unsafe {
-                                (self.process_fn)(
-                                        inst.as_ptr(),
-                                        buffer.samples.as_mut_ptr(),
-                                        frames,
-                                        buffer.channels,
-                                )
-                        }
+        fn get_io_config(&self) -> IOConfig {
+                self.io_config.clone()
+        }
+
+        fn latency(&self) -> u32 {
+                match (&self.latency_fn, self.inst) {
+                        (Some(latency_fn), Some(inst)) => unsafe { (latency_fn)(inst.as_ptr()) },
+                        _ => 0,
+                }
+        }
+
+        // 将音频缓冲区转换为交错样本交给插件就地处理，再转换回平面缓冲
+        fn process(&mut self, buffer: &mut AudioBuffer, events: &[PluginEvent], _output_events: &mut Vec<PluginEvent>) {
+                self.send_midi(events);
+                let frames = buffer.frames();
+                let Some(inst) = self.inst else {
+                        return;
+                };
+                // 只使用预分配缓冲的前缀；超出预留大小的块无法处理，输出静音
+                let Some(interleaved) = self.interleaved.get_mut(..buffer.samples.len()) else {
+                        buffer.samples.fill(0.0);
+                        return;
+                };
+                interleave(buffer.samples, interleaved, buffer.channels);
+                unsafe {
+                        (self.process_fn)(
+                                inst.as_ptr(),
+                                interleaved.as_mut_ptr(),
+                                frames,
+                                buffer.channels,
+                        )
                 }
+                deinterleave(interleaved, buffer.samples, buffer.channels);
         }
 
         // 读取参数（如果插件导出该接口），否则返回默认值 0.0
@@ -322,6 +460,27 @@ impl Plugin for FFIPlugin {
                 }
         }
 
+        fn param_value_to_text(&self, id: u32, value: f32) -> Option<String> {
+                let (value_to_text, inst) = (self.value_to_text_fn?, self.inst?);
+                let mut buf = [0 as c_char; 256];
+                unsafe {
+                        if !value_to_text(inst.as_ptr(), id, value, buf.as_mut_ptr(), buf.len()) {
+                                return None;
+                        }
+                        // 防御：插件未写入结尾的 0 时截断
+                        buf[buf.len() - 1] = 0;
+                        Some(CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned())
+                }
+        }
+
+        fn param_text_to_value(&self, id: u32, text: &str) -> Option<f32> {
+                let (text_to_value, inst) = (self.text_to_value_fn?, self.inst?);
+                let text = CString::new(text).ok()?;
+                let mut value = 0.0f32;
+                let ok = unsafe { text_to_value(inst.as_ptr(), id, text.as_ptr(), &mut value) };
+                ok.then_some(value)
+        }
+
         // 写入参数（若插件未实现此函数则忽略）
         fn set_param(&mut self, id: u32, value: f32) {
                 if let Some(set_fn) = &self.set_param_fn {
@@ -1,3 +1,9 @@
+pub mod automation;
+pub mod buffer;
+pub mod channel_layout;
 pub mod clip;
 pub mod ffi_plugin;
 pub mod plugin;
+pub mod resampler;
+pub mod smoothing;
+pub mod threads;
@@ -1,28 +1,172 @@
+use crate::audio::plugins::mixer::metronome::MetronomeSettings;
+use crate::audio::plugins::mixer::safety::SafetySettings;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-/// 简单的 MIDI 事件：按下 / 释放
-#[derive(Debug, Clone, Copy)]
+/// 普通 MIDI（非 MPE）下的弯音范围（半音）
+pub const DEFAULT_PITCH_BEND_RANGE: f32 = 2.0;
+
+/// 单音符表情的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteExpressionKind {
+        /// 音高偏移（半音，可为负）
+        Pitch,
+        /// 压力 0.0-1.0
+        Pressure,
+        /// 明亮度 / 音色（MPE 中为 CC74）0.0-1.0
+        Brightness,
+}
+
+/// MIDI 通道消息（通道 0-15）：音符按下 / 释放、控制器、弯音、触后、音色切换与单音符表情。
+/// 连续量使用浮点：力度、CC 与触后为 0.0-1.0，弯音为 -1.0-1.0（0 为中心）。
+/// `note_id` 用于把表情与具体的音符对应（同音高的叠音也能区分）；为 None 时按通道 + 音高匹配。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum NoteEvent {
         NoteOn {
-                #[allow(dead_code)]
+                #[serde(default)]
+                channel: u8,
                 note: u8,
-                #[allow(dead_code)]
                 velocity: f32,
+                #[serde(default)]
+                note_id: Option<u32>,
         },
         NoteOff {
-                #[allow(dead_code)]
+                #[serde(default)]
+                channel: u8,
+                note: u8,
+                #[serde(default)]
+                note_id: Option<u32>,
+        },
+        /// 单音符表情：值的含义见 `NoteExpressionKind`
+        NoteExpression {
+                channel: u8,
+                note: u8,
+                note_id: Option<u32>,
+                expression: NoteExpressionKind,
+                value: f32,
+        },
+        ControlChange {
+                channel: u8,
+                controller: u8,
+                value: f32,
+        },
+        PitchBend {
+                channel: u8,
+                value: f32,
+        },
+        ChannelPressure {
+                channel: u8,
+                pressure: f32,
+        },
+        /// 单音符触后（Poly Aftertouch）
+        PolyPressure {
+                channel: u8,
                 note: u8,
+                pressure: f32,
+        },
+        ProgramChange {
+                channel: u8,
+                program: u8,
         },
 }
 
-#[derive(Debug, Clone)]
+impl NoteEvent {
+        pub fn channel(&self) -> u8 {
+                match *self {
+                        NoteEvent::NoteOn { channel, .. }
+                        | NoteEvent::NoteOff { channel, .. }
+                        | NoteEvent::NoteExpression { channel, .. }
+                        | NoteEvent::ControlChange { channel, .. }
+                        | NoteEvent::PitchBend { channel, .. }
+                        | NoteEvent::ChannelPressure { channel, .. }
+                        | NoteEvent::PolyPressure { channel, .. }
+                        | NoteEvent::ProgramChange { channel, .. } => channel,
+                }
+        }
+
+        /// 弯音值（-1.0-1.0）编码为 14 位的两个数据字节
+        pub fn pitch_bend_bytes(value: f32) -> [u8; 2] {
+                let bend = ((value.clamp(-1.0, 1.0) + 1.0) * 8192.0).round().min(16383.0) as u16;
+                [(bend & 0x7f) as u8, (bend >> 7) as u8]
+        }
+
+        /// 编码为 MIDI 1.0 字节（状态字节 + 两个数据字节；只有一个数据字节的消息第三字节为 0）。
+        /// 单音符表情在普通 MIDI 中没有对应消息：压力降级为单音符触后，音高与明亮度作用于整个通道
+        /// （弯音按 `DEFAULT_PITCH_BEND_RANGE` 换算，明亮度为 CC74）；MPE 编码见 `audio::midi::mpe`。
+        pub fn to_midi_bytes(&self) -> [u8; 3] {
+                let seven_bit = |v: f32| (v.clamp(0.0, 1.0) * 127.0).round() as u8;
+                let status = |kind: u8| kind | (self.channel() & 0x0f);
+                match *self {
+                        NoteEvent::NoteOn { note, velocity, .. } => {
+                                // 力度 0 的 NoteOn 会被当作 NoteOff，最小发送 1
+                                [status(0x90), note & 0x7f, seven_bit(velocity).max(1)]
+                        }
+                        NoteEvent::NoteOff { note, .. } => [status(0x80), note & 0x7f, 0],
+                        NoteEvent::ControlChange { controller, value, .. } => {
+                                [status(0xb0), controller & 0x7f, seven_bit(value)]
+                        }
+                        NoteEvent::PitchBend { value, .. } => {
+                                let [lsb, msb] = Self::pitch_bend_bytes(value);
+                                [status(0xe0), lsb, msb]
+                        }
+                        NoteEvent::NoteExpression {
+                                note,
+                                expression,
+                                value,
+                                ..
+                        } => match expression {
+                                NoteExpressionKind::Pitch => {
+                                        let [lsb, msb] = Self::pitch_bend_bytes(value / DEFAULT_PITCH_BEND_RANGE);
+                                        [status(0xe0), lsb, msb]
+                                }
+                                NoteExpressionKind::Pressure => [status(0xa0), note & 0x7f, seven_bit(value)],
+                                NoteExpressionKind::Brightness => [status(0xb0), 74, seven_bit(value)],
+                        },
+                        NoteEvent::ChannelPressure { pressure, .. } => [status(0xd0), seven_bit(pressure), 0],
+                        NoteEvent::PolyPressure { note, pressure, .. } => {
+                                [status(0xa0), note & 0x7f, seven_bit(pressure)]
+                        }
+                        NoteEvent::ProgramChange { program, .. } => [status(0xc0), program & 0x7f, 0],
+                }
+        }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// 音频图中的参数地址：节点（乐器实例 / 混音轨道 / 插入效果）的 UUID + 该节点内的参数 ID。
+/// Tauri 命令、事件队列与自动化共用此地址，不受轨道/乐器顺序变化影响。
+pub struct ParamAddress {
+        pub node: Uuid,
+        pub param_id: u32,
+}
+
+impl ParamAddress {
+        pub fn new(node: Uuid, param_id: u32) -> Self {
+                Self { node, param_id }
+        }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// 插件运行时事件：包含 MIDI、参数变化、传输状态等
 pub enum PluginEvent {
         Midi(NoteEvent),
+        /// 带块内时间（相对于块起点的帧偏移）的 MIDI 事件，例如音序器按子块采样的单音符表情。
+        /// 不支持块内时间的插件把它当作块起点的 `Midi` 处理（见 `as_midi`）
+        MidiAt {
+                frame: u32,
+                event: NoteEvent,
+        },
+        /// 发给插件自身的参数变化（参数 ID 为插件内部 ID）
         Parameter {
                 id: u32,
                 value: f32,
         },
+        /// 发给音频图中某个节点的参数变化；由容器（Mixer）按节点分发并转换为 `Parameter`
+        NodeParameter {
+                address: ParamAddress,
+                value: f32,
+        },
         Transport {
                 // 是否正在播放
                 playing: bool,
@@ -31,27 +175,149 @@ pub enum PluginEvent {
                 // 可选节拍（BPM）
                 tempo: Option<f64>,
         },
+        /// 释放所有发声的音符；`panic` 时还向每个乐器的 16 个通道发送
+        /// All Sound Off / Reset All Controllers / All Notes Off（CC120 / 121 / 123）
+        AllNotesOff {
+                panic: bool,
+        },
+        /// 开始录音（先播放节拍器设置中的预备小节）或结束录音
+        Record {
+                recording: bool,
+        },
+        /// 节拍器设置
+        Metronome(MetronomeSettings),
+        /// 总输出安全级（限制器）设置
+        MasterSafety(SafetySettings),
+        /// 插入效果的旁通与推子前/后位置（`node` 为插入实例的节点 ID）
+        InsertState {
+                node: Uuid,
+                bypass: bool,
+                post_fader: bool,
+        },
+        /// 工程拍号
+        TimeSignature {
+                numerator: u32,
+                denominator: u32,
+        },
         #[allow(dead_code)]
         Custom(String),
 }
 
-/// 音频缓冲区借用：交错样本数组、通道数与采样率
+impl PluginEvent {
+        /// MIDI 事件及其块内帧偏移（`Midi` 为 0）
+        pub fn as_midi(&self) -> Option<(u32, &NoteEvent)> {
+                match self {
+                        PluginEvent::Midi(event) => Some((0, event)),
+                        PluginEvent::MidiAt { frame, event } => Some((*frame, event)),
+                        _ => None,
+                }
+        }
+
+        /// 若为发往 `node` 的节点参数事件，则转换为该节点内部的 `Parameter` 事件
+        pub fn for_node(&self, node: Uuid) -> Option<PluginEvent> {
+                match self {
+                        PluginEvent::NodeParameter { address, value } if address.node == node => {
+                                Some(PluginEvent::Parameter {
+                                        id: address.param_id,
+                                        value: *value,
+                                })
+                        }
+                        _ => None,
+                }
+        }
+}
+
+/// 音频缓冲区借用：平面（非交错）样本数组、通道数与采样率。
+/// 各通道的样本连续存放，通道 `c` 为 `samples[c * frames..(c + 1) * frames]`（见 `buffer` 模块）
 pub struct AudioBuffer<'a> {
         pub samples: &'a mut [f32],
         pub channels: usize,
         pub sample_rate: f32,
 }
 
+impl AudioBuffer<'_> {
+        /// 每个通道的帧数
+        pub fn frames(&self) -> usize {
+                self.samples.len() / self.channels.max(1)
+        }
+
+        /// 第 `index` 个通道的样本；超出通道数时为空
+        pub fn channel(&self, index: usize) -> &[f32] {
+                let frames = self.frames();
+                self.samples.get(index * frames..(index + 1) * frames).unwrap_or(&[])
+        }
+
+        pub fn channel_mut(&mut self, index: usize) -> &mut [f32] {
+                let frames = self.frames();
+                self.samples
+                        .get_mut(index * frames..(index + 1) * frames)
+                        .unwrap_or(&mut [])
+        }
+
+        /// 逐通道遍历
+        pub fn iter_channels(&self) -> std::slice::ChunksExact<'_, f32> {
+                self.samples.chunks_exact(self.frames().max(1))
+        }
+
+        pub fn iter_channels_mut(&mut self) -> std::slice::ChunksExactMut<'_, f32> {
+                let frames = self.frames().max(1);
+                self.samples.chunks_exact_mut(frames)
+        }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-/// 插件 I/O 通道配置
+/// 单个音频端口描述（例如主输入、侧链输入）
+pub struct AudioPortConfig {
+        pub name: String,
+        pub channels: usize,
+        // 主端口承载轨道信号；非主端口（如侧链）目前输入静音
+        pub is_main: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 插件 I/O 通道配置：`inputs`/`outputs` 为主端口通道数（0 输入表示乐器/发生器）
 pub struct IOConfig {
         pub inputs: usize,
         pub outputs: usize,
+        #[serde(default)]
+        pub input_ports: Vec<AudioPortConfig>,
+        #[serde(default)]
+        pub output_ports: Vec<AudioPortConfig>,
+}
+
+impl IOConfig {
+        /// 由通道数构造仅含主端口的配置
+        pub fn simple(inputs: usize, outputs: usize) -> Self {
+                let port = |name: &str, channels: usize| AudioPortConfig {
+                        name: name.to_string(),
+                        channels,
+                        is_main: true,
+                };
+                Self {
+                        inputs,
+                        outputs,
+                        input_ports: if inputs > 0 {
+                                vec![port("Input", inputs)]
+                        } else {
+                                Vec::new()
+                        },
+                        output_ports: if outputs > 0 {
+                                vec![port("Output", outputs)]
+                        } else {
+                                Vec::new()
+                        },
+                }
+        }
+
+        /// 是否需要音频输入（效果器）；否则视为乐器/发生器
+        pub fn has_audio_input(&self) -> bool {
+                self.inputs > 0
+        }
 }
 
 impl Default for IOConfig {
         fn default() -> Self {
-                Self { inputs: 2, outputs: 2 }
+                Self::simple(2, 2)
         }
 }
 
@@ -72,8 +338,53 @@ pub enum ParameterType {
         Enum(Vec<String>),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+/// 参数曲线：决定归一化位置（0..1，即推子/旋钮行程）与实际值之间的映射
+pub enum ParameterTaper {
+        #[default]
+        Linear,
+        /// 对数曲线（要求 min > 0，例如频率）；否则按线性处理
+        Logarithmic,
+        /// 幂曲线：位置 = 线性位置 ^ skew；skew < 1 时低端占用更多行程
+        Skew(f32),
+}
+
+impl ParameterTaper {
+        /// 由 manifest / info JSON 中的 `taper`（"linear" | "log" | "skew"）与 `skew` 字段解析
+        pub fn parse(taper: Option<&str>, skew: Option<f32>) -> Self {
+                match (taper, skew) {
+                        (Some("log") | Some("logarithmic"), _) => ParameterTaper::Logarithmic,
+                        (Some("linear"), _) => ParameterTaper::Linear,
+                        (_, Some(s)) if s > 0.0 && s != 1.0 => ParameterTaper::Skew(s),
+                        _ => ParameterTaper::Linear,
+                }
+        }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+/// 参数标志
+pub struct ParameterFlags {
+        /// 可被自动化
+        pub automatable: bool,
+        /// 只读（例如插件报告的增益衰减量），UI 不允许修改
+        pub read_only: bool,
+        /// 不在通用参数界面中显示
+        pub hidden: bool,
+}
+
+impl Default for ParameterFlags {
+        fn default() -> Self {
+                Self {
+                        automatable: true,
+                        read_only: false,
+                        hidden: false,
+                }
+        }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-/// 单参数描述：ID、名称、范围与类型
+/// 单参数描述：ID、名称、范围与类型，以及单位、曲线、步进、标志与分组等显示元数据
 pub struct PluginParameter {
         pub id: u32,
         pub name: String,
@@ -81,6 +392,181 @@ pub struct PluginParameter {
         pub max_value: f32,
         pub default_value: f32,
         pub value_type: ParameterType,
+        /// 显示单位（例如 "dB"、"Hz"、"%"、"ms"），用于格式化与解析文本
+        #[serde(default)]
+        pub unit: String,
+        #[serde(default)]
+        pub taper: ParameterTaper,
+        /// 离散步数（0 表示连续或由类型推导，见 `steps`）
+        #[serde(default)]
+        pub step_count: u32,
+        #[serde(default)]
+        pub flags: ParameterFlags,
+        /// 参数分组（可用 "/" 表示层级，例如 "Filter/Envelope"），空字符串表示不分组
+        #[serde(default)]
+        pub group: String,
+}
+
+impl PluginParameter {
+        /// 以默认元数据（无单位、线性、连续、可自动化、不分组）构造参数描述
+        pub fn new(
+                id: u32,
+                name: &str,
+                min_value: f32,
+                max_value: f32,
+                default_value: f32,
+                value_type: ParameterType,
+        ) -> Self {
+                Self {
+                        id,
+                        name: name.to_string(),
+                        min_value,
+                        max_value,
+                        default_value,
+                        value_type,
+                        unit: String::new(),
+                        taper: ParameterTaper::Linear,
+                        step_count: 0,
+                        flags: ParameterFlags::default(),
+                        group: String::new(),
+                }
+        }
+
+        /// 离散步数：显式的 `step_count` 优先，否则由类型推导（Float 为 0，即连续）
+        pub fn steps(&self) -> u32 {
+                if self.step_count > 0 {
+                        return self.step_count;
+                }
+                match &self.value_type {
+                        ParameterType::Float => 0,
+                        ParameterType::Bool => 1,
+                        ParameterType::Int => (self.max_value - self.min_value).round().max(0.0) as u32,
+                        ParameterType::Enum(labels) => labels.len().saturating_sub(1) as u32,
+                }
+        }
+
+        /// 实际值 -> 归一化位置（0..1）
+        pub fn to_normalized(&self, value: f32) -> f32 {
+                let range = self.max_value - self.min_value;
+                if range <= 0.0 {
+                        return 0.0;
+                }
+                let linear = ((value - self.min_value) / range).clamp(0.0, 1.0);
+                match self.taper {
+                        ParameterTaper::Linear => linear,
+                        ParameterTaper::Logarithmic if self.min_value > 0.0 => {
+                                (value.max(self.min_value) / self.min_value).ln()
+                                        / (self.max_value / self.min_value).ln()
+                        }
+                        ParameterTaper::Logarithmic => linear,
+                        ParameterTaper::Skew(skew) => linear.powf(skew),
+                }
+                .clamp(0.0, 1.0)
+        }
+
+        /// 归一化位置（0..1）-> 实际值，并按步进量化
+        pub fn from_normalized(&self, position: f32) -> f32 {
+                let position = position.clamp(0.0, 1.0);
+                let range = self.max_value - self.min_value;
+                let value = match self.taper {
+                        ParameterTaper::Logarithmic if self.min_value > 0.0 => {
+                                self.min_value * (self.max_value / self.min_value).powf(position)
+                        }
+                        ParameterTaper::Skew(skew) => self.min_value + range * position.powf(1.0 / skew),
+                        _ => self.min_value + range * position,
+                };
+                self.quantize(value)
+        }
+
+        /// 限制到参数范围并对齐到最近的步进（整数 / 布尔 / 枚举参数取整）
+        pub fn quantize(&self, value: f32) -> f32 {
+                let value = value.clamp(self.min_value, self.max_value);
+                let steps = self.steps();
+                let range = self.max_value - self.min_value;
+                if steps == 0 || range <= 0.0 {
+                        return value;
+                }
+                let step = range / steps as f32;
+                self.min_value + ((value - self.min_value) / step).round() * step
+        }
+
+        /// 按元数据把值格式化为显示文本，例如 "-6.0 dB"、"1.2 kHz"、"250 ms"
+        pub fn format_value(&self, value: f32) -> String {
+                match &self.value_type {
+                        ParameterType::Enum(labels) => {
+                                let index = (value - self.min_value).round().max(0.0) as usize;
+                                labels.get(index)
+                                        .cloned()
+                                        .unwrap_or_else(|| format!("{}", value.round()))
+                        }
+                        ParameterType::Bool => if value >= 0.5 { "On" } else { "Off" }.to_string(),
+                        ParameterType::Int => with_unit(format!("{}", value.round() as i64), &self.unit),
+                        ParameterType::Float => match self.unit.as_str() {
+                                "dB" if value == f32::NEG_INFINITY => "-inf dB".to_string(),
+                                "dB" => format!("{:.1} dB", value),
+                                "Hz" if value.abs() >= 1000.0 => format!("{:.1} kHz", value / 1000.0),
+                                "Hz" => format!("{} Hz", format_significant(value)),
+                                "ms" if value.abs() >= 1000.0 => format!("{:.2} s", value / 1000.0),
+                                "ms" => format!("{:.1} ms", value),
+                                "%" => format!("{:.0}%", value),
+                                unit => with_unit(format!("{:.2}", value), unit),
+                        },
+                }
+        }
+
+        /// 解析用户输入的文本（可带单位、"k" 前缀或枚举项名称），结果限制在参数范围内
+        pub fn parse_value(&self, text: &str) -> Option<f32> {
+                let text = text.trim();
+                match &self.value_type {
+                        ParameterType::Enum(labels) => {
+                                if let Some(index) = labels.iter().position(|l| l.eq_ignore_ascii_case(text)) {
+                                        return Some(self.min_value + index as f32);
+                                }
+                        }
+                        ParameterType::Bool => match text.to_ascii_lowercase().as_str() {
+                                "on" | "true" | "yes" => return Some(1.0),
+                                "off" | "false" | "no" => return Some(0.0),
+                                _ => {}
+                        },
+                        _ => {}
+                }
+
+                let lower = text.to_ascii_lowercase();
+                if self.unit == "dB" && (lower.starts_with("-inf") || lower.starts_with("-∞")) {
+                        return Some(self.min_value);
+                }
+                let split = lower
+                        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+                        .unwrap_or(lower.len());
+                let number: f32 = lower[..split].trim().parse().ok()?;
+                let suffix = lower[split..].trim();
+                let scale = match suffix {
+                        "k" | "khz" => 1000.0,
+                        "s" if self.unit == "ms" => 1000.0,
+                        _ => 1.0,
+                };
+                Some(self.quantize(number * scale))
+        }
+}
+
+fn with_unit(number: String, unit: &str) -> String {
+        if unit.is_empty() {
+                number
+        } else {
+                format!("{} {}", number, unit)
+        }
+}
+
+// 小数位随数量级减少：0.25 / 2.5 / 25 / 250
+fn format_significant(value: f32) -> String {
+        let magnitude = value.abs();
+        if magnitude < 10.0 {
+                format!("{:.2}", value)
+        } else if magnitude < 100.0 {
+                format!("{:.1}", value)
+        } else {
+                format!("{:.0}", value)
+        }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -117,10 +603,58 @@ pub trait Plugin: Send + Sync {
                 IOConfig::default()
         }
 
-        /// 核心处理：就地修改 `buffer.samples`，并可读取 `events`、产生 `output_events`
+        /// 可选：插件能按 `note_id` 区分同一 (通道, 音高) 上重叠的音符。
+        /// 不支持时宿主把同音高重叠音符的 NoteOff 合并到最后一个音符结束时发送
+        fn supports_note_ids(&self) -> bool {
+                false
+        }
+
+        /// 可选：处理延迟（采样数），宿主据此做延迟补偿
+        fn latency(&self) -> u32 {
+                0
+        }
+
+        /// 可选：附加（非主）输入端口的缓冲区，下标为 `get_io_config().input_ports` 中非主端口的顺序。
+        /// 支持多端口的宿主（如 JACK）在 `process` 之前写入 `len` 个平面样本（与主缓冲区布局相同）
+        fn aux_input_mut(&mut self, _index: usize, _len: usize) -> Option<&mut [f32]> {
+                None
+        }
+
+        /// 可选：附加（非主）输出端口在最近一次 `process` 中产生的平面样本，下标规则同 `aux_input_mut`
+        fn aux_output(&self, _index: usize) -> Option<&[f32]> {
+                None
+        }
+
+        /// 核心处理：就地修改 `buffer` 的各通道样本，并可读取 `events`、产生 `output_events`
         fn process(&mut self, buffer: &mut AudioBuffer, events: &[PluginEvent], output_events: &mut Vec<PluginEvent>);
 
         /// 参数访问接口
         fn get_param(&self, id: u32) -> f32;
         fn set_param(&mut self, id: u32, value: f32);
+
+        /// 可选：由插件把参数值格式化为显示文本；返回 None 时宿主按参数元数据格式化
+        fn param_value_to_text(&self, _id: u32, _value: f32) -> Option<String> {
+                None
+        }
+
+        /// 可选：由插件把文本解析为参数值；返回 None 时宿主按参数元数据解析
+        fn param_text_to_value(&self, _id: u32, _text: &str) -> Option<f32> {
+                None
+        }
+
+        /// 可选：立即采用当前参数值，结束进行中的参数渐变等过渡状态
+        fn reset(&mut self) {}
+
+        /// 可选：主线程维护回调（由主线程泵周期性调用，用于处理插件发起的回调/重启/重新扫描请求）
+        fn on_main_thread(&mut self) {}
+
+        /// 可选：插件自上次查询以来是否标记了状态已修改（读取后清除）
+        fn take_state_dirty(&mut self) -> bool {
+                false
+        }
+
+        /// 可选：插件已崩溃/失去响应时返回一次失败描述（读取后清除），供主线程报告给 UI
+        fn take_crash_report(&mut self) -> Option<String> {
+                None
+        }
 }
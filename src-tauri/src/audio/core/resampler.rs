@@ -0,0 +1,155 @@
+use crate::audio::core::buffer::PlanarBuffer;
+use std::f64::consts::PI;
+
+// 设备边界的采样率转换：工程采样率与设备采样率不同时，音频图以工程采样率运行，
+// 输出端按需拉取工程采样率的音频块，并用加窗 sinc 插值转换为设备采样率。
+// 降采样时截止频率随比例降低以避免混叠。插值引入 HALF_TAPS - 1 帧（工程采样率）的固定延迟。
+// 待插值的输入存放在创建时预分配的环形缓冲中，音频线程上的处理不分配内存。
+
+// 插值核单侧的抽头数
+const HALF_TAPS: usize = 16;
+const TAPS: usize = HALF_TAPS * 2;
+// 预计算的分数相位数（相邻相位之间线性插值）
+const PHASES: usize = 256;
+// 截止频率相对奈奎斯特频率的比例，为有限长度的插值核留出过渡带
+const ROLLOFF: f64 = 0.95;
+// 单次插值的最大输出帧数；更长的设备块分段处理
+const MAX_BLOCK_FRAMES: usize = 8192;
+
+pub struct Resampler {
+        channels: usize,
+        // 每个输出帧在输入中前进的帧数（源采样率 / 目标采样率）
+        ratio: f64,
+        // 下一个输出帧相对最早未消费输入帧的位置
+        position: f64,
+        // 尚未消费的源采样率样本：每通道一段 2 × capacity 的镜像环形缓冲，
+        // 每个样本同时写入 i 与 i + capacity，任意位置起的插值窗口都是连续切片
+        ring: Vec<f32>,
+        capacity: usize,
+        // 最早未消费输入帧在环中的位置，以及未消费的帧数
+        start: usize,
+        available: usize,
+        // 从音频图拉取的源采样率块（按最大块预分配）
+        block: PlanarBuffer,
+        // 插值核：PHASES + 1 个相位 × TAPS 个抽头
+        kernel: Vec<f32>,
+}
+
+impl Resampler {
+        pub fn new(source_rate: f64, target_rate: f64, channels: usize) -> Self {
+                let ratio = source_rate / target_rate;
+                let cutoff = ratio.recip().min(1.0) * ROLLOFF;
+                let mut kernel = Vec::with_capacity((PHASES + 1) * TAPS);
+                for phase in 0..=PHASES {
+                        let fraction = phase as f64 / PHASES as f64;
+                        let row: Vec<f64> = (0..TAPS)
+                                .map(|tap| {
+                                        // 抽头相对插值位置的距离（输入帧）
+                                        let x = tap as f64 - (HALF_TAPS - 1) as f64 - fraction;
+                                        let t = (x / HALF_TAPS as f64 + 1.0) / 2.0;
+                                        let window = 0.42 - 0.5 * (2.0 * PI * t).cos() + 0.08 * (4.0 * PI * t).cos();
+                                        let sinc = if x == 0.0 {
+                                                1.0
+                                        } else {
+                                                (PI * cutoff * x).sin() / (PI * cutoff * x)
+                                        };
+                                        sinc * window.max(0.0)
+                                })
+                                .collect();
+                        // 每个相位的增益归一化为 1，保证直流不变
+                        let sum: f64 = row.iter().sum();
+                        kernel.extend(row.iter().map(|w| (w / sum) as f32));
+                }
+                // 一个最大块所需的输入加上插值核两侧的历史
+                let max_render = (MAX_BLOCK_FRAMES as f64 * ratio).ceil() as usize + TAPS + 2;
+                let capacity = max_render + TAPS;
+                Self {
+                        channels,
+                        ratio,
+                        position: (HALF_TAPS - 1) as f64,
+                        // 起始的 HALF_TAPS - 1 帧静音作为插值核左侧的历史
+                        ring: vec![0.0; channels * 2 * capacity],
+                        capacity,
+                        start: 0,
+                        available: HALF_TAPS - 1,
+                        block: PlanarBuffer::new(channels, max_render),
+                        kernel,
+                }
+        }
+
+        /// 以目标采样率填充平面 `output`（`channels` 个通道）。输入不足时调用 `render`
+        /// （每 `MAX_BLOCK_FRAMES` 个输出帧至多一次），由它把源采样率的音频写入传入的平面缓冲（已按所需帧数清零）。
+        pub fn process(&mut self, output: &mut [f32], mut render: impl FnMut(&mut PlanarBuffer)) {
+                let channels = self.channels;
+                if channels == 0 {
+                        return;
+                }
+                let frames = output.len() / channels;
+                let mut offset = 0;
+                while offset < frames {
+                        let chunk = (frames - offset).min(MAX_BLOCK_FRAMES);
+                        self.process_chunk(output, frames, offset, chunk, &mut render);
+                        offset += chunk;
+                }
+        }
+
+        // 填充每个通道 [offset, offset + chunk) 的输出帧（`output` 每通道 `frames` 帧）
+        fn process_chunk(
+                &mut self,
+                output: &mut [f32],
+                frames: usize,
+                offset: usize,
+                chunk: usize,
+                render: &mut impl FnMut(&mut PlanarBuffer),
+        ) {
+                let channels = self.channels;
+                let span = 2 * self.capacity;
+
+                // 本块最后一个输出帧需要的输入范围
+                let last = self.position + (chunk - 1) as f64 * self.ratio;
+                let needed = last as usize + HALF_TAPS + 1;
+                if needed > self.available {
+                        // 容量按最大块预分配，resize 不会分配
+                        self.block.resize(channels, needed - self.available);
+                        render(&mut self.block);
+                        for c in 0..channels {
+                                let ring = &mut self.ring[c * span..(c + 1) * span];
+                                for (i, sample) in self.block.channel(c).iter().enumerate() {
+                                        let index = (self.start + self.available + i) % self.capacity;
+                                        ring[index] = *sample;
+                                        ring[index + self.capacity] = *sample;
+                                }
+                        }
+                        self.available = needed;
+                }
+
+                for (c, out) in output.chunks_exact_mut(frames).enumerate() {
+                        let ring = &self.ring[c * span..(c + 1) * span];
+                        for (i, sample) in out[offset..offset + chunk].iter_mut().enumerate() {
+                                let position = self.position + i as f64 * self.ratio;
+                                let base = position as usize;
+                                let phase = (position - base as f64) * PHASES as f64;
+                                let index = phase as usize;
+                                let t = (phase - index as f64) as f32;
+                                let (a, b) = (
+                                        &self.kernel[index * TAPS..(index + 1) * TAPS],
+                                        &self.kernel[(index + 1) * TAPS..(index + 2) * TAPS],
+                                );
+                                let first = (self.start + base + 1 - HALF_TAPS) % self.capacity;
+                                let input = &ring[first..first + TAPS];
+                                let mut sum = 0.0;
+                                for ((s, wa), wb) in input.iter().zip(a).zip(b) {
+                                        sum += s * (wa + (wb - wa) * t);
+                                }
+                                *sample = sum;
+                        }
+                }
+
+                // 丢弃之后不再需要的输入，保留插值核左侧所需的历史
+                self.position += chunk as f64 * self.ratio;
+                let consumed = (self.position as usize + 1).saturating_sub(HALF_TAPS);
+                self.start = (self.start + consumed) % self.capacity;
+                self.available -= consumed;
+                self.position -= consumed as f64;
+        }
+}
@@ -0,0 +1,328 @@
+use crate::audio::core::buffer::PlanarBuffer;
+use crate::audio::core::channel_layout::MAX_CHANNELS;
+use crate::audio::core::plugin::{AudioBuffer, Plugin, PluginEvent, PluginParameter};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU32, Ordering};
+
+// 宿主侧参数平滑：推子/声像等参数变化在一段时间内渐变到目标值，避免拉链噪声（zipper noise）。
+// 平滑配置为全局设置（原子变量），音频线程在每次参数变化时读取。
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// 渐变曲线
+pub enum SmoothingMode {
+        /// 线性：在平滑时间内匀速到达目标
+        Linear,
+        /// 指数（一阶低通）：平滑时间结束时与目标的差距降至 -60 dB 并对齐到目标
+        Exponential,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// 参数平滑设置
+pub struct SmoothingConfig {
+        pub mode: SmoothingMode,
+        /// 平滑时间（毫秒），0 表示立即跳变
+        pub time_ms: f32,
+        /// 是否把插件（乐器/插入效果）的参数变化也拆分为子块斜坡送达
+        pub ramp_plugin_params: bool,
+}
+
+impl Default for SmoothingConfig {
+        fn default() -> Self {
+                Self {
+                        mode: SmoothingMode::Linear,
+                        time_ms: 20.0,
+                        ramp_plugin_params: false,
+                }
+        }
+}
+
+static SMOOTHING_MODE: AtomicU8 = AtomicU8::new(0); // 0 = Linear, 1 = Exponential
+static SMOOTHING_TIME_BITS: AtomicU32 = AtomicU32::new(0x41a0_0000); // 20.0f32
+static RAMP_PLUGIN_PARAMS: AtomicBool = AtomicBool::new(false);
+
+/// 当前的全局平滑设置
+pub fn smoothing_config() -> SmoothingConfig {
+        SmoothingConfig {
+                mode: match SMOOTHING_MODE.load(Ordering::Relaxed) {
+                        1 => SmoothingMode::Exponential,
+                        _ => SmoothingMode::Linear,
+                },
+                time_ms: f32::from_bits(SMOOTHING_TIME_BITS.load(Ordering::Relaxed)),
+                ramp_plugin_params: RAMP_PLUGIN_PARAMS.load(Ordering::Relaxed),
+        }
+}
+
+/// 更新全局平滑设置（对之后的参数变化生效）
+pub fn set_smoothing_config(config: SmoothingConfig) {
+        let mode = match config.mode {
+                SmoothingMode::Linear => 0,
+                SmoothingMode::Exponential => 1,
+        };
+        SMOOTHING_MODE.store(mode, Ordering::Relaxed);
+        SMOOTHING_TIME_BITS.store(config.time_ms.max(0.0).to_bits(), Ordering::Relaxed);
+        RAMP_PLUGIN_PARAMS.store(config.ramp_plugin_params, Ordering::Relaxed);
+}
+
+/// 单个平滑参数值
+#[derive(Debug, Clone)]
+pub struct SmoothedValue {
+        current: f32,
+        target: f32,
+        mode: SmoothingMode,
+        // 线性模式每采样的增量
+        step: f32,
+        // 指数模式每采样的衰减系数
+        coeff: f32,
+        // 剩余的渐变采样数
+        remaining: u32,
+}
+
+impl SmoothedValue {
+        pub fn new(value: f32) -> Self {
+                Self {
+                        current: value,
+                        target: value,
+                        mode: SmoothingMode::Linear,
+                        step: 0.0,
+                        coeff: 0.0,
+                        remaining: 0,
+                }
+        }
+
+        /// 立即跳到指定值（不渐变）
+        pub fn reset(&mut self, value: f32) {
+                *self = Self::new(value);
+        }
+
+        /// 设置新的目标值，按配置从当前值开始渐变
+        pub fn set_target(&mut self, target: f32, config: SmoothingConfig, sample_rate: f32) {
+                let samples = (config.time_ms * sample_rate / 1000.0).round();
+                if samples < 1.0 || target == self.current {
+                        self.reset(target);
+                        return;
+                }
+                self.target = target;
+                self.mode = config.mode;
+                self.remaining = samples as u32;
+                self.step = (target - self.current) / samples;
+                self.coeff = (0.001f32.ln() / samples).exp();
+        }
+
+        pub fn is_smoothing(&self) -> bool {
+                self.remaining > 0
+        }
+
+        pub fn target(&self) -> f32 {
+                self.target
+        }
+
+        /// 前进一个采样并返回该采样的值
+        #[inline]
+        pub fn next_value(&mut self) -> f32 {
+                self.advance(1)
+        }
+
+        /// 前进 `samples` 个采样并返回到达的值（用于按子块更新参数）
+        pub fn advance(&mut self, samples: u32) -> f32 {
+                if self.remaining == 0 {
+                        return self.current;
+                }
+                if samples >= self.remaining {
+                        self.reset(self.target);
+                        return self.current;
+                }
+                self.remaining -= samples;
+                self.current = match self.mode {
+                        SmoothingMode::Linear => self.current + self.step * samples as f32,
+                        SmoothingMode::Exponential => {
+                                self.target + (self.current - self.target) * self.coeff.powi(samples as i32)
+                        }
+                };
+                self.current
+        }
+}
+
+// 斜坡子块长度（帧）：插件参数在子块边界更新
+pub const RAMP_CHUNK_FRAMES: usize = 32;
+// 每块事件缓冲的预留容量
+const RAMP_EVENT_CAPACITY: usize = 256;
+
+// 斜坡分配内部子块缓冲时预留的通道数
+const RAMP_CHUNK_CHANNELS: usize = MAX_CHANNELS;
+
+// 单个可自动化参数的斜坡状态
+#[derive(Debug, Clone)]
+struct RampSlot {
+        id: u32,
+        smoother: SmoothedValue,
+        // 已收到过该参数的变化；宿主不知道参数的当前值，因此首个变化直接送达
+        seen: bool,
+}
+
+/// 为插件生成参数斜坡：启用 `ramp_plugin_params` 时，把 `Parameter` 事件转换为平滑值，
+/// 并把音频块拆分为若干子块，在每个子块之前写入插值后的参数值。
+/// 平面缓冲的子块在内存中不连续，因此子块先复制到内部缓冲处理后再写回；一旦开始拆分，
+/// 该块剩余部分都按不超过 RAMP_CHUNK_FRAMES 帧（通道多于 MAX_CHANNELS 时按比例更短）的子块处理，
+/// 内部缓冲不会超出创建时预留的大小。
+/// 参数首次出现时宿主不知道其当前值，因此首个变化直接送达，之后的变化才渐变。
+/// 每个可自动化参数的平滑状态在 `for_parameters` 中（音频线程之外）一次性分配，
+/// 音频线程中只按参数 ID 查找；不在列表中的参数（只读、不可自动化或未声明）直接送达，不做渐变。
+pub struct ParamRamper {
+        // 按参数 ID 排序
+        slots: Vec<RampSlot>,
+        // 是否有参数收到过变化（关闭斜坡后无需遍历 slots）
+        active: bool,
+        events: Vec<PluginEvent>,
+        // 当前子块的事件（带块内时间的事件换算为子块内的偏移）
+        chunk_events: Vec<PluginEvent>,
+        chunk: PlanarBuffer,
+}
+
+impl Default for ParamRamper {
+        fn default() -> Self {
+                Self::new()
+        }
+}
+
+impl ParamRamper {
+        pub fn new() -> Self {
+                let mut chunk = PlanarBuffer::default();
+                chunk.resize(RAMP_CHUNK_CHANNELS, RAMP_CHUNK_FRAMES);
+                Self {
+                        slots: Vec::new(),
+                        active: false,
+                        events: Vec::with_capacity(RAMP_EVENT_CAPACITY),
+                        chunk_events: Vec::with_capacity(RAMP_EVENT_CAPACITY),
+                        chunk,
+                }
+        }
+
+        /// 内部子块缓冲的容量（样本数），创建后不再增长
+        pub fn chunk_capacity(&self) -> usize {
+                self.chunk.capacity()
+        }
+
+        /// 为 `parameters` 中可自动化、非只读的参数预先分配平滑状态（在音频线程之外调用）
+        pub fn for_parameters(parameters: &[PluginParameter]) -> Self {
+                let mut ramper = Self::new();
+                ramper.slots = parameters
+                        .iter()
+                        .filter(|p| p.flags.automatable && !p.flags.read_only)
+                        .map(|p| RampSlot {
+                                id: p.id,
+                                smoother: SmoothedValue::new(p.default_value),
+                                seen: false,
+                        })
+                        .collect();
+                ramper.slots.sort_by_key(|slot| slot.id);
+                ramper.slots.dedup_by_key(|slot| slot.id);
+                ramper
+        }
+
+        pub fn process(
+                &mut self,
+                plugin: &mut dyn Plugin,
+                buffer: &mut AudioBuffer,
+                events: &[PluginEvent],
+                output_events: &mut Vec<PluginEvent>,
+        ) {
+                let config = smoothing_config();
+                if !config.ramp_plugin_params {
+                        if self.active {
+                                // 关闭斜坡：让进行中的渐变直接到达目标
+                                for slot in self.slots.iter_mut().filter(|slot| slot.seen) {
+                                        if slot.smoother.is_smoothing() {
+                                                plugin.set_param(slot.id, slot.smoother.target());
+                                        }
+                                        slot.seen = false;
+                                }
+                                self.active = false;
+                        }
+                        plugin.process(buffer, events, output_events);
+                        return;
+                }
+
+                self.events.clear();
+                for event in events {
+                        let PluginEvent::Parameter { id, value } = event else {
+                                self.events.push(event.clone());
+                                continue;
+                        };
+                        match self.slots.binary_search_by_key(id, |slot| slot.id) {
+                                Ok(index) if self.slots[index].seen => {
+                                        self.slots[index]
+                                                .smoother
+                                                .set_target(*value, config, buffer.sample_rate)
+                                }
+                                Ok(index) => {
+                                        let slot = &mut self.slots[index];
+                                        slot.smoother.reset(*value);
+                                        slot.seen = true;
+                                        self.active = true;
+                                        self.events.push(event.clone());
+                                }
+                                Err(_) => self.events.push(event.clone()),
+                        }
+                }
+
+                let channels = buffer.channels.max(1);
+                let frames = buffer.frames();
+                // 预留的 RAMP_CHUNK_CHANNELS × RAMP_CHUNK_FRAMES 个样本能容纳的子块长度
+                let chunk_frames = (RAMP_CHUNK_CHANNELS * RAMP_CHUNK_FRAMES / channels).clamp(1, RAMP_CHUNK_FRAMES);
+                let mut offset = 0;
+                while offset < frames {
+                        let ramping = self.slots.iter().any(|slot| slot.smoother.is_smoothing());
+                        // 没有进行中的渐变时整块原地处理；块已拆分时剩余部分继续按子块处理
+                        let len = if ramping || offset > 0 {
+                                chunk_frames.min(frames - offset)
+                        } else {
+                                frames
+                        };
+                        if ramping {
+                                for slot in self.slots.iter_mut() {
+                                        if slot.smoother.is_smoothing() {
+                                                plugin.set_param(slot.id, slot.smoother.advance(len as u32));
+                                        }
+                                }
+                        }
+                        if len == frames {
+                                plugin.process(buffer, &self.events, output_events);
+                                break;
+                        }
+                        // 没有块内时间的事件随第一个子块送达；`MidiAt` 送往包含其帧的子块（超出块长的归入最后一个子块）
+                        self.chunk_events.clear();
+                        let last = offset + len >= frames;
+                        for event in &self.events {
+                                match event {
+                                        PluginEvent::MidiAt { frame, event } => {
+                                                let frame = *frame as usize;
+                                                if frame >= offset && (frame < offset + len || last) {
+                                                        self.chunk_events.push(PluginEvent::MidiAt {
+                                                                frame: (frame - offset).min(len - 1) as u32,
+                                                                event: *event,
+                                                        });
+                                                }
+                                        }
+                                        _ if offset == 0 => self.chunk_events.push(event.clone()),
+                                        _ => {}
+                                }
+                        }
+                        // channels × len 不超过预留的大小，resize 不会重新分配
+                        self.chunk.resize(channels, len);
+                        for c in 0..channels {
+                                self.chunk
+                                        .channel_mut(c)
+                                        .copy_from_slice(&buffer.channel(c)[offset..offset + len]);
+                        }
+                        plugin.process(
+                                &mut self.chunk.as_audio_buffer(buffer.sample_rate),
+                                &self.chunk_events,
+                                output_events,
+                        );
+                        for c in 0..channels {
+                                buffer.channel_mut(c)[offset..offset + len].copy_from_slice(self.chunk.channel(c));
+                        }
+                        offset += len;
+                }
+        }
+}
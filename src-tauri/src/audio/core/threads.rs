@@ -0,0 +1,47 @@
+use std::cell::Cell;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::ThreadId;
+
+// 线程身份与主线程回调调度：供插件宿主（例如 CLAP 的 thread-check 扩展）判断当前线程，
+// 并让音频线程/插件回调以无锁方式请求主线程泵（pump）执行一次维护工作。
+
+static MAIN_THREAD: OnceLock<ThreadId> = OnceLock::new();
+
+// 插件请求主线程回调时置位；由主线程泵读取并清除
+static MAIN_THREAD_PUMP_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+        static IS_AUDIO_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
+/// 将调用线程登记为主线程（应在应用启动时于 UI 线程调用一次）
+pub fn register_main_thread() {
+        let _ = MAIN_THREAD.set(std::thread::current().id());
+}
+
+pub fn is_main_thread() -> bool {
+        MAIN_THREAD
+                .get()
+                .map(|id| *id == std::thread::current().id())
+                .unwrap_or(false)
+}
+
+/// 将调用线程标记为音频线程（在音频回调内调用，开销仅为一次 TLS 写入）
+pub fn mark_audio_thread() {
+        IS_AUDIO_THREAD.with(|flag| flag.set(true));
+}
+
+pub fn is_audio_thread() -> bool {
+        IS_AUDIO_THREAD.with(|flag| flag.get())
+}
+
+/// 请求主线程泵尽快运行（可在任意线程调用）
+pub fn request_main_thread_pump() {
+        MAIN_THREAD_PUMP_REQUESTED.store(true, Ordering::Release);
+}
+
+/// 读取并清除主线程泵请求标志
+pub fn take_main_thread_pump_request() -> bool {
+        MAIN_THREAD_PUMP_REQUESTED.swap(false, Ordering::AcqRel)
+}
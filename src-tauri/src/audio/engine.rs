@@ -1,24 +1,124 @@
-use crate::audio::core::plugin::{AudioBuffer, Plugin, PluginEvent};
+use crate::audio::core::buffer::PlanarBuffer;
+use crate::audio::core::plugin::{Plugin, PluginEvent};
+use crate::audio::core::resampler::Resampler;
+use crate::audio::core::threads;
+#[cfg(target_os = "linux")]
+use crate::audio::jack::JackClient;
+use crate::audio::jack::JackSettings;
+use crate::daw::sequencer::set_device_latency;
 use anyhow::Result;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use crossbeam_channel::{Receiver, Sender, unbounded};
+use crossbeam_channel::{Receiver, Sender, bounded, unbounded};
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "linux")]
+use std::collections::HashMap;
+use std::time::Duration;
+
+// 停止前等待音频图释放发声音符的最长时间
+const RELEASE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// 音频后端：系统默认输出设备（cpal）或 JACK
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AudioBackend {
+        #[default]
+        Cpal,
+        Jack(JackSettings),
+}
+
+// 正在运行的后端
+enum Output {
+        // 持有流即保持播放，Drop 时停止
+        #[allow(dead_code)]
+        Cpal(cpal::Stream),
+        #[cfg(target_os = "linux")]
+        Jack(JackClient),
+}
 
 pub struct AudioEngine {
-        // 当前输出流；`None` 表示未启动
-        stream: Option<cpal::Stream>,
+        backend: AudioBackend,
+        // 工程采样率；与设备采样率不同时在设备边界重采样，`None` 表示跟随设备
+        sample_rate: Option<u32>,
+        // 当前输出；`None` 表示未启动
+        stream: Option<Output>,
         // 发送到音频回调线程的插件事件通道
         command_sender: Option<Sender<PluginEvent>>,
+        // 音频回调处理完“全部音符关闭”后的应答（见 `release_notes`）
+        release_ack: Option<Receiver<()>>,
+        // 上一个 JACK 客户端关闭时的端口连接，重建音频图后恢复
+        #[cfg(target_os = "linux")]
+        jack_connections: HashMap<String, Vec<String>>,
 }
 
 impl AudioEngine {
         pub fn new() -> Self {
                 Self {
+                        backend: AudioBackend::default(),
+                        sample_rate: None,
                         stream: None,
                         command_sender: None,
+                        release_ack: None,
+                        #[cfg(target_os = "linux")]
+                        jack_connections: HashMap::new(),
                 }
         }
 
-        pub fn start(&mut self, mut plugin: Box<dyn Plugin>) -> Result<()> {
+        pub fn backend(&self) -> &AudioBackend {
+                &self.backend
+        }
+
+        /// 选择后端；在下一次 `start` 时生效
+        pub fn set_backend(&mut self, backend: AudioBackend) {
+                self.backend = backend;
+        }
+
+        pub fn sample_rate(&self) -> Option<u32> {
+                self.sample_rate
+        }
+
+        /// 设置工程采样率；在下一次 `start` 时生效
+        pub fn set_sample_rate(&mut self, sample_rate: Option<u32>) {
+                self.sample_rate = sample_rate.filter(|rate| *rate > 0);
+        }
+
+        pub fn start(&mut self, plugin: Box<dyn Plugin>) -> Result<()> {
+                // 创建事件通道：主线程可通过 `send_event` 发送事件到音频回调
+                let (tx, rx): (Sender<PluginEvent>, Receiver<PluginEvent>) = unbounded();
+                // 容量为 1 的有界通道预先分配，音频线程上的 try_send 不会分配也不会阻塞
+                let (ack_tx, ack_rx) = bounded(1);
+
+                let output = match &self.backend {
+                        AudioBackend::Cpal => Output::Cpal(self.start_cpal(plugin, rx, ack_tx)?),
+                        #[cfg(target_os = "linux")]
+                        AudioBackend::Jack(settings) => Output::Jack(
+                                JackClient::start(
+                                        settings,
+                                        plugin,
+                                        self.sample_rate,
+                                        rx,
+                                        ack_tx,
+                                        &self.jack_connections,
+                                )
+                                .map_err(anyhow::Error::msg)?,
+                        ),
+                        #[cfg(not(target_os = "linux"))]
+                        AudioBackend::Jack(_) => {
+                                return Err(anyhow::anyhow!("JACK backend is only supported on Linux"));
+                        }
+                };
+                self.command_sender = Some(tx);
+                self.release_ack = Some(ack_rx);
+                self.stream = Some(output);
+
+                Ok(())
+        }
+
+        fn start_cpal(
+                &self,
+                mut plugin: Box<dyn Plugin>,
+                rx: Receiver<PluginEvent>,
+                release_ack: Sender<()>,
+        ) -> Result<cpal::Stream> {
                 let host = cpal::default_host();
                 let device = host
                         .default_output_device()
@@ -28,36 +128,56 @@ impl AudioEngine {
                 let sample_format = config.sample_format();
                 let config: cpal::StreamConfig = config.into();
                 let channels = config.channels as usize;
-                let sample_rate = config.sample_rate.0 as f32;
+                let device_rate = config.sample_rate.0;
+                let sample_rate = self.sample_rate.unwrap_or(device_rate) as f32;
 
                 println!("Audio Device: {:?}", device.name());
-                println!("Sample Rate: {}, Channels: {}", sample_rate, channels);
+                println!("Sample Rate: {}, Channels: {}", device_rate, channels);
 
-                // 创建事件通道：主线程可通过 `send_event` 发送事件到音频回调
-                let (tx, rx): (Sender<PluginEvent>, Receiver<PluginEvent>) = unbounded();
-                self.command_sender = Some(tx);
+                // 工程采样率与设备不同时，音频图以工程采样率运行，输出重采样到设备采样率
+                let mut resampler = (sample_rate as u32 != device_rate).then(|| {
+                        println!("Resampling from project rate {} Hz", sample_rate);
+                        Resampler::new(sample_rate as f64, device_rate as f64, channels)
+                });
 
                 let err_fn = |err| eprintln!("an error occurred on stream: {}", err);
+                let mut planar = PlanarBuffer::default();
+                // 重采样时某个设备块可能不需要渲染新的音频，事件留到下一次渲染
+                let mut events = Vec::new();
+                let mut output_events = Vec::new();
 
                 let stream = match sample_format {
                         cpal::SampleFormat::F32 => device.build_output_stream(
                                 &config,
-                                move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                                move |data: &mut [f32], info: &cpal::OutputCallbackInfo| {
+                                        // 供插件宿主的线程检查使用
+                                        threads::mark_audio_thread();
+                                        // 设备报告的回放时刻与回调时刻之差即输出延迟
+                                        let timestamp = info.timestamp();
+                                        if let Some(latency) = timestamp.playback.duration_since(&timestamp.callback) {
+                                                set_device_latency(latency.as_secs_f64());
+                                        }
+
                                         // 非阻塞读取该音频块期间到达的所有事件
-                                        let mut events = Vec::new();
                                         while let Ok(event) = rx.try_recv() {
                                                 events.push(event);
                                         }
 
-                                        let mut buffer = AudioBuffer {
-                                                samples: data,
-                                                channels,
-                                                sample_rate,
-                                        };
-
+                                        // 设备缓冲为交错格式：插件在平面缓冲上处理，之后再交错写回设备
+                                        planar.resize(channels, data.len() / channels.max(1));
                                         // 插件就地处理 samples，可能产生输出事件
-                                        let mut output_events = Vec::new();
-                                        plugin.process(&mut buffer, &events, &mut output_events);
+                                        let mut render = |block: &mut PlanarBuffer| {
+                                                output_events.clear();
+                                                let mut buffer = block.as_audio_buffer(sample_rate);
+                                                plugin.process(&mut buffer, &events, &mut output_events);
+                                                acknowledge_release(&events, &release_ack);
+                                                events.clear();
+                                        };
+                                        match resampler.as_mut() {
+                                                Some(resampler) => resampler.process(planar.as_mut_slice(), render),
+                                                None => render(&mut planar),
+                                        }
+                                        planar.write_interleaved(data);
                                 },
                                 err_fn,
                                 None,
@@ -71,18 +191,43 @@ impl AudioEngine {
                 };
 
                 stream.play()?;
-                self.stream = Some(stream);
 
-                Ok(())
+                Ok(stream)
         }
 
         pub fn stop(&mut self) {
+                self.release_notes();
+                #[cfg(target_os = "linux")]
+                if let Some(Output::Jack(client)) = &self.stream {
+                        self.jack_connections = client.connections();
+                }
                 self.stream = None;
                 self.command_sender = None;
+                self.release_ack = None;
+        }
+
+        // 停止 / 重建前让音频图为所有发声的音符发送 NoteOff：
+        // 音频回调在处理完携带该事件的块后通过有界通道应答，主线程阻塞等待应答（或超时）
+        fn release_notes(&self) {
+                let (Some(sender), Some(ack), Some(_)) = (&self.command_sender, &self.release_ack, &self.stream) else {
+                        return;
+                };
+                // 丢弃之前未被等待的应答
+                while ack.try_recv().is_ok() {}
+                if sender.send(PluginEvent::AllNotesOff { panic: false }).is_err() {
+                        return;
+                }
+                let _ = ack.recv_timeout(RELEASE_TIMEOUT);
         }
 
+        /// 输出正在运行（JACK 服务器关闭后视为已停止）
         pub fn is_running(&self) -> bool {
-                self.stream.is_some()
+                match &self.stream {
+                        Some(Output::Cpal(_)) => true,
+                        #[cfg(target_os = "linux")]
+                        Some(Output::Jack(client)) => client.is_alive(),
+                        None => false,
+                }
         }
 
         pub fn send_event(&self, event: PluginEvent) {
@@ -91,3 +236,10 @@ impl AudioEngine {
                 }
         }
 }
+
+/// 音频回调处理完一块后调用：块内包含“全部音符关闭”时应答等待中的 `release_notes`（不阻塞、不分配）
+pub(crate) fn acknowledge_release(events: &[PluginEvent], release_ack: &Sender<()>) {
+        if events.iter().any(|e| matches!(e, PluginEvent::AllNotesOff { .. })) {
+                let _ = release_ack.try_send(());
+        }
+}
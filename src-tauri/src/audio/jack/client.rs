@@ -0,0 +1,546 @@
+use super::ffi::{self, JackApi, JackClientPtr, JackPortPtr, JackPosition};
+use super::{JackSettings, JackTransportMode, unique_port_names};
+use crate::audio::core::buffer::PlanarBuffer;
+use crate::audio::core::plugin::{Plugin, PluginEvent};
+use crate::audio::core::resampler::Resampler;
+use crate::audio::core::threads;
+use crate::audio::engine::acknowledge_release;
+use crate::daw::sequencer::{get_playback_position, get_tempo, set_device_latency};
+use crossbeam_channel::{Receiver, Sender};
+use std::collections::HashMap;
+use std::ffi::{CStr, CString, c_void};
+use std::os::raw::c_int;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// 主输出与轨道端口均为立体声
+const CHANNELS: usize = 2;
+const SIDES: [&str; CHANNELS] = ["L", "R"];
+// 作为时基主控时报告的拍号与每拍 tick 数
+const BEATS_PER_BAR: f64 = 4.0;
+const TICKS_PER_BEAT: f64 = 1920.0;
+
+type StereoPorts = [JackPortPtr; CHANNELS];
+
+// 处理回调的状态：由 JACK 处理线程独占，客户端关闭后释放
+struct ProcessState {
+        api: &'static JackApi,
+        client: JackClientPtr,
+        plugin: Box<dyn Plugin>,
+        receiver: Receiver<PluginEvent>,
+        // 处理完“全部音符关闭”后应答引擎
+        release_ack: Sender<()>,
+        // JACK 服务器采样率（走带帧位置按它换算）与音频图运行的工程采样率
+        sample_rate: f32,
+        project_rate: f32,
+        master: StereoPorts,
+        // 与插件的附加输出 / 输入端口下标对应
+        outputs: Vec<StereoPorts>,
+        inputs: Vec<StereoPorts>,
+        // 主输出缓冲：JACK 端口本身即为逐通道缓冲，与平面格式之间直接按通道复制
+        master_buffer: PlanarBuffer,
+        // 采样率不同时：主输出与各轨道输出合并为一个多通道流整体重采样（外部输入端口此时不可用）
+        resampler: Option<Resampler>,
+        resampled: PlanarBuffer,
+        events: Vec<PluginEvent>,
+        output_events: Vec<PluginEvent>,
+        transport: JackTransportMode,
+        // 上一块的走带状态，用于检测 JACK 的启停与定位
+        rolling: Option<bool>,
+        expected_frame: Option<u32>,
+        tempo: Option<f64>,
+        last_position: f64,
+}
+
+fn port_samples<'a>(api: &JackApi, port: JackPortPtr, nframes: u32) -> &'a mut [f32] {
+        let data = unsafe { (api.port_get_buffer)(port, nframes) } as *mut f32;
+        if data.is_null() {
+                return &mut [];
+        }
+        unsafe { std::slice::from_raw_parts_mut(data, nframes as usize) }
+}
+
+// 平面样本 -> 各通道端口；`samples` 为 None 时输出静音
+fn write_ports(api: &JackApi, ports: &StereoPorts, samples: Option<&[f32]>, nframes: u32) {
+        let frames = nframes as usize;
+        for (channel, port) in ports.iter().enumerate() {
+                let out = port_samples(api, *port, nframes);
+                match samples.and_then(|s| s.get(channel * frames..(channel + 1) * frames)) {
+                        Some(samples) if samples.len() == out.len() => out.copy_from_slice(samples),
+                        _ => out.fill(0.0),
+                }
+        }
+}
+
+impl ProcessState {
+        // 本地走带命令转发给 JACK（在下一块生效，再由 follow_transport 同步到音序器）
+        fn forward_transport(&self, playing: bool, position: Option<f64>) {
+                if let Some(position) = position {
+                        let frame = (position.max(0.0) * self.sample_rate as f64) as u32;
+                        unsafe { (self.api.transport_locate)(self.client, frame) };
+                }
+                if playing {
+                        unsafe { (self.api.transport_start)(self.client) };
+                } else {
+                        unsafe { (self.api.transport_stop)(self.client) };
+                }
+        }
+
+        // 查询 JACK 走带，状态、位置或速度变化时为音序器生成 Transport 事件
+        fn follow_transport(&mut self, nframes: u32) {
+                let mut pos = JackPosition::zeroed();
+                let state = unsafe { (self.api.transport_query)(self.client, &mut pos) };
+                let rolling = state == ffi::TRANSPORT_ROLLING;
+                let frame = pos.frame;
+                let relocated = self.expected_frame != Some(frame);
+                let valid = pos.valid;
+                // 作为时基主控时速度由音序器决定，不回读
+                let tempo = (self.transport == JackTransportMode::Follow && valid & ffi::POSITION_BBT != 0)
+                        .then_some(pos.beats_per_minute)
+                        .filter(|t| *t > 0.0);
+                let tempo_changed = tempo.is_some() && tempo != self.tempo;
+                if relocated || self.rolling != Some(rolling) || tempo_changed {
+                        self.events.push(PluginEvent::Transport {
+                                playing: rolling,
+                                position: relocated.then(|| frame as f64 / self.sample_rate as f64),
+                                tempo: if tempo_changed { tempo } else { None },
+                        });
+                }
+                self.tempo = tempo.or(self.tempo);
+                self.rolling = Some(rolling);
+                self.expected_frame = Some(if rolling {
+                        frame.wrapping_add(nframes)
+                } else {
+                        frame
+                });
+        }
+
+        fn run(&mut self, nframes: u32) {
+                // 供插件宿主的线程检查使用
+                threads::mark_audio_thread();
+                let frames = nframes as usize;
+                let len = frames * CHANNELS;
+
+                // 主输出端口到物理输出的回放延迟（帧，按服务器采样率）
+                let mut range = ffi::JackLatencyRange::default();
+                unsafe { (self.api.port_get_latency_range)(self.master[0], ffi::PLAYBACK_LATENCY, &mut range) };
+                set_device_latency(range.max as f64 / self.sample_rate as f64);
+
+                self.events.clear();
+                while let Ok(event) = self.receiver.try_recv() {
+                        match event {
+                                PluginEvent::Transport { playing, position, .. }
+                                        if self.transport != JackTransportMode::Off =>
+                                {
+                                        self.forward_transport(playing, position)
+                                }
+                                event => self.events.push(event),
+                        }
+                }
+                if self.transport != JackTransportMode::Off {
+                        self.follow_transport(nframes);
+                }
+
+                if self.resampler.is_some() {
+                        self.run_resampled(nframes);
+                        return;
+                }
+
+                // 轨道输入端口 -> 插件的附加输入（平面，逐通道复制）
+                for (index, ports) in self.inputs.iter().enumerate() {
+                        let Some(dest) = self.plugin.aux_input_mut(index, len) else {
+                                continue;
+                        };
+                        for (port, dest) in ports.iter().zip(dest.chunks_exact_mut(frames.max(1))) {
+                                let input = port_samples(self.api, *port, nframes);
+                                if input.len() == dest.len() {
+                                        dest.copy_from_slice(input);
+                                }
+                        }
+                }
+
+                // 块大小变化时才重新分配
+                self.master_buffer.resize(CHANNELS, frames);
+                self.output_events.clear();
+                let mut buffer = self.master_buffer.as_audio_buffer(self.sample_rate);
+                self.plugin.process(&mut buffer, &self.events, &mut self.output_events);
+                acknowledge_release(&self.events, &self.release_ack);
+
+                write_ports(
+                        self.api,
+                        &self.master,
+                        Some(self.master_buffer.as_slice()),
+                        nframes,
+                );
+                for (index, ports) in self.outputs.iter().enumerate() {
+                        write_ports(self.api, ports, self.plugin.aux_output(index), nframes);
+                }
+                self.drive_transport();
+        }
+
+        // 以工程采样率渲染并重采样到 JACK 采样率
+        fn run_resampled(&mut self, nframes: u32) {
+                let frames = nframes as usize;
+                let streams = 1 + self.outputs.len();
+                self.resampled.resize(CHANNELS * streams, frames);
+                let Some(resampler) = self.resampler.as_mut() else {
+                        return;
+                };
+                let (plugin, master, events, output_events, release_ack) = (
+                        &mut self.plugin,
+                        &mut self.master_buffer,
+                        &mut self.events,
+                        &mut self.output_events,
+                        &self.release_ack,
+                );
+                let project_rate = self.project_rate;
+                resampler.process(self.resampled.as_mut_slice(), |block| {
+                        let block_frames = block.frames();
+                        master.resize(CHANNELS, block_frames);
+                        output_events.clear();
+                        plugin.process(
+                                &mut master.as_audio_buffer(project_rate),
+                                events,
+                                output_events,
+                        );
+                        acknowledge_release(events, release_ack);
+                        // 某个 JACK 块可能不需要渲染新的音频，事件只在实际渲染时消费
+                        events.clear();
+                        for stream in 0..streams {
+                                let source = match stream {
+                                        0 => Some(master.as_slice()),
+                                        _ => plugin.aux_output(stream - 1),
+                                };
+                                let Some(source) = source else {
+                                        continue;
+                                };
+                                for (c, chunk) in source.chunks_exact(block_frames.max(1)).take(CHANNELS).enumerate() {
+                                        block.channel_mut(stream * CHANNELS + c).copy_from_slice(chunk);
+                                }
+                        }
+                });
+
+                let samples = self.resampled.as_slice();
+                let stream_len = CHANNELS * frames;
+                write_ports(self.api, &self.master, samples.get(..stream_len), nframes);
+                for (index, ports) in self.outputs.iter().enumerate() {
+                        let start = (index + 1) * stream_len;
+                        write_ports(
+                                self.api,
+                                ports,
+                                samples.get(start..start + stream_len),
+                                nframes,
+                        );
+                }
+                self.drive_transport();
+        }
+
+        // 作为主控时，音序器的循环回绕同步为 JACK 定位
+        fn drive_transport(&mut self) {
+                if self.transport == JackTransportMode::Drive {
+                        let position = get_playback_position();
+                        if self.rolling == Some(true) && position < self.last_position {
+                                let frame = (position * self.sample_rate as f64) as u32;
+                                unsafe { (self.api.transport_locate)(self.client, frame) };
+                        }
+                        self.last_position = position;
+                }
+        }
+}
+
+unsafe extern "C" fn process_callback(nframes: u32, arg: *mut c_void) -> c_int {
+        let state = unsafe { &mut *(arg as *mut ProcessState) };
+        state.run(nframes);
+        0
+}
+
+unsafe extern "C" fn shutdown_callback(arg: *mut c_void) {
+        let alive = unsafe { &*(arg as *const AtomicBool) };
+        alive.store(false, Ordering::Relaxed);
+}
+
+// 时基主控：按音序器速度（4/4 拍）把帧位置换算为小节 / 拍 / tick
+unsafe extern "C" fn timebase_callback(
+        _state: c_int,
+        _nframes: u32,
+        pos: *mut JackPosition,
+        _new_pos: c_int,
+        _arg: *mut c_void,
+) {
+        let Some(pos) = (unsafe { pos.as_mut() }) else {
+                return;
+        };
+        let tempo = get_tempo();
+        let frame_rate = pos.frame_rate.max(1) as f64;
+        let beats = pos.frame as f64 / frame_rate * tempo / 60.0;
+        let bar = (beats / BEATS_PER_BAR).floor();
+        let beat = beats - bar * BEATS_PER_BAR;
+        pos.valid |= ffi::POSITION_BBT;
+        pos.bar = bar as i32 + 1;
+        pos.beat = beat.floor() as i32 + 1;
+        pos.tick = (beat.fract() * TICKS_PER_BEAT) as i32;
+        pos.bar_start_tick = bar * BEATS_PER_BAR * TICKS_PER_BEAT;
+        pos.beats_per_bar = BEATS_PER_BAR as f32;
+        pos.beat_type = 4.0;
+        pos.ticks_per_beat = TICKS_PER_BEAT;
+        pos.beats_per_minute = tempo;
+}
+
+struct RegisteredPort {
+        name: String,
+        port: JackPortPtr,
+        is_output: bool,
+}
+
+/// 已激活的 JACK 客户端；Drop 时停用并关闭
+pub struct JackClient {
+        api: &'static JackApi,
+        client: JackClientPtr,
+        ports: Vec<RegisteredPort>,
+        process: *mut ProcessState,
+        alive: Arc<AtomicBool>,
+        timebase: bool,
+}
+
+// 客户端句柄只在持有引擎锁的线程上使用；处理状态由 JACK 线程独占
+unsafe impl Send for JackClient {}
+
+impl JackClient {
+        /// 连接 JACK 服务器、注册端口并开始处理。
+        /// `connections` 为上一次关闭时保存的端口连接（端口短名 -> 对端全名），音频图重建后据此恢复。
+        pub fn start(
+                settings: &JackSettings,
+                plugin: Box<dyn Plugin>,
+                project_rate: Option<u32>,
+                receiver: Receiver<PluginEvent>,
+                release_ack: Sender<()>,
+                connections: &HashMap<String, Vec<String>>,
+        ) -> Result<Self, String> {
+                let api = JackApi::get()?;
+                let name = CString::new(settings.client_name.as_str()).map_err(|_| "Invalid JACK client name")?;
+                let mut status: c_int = 0;
+                let client = unsafe { (api.client_open)(name.as_ptr(), ffi::NO_START_SERVER, &mut status) };
+                if client.is_null() {
+                        return Err(format!(
+                                "Failed to connect to JACK server (status 0x{:x})",
+                                status
+                        ));
+                }
+                let mut jack = Self {
+                        api,
+                        client,
+                        ports: Vec::new(),
+                        process: std::ptr::null_mut(),
+                        alive: Arc::new(AtomicBool::new(true)),
+                        timebase: false,
+                };
+                let sample_rate = unsafe { (api.get_sample_rate)(client) } as f32;
+                println!(
+                        "JACK client: {}, Sample Rate: {}",
+                        settings.client_name, sample_rate
+                );
+
+                // 主输出取插件主输出端口的名称，轨道端口取附加端口的名称
+                let io = plugin.get_io_config();
+                let main_name = io
+                        .output_ports
+                        .iter()
+                        .find(|p| p.is_main)
+                        .map_or_else(|| "Master".to_string(), |p| p.name.clone());
+                let aux_outputs: Vec<String> = io
+                        .output_ports
+                        .iter()
+                        .filter(|p| !p.is_main)
+                        .map(|p| p.name.clone())
+                        .collect();
+                let aux_inputs: Vec<String> = io
+                        .input_ports
+                        .iter()
+                        .filter(|p| !p.is_main)
+                        .map(|p| p.name.clone())
+                        .collect();
+
+                let mut output_names = vec![main_name];
+                if settings.track_outputs {
+                        output_names.extend(aux_outputs);
+                }
+                let mut outputs = Vec::new();
+                for base in unique_port_names(&output_names) {
+                        outputs.push(jack.register_stereo(&format!("{} out", base), true)?);
+                }
+                let master = outputs.remove(0);
+                let mut inputs = Vec::new();
+                if settings.track_inputs {
+                        for base in unique_port_names(&aux_inputs) {
+                                inputs.push(jack.register_stereo(&format!("{} in", base), false)?);
+                        }
+                }
+
+                // 工程采样率与服务器不同时整体重采样输出端口
+                let project_rate = project_rate.map_or(sample_rate, |rate| rate as f32);
+                let resampler = (project_rate != sample_rate).then(|| {
+                        println!("JACK: resampling from project rate {} Hz", project_rate);
+                        Resampler::new(
+                                project_rate as f64,
+                                sample_rate as f64,
+                                CHANNELS * (1 + outputs.len()),
+                        )
+                });
+
+                let state = Box::new(ProcessState {
+                        api,
+                        client,
+                        plugin,
+                        receiver,
+                        release_ack,
+                        sample_rate,
+                        project_rate,
+                        master,
+                        outputs,
+                        inputs,
+                        master_buffer: PlanarBuffer::default(),
+                        resampler,
+                        resampled: PlanarBuffer::default(),
+                        events: Vec::new(),
+                        output_events: Vec::new(),
+                        transport: settings.transport,
+                        rolling: None,
+                        expected_frame: None,
+                        tempo: None,
+                        last_position: 0.0,
+                });
+                jack.process = Box::into_raw(state);
+                unsafe {
+                        (api.set_process_callback)(client, process_callback, jack.process as *mut c_void);
+                        (api.on_shutdown)(
+                                client,
+                                shutdown_callback,
+                                Arc::as_ptr(&jack.alive) as *mut c_void,
+                        );
+                }
+                if settings.transport == JackTransportMode::Drive {
+                        let result = unsafe {
+                                (api.set_timebase_callback)(client, 0, timebase_callback, std::ptr::null_mut())
+                        };
+                        if result == 0 {
+                                jack.timebase = true;
+                        } else {
+                                println!("JACK: failed to become timebase master");
+                        }
+                }
+                if unsafe { (api.activate)(client) } != 0 {
+                        return Err("Failed to activate JACK client".to_string());
+                }
+                jack.restore_connections(connections, settings.auto_connect);
+                Ok(jack)
+        }
+
+        fn register_stereo(&mut self, base: &str, is_output: bool) -> Result<StereoPorts, String> {
+                let flags = if is_output {
+                        ffi::PORT_IS_OUTPUT
+                } else {
+                        ffi::PORT_IS_INPUT
+                };
+                let mut ports = [std::ptr::null_mut(); CHANNELS];
+                for (port, side) in ports.iter_mut().zip(SIDES) {
+                        let name = format!("{} {}", base, side);
+                        let c_name = CString::new(name.as_str()).map_err(|_| "Invalid JACK port name")?;
+                        *port = unsafe {
+                                (self.api.port_register)(
+                                        self.client,
+                                        c_name.as_ptr(),
+                                        ffi::DEFAULT_AUDIO_TYPE.as_ptr() as *const _,
+                                        flags,
+                                        0,
+                                )
+                        };
+                        if port.is_null() {
+                                return Err(format!("Failed to register JACK port: {}", name));
+                        }
+                        self.ports.push(RegisteredPort {
+                                name,
+                                port: *port,
+                                is_output,
+                        });
+                }
+                Ok(ports)
+        }
+
+        fn full_name(&self, port: JackPortPtr) -> Option<CString> {
+                let name = unsafe { (self.api.port_name)(port) };
+                (!name.is_null()).then(|| unsafe { CStr::from_ptr(name) }.to_owned())
+        }
+
+        fn connect(&self, source: &CStr, destination: &CStr) {
+                unsafe { (self.api.connect)(self.client, source.as_ptr(), destination.as_ptr()) };
+        }
+
+        fn restore_connections(&self, connections: &HashMap<String, Vec<String>>, auto_connect: bool) {
+                for port in &self.ports {
+                        let (Some(targets), Some(own)) = (connections.get(&port.name), self.full_name(port.port))
+                        else {
+                                continue;
+                        };
+                        for target in targets.iter().filter_map(|t| CString::new(t.as_str()).ok()) {
+                                if port.is_output {
+                                        self.connect(&own, &target);
+                                } else {
+                                        self.connect(&target, &own);
+                                }
+                        }
+                }
+
+                // 主输出（前两个注册的端口）没有保存的连接时连接到物理播放端口
+                let master = &self.ports[..CHANNELS.min(self.ports.len())];
+                if !auto_connect || master.iter().any(|p| connections.contains_key(&p.name)) {
+                        return;
+                }
+                let playback = ffi::take_name_list(self.api, unsafe {
+                        (self.api.get_ports)(
+                                self.client,
+                                std::ptr::null(),
+                                ffi::DEFAULT_AUDIO_TYPE.as_ptr() as *const _,
+                                ffi::PORT_IS_PHYSICAL | ffi::PORT_IS_INPUT,
+                        )
+                });
+                for (port, target) in master.iter().zip(playback) {
+                        let (Some(own), Ok(target)) = (self.full_name(port.port), CString::new(target)) else {
+                                continue;
+                        };
+                        self.connect(&own, &target);
+                }
+        }
+
+        /// 当前各端口的连接（端口短名 -> 对端全名），在关闭前保存以便重建后恢复
+        pub fn connections(&self) -> HashMap<String, Vec<String>> {
+                self.ports
+                        .iter()
+                        .map(|p| {
+                                let list = unsafe { (self.api.port_get_all_connections)(self.client, p.port) };
+                                (p.name.clone(), ffi::take_name_list(self.api, list))
+                        })
+                        .filter(|(_, targets)| !targets.is_empty())
+                        .collect()
+        }
+
+        /// JACK 服务器仍在运行（服务器关闭后客户端失效）
+        pub fn is_alive(&self) -> bool {
+                self.alive.load(Ordering::Relaxed)
+        }
+}
+
+impl Drop for JackClient {
+        fn drop(&mut self) {
+                unsafe {
+                        (self.api.deactivate)(self.client);
+                        if self.timebase {
+                                (self.api.release_timebase)(self.client);
+                        }
+                        (self.api.client_close)(self.client);
+                }
+                // 客户端关闭后不会再调用处理回调
+                if !self.process.is_null() {
+                        drop(unsafe { Box::from_raw(self.process) });
+                }
+        }
+}
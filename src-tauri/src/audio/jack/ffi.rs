@@ -0,0 +1,162 @@
+use libloading::Library;
+use std::ffi::c_void;
+use std::os::raw::{c_char, c_int, c_ulong};
+use std::sync::OnceLock;
+
+// libjack 的运行时绑定：启动 JACK 后端时才通过 dlopen 加载 `libjack.so.0`，
+// 未安装 JACK（或 PipeWire-JACK）的系统上不影响其它后端。只声明本宿主用到的函数。
+
+pub type JackClientPtr = *mut c_void;
+pub type JackPortPtr = *mut c_void;
+
+pub type ProcessCallback = unsafe extern "C" fn(u32, *mut c_void) -> c_int;
+pub type ShutdownCallback = unsafe extern "C" fn(*mut c_void);
+pub type TimebaseCallback = unsafe extern "C" fn(c_int, u32, *mut JackPosition, c_int, *mut c_void);
+
+pub const DEFAULT_AUDIO_TYPE: &[u8] = b"32 bit float mono audio\0";
+
+// jack_options_t
+pub const NO_START_SERVER: c_int = 0x01;
+// JackPortFlags
+pub const PORT_IS_INPUT: c_ulong = 0x1;
+pub const PORT_IS_OUTPUT: c_ulong = 0x2;
+pub const PORT_IS_PHYSICAL: c_ulong = 0x4;
+// jack_latency_callback_mode_t
+pub const PLAYBACK_LATENCY: c_int = 1;
+// jack_transport_state_t
+pub const TRANSPORT_ROLLING: c_int = 1;
+// jack_position_bits_t
+pub const POSITION_BBT: c_int = 0x10;
+
+/// `jack_latency_range_t`（帧）
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JackLatencyRange {
+        pub min: u32,
+        pub max: u32,
+}
+
+/// `jack_position_t`（types.h 中以 packed 方式声明，共 136 字节）
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub struct JackPosition {
+        pub unique_1: u64,
+        pub usecs: u64,
+        pub frame_rate: u32,
+        pub frame: u32,
+        pub valid: c_int,
+        pub bar: i32,
+        pub beat: i32,
+        pub tick: i32,
+        pub bar_start_tick: f64,
+        pub beats_per_bar: f32,
+        pub beat_type: f32,
+        pub ticks_per_beat: f64,
+        pub beats_per_minute: f64,
+        pub frame_time: f64,
+        pub next_time: f64,
+        pub bbt_offset: u32,
+        pub audio_frames_per_video_frame: f32,
+        pub video_offset: u32,
+        pub tick_double: f64,
+        pub padding: [i32; 5],
+        pub unique_2: u64,
+}
+
+impl JackPosition {
+        pub fn zeroed() -> Self {
+                // 全零是合法的 jack_position_t
+                unsafe { std::mem::zeroed() }
+        }
+}
+
+/// 已加载的 libjack 函数表
+pub struct JackApi {
+        // 持有动态库以保证函数指针有效
+        _lib: Library,
+        pub client_open: unsafe extern "C" fn(*const c_char, c_int, *mut c_int, ...) -> JackClientPtr,
+        pub client_close: unsafe extern "C" fn(JackClientPtr) -> c_int,
+        pub get_sample_rate: unsafe extern "C" fn(JackClientPtr) -> u32,
+        pub set_process_callback: unsafe extern "C" fn(JackClientPtr, ProcessCallback, *mut c_void) -> c_int,
+        pub on_shutdown: unsafe extern "C" fn(JackClientPtr, ShutdownCallback, *mut c_void),
+        pub activate: unsafe extern "C" fn(JackClientPtr) -> c_int,
+        pub deactivate: unsafe extern "C" fn(JackClientPtr) -> c_int,
+        pub port_register:
+                unsafe extern "C" fn(JackClientPtr, *const c_char, *const c_char, c_ulong, c_ulong) -> JackPortPtr,
+        pub port_get_buffer: unsafe extern "C" fn(JackPortPtr, u32) -> *mut c_void,
+        pub port_name: unsafe extern "C" fn(JackPortPtr) -> *const c_char,
+        pub port_get_latency_range: unsafe extern "C" fn(JackPortPtr, c_int, *mut JackLatencyRange),
+        pub port_get_all_connections: unsafe extern "C" fn(JackClientPtr, JackPortPtr) -> *mut *const c_char,
+        pub get_ports: unsafe extern "C" fn(JackClientPtr, *const c_char, *const c_char, c_ulong) -> *mut *const c_char,
+        pub connect: unsafe extern "C" fn(JackClientPtr, *const c_char, *const c_char) -> c_int,
+        pub free: unsafe extern "C" fn(*mut c_void),
+        pub transport_query: unsafe extern "C" fn(JackClientPtr, *mut JackPosition) -> c_int,
+        pub transport_start: unsafe extern "C" fn(JackClientPtr),
+        pub transport_stop: unsafe extern "C" fn(JackClientPtr),
+        pub transport_locate: unsafe extern "C" fn(JackClientPtr, u32) -> c_int,
+        pub set_timebase_callback: unsafe extern "C" fn(JackClientPtr, c_int, TimebaseCallback, *mut c_void) -> c_int,
+        pub release_timebase: unsafe extern "C" fn(JackClientPtr) -> c_int,
+}
+
+impl JackApi {
+        fn load() -> Result<Self, String> {
+                let lib = unsafe { Library::new("libjack.so.0") }
+                        .map_err(|e| format!("Failed to load libjack: {}", e))?;
+                Ok(Self {
+                        client_open: sym(&lib, "jack_client_open")?,
+                        client_close: sym(&lib, "jack_client_close")?,
+                        get_sample_rate: sym(&lib, "jack_get_sample_rate")?,
+                        set_process_callback: sym(&lib, "jack_set_process_callback")?,
+                        on_shutdown: sym(&lib, "jack_on_shutdown")?,
+                        activate: sym(&lib, "jack_activate")?,
+                        deactivate: sym(&lib, "jack_deactivate")?,
+                        port_register: sym(&lib, "jack_port_register")?,
+                        port_get_buffer: sym(&lib, "jack_port_get_buffer")?,
+                        port_name: sym(&lib, "jack_port_name")?,
+                        port_get_latency_range: sym(&lib, "jack_port_get_latency_range")?,
+                        port_get_all_connections: sym(&lib, "jack_port_get_all_connections")?,
+                        get_ports: sym(&lib, "jack_get_ports")?,
+                        connect: sym(&lib, "jack_connect")?,
+                        free: sym(&lib, "jack_free")?,
+                        transport_query: sym(&lib, "jack_transport_query")?,
+                        transport_start: sym(&lib, "jack_transport_start")?,
+                        transport_stop: sym(&lib, "jack_transport_stop")?,
+                        transport_locate: sym(&lib, "jack_transport_locate")?,
+                        set_timebase_callback: sym(&lib, "jack_set_timebase_callback")?,
+                        release_timebase: sym(&lib, "jack_release_timebase")?,
+                        _lib: lib,
+                })
+        }
+
+        /// 进程内只加载一次；加载失败时返回原因
+        pub fn get() -> Result<&'static JackApi, String> {
+                static API: OnceLock<Result<JackApi, String>> = OnceLock::new();
+                API.get_or_init(JackApi::load).as_ref().map_err(Clone::clone)
+        }
+}
+
+// 取出函数指针（复制出 Symbol，Library 由 JackApi 持有）
+fn sym<T: Copy>(lib: &Library, name: &str) -> Result<T, String> {
+        unsafe { lib.get::<T>(name.as_bytes()) }
+                .map(|symbol| *symbol)
+                .map_err(|e| format!("libjack: missing {}: {}", name, e))
+}
+
+// 把 libjack 返回的以 NULL 结尾的名称数组转换为字符串并释放
+pub fn take_name_list(api: &JackApi, list: *mut *const c_char) -> Vec<String> {
+        let mut names = Vec::new();
+        if list.is_null() {
+                return names;
+        }
+        let mut index = 0;
+        loop {
+                let name = unsafe { *list.add(index) };
+                if name.is_null() {
+                        break;
+                }
+                names.push(unsafe { std::ffi::CStr::from_ptr(name) }.to_string_lossy().into_owned());
+                index += 1;
+        }
+        unsafe { (api.free)(list as *mut c_void) };
+        names
+}
@@ -0,0 +1,83 @@
+/// JACK 音频后端（Linux，含 PipeWire-JACK）：每个混音轨道可注册独立的立体声输入 / 输出端口，
+/// 并可选地接入 JACK 走带——跟随（Follow）或作为时基主控（Drive）。
+/// libjack 在运行时加载，未安装 JACK 时仅该后端不可用。
+use serde::{Deserialize, Serialize};
+
+#[cfg(target_os = "linux")]
+mod client;
+#[cfg(target_os = "linux")]
+mod ffi;
+
+#[cfg(target_os = "linux")]
+pub use client::JackClient;
+
+/// JACK 走带集成方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JackTransportMode {
+        /// 不使用 JACK 走带，音序器独立运行
+        #[default]
+        Off,
+        /// 音序器跟随 JACK 走带（播放状态、位置与 BBT 速度）；本地的播放 / 暂停 / 定位转发给 JACK
+        Follow,
+        /// 同 Follow，并作为 JACK 时基主控，按音序器速度向其它客户端提供小节 / 拍位置
+        Drive,
+}
+
+fn default_client_name() -> String {
+        "My DAW".to_string()
+}
+
+fn default_true() -> bool {
+        true
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JackSettings {
+        #[serde(default = "default_client_name")]
+        pub client_name: String,
+        /// 为每个混音轨道注册立体声输出端口（推子后信号）
+        #[serde(default)]
+        pub track_outputs: bool,
+        /// 为每个混音轨道注册立体声输入端口（混入轨道输入，经插入效果处理）
+        #[serde(default)]
+        pub track_inputs: bool,
+        /// 主输出没有已保存的连接时自动连接到物理播放端口
+        #[serde(default = "default_true")]
+        pub auto_connect: bool,
+        #[serde(default)]
+        pub transport: JackTransportMode,
+}
+
+impl Default for JackSettings {
+        fn default() -> Self {
+                Self {
+                        client_name: default_client_name(),
+                        track_outputs: false,
+                        track_inputs: false,
+                        auto_connect: true,
+                        transport: JackTransportMode::Off,
+                }
+        }
+}
+
+/// 由插件端口名生成 JACK 端口名：去掉 JACK 保留的 ':'，重名时追加序号
+pub fn unique_port_names(names: &[String]) -> Vec<String> {
+        let mut used: Vec<String> = Vec::new();
+        for name in names {
+                let base = name.replace(':', "-");
+                let base = if base.trim().is_empty() {
+                        "Port".to_string()
+                } else {
+                        base
+                };
+                let mut candidate = base.clone();
+                let mut suffix = 2;
+                while used.contains(&candidate) {
+                        candidate = format!("{} {}", base, suffix);
+                        suffix += 1;
+                }
+                used.push(candidate);
+        }
+        used
+}
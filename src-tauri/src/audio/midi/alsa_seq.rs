@@ -0,0 +1,206 @@
+/// ALSA sequencer 后端：每个输入 / 输出连接使用独立的 sequencer 客户端与工作线程。
+use super::{MidiMessage, MidiPortInfo};
+use alsa::seq::{Addr, ClientIter, MidiEvent, PortCap, PortIter, PortSubscribe, PortType};
+use alsa::{Direction, PollDescriptors, Seq};
+use crossbeam_channel::{Sender, bounded};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Instant;
+
+// 读取线程检查停止标志的间隔（毫秒）
+const POLL_TIMEOUT_MS: i32 = 50;
+// 输出队列容量：足够容纳若干块的同步消息（每块最多几十条时钟 / 走带消息）
+const OUTPUT_QUEUE_CAPACITY: usize = 1024;
+
+fn open_seq(dir: Option<Direction>, nonblock: bool) -> Result<Seq, String> {
+        let seq = Seq::open(None, dir, nonblock).map_err(|e| format!("Failed to open ALSA sequencer: {}", e))?;
+        let _ = seq.set_client_name(c"MyDAW");
+        Ok(seq)
+}
+
+// 具备指定能力的 MIDI 端口（不含本进程自己的客户端）
+fn list_ports(caps: PortCap) -> Vec<MidiPortInfo> {
+        let Ok(seq) = open_seq(None, false) else {
+                return Vec::new();
+        };
+        let own_client = seq.client_id().unwrap_or(-1);
+        let mut ports = Vec::new();
+        for client in ClientIter::new(&seq) {
+                if client.get_client() == own_client {
+                        continue;
+                }
+                let client_name = client.get_name().unwrap_or("").to_string();
+                for port in PortIter::new(&seq, client.get_client()) {
+                        if !port.get_capability().contains(caps) || !port.get_type().contains(PortType::MIDI_GENERIC) {
+                                continue;
+                        }
+                        let addr = port.addr();
+                        ports.push(MidiPortInfo {
+                                id: format!("{}:{}", addr.client, addr.port),
+                                name: format!("{}:{}", client_name, port.get_name().unwrap_or("")),
+                        });
+                }
+        }
+        ports
+}
+
+/// 可被订阅读取的 MIDI 端口
+pub fn list_input_ports() -> Vec<MidiPortInfo> {
+        list_ports(PortCap::READ | PortCap::SUBS_READ)
+}
+
+/// 可被订阅写入的 MIDI 端口
+pub fn list_output_ports() -> Vec<MidiPortInfo> {
+        list_ports(PortCap::WRITE | PortCap::SUBS_WRITE)
+}
+
+pub struct InputConnection {
+        running: Arc<AtomicBool>,
+        thread: Option<JoinHandle<()>>,
+}
+
+impl InputConnection {
+        pub fn open<F>(port_id: &str, mut callback: F) -> Result<Self, String>
+        where
+                F: FnMut(MidiMessage) + Send + 'static,
+        {
+                let sender: Addr = port_id
+                        .parse()
+                        .map_err(|_| format!("Invalid MIDI port id: {}", port_id))?;
+                let seq = open_seq(Some(Direction::Capture), true)?;
+                let port = seq
+                        .create_simple_port(
+                                c"input",
+                                PortCap::WRITE | PortCap::SUBS_WRITE,
+                                PortType::MIDI_GENERIC | PortType::APPLICATION,
+                        )
+                        .map_err(|e| format!("Failed to create MIDI port: {}", e))?;
+                let subscribe = PortSubscribe::empty().map_err(|e| e.to_string())?;
+                subscribe.set_sender(sender);
+                subscribe.set_dest(Addr {
+                        client: seq.client_id().map_err(|e| e.to_string())?,
+                        port,
+                });
+                seq.subscribe_port(&subscribe)
+                        .map_err(|e| format!("Failed to connect MIDI port {}: {}", port_id, e))?;
+
+                let running = Arc::new(AtomicBool::new(true));
+                let flag = running.clone();
+                let thread = std::thread::Builder::new()
+                        .name("midi-input".to_string())
+                        .spawn(move || {
+                                let Ok(decoder) = MidiEvent::new(256) else {
+                                        return;
+                                };
+                                decoder.enable_running_status(false);
+                                let mut buf = [0u8; 256];
+                                while flag.load(Ordering::Relaxed) {
+                                        let Ok(mut fds) = (&seq, Some(Direction::Capture)).get() else {
+                                                break;
+                                        };
+                                        if alsa::poll::poll(&mut fds, POLL_TIMEOUT_MS).unwrap_or(0) == 0 {
+                                                continue;
+                                        }
+                                        let mut input = seq.input();
+                                        // 非阻塞读取：取完当前所有事件（EAGAIN 时返回错误）
+                                        while let Ok(mut event) = input.event_input() {
+                                                let len = decoder.decode(&mut buf, &mut event).unwrap_or(0);
+                                                if let Some(msg) = MidiMessage::parse(&buf[..len]) {
+                                                        callback(msg);
+                                                }
+                                        }
+                                }
+                        })
+                        .map_err(|e| e.to_string())?;
+                Ok(Self {
+                        running,
+                        thread: Some(thread),
+                })
+        }
+}
+
+impl Drop for InputConnection {
+        fn drop(&mut self) {
+                self.running.store(false, Ordering::Relaxed);
+                if let Some(thread) = self.thread.take() {
+                        let _ = thread.join();
+                }
+        }
+}
+
+pub struct OutputConnection {
+        // 有界队列在打开时预分配；音频线程只做 try_send，队列满时丢弃消息
+        sender: Option<Sender<(Instant, MidiMessage)>>,
+        thread: Option<JoinHandle<()>>,
+}
+
+impl OutputConnection {
+        pub fn open(port_id: &str) -> Result<Self, String> {
+                let dest: Addr = port_id
+                        .parse()
+                        .map_err(|_| format!("Invalid MIDI port id: {}", port_id))?;
+                let seq = open_seq(Some(Direction::Playback), false)?;
+                let port = seq
+                        .create_simple_port(
+                                c"output",
+                                PortCap::READ | PortCap::SUBS_READ,
+                                PortType::MIDI_GENERIC | PortType::APPLICATION,
+                        )
+                        .map_err(|e| format!("Failed to create MIDI port: {}", e))?;
+                let subscribe = PortSubscribe::empty().map_err(|e| e.to_string())?;
+                subscribe.set_sender(Addr {
+                        client: seq.client_id().map_err(|e| e.to_string())?,
+                        port,
+                });
+                subscribe.set_dest(dest);
+                seq.subscribe_port(&subscribe)
+                        .map_err(|e| format!("Failed to connect MIDI port {}: {}", port_id, e))?;
+
+                // 发送线程：按到期时间依次发送；通道关闭（连接被 drop）后退出
+                let (sender, receiver) = bounded::<(Instant, MidiMessage)>(OUTPUT_QUEUE_CAPACITY);
+                let thread = std::thread::Builder::new()
+                        .name("midi-output".to_string())
+                        .spawn(move || {
+                                let Ok(mut encoder) = MidiEvent::new(256) else {
+                                        return;
+                                };
+                                encoder.enable_running_status(false);
+                                while let Ok((due, msg)) = receiver.recv() {
+                                        let now = Instant::now();
+                                        if due > now {
+                                                std::thread::sleep(due - now);
+                                        }
+                                        encoder.reset_encode();
+                                        let Ok((_, Some(mut event))) = encoder.encode(&msg.to_bytes()) else {
+                                                continue;
+                                        };
+                                        event.set_source(port);
+                                        event.set_subs();
+                                        event.set_direct();
+                                        let _ = seq.event_output_direct(&mut event);
+                                }
+                        })
+                        .map_err(|e| e.to_string())?;
+                Ok(Self {
+                        sender: Some(sender),
+                        thread: Some(thread),
+                })
+        }
+
+        pub fn send_at(&self, due: Instant, msg: MidiMessage) {
+                if let Some(sender) = &self.sender {
+                        let _ = sender.try_send((due, msg));
+                }
+        }
+}
+
+impl Drop for OutputConnection {
+        fn drop(&mut self) {
+                // 先关闭通道让发送线程退出，再等待其结束
+                self.sender = None;
+                if let Some(thread) = self.thread.take() {
+                        let _ = thread.join();
+                }
+        }
+}
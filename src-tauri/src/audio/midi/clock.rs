@@ -0,0 +1,176 @@
+use super::MidiMessage;
+use crossbeam_channel::{Receiver, Sender, TrySendError, bounded};
+use serde::{Deserialize, Serialize};
+use std::sync::{LazyLock, OnceLock};
+use std::time::Instant;
+
+// 外部走带同步输入（MIDI Clock 从属模式）：跟随外部主机的 Clock / Start / Stop / Continue / Song Position Pointer，
+// 计算主机的歌曲位置（四分音符）与平滑后的速度，由音序器据此调整播放位置与播放速率。
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MidiClockInputSettings {
+        /// 跟随外部 MIDI Clock；启用时本地的播放 / 暂停 / 定位命令让位于外部主机
+        #[serde(default)]
+        pub enabled: bool,
+        /// 只接受该输入端口（`MidiPortInfo::name`）的同步消息；None 表示任意已打开的输入端口
+        #[serde(default)]
+        pub port: Option<String>,
+}
+
+impl MidiClockInputSettings {
+        pub fn accepts(&self, port: &str) -> bool {
+                self.enabled && self.port.as_deref().is_none_or(|p| p == port)
+        }
+}
+
+/// 进程内单调时钟（秒），用于在线程之间传递消息的接收时间
+pub fn monotonic_seconds() -> f64 {
+        static ORIGIN: OnceLock<Instant> = OnceLock::new();
+        ORIGIN.get_or_init(Instant::now).elapsed().as_secs_f64()
+}
+
+/// 交给音序器的走带更新
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClockUpdate {
+        pub playing: bool,
+        /// 主机的歌曲位置（四分音符），对应消息接收时刻
+        pub beats: f64,
+        /// 平滑后的主机速度（BPM）；尚未测得时为 None
+        pub tempo: Option<f64>,
+        /// 需要直接跳到 `beats`（开始、继续或定位），否则只做相位校正
+        pub relocate: bool,
+        /// 消息接收时间（`monotonic_seconds`）
+        pub received: f64,
+}
+
+// MIDI 输入线程 -> 音频线程的走带更新队列容量
+const CLOCK_QUEUE_CAPACITY: usize = 256;
+
+// 有界队列在首次使用时预分配，两端都不加锁；不经过音频引擎的互斥锁
+static CLOCK_UPDATES: LazyLock<(Sender<ClockUpdate>, Receiver<ClockUpdate>)> =
+        LazyLock::new(|| bounded(CLOCK_QUEUE_CAPACITY));
+
+/// MIDI 输入线程：把走带更新交给音频线程。引擎未运行时队列没有消费者，
+/// 队列满时丢弃最旧的更新，保证最新的状态（例如 Start）不会丢失
+pub fn push_clock_update(update: ClockUpdate) {
+        let (sender, receiver) = &*CLOCK_UPDATES;
+        if let Err(TrySendError::Full(update)) = sender.try_send(update) {
+                let _ = receiver.try_recv();
+                let _ = sender.try_send(update);
+        }
+}
+
+/// 音频线程：按到达顺序取出待处理的走带更新（不阻塞、不分配）
+pub fn pop_clock_update() -> Option<ClockUpdate> {
+        CLOCK_UPDATES.1.try_recv().ok()
+}
+
+// 每个四分音符的 Clock 数
+const CLOCKS_PER_QUARTER: f64 = 24.0;
+// 每个十六分音符的 Clock 数（Song Position Pointer 的单位）
+const CLOCKS_PER_SIXTEENTH: u64 = 6;
+// 可接受的 Clock 间隔（对应约 20 - 400 BPM），超出范围视为中断并重新测量
+const MIN_CLOCK_INTERVAL: f64 = 60.0 / (400.0 * CLOCKS_PER_QUARTER);
+const MAX_CLOCK_INTERVAL: f64 = 60.0 / (20.0 * CLOCKS_PER_QUARTER);
+// 间隔的指数平滑系数：抖动较大的 USB / 网络 MIDI 上也能得到稳定的速度
+const SMOOTHING: f64 = 0.05;
+
+/// 跟随外部 MIDI Clock 的状态机
+#[derive(Debug, Default)]
+pub struct MidiClockFollower {
+        // 下一个 Clock 对应的歌曲位置（Clock 数）
+        clocks: u64,
+        // 收到 Start / Continue，等待第一个 Clock 开始走带
+        armed: bool,
+        running: bool,
+        interval: Option<f64>,
+        last_clock: Option<f64>,
+}
+
+impl MidiClockFollower {
+        pub fn new() -> Self {
+                Self::default()
+        }
+
+        /// 外部主机正在走带
+        pub fn is_running(&self) -> bool {
+                self.running || self.armed
+        }
+
+        /// 平滑后的主机速度（BPM）
+        pub fn tempo(&self) -> Option<f64> {
+                self.interval.map(|i| 60.0 / (i * CLOCKS_PER_QUARTER))
+        }
+
+        fn beats(&self) -> f64 {
+                self.clocks as f64 / CLOCKS_PER_QUARTER
+        }
+
+        fn update(&self, playing: bool, relocate: bool, at: f64) -> ClockUpdate {
+                ClockUpdate {
+                        playing,
+                        beats: self.beats(),
+                        tempo: self.tempo(),
+                        relocate,
+                        received: at,
+                }
+        }
+
+        // 测量 Clock 间隔；主机停止时仍可能持续发送 Clock，此时也用于测速
+        fn measure(&mut self, at: f64) {
+                if let Some(last) = self.last_clock {
+                        let dt = at - last;
+                        if (MIN_CLOCK_INTERVAL..=MAX_CLOCK_INTERVAL).contains(&dt) {
+                                self.interval = Some(match self.interval {
+                                        Some(i) => i + (dt - i) * SMOOTHING,
+                                        None => dt,
+                                });
+                        } else if dt > MAX_CLOCK_INTERVAL {
+                                self.interval = None;
+                        }
+                }
+                self.last_clock = Some(at);
+        }
+
+        /// 处理一条消息（`at` 为接收时间，秒）；非同步消息返回 None
+        pub fn handle(&mut self, msg: &MidiMessage, at: f64) -> Option<ClockUpdate> {
+                match *msg {
+                        MidiMessage::Start => {
+                                self.clocks = 0;
+                                self.armed = true;
+                                self.running = false;
+                                None
+                        }
+                        MidiMessage::Continue => {
+                                self.armed = true;
+                                self.running = false;
+                                None
+                        }
+                        MidiMessage::Stop => {
+                                let was_running = self.is_running();
+                                self.armed = false;
+                                self.running = false;
+                                was_running.then(|| self.update(false, false, at))
+                        }
+                        // 规范要求主机只在停止时发送 Song Position Pointer
+                        MidiMessage::SongPosition(sixteenths) => {
+                                self.clocks = sixteenths as u64 * CLOCKS_PER_SIXTEENTH;
+                                (!self.running).then(|| self.update(false, true, at))
+                        }
+                        MidiMessage::Clock => {
+                                self.measure(at);
+                                if !self.armed && !self.running {
+                                        return None;
+                                }
+                                // Start / Continue 之后的第一个 Clock 标志走带真正开始
+                                let relocate = self.armed;
+                                self.armed = false;
+                                self.running = true;
+                                let update = self.update(true, relocate, at);
+                                self.clocks += 1;
+                                Some(update)
+                        }
+                        _ => None,
+                }
+        }
+}
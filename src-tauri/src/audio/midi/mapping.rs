@@ -0,0 +1,249 @@
+use super::MidiMessage;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// MIDI 控制映射：把控制器消息（CC / 弯音 / 触后）绑定到插件参数、混音轨道音量/声像或走带动作。
+// 映射在目标的归一化行程（0..1）上工作：输入先按 `min..max` 缩放，再按接管模式决定是否/如何写入。
+
+/// 控制器消息类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MidiControlKind {
+        ControlChange { controller: u8 },
+        PitchBend,
+        ChannelPressure,
+        PolyPressure { note: u8 },
+}
+
+/// 映射的消息来源；`port` / `channel` 为 None 表示匹配任意端口 / 通道
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MidiControlSource {
+        /// 输入端口名（`MidiPortInfo::name`）
+        #[serde(default)]
+        pub port: Option<String>,
+        /// MIDI 通道 0..=15
+        #[serde(default)]
+        pub channel: Option<u8>,
+        pub kind: MidiControlKind,
+}
+
+/// 从控制器消息中取出的输入值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ControlInput {
+        /// 归一化值 0..1
+        pub value: f32,
+        /// 7 位原始值（相对模式使用；弯音为高 7 位）
+        pub raw: u8,
+}
+
+impl MidiControlSource {
+        /// 学习：用收到的消息构造来源（非控制器消息返回 None）
+        pub fn learn(port: &str, msg: &MidiMessage) -> Option<Self> {
+                let (channel, kind) = match *msg {
+                        MidiMessage::ControlChange {
+                                channel, controller, ..
+                        } => (channel, MidiControlKind::ControlChange { controller }),
+                        MidiMessage::PitchBend { channel, .. } => (channel, MidiControlKind::PitchBend),
+                        MidiMessage::ChannelPressure { channel, .. } => (channel, MidiControlKind::ChannelPressure),
+                        MidiMessage::PolyPressure { channel, note, .. } => {
+                                (channel, MidiControlKind::PolyPressure { note })
+                        }
+                        _ => return None,
+                };
+                Some(Self {
+                        port: Some(port.to_string()),
+                        channel: Some(channel),
+                        kind,
+                })
+        }
+
+        /// 消息与来源匹配时返回输入值
+        pub fn matches(&self, port: &str, msg: &MidiMessage) -> Option<ControlInput> {
+                if self.port.as_deref().is_some_and(|p| p != port) {
+                        return None;
+                }
+                let (channel, raw, value) = match (*msg, self.kind) {
+                        (
+                                MidiMessage::ControlChange {
+                                        channel,
+                                        controller,
+                                        value,
+                                },
+                                MidiControlKind::ControlChange { controller: c },
+                        ) if controller == c => (channel, value, value as f32 / 127.0),
+                        (MidiMessage::PitchBend { channel, value }, MidiControlKind::PitchBend) => {
+                                (channel, (value >> 7) as u8, value as f32 / 16383.0)
+                        }
+                        (MidiMessage::ChannelPressure { channel, pressure }, MidiControlKind::ChannelPressure) => {
+                                (channel, pressure, pressure as f32 / 127.0)
+                        }
+                        (
+                                MidiMessage::PolyPressure {
+                                        channel,
+                                        note,
+                                        pressure,
+                                },
+                                MidiControlKind::PolyPressure { note: n },
+                        ) if note == n => (channel, pressure, pressure as f32 / 127.0),
+                        _ => return None,
+                };
+                if self.channel.is_some_and(|c| c != channel) {
+                        return None;
+                }
+                Some(ControlInput { value, raw })
+        }
+}
+
+/// 走带动作（按钮按下，即输入越过中点时触发）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransportAction {
+        Play,
+        Pause,
+        Stop,
+        TogglePlay,
+}
+
+/// 映射目标
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MappingTarget {
+        /// 插件实例（乐器或插入效果）的参数
+        PluginParameter {
+                instance_id: String,
+                param_id: u32,
+        },
+        /// 混音轨道音量（按轨道节点 ID）
+        MixerVolume {
+                node: Uuid,
+        },
+        /// 混音轨道声像
+        MixerPan {
+                node: Uuid,
+        },
+        Transport {
+                action: TransportAction,
+        },
+}
+
+/// 控制器与目标当前值不一致时的接管方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TakeoverMode {
+        /// 直接跳到控制器位置
+        #[default]
+        Jump,
+        /// 控制器经过目标当前值后才开始生效，避免参数突跳
+        Pickup,
+        /// 相对编码器：CC 1..63 增加、65..127 减少（二进制补码）
+        Relative,
+}
+
+/// 映射的保存位置：随工程保存，或作为全局设置保存在应用数据目录
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingScope {
+        #[default]
+        Project,
+        Global,
+}
+
+// Pickup 模式下认为“已追上”的距离（归一化）
+const PICKUP_THRESHOLD: f32 = 0.02;
+// 相对模式每一格的步长（占 min..max 范围的比例）
+const RELATIVE_STEP: f32 = 1.0 / 128.0;
+
+fn default_max() -> f32 {
+        1.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MidiMapping {
+        pub id: Uuid,
+        pub source: MidiControlSource,
+        pub target: MappingTarget,
+        /// 控制器全程对应的目标行程范围（归一化，min > max 时反向）
+        #[serde(default)]
+        pub min: f32,
+        #[serde(default = "default_max")]
+        pub max: f32,
+        #[serde(default)]
+        pub takeover: TakeoverMode,
+        #[serde(default)]
+        pub scope: MappingScope,
+        // 运行时状态：Pickup 是否已追上、上次写入的值与上次的输入值
+        #[serde(skip)]
+        picked_up: bool,
+        #[serde(skip)]
+        last_sent: Option<f32>,
+        #[serde(skip)]
+        last_input: Option<f32>,
+}
+
+impl MidiMapping {
+        pub fn new(source: MidiControlSource, target: MappingTarget) -> Self {
+                Self {
+                        id: Uuid::new_v4(),
+                        source,
+                        target,
+                        min: 0.0,
+                        max: default_max(),
+                        takeover: TakeoverMode::Jump,
+                        scope: MappingScope::Project,
+                        picked_up: false,
+                        last_sent: None,
+                        last_input: None,
+                }
+        }
+
+        /// 根据输入与目标当前的归一化值计算要写入的新值（None 表示不写入）
+        pub fn resolve(&mut self, input: ControlInput, current: f32) -> Option<f32> {
+                let previous_input = self.last_input.replace(input.value);
+                let scaled = self.min + (self.max - self.min) * input.value;
+                let value = match self.takeover {
+                        TakeoverMode::Jump => scaled,
+                        TakeoverMode::Relative => {
+                                let steps = match input.raw {
+                                        0 | 64 => 0,
+                                        r if r < 64 => r as i32,
+                                        r => r as i32 - 128,
+                                };
+                                let (lo, hi) = (self.min.min(self.max), self.min.max(self.max));
+                                (current + steps as f32 * RELATIVE_STEP * (hi - lo)).clamp(lo, hi)
+                        }
+                        TakeoverMode::Pickup => {
+                                // 目标被其它途径（界面、自动化）改动后需要重新追上
+                                if self.last_sent
+                                        .is_some_and(|sent| (sent - current).abs() > PICKUP_THRESHOLD)
+                                {
+                                        self.picked_up = false;
+                                }
+                                if !self.picked_up {
+                                        let previous = previous_input.map(|p| self.min + (self.max - self.min) * p);
+                                        let crossed =
+                                                previous.is_some_and(|p| (p - current) * (scaled - current) <= 0.0);
+                                        if (scaled - current).abs() > PICKUP_THRESHOLD && !crossed {
+                                                return None;
+                                        }
+                                        self.picked_up = true;
+                                }
+                                scaled
+                        }
+                };
+                self.last_sent = Some(value);
+                Some(value)
+        }
+
+        /// 走带映射：输入从中点以下越过中点（按钮按下）时返回 true
+        pub fn trigger(&mut self, input: ControlInput) -> bool {
+                let previous = self.last_input.replace(input.value);
+                input.value >= 0.5 && previous.is_none_or(|p| p < 0.5)
+        }
+
+        /// 清除接管状态（目标或模式改变后）
+        pub fn reset_takeover(&mut self) {
+                self.picked_up = false;
+                self.last_sent = None;
+                self.last_input = None;
+        }
+}
@@ -0,0 +1,296 @@
+/// MIDI 硬件端口：枚举输入 / 输出端口、打开连接，把收到的字节流解析为 `MidiMessage`，或按时间发送消息。
+/// Linux 上通过 ALSA sequencer 实现；其它平台暂不支持，端口列表为空。
+pub mod clock;
+pub mod mapping;
+pub mod mpe;
+pub mod sync;
+
+#[cfg(target_os = "linux")]
+mod alsa_seq;
+
+use serde::Serialize;
+use std::time::Instant;
+
+/// 解析后的 MIDI 消息；通道为 0..=15
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiMessage {
+        NoteOff {
+                channel: u8,
+                note: u8,
+                velocity: u8,
+        },
+        NoteOn {
+                channel: u8,
+                note: u8,
+                velocity: u8,
+        },
+        PolyPressure {
+                channel: u8,
+                note: u8,
+                pressure: u8,
+        },
+        ControlChange {
+                channel: u8,
+                controller: u8,
+                value: u8,
+        },
+        ProgramChange {
+                channel: u8,
+                program: u8,
+        },
+        ChannelPressure {
+                channel: u8,
+                pressure: u8,
+        },
+        /// 14 位弯音值，8192 为中心
+        PitchBend {
+                channel: u8,
+                value: u16,
+        },
+        /// MTC 四分之一帧（高 4 位为片段序号 0-7，低 4 位为数据）
+        QuarterFrame(u8),
+        /// Song Position Pointer（单位：十六分音符）
+        SongPosition(u16),
+        Clock,
+        Start,
+        Continue,
+        Stop,
+        /// MTC 完整帧（SysEx），定位时发送；`rate` 为 MTC 帧率编码 0-3
+        TimecodeFullFrame {
+                hours: u8,
+                minutes: u8,
+                seconds: u8,
+                frames: u8,
+                rate: u8,
+        },
+}
+
+impl MidiMessage {
+        /// 解析一条完整的 MIDI 消息（不支持 running status 与 SysEx）
+        pub fn parse(bytes: &[u8]) -> Option<Self> {
+                let status = *bytes.first()?;
+                let data = |i: usize| bytes.get(i).map(|b| b & 0x7f);
+                let channel = status & 0x0f;
+                let msg = match status & 0xf0 {
+                        0x80 => MidiMessage::NoteOff {
+                                channel,
+                                note: data(1)?,
+                                velocity: data(2)?,
+                        },
+                        // velocity 为 0 的 NoteOn 等同于 NoteOff
+                        0x90 => match data(2)? {
+                                0 => MidiMessage::NoteOff {
+                                        channel,
+                                        note: data(1)?,
+                                        velocity: 0,
+                                },
+                                velocity => MidiMessage::NoteOn {
+                                        channel,
+                                        note: data(1)?,
+                                        velocity,
+                                },
+                        },
+                        0xa0 => MidiMessage::PolyPressure {
+                                channel,
+                                note: data(1)?,
+                                pressure: data(2)?,
+                        },
+                        0xb0 => MidiMessage::ControlChange {
+                                channel,
+                                controller: data(1)?,
+                                value: data(2)?,
+                        },
+                        0xc0 => MidiMessage::ProgramChange {
+                                channel,
+                                program: data(1)?,
+                        },
+                        0xd0 => MidiMessage::ChannelPressure {
+                                channel,
+                                pressure: data(1)?,
+                        },
+                        0xe0 => MidiMessage::PitchBend {
+                                channel,
+                                value: data(1)? as u16 | (data(2)? as u16) << 7,
+                        },
+                        _ => match status {
+                                0xf1 => MidiMessage::QuarterFrame(data(1)?),
+                                0xf2 => MidiMessage::SongPosition(data(1)? as u16 | (data(2)? as u16) << 7),
+                                0xf8 => MidiMessage::Clock,
+                                0xfa => MidiMessage::Start,
+                                0xfb => MidiMessage::Continue,
+                                0xfc => MidiMessage::Stop,
+                                _ => return None,
+                        },
+                };
+                Some(msg)
+        }
+
+        /// 编码为 MIDI 字节
+        pub fn to_bytes(&self) -> Vec<u8> {
+                let seven = |v: u8| v & 0x7f;
+                let status = |kind: u8, channel: u8| kind | (channel & 0x0f);
+                match *self {
+                        MidiMessage::NoteOff {
+                                channel,
+                                note,
+                                velocity,
+                        } => vec![status(0x80, channel), seven(note), seven(velocity)],
+                        MidiMessage::NoteOn {
+                                channel,
+                                note,
+                                velocity,
+                        } => vec![status(0x90, channel), seven(note), seven(velocity)],
+                        MidiMessage::PolyPressure {
+                                channel,
+                                note,
+                                pressure,
+                        } => vec![status(0xa0, channel), seven(note), seven(pressure)],
+                        MidiMessage::ControlChange {
+                                channel,
+                                controller,
+                                value,
+                        } => vec![status(0xb0, channel), seven(controller), seven(value)],
+                        MidiMessage::ProgramChange { channel, program } => vec![status(0xc0, channel), seven(program)],
+                        MidiMessage::ChannelPressure { channel, pressure } => {
+                                vec![status(0xd0, channel), seven(pressure)]
+                        }
+                        MidiMessage::PitchBend { channel, value } => {
+                                vec![
+                                        status(0xe0, channel),
+                                        (value & 0x7f) as u8,
+                                        ((value >> 7) & 0x7f) as u8,
+                                ]
+                        }
+                        MidiMessage::QuarterFrame(data) => vec![0xf1, seven(data)],
+                        MidiMessage::SongPosition(position) => {
+                                vec![
+                                        0xf2,
+                                        (position & 0x7f) as u8,
+                                        ((position >> 7) & 0x7f) as u8,
+                                ]
+                        }
+                        MidiMessage::Clock => vec![0xf8],
+                        MidiMessage::Start => vec![0xfa],
+                        MidiMessage::Continue => vec![0xfb],
+                        MidiMessage::Stop => vec![0xfc],
+                        MidiMessage::TimecodeFullFrame {
+                                hours,
+                                minutes,
+                                seconds,
+                                frames,
+                                rate,
+                        } => vec![
+                                0xf0,
+                                0x7f,
+                                0x7f,
+                                0x01,
+                                0x01,
+                                (rate & 0x03) << 5 | (hours & 0x1f),
+                                seven(minutes),
+                                seven(seconds),
+                                seven(frames),
+                                0xf7,
+                        ],
+                }
+        }
+}
+
+/// MIDI 端口描述：`id` 用于打开连接（会话内有效），`name` 在重启后保持不变，用于持久化的映射
+#[derive(Debug, Clone, Serialize)]
+pub struct MidiPortInfo {
+        pub id: String,
+        pub name: String,
+}
+
+/// 已打开的 MIDI 输入连接；drop 时断开
+pub struct MidiInputConnection {
+        pub port: MidiPortInfo,
+        #[cfg(target_os = "linux")]
+        _inner: alsa_seq::InputConnection,
+}
+
+/// 已打开的 MIDI 输出连接：消息由独立线程按指定时间发送；drop 时断开
+pub struct MidiOutputConnection {
+        pub port: MidiPortInfo,
+        #[cfg(target_os = "linux")]
+        inner: alsa_seq::OutputConnection,
+}
+
+impl MidiOutputConnection {
+        /// 在 `due` 时刻发送消息（已过期则立即发送）；不阻塞、不分配，可在音频线程调用。
+        /// 发送队列容量固定，队列已满时丢弃消息
+        pub fn send_at(&self, due: Instant, msg: MidiMessage) {
+                #[cfg(target_os = "linux")]
+                self.inner.send_at(due, msg);
+                #[cfg(not(target_os = "linux"))]
+                let _ = (due, msg);
+        }
+}
+
+/// 可用的 MIDI 输入端口
+pub fn list_input_ports() -> Vec<MidiPortInfo> {
+        #[cfg(target_os = "linux")]
+        {
+                alsa_seq::list_input_ports()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+                Vec::new()
+        }
+}
+
+/// 打开输入端口；`callback` 在独立线程中为每条收到的消息调用
+pub fn connect_input<F>(port_id: &str, callback: F) -> Result<MidiInputConnection, String>
+where
+        F: FnMut(MidiMessage) + Send + 'static,
+{
+        let port = list_input_ports()
+                .into_iter()
+                .find(|p| p.id == port_id)
+                .ok_or_else(|| format!("MIDI input port not found: {}", port_id))?;
+        #[cfg(target_os = "linux")]
+        {
+                let inner = alsa_seq::InputConnection::open(&port.id, callback)?;
+                Ok(MidiInputConnection { port, _inner: inner })
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+                let _ = callback;
+                Err(format!(
+                        "MIDI input is not supported on this platform: {}",
+                        port.name
+                ))
+        }
+}
+
+/// 可用的 MIDI 输出端口
+pub fn list_output_ports() -> Vec<MidiPortInfo> {
+        #[cfg(target_os = "linux")]
+        {
+                alsa_seq::list_output_ports()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+                Vec::new()
+        }
+}
+
+/// 打开输出端口
+pub fn connect_output(port_id: &str) -> Result<MidiOutputConnection, String> {
+        let port = list_output_ports()
+                .into_iter()
+                .find(|p| p.id == port_id)
+                .ok_or_else(|| format!("MIDI output port not found: {}", port_id))?;
+        #[cfg(target_os = "linux")]
+        {
+                let inner = alsa_seq::OutputConnection::open(&port.id)?;
+                Ok(MidiOutputConnection { port, inner })
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+                Err(format!(
+                        "MIDI output is not supported on this platform: {}",
+                        port.name
+                ))
+        }
+}
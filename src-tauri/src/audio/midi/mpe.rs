@@ -0,0 +1,223 @@
+use crate::audio::core::plugin::{NoteEvent, NoteExpressionKind};
+
+// MPE（MIDI Polyphonic Expression）编码：把带音符 ID 与单音符表情的事件转换为“每音符一个通道”的 MIDI 1.0 消息。
+// 使用下区（Lower Zone）：通道 0 为主通道，通道 1..=15 为成员通道；每个新音符分配一个成员通道，
+// 该音符的音高 / 压力 / 明亮度表情分别以该通道的弯音 / 通道触后 / CC74 发送。
+// 普通控制器、弯音与音色切换作用于整个区，发往主通道。
+
+/// 成员通道的弯音范围（半音，MPE 规范的默认值）
+pub const MPE_PITCH_BEND_RANGE: f32 = 48.0;
+
+const MASTER_CHANNEL: u8 = 0;
+const MEMBER_CHANNELS: usize = 15;
+
+#[derive(Clone, Copy, PartialEq)]
+enum VoiceKey {
+        Id(u32),
+        Note { channel: u8, note: u8 },
+}
+
+impl VoiceKey {
+        fn new(channel: u8, note: u8, note_id: Option<u32>) -> Self {
+                match note_id {
+                        Some(id) => VoiceKey::Id(id),
+                        None => VoiceKey::Note { channel, note },
+                }
+        }
+}
+
+#[derive(Clone, Copy)]
+struct Voice {
+        key: Option<VoiceKey>,
+        // 发往插件的音高（成员通道上）
+        note: u8,
+        // 最近一次分配 / 释放的序号，用于选择最久未用的通道
+        stamp: u64,
+}
+
+/// 每音符一个通道的编码器；固定容量，音频线程上不分配内存
+pub struct MpeEncoder {
+        voices: [Voice; MEMBER_CHANNELS],
+        counter: u64,
+        configured: bool,
+}
+
+impl Default for MpeEncoder {
+        fn default() -> Self {
+                Self::new()
+        }
+}
+
+impl MpeEncoder {
+        pub fn new() -> Self {
+                Self {
+                        voices: [Voice {
+                                key: None,
+                                note: 0,
+                                stamp: 0,
+                        }; MEMBER_CHANNELS],
+                        counter: 0,
+                        configured: false,
+                }
+        }
+
+        fn member_channel(index: usize) -> u8 {
+                index as u8 + 1
+        }
+
+        fn find(&self, key: VoiceKey) -> Option<usize> {
+                self.voices.iter().position(|v| v.key == Some(key))
+        }
+
+        fn next_stamp(&mut self) -> u64 {
+                self.counter += 1;
+                self.counter
+        }
+
+        // 区配置：主通道上的 MPE Configuration Message（RPN 6）与各成员通道的弯音范围（RPN 0）
+        fn configure(&mut self, out: &mut impl FnMut([u8; 3])) {
+                self.configured = true;
+                let rpn = |out: &mut dyn FnMut([u8; 3]), channel: u8, number: u8, value: u8| {
+                        let status = 0xb0 | channel;
+                        out([status, 101, 0]);
+                        out([status, 100, number]);
+                        out([status, 6, value]);
+                        out([status, 38, 0]);
+                        // 复位为空 RPN，防止后续数据输入误改参数
+                        out([status, 101, 127]);
+                        out([status, 100, 127]);
+                };
+                rpn(out, MASTER_CHANNEL, 6, MEMBER_CHANNELS as u8);
+                for index in 0..MEMBER_CHANNELS {
+                        rpn(
+                                out,
+                                Self::member_channel(index),
+                                0,
+                                MPE_PITCH_BEND_RANGE as u8,
+                        );
+                }
+        }
+
+        /// 编码一个事件，按顺序把生成的 MIDI 消息交给 `out`
+        pub fn encode(&mut self, event: &NoteEvent, mut out: impl FnMut([u8; 3])) {
+                if !self.configured {
+                        self.configure(&mut out);
+                }
+                match *event {
+                        NoteEvent::NoteOn {
+                                channel,
+                                note,
+                                velocity,
+                                note_id,
+                        } => {
+                                let key = VoiceKey::new(channel, note, note_id);
+                                // 同一音符重复按下时先释放旧的发声
+                                if let Some(index) = self.find(key) {
+                                        self.release(index, &mut out);
+                                }
+                                let index = self.allocate(&mut out);
+                                let member = Self::member_channel(index);
+                                // 新音符开始前把成员通道的表情复位（弯音居中、压力 0、明亮度居中）
+                                let [lsb, msb] = NoteEvent::pitch_bend_bytes(0.0);
+                                out([0xe0 | member, lsb, msb]);
+                                out([0xd0 | member, 0, 0]);
+                                out([0xb0 | member, 74, 64]);
+                                let stamp = self.next_stamp();
+                                self.voices[index] = Voice {
+                                        key: Some(key),
+                                        note,
+                                        stamp,
+                                };
+                                let bytes = NoteEvent::NoteOn {
+                                        channel: member,
+                                        note,
+                                        velocity,
+                                        note_id,
+                                }
+                                .to_midi_bytes();
+                                out(bytes);
+                        }
+                        NoteEvent::NoteOff { channel, note, note_id } => {
+                                if let Some(index) = self.find(VoiceKey::new(channel, note, note_id)) {
+                                        self.release(index, &mut out);
+                                }
+                        }
+                        NoteEvent::NoteExpression {
+                                channel,
+                                note,
+                                note_id,
+                                expression,
+                                value,
+                        } => {
+                                let Some(index) = self.find(VoiceKey::new(channel, note, note_id)) else {
+                                        return;
+                                };
+                                let member = Self::member_channel(index);
+                                let seven_bit = |v: f32| (v.clamp(0.0, 1.0) * 127.0).round() as u8;
+                                match expression {
+                                        NoteExpressionKind::Pitch => {
+                                                let [lsb, msb] =
+                                                        NoteEvent::pitch_bend_bytes(value / MPE_PITCH_BEND_RANGE);
+                                                out([0xe0 | member, lsb, msb]);
+                                        }
+                                        NoteExpressionKind::Pressure => out([0xd0 | member, seven_bit(value), 0]),
+                                        NoteExpressionKind::Brightness => out([0xb0 | member, 74, seven_bit(value)]),
+                                }
+                        }
+                        // MPE 中单音符触后以成员通道的通道触后表示
+                        NoteEvent::PolyPressure {
+                                channel,
+                                note,
+                                pressure,
+                        } => {
+                                let voice = self
+                                        .voices
+                                        .iter()
+                                        .position(|v| v.key == Some(VoiceKey::Note { channel, note }))
+                                        .or_else(|| self.voices.iter().position(|v| v.key.is_some() && v.note == note));
+                                if let Some(index) = voice {
+                                        let member = Self::member_channel(index);
+                                        let bytes = NoteEvent::ChannelPressure {
+                                                channel: member,
+                                                pressure,
+                                        }
+                                        .to_midi_bytes();
+                                        out(bytes);
+                                }
+                        }
+                        // 其余通道消息作用于整个区
+                        NoteEvent::ControlChange { .. }
+                        | NoteEvent::PitchBend { .. }
+                        | NoteEvent::ChannelPressure { .. }
+                        | NoteEvent::ProgramChange { .. } => {
+                                let mut bytes = event.to_midi_bytes();
+                                bytes[0] = (bytes[0] & 0xf0) | MASTER_CHANNEL;
+                                out(bytes);
+                        }
+                }
+        }
+
+        // 选择空闲且最久未用的成员通道；全部占用时抢占最早开始的音符
+        fn allocate(&mut self, out: &mut impl FnMut([u8; 3])) -> usize {
+                let free = (0..MEMBER_CHANNELS)
+                        .filter(|&i| self.voices[i].key.is_none())
+                        .min_by_key(|&i| self.voices[i].stamp);
+                match free {
+                        Some(index) => index,
+                        None => {
+                                let oldest = (0..MEMBER_CHANNELS).min_by_key(|&i| self.voices[i].stamp).unwrap_or(0);
+                                self.release(oldest, out);
+                                oldest
+                        }
+                }
+        }
+
+        fn release(&mut self, index: usize, out: &mut impl FnMut([u8; 3])) {
+                let member = Self::member_channel(index);
+                out([0x80 | member, self.voices[index].note & 0x7f, 0]);
+                let stamp = self.next_stamp();
+                let voice = &mut self.voices[index];
+                voice.key = None;
+                voice.stamp = stamp;
+        }
+}
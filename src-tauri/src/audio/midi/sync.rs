@@ -0,0 +1,287 @@
+use super::{MidiMessage, MidiOutputConnection, connect_output, list_output_ports};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// 走带同步输出：根据音序器的播放时间与速度生成 MIDI Clock（24 ppqn，含 Start / Stop / Continue 与
+// Song Position Pointer）和 MTC 四分之一帧，按时间发送到选定的 MIDI 输出端口。
+// 生成器状态保存在全局，音频图重建后仍能检测到定位并保持外部设备同步。
+
+/// MTC 帧率（不支持丢帧格式）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MtcFrameRate {
+        Fps24,
+        #[default]
+        Fps25,
+        Fps30,
+}
+
+impl MtcFrameRate {
+        pub fn fps(self) -> u32 {
+                match self {
+                        MtcFrameRate::Fps24 => 24,
+                        MtcFrameRate::Fps25 => 25,
+                        MtcFrameRate::Fps30 => 30,
+                }
+        }
+
+        // MTC 中的帧率编码（2 位）
+        fn code(self) -> u8 {
+                match self {
+                        MtcFrameRate::Fps24 => 0,
+                        MtcFrameRate::Fps25 => 1,
+                        MtcFrameRate::Fps30 => 3,
+                }
+        }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MidiSyncSettings {
+        /// 输出端口名（`MidiPortInfo::name`）；None 表示关闭同步输出
+        #[serde(default)]
+        pub port: Option<String>,
+        /// 发送 MIDI Clock 与 Start / Stop / Continue / Song Position Pointer
+        #[serde(default)]
+        pub clock: bool,
+        /// 发送 MTC 四分之一帧（定位时发送完整帧）
+        #[serde(default)]
+        pub timecode: bool,
+        #[serde(default)]
+        pub frame_rate: MtcFrameRate,
+        /// 发送时间偏移（毫秒）：正值延后，负值提前，用于对齐外部设备
+        #[serde(default)]
+        pub latency_offset_ms: f64,
+}
+
+impl MidiSyncSettings {
+        pub fn enabled(&self) -> bool {
+                self.port.is_some() && (self.clock || self.timecode)
+        }
+}
+
+// 每个四分音符的 Clock 数
+const CLOCKS_PER_QUARTER: f64 = 24.0;
+// 每个十六分音符的 Clock 数（Song Position Pointer 的单位）
+const CLOCKS_PER_SIXTEENTH: f64 = 6.0;
+// 判断是否发生定位（跳转）的容差（秒）
+const JUMP_EPSILON: f64 = 1e-6;
+
+/// 同步消息生成器：按块处理歌曲时间，输出 (块内偏移秒, 消息)
+pub struct MidiSyncGenerator {
+        clock: bool,
+        timecode: bool,
+        frame_rate: MtcFrameRate,
+        was_playing: bool,
+        last_end: Option<f64>,
+        tempo: f64,
+        // 下一个要发送的 Clock / 四分之一帧的序号（从歌曲开头计）
+        next_clock: u64,
+        next_quarter_frame: u64,
+}
+
+impl MidiSyncGenerator {
+        pub fn new(settings: &MidiSyncSettings) -> Self {
+                Self {
+                        clock: settings.clock,
+                        timecode: settings.timecode,
+                        frame_rate: settings.frame_rate,
+                        was_playing: false,
+                        last_end: None,
+                        tempo: 0.0,
+                        next_clock: 0,
+                        next_quarter_frame: 0,
+                }
+        }
+
+        fn full_frame(&self, time: f64) -> MidiMessage {
+                let fps = self.frame_rate.fps() as u64;
+                let (hours, minutes, seconds, frames) = split_timecode((time.max(0.0) * fps as f64) as u64, fps);
+                MidiMessage::TimecodeFullFrame {
+                        hours,
+                        minutes,
+                        seconds,
+                        frames,
+                        rate: self.frame_rate.code(),
+                }
+        }
+
+        // 第 `index` 个四分之一帧：每 8 片描述一次时间码（片段 0 所在的帧），跨越 2 帧
+        fn quarter_frame(&self, index: u64) -> MidiMessage {
+                let fps = self.frame_rate.fps() as u64;
+                let piece = (index % 8) as u8;
+                let (hours, minutes, seconds, frames) = split_timecode(index / 8 * 2, fps);
+                let nibble = match piece {
+                        0 => frames & 0x0f,
+                        1 => frames >> 4,
+                        2 => seconds & 0x0f,
+                        3 => seconds >> 4,
+                        4 => minutes & 0x0f,
+                        5 => minutes >> 4,
+                        6 => hours & 0x0f,
+                        _ => (hours >> 4) & 0x01 | self.frame_rate.code() << 1,
+                };
+                MidiMessage::QuarterFrame(piece << 4 | nibble)
+        }
+
+        /// 处理歌曲时间 `[start, end)`；非播放状态下 `end == start`
+        pub fn process(
+                &mut self,
+                start: f64,
+                end: f64,
+                playing: bool,
+                tempo: f64,
+                mut out: impl FnMut(f64, MidiMessage),
+        ) {
+                let jumped = self.last_end.is_some_and(|e| (e - start).abs() > JUMP_EPSILON);
+                self.last_end = Some(end);
+                let clock_len = 60.0 / tempo.max(1.0) / CLOCKS_PER_QUARTER;
+
+                if !playing {
+                        if self.was_playing && self.clock {
+                                out(0.0, MidiMessage::Stop);
+                        }
+                        // 停止状态下定位：更新外部设备的位置显示
+                        if jumped || self.was_playing {
+                                if self.clock {
+                                        let sixteenths = (start / (clock_len * CLOCKS_PER_SIXTEENTH)).floor();
+                                        out(
+                                                0.0,
+                                                MidiMessage::SongPosition(sixteenths.min(16383.0) as u16),
+                                        );
+                                }
+                                if self.timecode {
+                                        out(0.0, self.full_frame(start));
+                                }
+                        }
+                        self.was_playing = false;
+                        return;
+                }
+
+                if !self.was_playing || jumped {
+                        if self.was_playing && self.clock {
+                                // 播放中跳转（含循环回绕）：外部设备只在停止状态下接受新的位置
+                                out(0.0, MidiMessage::Stop);
+                        }
+                        if self.clock {
+                                if start <= JUMP_EPSILON {
+                                        out(0.0, MidiMessage::Start);
+                                        self.next_clock = 0;
+                                } else {
+                                        // 从下一个十六分音符开始继续：之前的 Clock 不发送，保证对齐
+                                        let sixteenths =
+                                                (start / (clock_len * CLOCKS_PER_SIXTEENTH)).ceil().min(16383.0);
+                                        out(0.0, MidiMessage::SongPosition(sixteenths as u16));
+                                        out(0.0, MidiMessage::Continue);
+                                        self.next_clock = (sixteenths * CLOCKS_PER_SIXTEENTH) as u64;
+                                }
+                        }
+                        if self.timecode {
+                                out(0.0, self.full_frame(start));
+                                let quarter_len = 1.0 / (self.frame_rate.fps() as f64 * 4.0);
+                                self.next_quarter_frame = (start / quarter_len).ceil() as u64;
+                        }
+                }
+                self.was_playing = true;
+
+                // 速度变化后按新的 Clock 间隔重新对齐序号
+                if tempo != self.tempo {
+                        if self.tempo > 0.0 && self.clock {
+                                self.next_clock = (start / clock_len).ceil() as u64;
+                        }
+                        self.tempo = tempo;
+                }
+
+                if self.clock {
+                        loop {
+                                let time = self.next_clock as f64 * clock_len;
+                                if time >= end {
+                                        break;
+                                }
+                                out((time - start).max(0.0), MidiMessage::Clock);
+                                self.next_clock += 1;
+                        }
+                }
+                if self.timecode {
+                        let quarter_len = 1.0 / (self.frame_rate.fps() as f64 * 4.0);
+                        loop {
+                                let time = self.next_quarter_frame as f64 * quarter_len;
+                                if time >= end {
+                                        break;
+                                }
+                                out(
+                                        (time - start).max(0.0),
+                                        self.quarter_frame(self.next_quarter_frame),
+                                );
+                                self.next_quarter_frame += 1;
+                        }
+                }
+        }
+}
+
+// 总帧数 -> (时, 分, 秒, 帧)
+fn split_timecode(total_frames: u64, fps: u64) -> (u8, u8, u8, u8) {
+        let frames = (total_frames % fps) as u8;
+        let total_seconds = total_frames / fps;
+        let seconds = (total_seconds % 60) as u8;
+        let minutes = (total_seconds / 60 % 60) as u8;
+        let hours = (total_seconds / 3600 % 24) as u8;
+        (hours, minutes, seconds, frames)
+}
+
+struct SyncOutput {
+        settings: MidiSyncSettings,
+        generator: MidiSyncGenerator,
+        connection: MidiOutputConnection,
+}
+
+static SYNC_OUTPUT: Mutex<Option<SyncOutput>> = Mutex::new(None);
+
+/// 应用同步输出设置：按端口名打开输出端口（设置关闭时断开）
+pub fn configure(settings: &MidiSyncSettings) -> Result<(), String> {
+        let next = match (&settings.port, settings.enabled()) {
+                (Some(name), true) => {
+                        let port = list_output_ports()
+                                .into_iter()
+                                .find(|p| &p.name == name)
+                                .ok_or_else(|| format!("MIDI output port not found: {}", name))?;
+                        Some(SyncOutput {
+                                settings: settings.clone(),
+                                generator: MidiSyncGenerator::new(settings),
+                                connection: connect_output(&port.id)?,
+                        })
+                }
+                _ => None,
+        };
+        let previous = {
+                let mut output = SYNC_OUTPUT.lock().map_err(|_| "Failed to lock MIDI sync output")?;
+                std::mem::replace(&mut *output, next)
+        };
+        // 在释放锁之后断开旧连接（会等待其发送线程退出）
+        drop(previous);
+        Ok(())
+}
+
+/// 音频线程：为本块生成同步消息并排入输出队列。
+/// `latency` 为本块音频从回调开始到实际播放的时间（秒，音频图延迟 + 设备输出延迟），
+/// 与设置中的偏移一起推迟发送时间，使消息与本块音频同时到达。
+pub fn process_block(start: f64, end: f64, playing: bool, tempo: f64, latency: f64) {
+        let Ok(mut guard) = SYNC_OUTPUT.try_lock() else {
+                return;
+        };
+        let Some(output) = guard.as_mut() else {
+                return;
+        };
+        let now = Instant::now();
+        let base = latency + output.settings.latency_offset_ms / 1000.0;
+        let connection = &output.connection;
+        output.generator.process(start, end, playing, tempo, |offset, msg| {
+                let delay = offset + base;
+                let due = if delay >= 0.0 {
+                        now + Duration::from_secs_f64(delay)
+                } else {
+                        now.checked_sub(Duration::from_secs_f64(-delay)).unwrap_or(now)
+                };
+                connection.send_at(due, msg);
+        });
+}
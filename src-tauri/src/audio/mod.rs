@@ -1,4 +1,8 @@
+pub mod analysis;
 pub mod core;
 pub mod engine;
+pub mod jack;
+pub mod midi;
 pub mod plugins;
 pub mod processor;
+pub mod sandbox;
@@ -0,0 +1,368 @@
+use crate::audio::core::plugin::{NoteEvent, NoteExpressionKind};
+use clap_sys::events::{
+        CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI, CLAP_EVENT_MIDI2, CLAP_EVENT_NOTE_EXPRESSION, CLAP_EVENT_NOTE_OFF,
+        CLAP_EVENT_NOTE_ON, CLAP_EVENT_PARAM_VALUE, CLAP_NOTE_EXPRESSION_BRIGHTNESS, CLAP_NOTE_EXPRESSION_PRESSURE,
+        CLAP_NOTE_EXPRESSION_TUNING, clap_event_header, clap_event_midi, clap_event_midi2, clap_event_note,
+        clap_event_note_expression, clap_event_param_value, clap_input_events, clap_output_events,
+};
+use clap_sys::ext::note_ports::{
+        CLAP_NOTE_DIALECT_CLAP, CLAP_NOTE_DIALECT_MIDI, CLAP_NOTE_DIALECT_MIDI_MPE, CLAP_NOTE_DIALECT_MIDI2,
+        clap_note_dialect,
+};
+use std::ffi::c_void;
+use std::ptr;
+
+// CLAP 事件列表：宿主通过 `clap_input_events` 向插件传入事件，
+// 插件通过 `clap_output_events` 回传事件（例如 UI 引起的参数变化）。
+// 列表容量在创建时预分配，音频线程上不再分配内存。
+
+/// 可存放任意受支持 CLAP 事件的定长存储单元（所有事件均以 header 开头）
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union ClapEvent {
+        pub header: clap_event_header,
+        pub param_value: clap_event_param_value,
+        pub midi: clap_event_midi,
+        pub note: clap_event_note,
+        pub note_expression: clap_event_note_expression,
+        pub midi2: clap_event_midi2,
+}
+
+/// 单音符音高弯音（MIDI 2.0 Per-Note Pitch Bend）的默认范围（半音）
+const MIDI2_PER_NOTE_PITCH_RANGE: f32 = 48.0;
+
+/// 宿主向插件第一个输入音符端口发送音符事件时使用的格式，由端口声明的方言决定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteDialect {
+        /// CLAP 音符 / 表情事件；`midi` 表示端口同时接受 MIDI 1.0（用于控制器、弯音等通道消息）
+        Clap { midi: bool },
+        /// MIDI 1.0（含 MPE）字节消息
+        Midi,
+        /// MIDI 2.0 UMP 通道消息
+        Midi2,
+}
+
+impl NoteDialect {
+        /// 根据端口支持的方言与首选方言选择格式：首选方言受支持时使用它，
+        /// 否则按 CLAP、MIDI 2.0、MIDI 1.0 的顺序；端口不支持任何已知方言时为 None
+        pub fn select(supported: clap_note_dialect, preferred: clap_note_dialect) -> Option<Self> {
+                let midi1 = CLAP_NOTE_DIALECT_MIDI | CLAP_NOTE_DIALECT_MIDI_MPE;
+                let clap = NoteDialect::Clap {
+                        midi: supported & midi1 != 0,
+                };
+                let preferred = preferred & supported;
+                if preferred & CLAP_NOTE_DIALECT_CLAP != 0 {
+                        Some(clap)
+                } else if preferred & CLAP_NOTE_DIALECT_MIDI2 != 0 {
+                        Some(NoteDialect::Midi2)
+                } else if preferred & midi1 != 0 {
+                        Some(NoteDialect::Midi)
+                } else if supported & CLAP_NOTE_DIALECT_CLAP != 0 {
+                        Some(clap)
+                } else if supported & CLAP_NOTE_DIALECT_MIDI2 != 0 {
+                        Some(NoteDialect::Midi2)
+                } else if supported & midi1 != 0 {
+                        Some(NoteDialect::Midi)
+                } else {
+                        None
+                }
+        }
+}
+
+// MIDI 2.0 连续量：单极性 0..1 与双极性 -1..1（中心 0x80000000）映射为 32 位
+fn unipolar32(v: f32) -> u32 {
+        (v.clamp(0.0, 1.0) as f64 * u32::MAX as f64).round() as u32
+}
+
+fn bipolar32(v: f32) -> u32 {
+        let v = v.clamp(-1.0, 1.0) as f64;
+        let offset = if v >= 0.0 {
+                v * 0x7fff_ffff as f64
+        } else {
+                v * 0x8000_0000u32 as f64
+        };
+        (0x8000_0000u32 as f64 + offset).round() as u32
+}
+
+/// 编码为 MIDI 2.0 通道声音消息（UMP 类型 0x4，第 0 组）。单音符表情使用 MIDI 2.0 的单音符消息：
+/// 音高为 Per-Note Pitch Bend（默认 ±48 半音），压力为单音符触后，明亮度为单音符可分配控制器 74
+fn midi2_words(event: &NoteEvent) -> [u32; 4] {
+        let word0 = |status: u8, byte2: u8, byte3: u8| {
+                0x4000_0000
+                        | ((status as u32) << 20)
+                        | (((event.channel() & 0x0f) as u32) << 16)
+                        | (((byte2 & 0x7f) as u32) << 8)
+                        | byte3 as u32
+        };
+        let (w0, w1) = match *event {
+                NoteEvent::NoteOn { note, velocity, .. } => {
+                        // 力度 0 在 MIDI 2.0 中合法，但部分接收端仍视作 NoteOff，最小发送 1
+                        let velocity = (velocity.clamp(0.0, 1.0) * 65535.0).round().max(1.0) as u32;
+                        (word0(0x9, note, 0), velocity << 16)
+                }
+                NoteEvent::NoteOff { note, .. } => (word0(0x8, note, 0), 0),
+                NoteEvent::NoteExpression {
+                        note,
+                        expression,
+                        value,
+                        ..
+                } => match expression {
+                        NoteExpressionKind::Pitch => (
+                                word0(0x6, note, 0),
+                                bipolar32(value / MIDI2_PER_NOTE_PITCH_RANGE),
+                        ),
+                        NoteExpressionKind::Pressure => (word0(0xa, note, 0), unipolar32(value)),
+                        NoteExpressionKind::Brightness => (word0(0x1, note, 74), unipolar32(value)),
+                },
+                NoteEvent::ControlChange { controller, value, .. } => (word0(0xb, controller, 0), unipolar32(value)),
+                NoteEvent::PitchBend { value, .. } => (word0(0xe, 0, 0), bipolar32(value)),
+                NoteEvent::ChannelPressure { pressure, .. } => (word0(0xd, 0, 0), unipolar32(pressure)),
+                NoteEvent::PolyPressure { note, pressure, .. } => (word0(0xa, note, 0), unipolar32(pressure)),
+                // 不带音色库选择（选项标志为 0）
+                NoteEvent::ProgramChange { program, .. } => (word0(0xc, 0, 0), ((program & 0x7f) as u32) << 24),
+        };
+        [w0, w1, 0, 0]
+}
+
+fn core_header<T>(time: u32, type_: u16) -> clap_event_header {
+        clap_event_header {
+                size: std::mem::size_of::<T>() as u32,
+                time,
+                space_id: CLAP_CORE_EVENT_SPACE_ID,
+                type_,
+                flags: 0,
+        }
+}
+
+impl ClapEvent {
+        pub fn param_value(time: u32, param_id: u32, value: f64) -> Self {
+                ClapEvent {
+                        param_value: clap_event_param_value {
+                                header: clap_event_header {
+                                        size: std::mem::size_of::<clap_event_param_value>() as u32,
+                                        time,
+                                        space_id: CLAP_CORE_EVENT_SPACE_ID,
+                                        type_: CLAP_EVENT_PARAM_VALUE,
+                                        flags: 0,
+                                },
+                                param_id,
+                                cookie: ptr::null_mut(),
+                                note_id: -1,
+                                port_index: -1,
+                                channel: -1,
+                                key: -1,
+                                value,
+                        },
+                }
+        }
+
+        /// MIDI 1.0 通道消息（发往指定的音符端口）
+        pub fn midi(time: u32, port_index: u16, data: [u8; 3]) -> Self {
+                ClapEvent {
+                        midi: clap_event_midi {
+                                header: clap_event_header {
+                                        size: std::mem::size_of::<clap_event_midi>() as u32,
+                                        time,
+                                        space_id: CLAP_CORE_EVENT_SPACE_ID,
+                                        type_: CLAP_EVENT_MIDI,
+                                        flags: 0,
+                                },
+                                port_index,
+                                data,
+                        },
+                }
+        }
+
+        /// MIDI 2.0 UMP 消息（发往指定的音符端口）
+        pub fn midi2(time: u32, port_index: u16, data: [u32; 4]) -> Self {
+                ClapEvent {
+                        midi2: clap_event_midi2 {
+                                header: core_header::<clap_event_midi2>(time, CLAP_EVENT_MIDI2),
+                                port_index,
+                                data,
+                        },
+                }
+        }
+
+        /// 按音符端口的方言转换宿主的 MIDI 事件，事件都发往第一个音符端口：
+        /// CLAP 方言下音符使用 CLAP 音符事件（携带 note_id），单音符表情使用 CLAP 音符表情，
+        /// 其余通道消息在端口接受 MIDI 1.0 时以 MIDI 1.0 发送，否则丢弃；
+        /// MIDI / MIDI 2.0 方言下所有事件都编码为对应的 MIDI 消息（note_id 无法传递）
+        pub fn from_note_event(time: u32, dialect: NoteDialect, event: &NoteEvent) -> Option<Self> {
+                match dialect {
+                        NoteDialect::Midi => return Some(Self::midi(time, 0, event.to_midi_bytes())),
+                        NoteDialect::Midi2 => return Some(Self::midi2(time, 0, midi2_words(event))),
+                        NoteDialect::Clap { .. } => {}
+                }
+                let id = |note_id: Option<u32>| note_id.map_or(-1, |id| (id & i32::MAX as u32) as i32);
+                Some(match *event {
+                        NoteEvent::NoteOn {
+                                channel,
+                                note,
+                                velocity,
+                                note_id,
+                        } => ClapEvent {
+                                note: clap_event_note {
+                                        header: core_header::<clap_event_note>(time, CLAP_EVENT_NOTE_ON),
+                                        note_id: id(note_id),
+                                        port_index: 0,
+                                        channel: channel as i16,
+                                        key: note as i16,
+                                        velocity: velocity as f64,
+                                },
+                        },
+                        NoteEvent::NoteOff { channel, note, note_id } => ClapEvent {
+                                note: clap_event_note {
+                                        header: core_header::<clap_event_note>(time, CLAP_EVENT_NOTE_OFF),
+                                        note_id: id(note_id),
+                                        port_index: 0,
+                                        channel: channel as i16,
+                                        key: note as i16,
+                                        velocity: 0.0,
+                                },
+                        },
+                        NoteEvent::NoteExpression {
+                                channel,
+                                note,
+                                note_id,
+                                expression,
+                                value,
+                        } => ClapEvent {
+                                note_expression: clap_event_note_expression {
+                                        header: core_header::<clap_event_note_expression>(
+                                                time,
+                                                CLAP_EVENT_NOTE_EXPRESSION,
+                                        ),
+                                        // 音高（半音）与 CLAP 的 tuning 单位一致；压力与明亮度均为 0..1
+                                        expression_id: match expression {
+                                                NoteExpressionKind::Pitch => CLAP_NOTE_EXPRESSION_TUNING,
+                                                NoteExpressionKind::Pressure => CLAP_NOTE_EXPRESSION_PRESSURE,
+                                                NoteExpressionKind::Brightness => CLAP_NOTE_EXPRESSION_BRIGHTNESS,
+                                        },
+                                        note_id: id(note_id),
+                                        port_index: 0,
+                                        channel: channel as i16,
+                                        key: note as i16,
+                                        value: value as f64,
+                                },
+                        },
+                        _ if dialect == (NoteDialect::Clap { midi: true }) => {
+                                Self::midi(time, 0, event.to_midi_bytes())
+                        }
+                        _ => return None,
+                })
+        }
+
+        pub fn time(&self) -> u32 {
+                unsafe { self.header.time }
+        }
+}
+
+pub struct InputEventList {
+        events: Vec<ClapEvent>,
+        capacity: usize,
+}
+
+impl InputEventList {
+        pub fn with_capacity(capacity: usize) -> Self {
+                Self {
+                        events: Vec::with_capacity(capacity),
+                        capacity,
+                }
+        }
+
+        pub fn clear(&mut self) {
+                self.events.clear();
+        }
+
+        pub fn is_empty(&self) -> bool {
+                self.events.is_empty()
+        }
+
+        /// 追加事件；超过预分配容量时丢弃（避免音频线程分配）
+        pub fn push(&mut self, event: ClapEvent) -> bool {
+                if self.events.len() >= self.capacity {
+                        return false;
+                }
+                self.events.push(event);
+                true
+        }
+
+        /// CLAP 要求输入事件按时间排序；稳定排序保证同一时刻的事件保持插入顺序
+        pub fn sort_by_time(&mut self) {
+                self.events.sort_by_key(|e| e.time());
+        }
+
+        /// 生成指向本列表的 C 接口描述（仅在本列表存活且未被修改期间有效）
+        pub fn as_raw(&self) -> clap_input_events {
+                clap_input_events {
+                        ctx: self as *const Self as *mut c_void,
+                        size: Some(input_events_size),
+                        get: Some(input_events_get),
+                }
+        }
+}
+
+unsafe extern "C" fn input_events_size(list: *const clap_input_events) -> u32 {
+        unsafe {
+                let events = &*((*list).ctx as *const InputEventList);
+                events.events.len() as u32
+        }
+}
+
+unsafe extern "C" fn input_events_get(list: *const clap_input_events, index: u32) -> *const clap_event_header {
+        unsafe {
+                let events = &*((*list).ctx as *const InputEventList);
+                match events.events.get(index as usize) {
+                        Some(e) => &e.header as *const clap_event_header,
+                        None => ptr::null(),
+                }
+        }
+}
+
+/// 插件回传的参数变化（param_id, value）
+pub struct OutputEventList {
+        pub param_changes: Vec<(u32, f64)>,
+        capacity: usize,
+}
+
+impl OutputEventList {
+        pub fn with_capacity(capacity: usize) -> Self {
+                Self {
+                        param_changes: Vec::with_capacity(capacity),
+                        capacity,
+                }
+        }
+
+        pub fn clear(&mut self) {
+                self.param_changes.clear();
+        }
+
+        pub fn as_raw(&mut self) -> clap_output_events {
+                clap_output_events {
+                        ctx: self as *mut Self as *mut c_void,
+                        try_push: Some(output_events_try_push),
+                }
+        }
+}
+
+unsafe extern "C" fn output_events_try_push(list: *const clap_output_events, event: *const clap_event_header) -> bool {
+        unsafe {
+                if event.is_null() {
+                        return false;
+                }
+                let out = &mut *((*list).ctx as *mut OutputEventList);
+                if (*event).space_id != CLAP_CORE_EVENT_SPACE_ID {
+                        // 忽略其他事件空间，但视为成功接收
+                        return true;
+                }
+                if (*event).type_ == CLAP_EVENT_PARAM_VALUE {
+                        if out.param_changes.len() >= out.capacity {
+                                return false;
+                        }
+                        let pv = &*(event as *const clap_event_param_value);
+                        out.param_changes.push((pv.param_id, pv.value));
+                }
+                true
+        }
+}
@@ -0,0 +1,328 @@
+use crate::audio::core::threads;
+use clap_sys::ext::audio_ports::{
+        CLAP_AUDIO_PORTS_RESCAN_CHANNEL_COUNT, CLAP_AUDIO_PORTS_RESCAN_FLAGS, CLAP_AUDIO_PORTS_RESCAN_IN_PLACE_PAIR,
+        CLAP_AUDIO_PORTS_RESCAN_LIST, CLAP_AUDIO_PORTS_RESCAN_NAMES, CLAP_AUDIO_PORTS_RESCAN_PORT_TYPE,
+        CLAP_EXT_AUDIO_PORTS, clap_host_audio_ports,
+};
+use clap_sys::ext::latency::{CLAP_EXT_LATENCY, clap_host_latency};
+use clap_sys::ext::log::{
+        CLAP_EXT_LOG, CLAP_LOG_DEBUG, CLAP_LOG_ERROR, CLAP_LOG_FATAL, CLAP_LOG_HOST_MISBEHAVING, CLAP_LOG_INFO,
+        CLAP_LOG_PLUGIN_MISBEHAVING, CLAP_LOG_WARNING, clap_log_severity,
+};
+use clap_sys::ext::params::{CLAP_EXT_PARAMS, clap_host_params, clap_param_clear_flags, clap_param_rescan_flags};
+use clap_sys::ext::state::{CLAP_EXT_STATE, clap_host_state};
+use clap_sys::ext::thread_check::{CLAP_EXT_THREAD_CHECK, clap_host_thread_check};
+use clap_sys::host::clap_host;
+use clap_sys::id::clap_id;
+use crossbeam_channel::{Receiver, Sender, bounded};
+use std::ffi::{CStr, CString, c_void};
+use std::os::raw::c_char;
+use std::ptr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+// CLAP 宿主（Host）实现：插件通过 `clap_host` 回调向宿主发出请求。
+// 回调可能来自任意线程（包括音频线程），因此这里只做原子置位，
+// 实际工作统一推迟到主线程泵调用 `ClapPlugin::on_main_thread` 时完成。
+
+// 音频线程日志队列的容量（条）与每条消息保留的字节数（更长的消息被截断）
+const AUDIO_LOG_CAPACITY: usize = 64;
+const AUDIO_LOG_BYTES: usize = 256;
+
+// 音频线程上的一条日志：定长记录，入队时不分配
+#[derive(Clone, Copy)]
+struct AudioLogRecord {
+        severity: clap_log_severity,
+        len: usize,
+        text: [u8; AUDIO_LOG_BYTES],
+}
+
+/// 插件发往宿主的请求集合（由 `clap_host.host_data` 指向）
+pub struct ClapHostShared {
+        // 插件名称（仅用于日志）
+        pub plugin_name: std::sync::Mutex<String>,
+        pub restart_requested: AtomicBool,
+        pub process_requested: AtomicBool,
+        pub callback_requested: AtomicBool,
+        // 累积的 CLAP_PARAM_RESCAN_* 标志
+        pub params_rescan_flags: AtomicU32,
+        pub params_flush_requested: AtomicBool,
+        pub latency_changed: AtomicBool,
+        // 累积的 CLAP_AUDIO_PORTS_RESCAN_* 标志
+        pub audio_ports_rescan_flags: AtomicU32,
+        pub state_dirty: AtomicBool,
+        // 音频线程上的日志：有界队列在创建时预分配，由主线程泵（`drain_audio_log`）打印
+        audio_log_tx: Sender<AudioLogRecord>,
+        audio_log_rx: Receiver<AudioLogRecord>,
+}
+
+impl ClapHostShared {
+        fn new() -> Self {
+                let (audio_log_tx, audio_log_rx) = bounded(AUDIO_LOG_CAPACITY);
+                Self {
+                        plugin_name: std::sync::Mutex::new(String::new()),
+                        restart_requested: AtomicBool::new(false),
+                        process_requested: AtomicBool::new(false),
+                        callback_requested: AtomicBool::new(false),
+                        params_rescan_flags: AtomicU32::new(0),
+                        params_flush_requested: AtomicBool::new(false),
+                        latency_changed: AtomicBool::new(false),
+                        audio_ports_rescan_flags: AtomicU32::new(0),
+                        state_dirty: AtomicBool::new(false),
+                        audio_log_tx,
+                        audio_log_rx,
+                }
+        }
+
+        /// 打印音频线程上排队的日志（在主线程调用）
+        pub fn drain_audio_log(&self) {
+                let name = self.name();
+                for record in self.audio_log_rx.try_iter() {
+                        print_log(
+                                &name,
+                                record.severity,
+                                &String::from_utf8_lossy(&record.text[..record.len]),
+                        );
+                }
+        }
+
+        fn name(&self) -> String {
+                self.plugin_name
+                        .lock()
+                        .map(|n| n.clone())
+                        .unwrap_or_else(|_| "CLAP".to_string())
+        }
+}
+
+// 为了保证 clap_host 所需的 C 字符串指针在插件生命周期内有效，
+// 我们把 clap_host 和对应的 CString 字段包装在一起并保存在 `ClapPlugin` 中。
+pub struct ClapHost {
+        pub host: clap_host,
+        pub shared: Arc<ClapHostShared>,
+        _name: CString,
+        _vendor: CString,
+        _url: CString,
+        _version: CString,
+}
+
+impl ClapHost {
+        pub fn new() -> Box<Self> {
+                let name = CString::new("MyDAW").unwrap_or_default();
+                let vendor = CString::new("MyDAW").unwrap_or_default();
+                let url = CString::new("").unwrap_or_default();
+                let version = CString::new("0.1.0").unwrap_or_default();
+                let shared = Arc::new(ClapHostShared::new());
+
+                let host = clap_host {
+                        clap_version: clap_sys::version::CLAP_VERSION,
+                        // host_data 指向共享请求状态；Arc 保证地址在插件生命周期内稳定
+                        host_data: Arc::as_ptr(&shared) as *mut c_void,
+                        name: name.as_ptr(),
+                        vendor: vendor.as_ptr(),
+                        url: url.as_ptr(),
+                        version: version.as_ptr(),
+                        get_extension: Some(host_get_extension),
+                        request_restart: Some(host_request_restart),
+                        request_process: Some(host_request_process),
+                        request_callback: Some(host_request_callback),
+                };
+
+                Box::new(Self {
+                        host,
+                        shared,
+                        _name: name,
+                        _vendor: vendor,
+                        _url: url,
+                        _version: version,
+                })
+        }
+}
+
+// 从 clap_host 指针取回共享状态（插件可能传入空指针，需检查）
+unsafe fn shared_from<'a>(host: *const clap_host) -> Option<&'a ClapHostShared> {
+        unsafe {
+                if host.is_null() || (*host).host_data.is_null() {
+                        return None;
+                }
+                Some(&*((*host).host_data as *const ClapHostShared))
+        }
+}
+
+// 各扩展的函数表；插件通过 get_extension 获得其静态地址
+static HOST_LOG: clap_sys::ext::log::clap_host_log = clap_sys::ext::log::clap_host_log { log: Some(host_log) };
+
+static HOST_THREAD_CHECK: clap_host_thread_check = clap_host_thread_check {
+        is_main_thread: Some(host_is_main_thread),
+        is_audio_thread: Some(host_is_audio_thread),
+};
+
+static HOST_PARAMS: clap_host_params = clap_host_params {
+        rescan: Some(host_params_rescan),
+        clear: Some(host_params_clear),
+        request_flush: Some(host_params_request_flush),
+};
+
+static HOST_LATENCY: clap_host_latency = clap_host_latency {
+        changed: Some(host_latency_changed),
+};
+
+static HOST_AUDIO_PORTS: clap_host_audio_ports = clap_host_audio_ports {
+        is_rescan_flag_supported: Some(host_audio_ports_is_rescan_flag_supported),
+        rescan: Some(host_audio_ports_rescan),
+};
+
+static HOST_STATE: clap_host_state = clap_host_state {
+        mark_dirty: Some(host_state_mark_dirty),
+};
+
+unsafe extern "C" fn host_get_extension(_host: *const clap_host, extension_id: *const c_char) -> *const c_void {
+        if extension_id.is_null() {
+                return ptr::null();
+        }
+        let id = unsafe { CStr::from_ptr(extension_id) };
+        if id == CLAP_EXT_LOG {
+                &HOST_LOG as *const _ as *const c_void
+        } else if id == CLAP_EXT_THREAD_CHECK {
+                &HOST_THREAD_CHECK as *const _ as *const c_void
+        } else if id == CLAP_EXT_PARAMS {
+                &HOST_PARAMS as *const _ as *const c_void
+        } else if id == CLAP_EXT_LATENCY {
+                &HOST_LATENCY as *const _ as *const c_void
+        } else if id == CLAP_EXT_AUDIO_PORTS {
+                &HOST_AUDIO_PORTS as *const _ as *const c_void
+        } else if id == CLAP_EXT_STATE {
+                &HOST_STATE as *const _ as *const c_void
+        } else {
+                ptr::null()
+        }
+}
+
+unsafe extern "C" fn host_request_restart(host: *const clap_host) {
+        if let Some(shared) = unsafe { shared_from(host) } {
+                shared.restart_requested.store(true, Ordering::Release);
+                threads::request_main_thread_pump();
+        }
+}
+
+unsafe extern "C" fn host_request_process(host: *const clap_host) {
+        // 宿主在引擎运行时始终调用 process，这里只记录请求
+        if let Some(shared) = unsafe { shared_from(host) } {
+                shared.process_requested.store(true, Ordering::Release);
+        }
+}
+
+unsafe extern "C" fn host_request_callback(host: *const clap_host) {
+        if let Some(shared) = unsafe { shared_from(host) } {
+                shared.callback_requested.store(true, Ordering::Release);
+                threads::request_main_thread_pump();
+        }
+}
+
+unsafe extern "C" fn host_log(host: *const clap_host, severity: clap_log_severity, msg: *const c_char) {
+        if threads::is_audio_thread() {
+                // 音频线程不加锁、不分配：消息复制到定长记录后交给主线程泵打印，队列满时丢弃
+                let Some(shared) = (unsafe { shared_from(host) }) else {
+                        return;
+                };
+                let bytes = if msg.is_null() {
+                        &[]
+                } else {
+                        unsafe { CStr::from_ptr(msg) }.to_bytes()
+                };
+                let len = bytes.len().min(AUDIO_LOG_BYTES);
+                let mut record = AudioLogRecord {
+                        severity,
+                        len,
+                        text: [0; AUDIO_LOG_BYTES],
+                };
+                record.text[..len].copy_from_slice(&bytes[..len]);
+                if shared.audio_log_tx.try_send(record).is_ok() {
+                        threads::request_main_thread_pump();
+                }
+                return;
+        }
+        let name = unsafe { shared_from(host) }
+                .map(|s| s.name())
+                .unwrap_or_else(|| "CLAP".to_string());
+        let text = if msg.is_null() {
+                String::new()
+        } else {
+                unsafe { CStr::from_ptr(msg) }.to_string_lossy().into_owned()
+        };
+        print_log(&name, severity, &text);
+}
+
+fn print_log(name: &str, severity: clap_log_severity, text: &str) {
+        let level = match severity {
+                CLAP_LOG_DEBUG => "DEBUG",
+                CLAP_LOG_INFO => "INFO",
+                CLAP_LOG_WARNING => "WARN",
+                CLAP_LOG_ERROR => "ERROR",
+                CLAP_LOG_FATAL => "FATAL",
+                CLAP_LOG_HOST_MISBEHAVING => "HOST-MISBEHAVING",
+                CLAP_LOG_PLUGIN_MISBEHAVING => "PLUGIN-MISBEHAVING",
+                _ => "LOG",
+        };
+        if severity >= CLAP_LOG_WARNING {
+                eprintln!("Clap[{}] {}: {}", name, level, text);
+        } else {
+                println!("Clap[{}] {}: {}", name, level, text);
+        }
+}
+
+unsafe extern "C" fn host_is_main_thread(_host: *const clap_host) -> bool {
+        threads::is_main_thread()
+}
+
+unsafe extern "C" fn host_is_audio_thread(_host: *const clap_host) -> bool {
+        threads::is_audio_thread()
+}
+
+unsafe extern "C" fn host_params_rescan(host: *const clap_host, flags: clap_param_rescan_flags) {
+        if let Some(shared) = unsafe { shared_from(host) } {
+                shared.params_rescan_flags.fetch_or(flags, Ordering::AcqRel);
+                threads::request_main_thread_pump();
+        }
+}
+
+unsafe extern "C" fn host_params_clear(_host: *const clap_host, _param_id: clap_id, _flags: clap_param_clear_flags) {
+        // 宿主尚未保存自动化/调制数据，无需清理
+}
+
+unsafe extern "C" fn host_params_request_flush(host: *const clap_host) {
+        if let Some(shared) = unsafe { shared_from(host) } {
+                shared.params_flush_requested.store(true, Ordering::Release);
+                threads::request_main_thread_pump();
+        }
+}
+
+unsafe extern "C" fn host_latency_changed(host: *const clap_host) {
+        if let Some(shared) = unsafe { shared_from(host) } {
+                shared.latency_changed.store(true, Ordering::Release);
+                threads::request_main_thread_pump();
+        }
+}
+
+unsafe extern "C" fn host_audio_ports_is_rescan_flag_supported(_host: *const clap_host, flag: u32) -> bool {
+        let supported = CLAP_AUDIO_PORTS_RESCAN_NAMES
+                | CLAP_AUDIO_PORTS_RESCAN_FLAGS
+                | CLAP_AUDIO_PORTS_RESCAN_CHANNEL_COUNT
+                | CLAP_AUDIO_PORTS_RESCAN_PORT_TYPE
+                | CLAP_AUDIO_PORTS_RESCAN_IN_PLACE_PAIR
+                | CLAP_AUDIO_PORTS_RESCAN_LIST;
+        flag & !supported == 0
+}
+
+unsafe extern "C" fn host_audio_ports_rescan(host: *const clap_host, flags: u32) {
+        if let Some(shared) = unsafe { shared_from(host) } {
+                shared.audio_ports_rescan_flags.fetch_or(flags, Ordering::AcqRel);
+                threads::request_main_thread_pump();
+        }
+}
+
+unsafe extern "C" fn host_state_mark_dirty(host: *const clap_host) {
+        if let Some(shared) = unsafe { shared_from(host) } {
+                shared.state_dirty.store(true, Ordering::Release);
+                threads::request_main_thread_pump();
+        }
+}
@@ -1 +1,4 @@
+pub mod events;
+pub mod host;
 pub mod plugin;
+pub mod ports;
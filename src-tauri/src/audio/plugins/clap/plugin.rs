@@ -1,49 +1,64 @@
-use crate::audio::core::plugin::{AudioBuffer, IOConfig, Plugin, PluginEvent, PluginInfo, PluginParameter, PluginType};
+use crate::audio::core::plugin::{
+        AudioBuffer, IOConfig, ParameterType, Plugin, PluginEvent, PluginInfo, PluginParameter, PluginType,
+};
+use crate::audio::plugins::clap::events::{ClapEvent, InputEventList, NoteDialect, OutputEventList};
+use crate::audio::plugins::clap::host::ClapHost;
+use crate::audio::plugins::clap::ports::{self, PortBuffers};
 use clap_sys::entry::clap_plugin_entry;
-use clap_sys::host::clap_host;
+use clap_sys::ext::audio_ports::{CLAP_EXT_AUDIO_PORTS, clap_plugin_audio_ports};
+use clap_sys::ext::latency::{CLAP_EXT_LATENCY, clap_plugin_latency};
+use clap_sys::ext::note_ports::{CLAP_EXT_NOTE_PORTS, clap_plugin_note_ports};
+use clap_sys::ext::params::{
+        CLAP_EXT_PARAMS, CLAP_PARAM_IS_AUTOMATABLE, CLAP_PARAM_IS_ENUM, CLAP_PARAM_IS_HIDDEN, CLAP_PARAM_IS_READONLY,
+        CLAP_PARAM_IS_STEPPED, CLAP_PARAM_RESCAN_ALL, CLAP_PARAM_RESCAN_INFO, clap_param_info, clap_plugin_params,
+};
+use clap_sys::ext::state::{CLAP_EXT_STATE, clap_plugin_state};
 use clap_sys::plugin::clap_plugin;
 use clap_sys::process::clap_process;
+use clap_sys::stream::{clap_istream, clap_ostream};
 use libloading::{Library, Symbol};
-use std::ffi::{CStr, CString};
+use std::ffi::{CStr, CString, c_void};
 use std::ptr;
 use std::sync::Arc;
-// use std::ptr::NonNull;
-use std::os::raw::c_char;
-
-// CLAP 主机（Host）的最小实现
-unsafe extern "C" fn host_get_extension(
-        _host: *const clap_host,
-        _extension_id: *const c_char,
-) -> *const std::ffi::c_void {
-        ptr::null()
-}
+use std::sync::atomic::Ordering;
 
-unsafe extern "C" fn host_request_restart(_host: *const clap_host) {}
-unsafe extern "C" fn host_request_process(_host: *const clap_host) {}
-unsafe extern "C" fn host_request_callback(_host: *const clap_host) {}
-
-// 为了保证 clap_host 所需的 C 字符串指针在插件生命周期内有效，
-// 我们把 clap_host 和对应的 CString 字段包装在一起并保存在 `ClapPlugin` 中。
-struct ClapHost {
-        host: clap_host,
-        _name: CString,
-        _vendor: CString,
-        _url: CString,
-        _version: CString,
-}
+// 每个处理块最多携带的事件数（预分配，避免实时线程分配）
+const MAX_BLOCK_EVENTS: usize = 1024;
 
 pub struct ClapPlugin {
         _library: Arc<Library>, // 保持库加载（防止被卸载）
         plugin: Option<*mut clap_plugin>,
         info: PluginInfo,
-        #[allow(dead_code)]
         io_config: IOConfig,
         params: Vec<PluginParameter>,
         // 保持 host 的生命周期与插件一致
-        _host: Box<ClapHost>,
-        // 预分配输出缓冲，避免实时线程分配
-        out_l: Vec<f32>,
-        out_r: Vec<f32>,
+        host: Box<ClapHost>,
+        // 插件扩展（在 init 之后查询，生命周期与插件实例一致）
+        params_ext: Option<*const clap_plugin_params>,
+        latency_ext: Option<*const clap_plugin_latency>,
+        state_ext: Option<*const clap_plugin_state>,
+        audio_ports_ext: Option<*const clap_plugin_audio_ports>,
+        note_ports_ext: Option<*const clap_plugin_note_ports>,
+        // 第一个输入音符端口的方言；没有音符端口时不发送音符事件
+        note_dialect: Option<NoteDialect>,
+        // 激活参数
+        sample_rate: f64,
+        active: bool,
+        // start_processing / stop_processing 必须在音频线程调用，因此在 process 中延迟调用
+        processing: bool,
+        // 插件请求重启（或端口变化）后等待音频线程先停止处理，再由主线程重新激活
+        restart_pending: bool,
+        // 插件报告的处理延迟（采样数），在激活或收到 latency.changed 后刷新
+        latency: u32,
+        // 尚未送达插件的参数变化（param_id, value）
+        pending_params: Vec<(u32, f64)>,
+        in_events: InputEventList,
+        out_events: OutputEventList,
+        // 按端口预分配的输入/输出缓冲，避免实时线程分配；端口变化时在主线程重建
+        inputs: PortBuffers,
+        outputs: PortBuffers,
+        main_input: Option<usize>,
+        main_output: Option<usize>,
         max_frames: usize,
 }
 // 在此集中管理 Send/Sync 的不安全声明：
@@ -53,7 +68,10 @@ unsafe impl Send for ClapPlugin {}
 unsafe impl Sync for ClapPlugin {}
 
 impl ClapPlugin {
-        pub unsafe fn new(path: &str) -> Result<Self, String> {
+        /// # Safety
+        /// `path` 必须指向一个有效的 CLAP 动态库；库中的代码将在当前进程内执行。
+        /// 插件以 `sample_rate` 激活。
+        pub unsafe fn new(path: &str, sample_rate: f64) -> Result<Self, String> {
                 unsafe {
                         let lib = Library::new(path).map_err(|e| e.to_string())?;
                         let lib = Arc::new(lib);
@@ -98,32 +116,8 @@ impl ClapPlugin {
                                 return Err("Failed to get plugin descriptor".to_string());
                         }
 
-                        // 创建 host 并保证 CString 的生命周期
-                        let host_name = CString::new("MyDAW").unwrap_or_else(|_| CString::new("MyDAW").unwrap());
-                        let host_vendor = CString::new("MyDAW").unwrap_or_else(|_| CString::new("MyDAW").unwrap());
-                        let host_url = CString::new("").unwrap_or_else(|_| CString::new("").unwrap());
-                        let host_version = CString::new("0.1.0").unwrap_or_else(|_| CString::new("0.1.0").unwrap());
-
-                        let host_struct = clap_host {
-                                clap_version: clap_sys::version::CLAP_VERSION,
-                                host_data: ptr::null_mut(),
-                                name: host_name.as_ptr() as *const _,
-                                vendor: host_vendor.as_ptr() as *const _,
-                                url: host_url.as_ptr() as *const _,
-                                version: host_version.as_ptr() as *const _,
-                                get_extension: Some(host_get_extension),
-                                request_restart: Some(host_request_restart),
-                                request_process: Some(host_request_process),
-                                request_callback: Some(host_request_callback),
-                        };
-
-                        let boxed_host = Box::new(ClapHost {
-                                host: host_struct,
-                                _name: host_name,
-                                _vendor: host_vendor,
-                                _url: host_url,
-                                _version: host_version,
-                        });
+                        // 创建 host（包含扩展回调与共享请求状态）
+                        let boxed_host = ClapHost::new();
 
                         let plugin_id = (*descriptor).id;
                         let create_plugin = (*factory).create_plugin.ok_or("No create_plugin")?;
@@ -140,19 +134,6 @@ impl ClapPlugin {
                                 return Err("create_plugin returned null pointer".to_string());
                         }
 
-                        // 调用 init 与 activate（unsafe 调用集中）
-                        let init_plugin = (*plugin_ptr_mut).init.ok_or("No plugin init")?;
-                        if !init_plugin(plugin_ptr_mut) {
-                                return Err("Failed to initialize plugin instance".to_string());
-                        }
-
-                        let activate = (*plugin_ptr_mut).activate.ok_or("No activate")?;
-                        // 使用采样率和块大小进行激活
-                        // TODO: 获取真实的采样率
-                        if !activate(plugin_ptr_mut, 44100.0, 32, 4096) {
-                                return Err("Failed to activate plugin".to_string());
-                        }
-
                         let name = {
                                 let p = (*descriptor).name;
                                 if p.is_null() {
@@ -176,16 +157,24 @@ impl ClapPlugin {
                                         CStr::from_ptr(plugin_id).to_string_lossy().into_owned()
                                 }
                         };
+                        if let Ok(mut n) = boxed_host.shared.plugin_name.lock() {
+                                *n = name.clone();
+                        }
 
-                        // TODO: 扫描参数
-                        let params = Vec::new();
+                        // 调用 init（unsafe 调用集中）
+                        let init_plugin = (*plugin_ptr_mut).init.ok_or("No plugin init")?;
+                        if !init_plugin(plugin_ptr_mut) {
+                                if let Some(destroy) = (*plugin_ptr_mut).destroy {
+                                        destroy(plugin_ptr_mut);
+                                }
+                                return Err("Failed to initialize plugin instance".to_string());
+                        }
 
                         // 预分配缓冲大小（以 frames 为单位），与 activate 中的 max_events/frames 保持一致
                         let max_frames = 4096usize;
-                        let out_l = vec![0.0f32; max_frames];
-                        let out_r = vec![0.0f32; max_frames];
+                        let io_config = IOConfig::simple(0, 2);
 
-                        Ok(Self {
+                        let mut plugin = Self {
                                 _library: lib,
                                 plugin: Some(plugin_ptr_mut),
                                 info: PluginInfo {
@@ -196,25 +185,281 @@ impl ClapPlugin {
                                         unique_id,
                                         parameters: None,
                                 },
-                                io_config: IOConfig { inputs: 0, outputs: 2 }, // 目前假设为立体声输出
-                                params,
-                                _host: boxed_host,
-                                out_l,
-                                out_r,
+                                inputs: PortBuffers::new(&io_config.input_ports, max_frames),
+                                outputs: PortBuffers::new(&io_config.output_ports, max_frames),
+                                main_input: None,
+                                main_output: None,
+                                io_config,
+                                params: Vec::new(),
+                                host: boxed_host,
+                                params_ext: None,
+                                latency_ext: None,
+                                state_ext: None,
+                                audio_ports_ext: None,
+                                note_ports_ext: None,
+                                note_dialect: None,
+                                sample_rate,
+                                active: false,
+                                processing: false,
+                                restart_pending: false,
+                                latency: 0,
+                                pending_params: Vec::with_capacity(MAX_BLOCK_EVENTS),
+                                in_events: InputEventList::with_capacity(MAX_BLOCK_EVENTS),
+                                out_events: OutputEventList::with_capacity(MAX_BLOCK_EVENTS),
                                 max_frames,
-                        })
+                        };
+
+                        plugin.params_ext = plugin.extension::<clap_plugin_params>(CLAP_EXT_PARAMS);
+                        plugin.latency_ext = plugin.extension::<clap_plugin_latency>(CLAP_EXT_LATENCY);
+                        plugin.state_ext = plugin.extension::<clap_plugin_state>(CLAP_EXT_STATE);
+                        plugin.audio_ports_ext = plugin.extension::<clap_plugin_audio_ports>(CLAP_EXT_AUDIO_PORTS);
+                        plugin.note_ports_ext = plugin.extension::<clap_plugin_note_ports>(CLAP_EXT_NOTE_PORTS);
+
+                        // 端口配置只能在未激活时查询
+                        plugin.rescan_audio_ports();
+
+                        // 扫描参数
+                        plugin.rescan_params();
+
+                        // 使用采样率和块大小进行激活（失败时 Drop 会负责销毁实例）
+                        if !plugin.activate() {
+                                return Err("Failed to activate plugin".to_string());
+                        }
+
+                        Ok(plugin)
+                }
+        }
+
+        // 查询插件扩展；插件返回的指针在其生命周期内有效
+        fn extension<T>(&self, id: &CStr) -> Option<*const T> {
+                let p = self.plugin?;
+                unsafe {
+                        let get_extension = (*p).get_extension?;
+                        let ext = get_extension(p, id.as_ptr());
+                        if ext.is_null() {
+                                None
+                        } else {
+                                Some(ext as *const T)
+                        }
+                }
+        }
+
+        fn activate(&mut self) -> bool {
+                let Some(p) = self.plugin else {
+                        return false;
+                };
+                if self.active {
+                        return true;
+                }
+                unsafe {
+                        let Some(activate) = (*p).activate else {
+                                return false;
+                        };
+                        self.active = activate(p, self.sample_rate, 32, self.max_frames as u32);
+                }
+                if self.active {
+                        // 延迟只允许在激活状态下查询
+                        self.latency = self.query_latency();
+                }
+                self.active
+        }
+
+        fn deactivate(&mut self) {
+                let Some(p) = self.plugin else {
+                        return;
+                };
+                unsafe {
+                        if self.processing {
+                                // 只会在 Drop 时发生（重启会先由音频线程停止处理）：
+                                // 此时实例已没有其他使用者，当前线程即是最后调用插件的线程。
+                                if let Some(stop) = (*p).stop_processing {
+                                        stop(p);
+                                }
+                                self.processing = false;
+                        }
+                        if self.active {
+                                if let Some(deactivate) = (*p).deactivate {
+                                        deactivate(p);
+                                }
+                                self.active = false;
+                        }
+                }
+        }
+
+        /// 读取音频端口并重建缓冲（必须在未激活状态下调用）；
+        /// 插件未实现 audio-ports 扩展时按无输入的立体声发生器处理
+        fn rescan_audio_ports(&mut self) {
+                let queried = match (self.plugin, self.audio_ports_ext) {
+                        (Some(p), Some(ext)) => unsafe { ports::query_io_config(p, ext) },
+                        _ => None,
+                };
+                self.io_config = queried.unwrap_or_else(|| IOConfig::simple(0, 2));
+                self.inputs = PortBuffers::new(&self.io_config.input_ports, self.max_frames);
+                self.outputs = PortBuffers::new(&self.io_config.output_ports, self.max_frames);
+                self.main_input = ports::main_port(&self.io_config.input_ports);
+                self.main_output = ports::main_port(&self.io_config.output_ports);
+                self.note_dialect = match (self.plugin, self.note_ports_ext) {
+                        (Some(p), Some(ext)) => unsafe { ports::query_note_dialect(p, ext) },
+                        _ => None,
+                };
+        }
+
+        fn query_latency(&self) -> u32 {
+                let (Some(p), Some(ext)) = (self.plugin, self.latency_ext) else {
+                        return 0;
+                };
+                unsafe { (*ext).get.map(|get| get(p)).unwrap_or(0) }
+        }
+
+        /// 通过 params 扩展重新读取参数列表
+        fn rescan_params(&mut self) {
+                let (Some(p), Some(ext)) = (self.plugin, self.params_ext) else {
+                        self.params.clear();
+                        return;
+                };
+                let mut params = Vec::new();
+                unsafe {
+                        let (Some(count), Some(get_info)) = ((*ext).count, (*ext).get_info) else {
+                                return;
+                        };
+                        for index in 0..count(p) {
+                                let mut info: clap_param_info = std::mem::zeroed();
+                                if !get_info(p, index, &mut info) {
+                                        continue;
+                                }
+                                let name = CStr::from_ptr(info.name.as_ptr()).to_string_lossy().into_owned();
+                                let value_type = if info.flags & CLAP_PARAM_IS_ENUM != 0 {
+                                        // 枚举项名称通过 value_to_text 获取
+                                        let mut labels = Vec::new();
+                                        let mut v = info.min_value.round();
+                                        while v <= info.max_value && labels.len() < 128 {
+                                                labels.push(self
+                                                        .value_to_text(info.id, v)
+                                                        .unwrap_or_else(|| v.to_string()));
+                                                v += 1.0;
+                                        }
+                                        ParameterType::Enum(labels)
+                                } else if info.flags & CLAP_PARAM_IS_STEPPED != 0 {
+                                        if info.min_value == 0.0 && info.max_value == 1.0 {
+                                                ParameterType::Bool
+                                        } else {
+                                                ParameterType::Int
+                                        }
+                                } else {
+                                        ParameterType::Float
+                                };
+                                let mut param = PluginParameter::new(
+                                        info.id,
+                                        &name,
+                                        info.min_value as f32,
+                                        info.max_value as f32,
+                                        info.default_value as f32,
+                                        value_type,
+                                );
+                                if info.flags & CLAP_PARAM_IS_STEPPED != 0 {
+                                        param.step_count = (info.max_value - info.min_value).round().max(0.0) as u32;
+                                }
+                                param.flags.automatable = info.flags & CLAP_PARAM_IS_AUTOMATABLE != 0;
+                                param.flags.read_only = info.flags & CLAP_PARAM_IS_READONLY != 0;
+                                param.flags.hidden = info.flags & CLAP_PARAM_IS_HIDDEN != 0;
+                                // CLAP 的 module 即以 "/" 分隔的分组路径
+                                param.group = CStr::from_ptr(info.module.as_ptr()).to_string_lossy().into_owned();
+                                params.push(param);
+                        }
+                }
+                self.params = params;
+        }
+
+        fn value_to_text(&self, param_id: u32, value: f64) -> Option<String> {
+                let (p, ext) = (self.plugin?, self.params_ext?);
+                unsafe {
+                        let value_to_text = (*ext).value_to_text?;
+                        let mut buf = [0 as std::os::raw::c_char; 256];
+                        if !value_to_text(p, param_id, value, buf.as_mut_ptr(), buf.len() as u32) {
+                                return None;
+                        }
+                        Some(CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned())
+                }
+        }
+
+        fn text_to_value(&self, param_id: u32, text: &str) -> Option<f64> {
+                let (p, ext) = (self.plugin?, self.params_ext?);
+                let text = CString::new(text).ok()?;
+                unsafe {
+                        let text_to_value = (*ext).text_to_value?;
+                        let mut value = 0.0f64;
+                        text_to_value(p, param_id, text.as_ptr(), &mut value).then_some(value)
+                }
+        }
+
+        // 把待发送的参数变化写入输入事件列表
+        fn fill_param_events(&mut self) {
+                self.in_events.clear();
+                for (id, value) in self.pending_params.drain(..) {
+                        if !self.in_events.push(ClapEvent::param_value(0, id, value)) {
+                                break;
+                        }
+                }
+        }
+
+        // 非处理状态下通过 params.flush 把参数变化送达插件（主线程调用）
+        fn flush_params(&mut self) {
+                let (Some(p), Some(ext)) = (self.plugin, self.params_ext) else {
+                        self.pending_params.clear();
+                        return;
+                };
+                self.fill_param_events();
+                self.out_events.clear();
+                unsafe {
+                        if let Some(flush) = (*ext).flush {
+                                let in_events = self.in_events.as_raw();
+                                let out_events = self.out_events.as_raw();
+                                flush(p, &in_events, &out_events);
+                        }
+                }
+                self.in_events.clear();
+        }
+}
+
+// 状态流：ostream 追加写入 Vec<u8>，istream 从切片顺序读取
+unsafe extern "C" fn ostream_write(stream: *const clap_ostream, buffer: *const c_void, size: u64) -> i64 {
+        unsafe {
+                // size 为 0 时 buffer 可能为空指针，不能用于构造切片
+                if size == 0 {
+                        return 0;
+                }
+                let out = &mut *((*stream).ctx as *mut Vec<u8>);
+                let bytes = std::slice::from_raw_parts(buffer as *const u8, size as usize);
+                out.extend_from_slice(bytes);
+                size as i64
+        }
+}
+
+struct StateReader<'a> {
+        data: &'a [u8],
+        pos: usize,
+}
+
+unsafe extern "C" fn istream_read(stream: *const clap_istream, buffer: *mut c_void, size: u64) -> i64 {
+        unsafe {
+                let reader = &mut *((*stream).ctx as *mut StateReader);
+                let remaining = reader.data.len() - reader.pos;
+                let n = remaining.min(size as usize);
+                if n == 0 {
+                        return 0;
                 }
+                ptr::copy_nonoverlapping(reader.data.as_ptr().add(reader.pos), buffer as *mut u8, n);
+                reader.pos += n;
+                n as i64
         }
 }
 
 impl Drop for ClapPlugin {
         fn drop(&mut self) {
                 // 将所有对 C 指针的交互集中到这里，并且进行空指针检查。
-                if let Some(p) = self.plugin {
+                self.deactivate();
+                if let Some(p) = self.plugin.take() {
                         unsafe {
-                                if let Some(deactivate) = (*p).deactivate {
-                                        deactivate(p);
-                                }
                                 if let Some(destroy) = (*p).destroy {
                                         destroy(p);
                                 }
@@ -233,42 +478,103 @@ impl Plugin for ClapPlugin {
                 self.params.clone()
         }
 
+        fn get_state(&self) -> Vec<u8> {
+                let (Some(p), Some(ext)) = (self.plugin, self.state_ext) else {
+                        return Vec::new();
+                };
+                let mut data: Vec<u8> = Vec::new();
+                unsafe {
+                        let Some(save) = (*ext).save else {
+                                return Vec::new();
+                        };
+                        let stream = clap_ostream {
+                                ctx: &mut data as *mut Vec<u8> as *mut c_void,
+                                write: Some(ostream_write),
+                        };
+                        if !save(p, &stream) {
+                                return Vec::new();
+                        }
+                }
+                data
+        }
+
+        fn set_state(&mut self, state: &[u8]) {
+                let (Some(p), Some(ext)) = (self.plugin, self.state_ext) else {
+                        return;
+                };
+                let mut reader = StateReader { data: state, pos: 0 };
+                unsafe {
+                        if let Some(load) = (*ext).load {
+                                let stream = clap_istream {
+                                        ctx: &mut reader as *mut StateReader as *mut c_void,
+                                        read: Some(istream_read),
+                                };
+                                if !load(p, &stream) {
+                                        eprintln!("Clap[{}]: failed to load state", self.info.name);
+                                }
+                        }
+                }
+                // 状态加载后参数值可能整体变化
+                self.pending_params.clear();
+        }
+
+        fn supports_note_ids(&self) -> bool {
+                matches!(self.note_dialect, Some(NoteDialect::Clap { .. }))
+        }
+
         fn get_io_config(&self) -> IOConfig {
                 self.io_config.clone()
         }
 
-        fn process(
-                &mut self,
-                buffer: &mut AudioBuffer,
-                _events: &[PluginEvent],
-                _output_events: &mut Vec<PluginEvent>,
-        ) {
+        fn latency(&self) -> u32 {
+                self.latency
+        }
+
+        fn process(&mut self, buffer: &mut AudioBuffer, events: &[PluginEvent], output_events: &mut Vec<PluginEvent>) {
+                // 块内的参数事件与主线程排队的参数变化一起送达插件
+                let param_events = events.iter().filter_map(|e| match e {
+                        PluginEvent::Parameter { id, value } => Some((*id, *value as f64)),
+                        _ => None,
+                });
+                for change in param_events {
+                        if self.pending_params.len() >= MAX_BLOCK_EVENTS {
+                                break;
+                        }
+                        self.pending_params.push(change);
+                }
+
                 // 把 unsafe 使用限制在最小范围：先取出裸指针并在单个 unsafe 块内调用
-                if let Some(p) = self.plugin {
+                if let (Some(p), true) = (self.plugin, self.active) {
                         unsafe {
                                 let process_fn = (*p).process.unwrap();
 
-                                // 准备音频缓冲区
-                                // CLAP 期望接收指向 f32 数组的指针数组（用于非交错/非 interleaved）
-                                // 我们的 AudioBuffer 是交错还是非交错？
-                                // `AudioBuffer` 结构的 `samples: &mut [f32]`，在我们的简单引擎中通常是交错的。
-                                // 但 CLAP 通常要求非交错格式。
-                                // 如果我们的引擎是交错的，我们需要进行去交错处理（de-interleave）。
-                                // 暂时假设仅支持单声道或采用一些 hack 处理。
-                                // 注意，`plugin.rs` 中的 `AudioBuffer` 有 `channels` 字段。
-
-                                // 如果是交错格式，我们需要用于去交错的临时缓冲区。
-                                // 暂时先假设我们只是传递指针。
-                                // 但如果没有提前准备，在音频线程中实现去交错（且不分配）并不容易。
-
-                                // 对于原型，假设为立体声并在足够小时将其去交错到栈数组，
-                                // 否则如果无法处理则直接输出静音。
+                                if self.restart_pending {
+                                        // 等待主线程重新激活：先在音频线程停止处理，期间输出静音
+                                        if self.processing {
+                                                if let Some(stop) = (*p).stop_processing {
+                                                        stop(p);
+                                                }
+                                                self.processing = false;
+                                                crate::audio::core::threads::request_main_thread_pump();
+                                        }
+                                        buffer.samples.fill(0.0);
+                                        return;
+                                }
 
-                                // 实际上，这里先实现一个占位（dummy）处理以证明插件能加载运行。
-                                // 真正的音频处理需要更复杂的缓冲区管理。
+                                if !self.processing {
+                                        let started = (*p).start_processing.map(|start| start(p)).unwrap_or(true);
+                                        if !started {
+                                                for s in buffer.samples.iter_mut() {
+                                                        *s = 0.0;
+                                                }
+                                                return;
+                                        }
+                                        self.processing = true;
+                                }
 
-                                // 准备输出缓冲：使用预分配缓冲，避免实时分配
-                                let frames = buffer.samples.len() / 2;
+                                // CLAP 与引擎内部都使用平面缓冲：
+                                // 先把轨道信号逐通道复制到主输入端口，处理后再把主输出端口复制回来。
+                                let frames = buffer.frames();
                                 if frames > self.max_frames {
                                         // 如果输入帧数超过预分配的限制，安全起见直接清零输出并返回
                                         for s in buffer.samples.iter_mut() {
@@ -277,46 +583,57 @@ impl Plugin for ClapPlugin {
                                         return;
                                 }
 
-                                let out_l = &mut self.out_l[..frames];
-                                let out_r = &mut self.out_r[..frames];
-
-                                // 清零输出缓冲（插件负责写入）
-                                for v in out_l.iter_mut() {
-                                        *v = 0.0;
+                                self.fill_param_events();
+                                // 音符、单音符表情、控制器、弯音等事件按端口方言送往第一个音符端口，保留块内时间
+                                let dialect = self.note_dialect;
+                                let last_frame = frames.saturating_sub(1) as u32;
+                                let midi_events = events.iter().filter_map(|e| {
+                                        let (frame, midi) = e.as_midi()?;
+                                        ClapEvent::from_note_event(frame.min(last_frame), dialect?, midi)
+                                });
+                                for event in midi_events {
+                                        if !self.in_events.push(event) {
+                                                break;
+                                        }
                                 }
-                                for v in out_r.iter_mut() {
-                                        *v = 0.0;
+                                self.in_events.sort_by_time();
+                                self.out_events.clear();
+
+                                // 非主输入端口（如侧链）暂无信号来源，保持静音
+                                self.inputs.clear(frames);
+                                self.outputs.clear(frames);
+                                if let Some(port) = self.main_input {
+                                        self.inputs.copy_from(port, buffer, frames);
                                 }
 
-                                let mut out_ptrs = [out_l.as_mut_ptr(), out_r.as_mut_ptr()];
-
-                                let mut audio_out = clap_sys::audio_buffer::clap_audio_buffer {
-                                        data32: out_ptrs.as_mut_ptr(),
-                                        data64: ptr::null_mut(),
-                                        channel_count: 2,
-                                        latency: 0,
-                                        constant_mask: 0,
-                                };
+                                let in_events = self.in_events.as_raw();
+                                let out_events = self.out_events.as_raw();
 
                                 let process_data = clap_process {
                                         steady_time: -1,
                                         frames_count: frames as u32,
                                         transport: ptr::null(),
-                                        audio_inputs: ptr::null_mut(), // 无输入
-                                        audio_outputs: &mut audio_out,
-                                        audio_inputs_count: 0,
-                                        audio_outputs_count: 1,
-                                        in_events: ptr::null(), // TODO: 事件
-                                        out_events: ptr::null(),
+                                        audio_inputs: self.inputs.as_ptr(),
+                                        audio_outputs: self.outputs.as_mut_ptr(),
+                                        audio_inputs_count: self.inputs.port_count() as u32,
+                                        audio_outputs_count: self.outputs.port_count() as u32,
+                                        in_events: &in_events,
+                                        out_events: &out_events,
                                 };
 
                                 process_fn(p, &process_data);
 
-                                // 重新交错回去
-                                for i in 0..frames {
-                                        let si = i * 2;
-                                        buffer.samples[si] = out_l[i];
-                                        buffer.samples[si + 1] = out_r[i];
+                                match self.main_output {
+                                        Some(port) => self.outputs.copy_to(port, buffer, frames),
+                                        None => buffer.samples.fill(0.0),
+                                }
+
+                                // 插件内部（例如其自带 GUI）引起的参数变化回传给宿主
+                                for (id, value) in self.out_events.param_changes.drain(..) {
+                                        output_events.push(PluginEvent::Parameter {
+                                                id,
+                                                value: value as f32,
+                                        });
                                 }
                         }
                 } else {
@@ -327,9 +644,96 @@ impl Plugin for ClapPlugin {
                 }
         }
 
-        fn get_param(&self, _id: u32) -> f32 {
-                0.0
+        fn get_param(&self, id: u32) -> f32 {
+                // 尚未送达的变化优先，保证 UI 读取到刚写入的值
+                if let Some((_, v)) = self.pending_params.iter().rev().find(|(pid, _)| *pid == id) {
+                        return *v as f32;
+                }
+                let (Some(p), Some(ext)) = (self.plugin, self.params_ext) else {
+                        return 0.0;
+                };
+                unsafe {
+                        let Some(get_value) = (*ext).get_value else {
+                                return 0.0;
+                        };
+                        let mut value = 0.0f64;
+                        if get_value(p, id, &mut value) {
+                                value as f32
+                        } else {
+                                0.0
+                        }
+                }
+        }
+
+        fn set_param(&mut self, id: u32, value: f32) {
+                // 参数变化排队，在下一个处理块（或主线程 flush）中以事件形式送达
+                if self.pending_params.len() < MAX_BLOCK_EVENTS {
+                        self.pending_params.push((id, value as f64));
+                }
+                if !self.processing {
+                        self.host.shared.params_flush_requested.store(true, Ordering::Release);
+                        crate::audio::core::threads::request_main_thread_pump();
+                }
+        }
+
+        fn param_value_to_text(&self, id: u32, value: f32) -> Option<String> {
+                self.value_to_text(id, value as f64)
         }
 
-        fn set_param(&mut self, _id: u32, _value: f32) {}
+        fn param_text_to_value(&self, id: u32, text: &str) -> Option<f32> {
+                self.text_to_value(id, text).map(|v| v as f32)
+        }
+
+        fn on_main_thread(&mut self) {
+                let shared = self.host.shared.clone();
+                shared.drain_audio_log();
+
+                let callback = shared.callback_requested.swap(false, Ordering::AcqRel);
+                if let (true, Some(p)) = (callback, self.plugin) {
+                        unsafe {
+                                if let Some(on_main_thread) = (*p).on_main_thread {
+                                        on_main_thread(p);
+                                }
+                        }
+                }
+
+                let rescan = shared.params_rescan_flags.swap(0, Ordering::AcqRel);
+                if rescan & (CLAP_PARAM_RESCAN_INFO | CLAP_PARAM_RESCAN_ALL) != 0 {
+                        self.rescan_params();
+                }
+
+                if shared.params_flush_requested.swap(false, Ordering::AcqRel) && !self.processing {
+                        self.flush_params();
+                }
+
+                let ports_rescan = shared.audio_ports_rescan_flags.swap(0, Ordering::AcqRel);
+                let latency_changed = shared.latency_changed.swap(false, Ordering::AcqRel);
+                let restart = shared.restart_requested.swap(false, Ordering::AcqRel);
+
+                if restart || ports_rescan != 0 {
+                        self.restart_pending = true;
+                }
+                if self.restart_pending {
+                        // 端口或配置变化需要重新激活插件；端口只能在未激活时重新查询。
+                        // 仍在处理时等待音频线程的下一个处理块调用 stop_processing 后再回到这里
+                        if self.processing {
+                                return;
+                        }
+                        self.restart_pending = false;
+                        self.deactivate();
+                        self.rescan_audio_ports();
+                        if !self.activate() {
+                                eprintln!(
+                                        "Clap[{}]: failed to re-activate after restart request",
+                                        self.info.name
+                                );
+                        }
+                } else if latency_changed {
+                        self.latency = self.query_latency();
+                }
+        }
+
+        fn take_state_dirty(&mut self) -> bool {
+                self.host.shared.state_dirty.swap(false, Ordering::AcqRel)
+        }
 }
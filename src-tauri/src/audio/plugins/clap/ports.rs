@@ -0,0 +1,205 @@
+use crate::audio::core::channel_layout::{ChannelLayout, MAX_CHANNELS};
+use crate::audio::core::plugin::{AudioBuffer, AudioPortConfig, IOConfig};
+use crate::audio::plugins::clap::events::NoteDialect;
+use clap_sys::audio_buffer::clap_audio_buffer;
+use clap_sys::ext::audio_ports::{CLAP_AUDIO_PORT_IS_MAIN, clap_audio_port_info, clap_plugin_audio_ports};
+use clap_sys::ext::note_ports::{clap_note_port_info, clap_plugin_note_ports};
+use clap_sys::plugin::clap_plugin;
+use std::ffi::CStr;
+use std::ptr;
+
+// CLAP 音频端口：查询插件声明的输入/输出端口，并为每个端口预分配平面（planar）缓冲。
+// 宿主引擎内部同样使用平面缓冲，process 前后按通道复制；通道数不一致时按布局上混 / 下混。
+
+/// 通过 audio-ports 扩展读取端口列表；插件未实现该扩展时返回 None
+///
+/// # Safety
+/// `plugin` 与 `ext` 必须来自同一个已初始化的插件实例。
+pub unsafe fn query_io_config(plugin: *const clap_plugin, ext: *const clap_plugin_audio_ports) -> Option<IOConfig> {
+        unsafe {
+                let count = (*ext).count?;
+                let get = (*ext).get?;
+
+                let read_ports = |is_input: bool| -> Vec<AudioPortConfig> {
+                        let n = count(plugin, is_input);
+                        let mut ports = Vec::with_capacity(n as usize);
+                        for i in 0..n {
+                                let mut info: clap_audio_port_info = std::mem::zeroed();
+                                if !get(plugin, i, is_input, &mut info) {
+                                        continue;
+                                }
+                                let name = CStr::from_ptr(info.name.as_ptr()).to_string_lossy().into_owned();
+                                ports.push(AudioPortConfig {
+                                        name,
+                                        channels: info.channel_count as usize,
+                                        is_main: info.flags & CLAP_AUDIO_PORT_IS_MAIN != 0,
+                                });
+                        }
+                        ports
+                };
+
+                let input_ports = read_ports(true);
+                let output_ports = read_ports(false);
+                Some(IOConfig {
+                        inputs: main_port(&input_ports).map(|i| input_ports[i].channels).unwrap_or(0),
+                        outputs: main_port(&output_ports).map(|i| output_ports[i].channels).unwrap_or(0),
+                        input_ports,
+                        output_ports,
+                })
+        }
+}
+
+/// 通过 note-ports 扩展读取第一个输入音符端口的方言；插件没有输入音符端口时返回 None
+///
+/// # Safety
+/// `plugin` 与 `ext` 必须来自同一个已初始化的插件实例。
+pub unsafe fn query_note_dialect(
+        plugin: *const clap_plugin,
+        ext: *const clap_plugin_note_ports,
+) -> Option<NoteDialect> {
+        unsafe {
+                let count = (*ext).count?;
+                let get = (*ext).get?;
+                if count(plugin, true) == 0 {
+                        return None;
+                }
+                let mut info: clap_note_port_info = std::mem::zeroed();
+                if !get(plugin, 0, true, &mut info) {
+                        return None;
+                }
+                NoteDialect::select(info.supported_dialects, info.preferred_dialect)
+        }
+}
+
+/// 主端口下标：优先取带 IS_MAIN 标志的端口，否则退回第一个端口
+pub fn main_port(ports: &[AudioPortConfig]) -> Option<usize> {
+        ports.iter()
+                .position(|p| p.is_main)
+                .or(if ports.is_empty() { None } else { Some(0) })
+}
+
+/// 一组端口的预分配缓冲（[端口][通道][帧]）以及传给插件的 `clap_audio_buffer` 描述
+pub struct PortBuffers {
+        data: Vec<Vec<Vec<f32>>>,
+        // 每个端口的通道指针数组；内部 Vec 创建后不再改变长度，指针保持有效
+        _ptrs: Vec<Vec<*mut f32>>,
+        raw: Vec<clap_audio_buffer>,
+}
+
+impl PortBuffers {
+        pub fn new(ports: &[AudioPortConfig], max_frames: usize) -> Self {
+                let mut data: Vec<Vec<Vec<f32>>> = ports
+                        .iter()
+                        .map(|p| (0..p.channels).map(|_| vec![0.0f32; max_frames]).collect())
+                        .collect();
+                let mut ptrs: Vec<Vec<*mut f32>> = data
+                        .iter_mut()
+                        .map(|port| port.iter_mut().map(|ch| ch.as_mut_ptr()).collect())
+                        .collect();
+                let raw = ptrs
+                        .iter_mut()
+                        .map(|port| clap_audio_buffer {
+                                data32: port.as_mut_ptr(),
+                                data64: ptr::null_mut(),
+                                channel_count: port.len() as u32,
+                                latency: 0,
+                                constant_mask: 0,
+                        })
+                        .collect();
+                Self { data, _ptrs: ptrs, raw }
+        }
+
+        pub fn port_count(&self) -> usize {
+                self.raw.len()
+        }
+
+        /// 清零前 `frames` 帧并复位常量标志
+        pub fn clear(&mut self, frames: usize) {
+                for port in self.data.iter_mut() {
+                        for ch in port.iter_mut() {
+                                ch[..frames].fill(0.0);
+                        }
+                }
+                for raw in self.raw.iter_mut() {
+                        raw.constant_mask = 0;
+                }
+        }
+
+        /// 把宿主缓冲写入指定端口：通道数一致时逐通道复制，否则按两者的通道布局上混 / 下混
+        pub fn copy_from(&mut self, port: usize, buffer: &AudioBuffer, frames: usize) {
+                let Some(channels) = self.data.get_mut(port) else {
+                        return;
+                };
+                let host_channels = buffer.channels;
+                if channels.len() == host_channels {
+                        for (c, ch) in channels.iter_mut().enumerate() {
+                                let src = buffer.channel(c);
+                                let n = frames.min(src.len());
+                                ch[..n].copy_from_slice(&src[..n]);
+                        }
+                        return;
+                }
+                let matrix = ChannelLayout::from_channels(channels.len())
+                        .mix_matrix_from(ChannelLayout::from_channels(host_channels));
+                for (c, ch) in channels.iter_mut().enumerate().take(MAX_CHANNELS) {
+                        ch[..frames].fill(0.0);
+                        for (i, gain) in matrix[c].iter().enumerate().take(host_channels) {
+                                if *gain != 0.0 {
+                                        mix_into(&mut ch[..frames], buffer.channel(i), *gain);
+                                }
+                        }
+                }
+        }
+
+        /// 把指定端口写回宿主缓冲（通道数不一致时按布局上混 / 下混）；端口不存在或无通道时输出静音
+        pub fn copy_to(&self, port: usize, buffer: &mut AudioBuffer, frames: usize) {
+                let channels = match self.data.get(port) {
+                        Some(c) if !c.is_empty() => c,
+                        _ => {
+                                buffer.samples.fill(0.0);
+                                return;
+                        }
+                };
+                let host_channels = buffer.channels;
+                if channels.len() == host_channels {
+                        for (ch, dst) in channels.iter().zip(buffer.iter_channels_mut()) {
+                                let n = frames.min(dst.len());
+                                dst[..n].copy_from_slice(&ch[..n]);
+                        }
+                        return;
+                }
+                let matrix = ChannelLayout::from_channels(host_channels)
+                        .mix_matrix_from(ChannelLayout::from_channels(channels.len()));
+                for (row, dst) in matrix.iter().zip(buffer.iter_channels_mut()) {
+                        dst.fill(0.0);
+                        for (gain, ch) in row.iter().zip(channels.iter()) {
+                                if *gain != 0.0 {
+                                        mix_into(dst, &ch[..frames], *gain);
+                                }
+                        }
+                }
+        }
+
+        pub fn as_ptr(&self) -> *const clap_audio_buffer {
+                if self.raw.is_empty() {
+                        ptr::null()
+                } else {
+                        self.raw.as_ptr()
+                }
+        }
+
+        pub fn as_mut_ptr(&mut self) -> *mut clap_audio_buffer {
+                if self.raw.is_empty() {
+                        ptr::null_mut()
+                } else {
+                        self.raw.as_mut_ptr()
+                }
+        }
+}
+
+// 把 `src` 乘以 `gain` 叠加到 `dst`（处理两者长度中较短的部分）
+fn mix_into(dst: &mut [f32], src: &[f32], gain: f32) {
+        for (d, s) in dst.iter_mut().zip(src) {
+                *d += gain * s;
+        }
+}
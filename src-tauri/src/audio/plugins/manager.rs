@@ -1,5 +1,6 @@
 use crate::audio::core::plugin::{Plugin, PluginInfo};
 use crate::audio::plugins::clap::plugin::ClapPlugin;
+use crate::audio::plugins::presets::{PluginPreset, parse_manifest_presets};
 use mlua::Lua;
 use std::collections::HashMap;
 use std::fs;
@@ -9,6 +10,10 @@ pub struct PluginManager {
         known_plugins: HashMap<String, PluginInfo>,
         clap_paths: HashMap<String, String>,
         local_paths: HashMap<String, String>,
+        // manifest 中声明的出厂预设，按 unique_id 索引
+        factory_presets: HashMap<String, Vec<PluginPreset>>,
+        // 新建实例的激活采样率
+        sample_rate: f32,
 }
 
 #[derive(Clone, Debug)]
@@ -25,11 +30,20 @@ impl PluginManager {
                         known_plugins: HashMap::new(),
                         clap_paths: HashMap::new(),
                         local_paths: HashMap::new(),
+                        factory_presets: HashMap::new(),
+                        sample_rate: 44100.0,
                 };
                 manager.scan_native_plugins();
                 manager
         }
 
+        /// 设置之后创建的插件实例使用的采样率
+        pub fn set_sample_rate(&mut self, sample_rate: f32) {
+                if sample_rate > 0.0 {
+                        self.sample_rate = sample_rate;
+                }
+        }
+
         pub fn get_plugin_source(&self, unique_id: &str) -> PluginSource {
                 if let Some(p) = self.local_paths.get(unique_id) {
                         return PluginSource::Local(PathBuf::from(p));
@@ -109,6 +123,17 @@ impl PluginManager {
                                                                                         Some(backend),
                                                                                 ) = (id, name, backend_tbl)
                                                                                 {
+                                                                                        let presets =
+                                                                                                parse_manifest_presets(
+                                                                                                        &id, &tbl,
+                                                                                                );
+                                                                                        if !presets.is_empty() {
+                                                                                                self.factory_presets
+                                                                                                        .insert(
+                                                                                                        id.clone(),
+                                                                                                        presets,
+                                                                                                );
+                                                                                        }
                                                                                         let btype: Option<String> =
                                                                                                 backend.get("type")
                                                                                                         .ok();
@@ -120,7 +145,7 @@ impl PluginManager {
                                                                                                                 let full = fs::canonicalize(plugin_folder.join(&bp)).unwrap_or(plugin_folder.join(&bp));
                                                                                                                 self.local_paths.insert(id.clone(), full.to_string_lossy().to_string());
                                                                                                                 // Try load plugin to get info
-                                                                                                                match unsafe { crate::audio::core::ffi_plugin::FFIPlugin::new(self.local_paths.get(&id).unwrap(), 44100.0) } {
+                                                                                                                match unsafe { crate::audio::core::ffi_plugin::FFIPlugin::new(self.local_paths.get(&id).unwrap(), self.sample_rate) } {
                                                                                                                         Ok(plugin) => {
                                                                                                                                 let info = plugin.info();
                                                                                                                                 let mut info = info.clone();
@@ -164,14 +189,26 @@ impl PluginManager {
                                                                                                                                                                 }
                                                                                                                                                         }
 
-                                                                                                                                                        let param = crate::audio::core::plugin::PluginParameter {
-                                                                                                                                                                id: id_v.unwrap_or(0),
-                                                                                                                                                                name: name_v.unwrap_or_else(|| "".to_string()),
-                                                                                                                                                                min_value: min_v.unwrap_or(0.0),
-                                                                                                                                                                max_value: max_v.unwrap_or(1.0),
-                                                                                                                                                                default_value: default_v.unwrap_or(0.0),
-                                                                                                                                                                value_type: value_type,
-                                                                                                                                                        };
+                                                                                                                                                        let mut param = crate::audio::core::plugin::PluginParameter::new(
+                                                                                                                                                                id_v.unwrap_or(0),
+                                                                                                                                                                &name_v.unwrap_or_default(),
+                                                                                                                                                                min_v.unwrap_or(0.0),
+                                                                                                                                                                max_v.unwrap_or(1.0),
+                                                                                                                                                                default_v.unwrap_or(0.0),
+                                                                                                                                                                value_type,
+                                                                                                                                                        );
+                                                                                                                                                        // 可选显示元数据：单位、曲线、步进、标志与分组
+                                                                                                                                                        param.unit = p.get::<String>("unit").unwrap_or_default();
+                                                                                                                                                        param.taper = crate::audio::core::plugin::ParameterTaper::parse(
+                                                                                                                                                                p.get::<String>("taper").ok().as_deref(),
+                                                                                                                                                                p.get::<f32>("skew").ok(),
+                                                                                                                                                        );
+                                                                                                                                                        param.step_count = p.get::<u32>("steps").unwrap_or(0);
+                                                                                                                                                        // 注意：Lua 中缺省的布尔字段为 nil，读取为 Option 以保留默认值
+                                                                                                                                                        param.flags.automatable = p.get::<Option<bool>>("automatable").ok().flatten().unwrap_or(true);
+                                                                                                                                                        param.flags.read_only = p.get::<bool>("read_only").unwrap_or(false);
+                                                                                                                                                        param.flags.hidden = p.get::<bool>("hidden").unwrap_or(false);
+                                                                                                                                                        param.group = p.get::<String>("group").unwrap_or_default();
                                                                                                                                                         params_vec.push(param);
                                                                                                                                                 }
                                                                                                                                         }
@@ -223,12 +260,13 @@ impl PluginManager {
                 self.known_plugins.clear();
                 self.clap_paths.clear();
                 self.local_paths.clear();
+                self.factory_presets.clear();
                 self.scan_native_plugins();
         }
 
         pub fn scan_clap_plugin(&mut self, path: &str) -> Result<PluginInfo, String> {
                 unsafe {
-                        let plugin = ClapPlugin::new(path)?;
+                        let plugin = ClapPlugin::new(path, self.sample_rate as f64)?;
                         let info = plugin.info();
                         self.known_plugins.insert(info.unique_id.clone(), info.clone());
                         self.clap_paths.insert(info.unique_id.clone(), path.to_string());
@@ -236,6 +274,16 @@ impl PluginManager {
                 }
         }
 
+        /// 已注册插件的元信息（包含 manifest 中声明的参数）
+        pub fn get_plugin_info(&self, unique_id: &str) -> Option<PluginInfo> {
+                self.known_plugins.get(unique_id).cloned()
+        }
+
+        /// manifest 中声明的出厂预设
+        pub fn get_factory_presets(&self, unique_id: &str) -> Vec<PluginPreset> {
+                self.factory_presets.get(unique_id).cloned().unwrap_or_default()
+        }
+
         pub fn get_available_plugins(&self) -> Vec<PluginInfo> {
                 self.known_plugins.values().cloned().collect()
         }
@@ -244,7 +292,7 @@ impl PluginManager {
                 // Do not instantiate builtin implementations in host; attempt CLAP or local FFI libs.
                 if let Some(path) = self.clap_paths.get(unique_id) {
                         unsafe {
-                                if let Ok(plugin) = ClapPlugin::new(path) {
+                                if let Ok(plugin) = ClapPlugin::new(path, self.sample_rate as f64) {
                                         return Some(Box::new(plugin));
                                 }
                         }
@@ -252,7 +300,9 @@ impl PluginManager {
 
                 if let Some(lib_path) = self.local_paths.get(unique_id) {
                         unsafe {
-                                if let Ok(plugin) = crate::audio::core::ffi_plugin::FFIPlugin::new(lib_path, 44100.0) {
+                                if let Ok(plugin) =
+                                        crate::audio::core::ffi_plugin::FFIPlugin::new(lib_path, self.sample_rate)
+                                {
                                         return Some(Box::new(plugin));
                                 }
                         }
@@ -261,6 +311,37 @@ impl PluginManager {
                 None
         }
 
+        /// 在独立的子宿主进程中创建插件（崩溃不会影响主进程）
+        pub fn create_plugin_sandboxed(&self, unique_id: &str) -> Option<Box<dyn Plugin>> {
+                use crate::audio::sandbox::{protocol::SandboxKind, spawn_sandboxed};
+
+                let source = if let Some(path) = self.clap_paths.get(unique_id) {
+                        Some((SandboxKind::Clap, path))
+                } else {
+                        self.local_paths.get(unique_id).map(|path| (SandboxKind::Local, path))
+                };
+                let (kind, path) = source?;
+                match spawn_sandboxed(kind, path, self.sample_rate) {
+                        Ok(plugin) => Some(plugin),
+                        Err(e) => {
+                                println!(
+                                        "PluginManager: failed to start sandboxed plugin {}: {}",
+                                        unique_id, e
+                                );
+                                None
+                        }
+                }
+        }
+
+        /// 按实例配置创建插件：`sandboxed` 为 true 时放到子进程中运行
+        pub fn create_instance(&self, unique_id: &str, sandboxed: bool) -> Option<Box<dyn Plugin>> {
+                if sandboxed {
+                        self.create_plugin_sandboxed(unique_id)
+                } else {
+                        self.create_plugin(unique_id)
+                }
+        }
+
         pub fn get_plugin_parameters(
                 &self,
                 unique_id: &str,
@@ -0,0 +1,60 @@
+// 延迟补偿用的整数采样延迟线：对平面缓冲的每个通道做环形缓冲延迟。
+// 环形缓冲在构建音频图时按最大补偿长度与通道数一次性分配（每通道 MAX_DELAY_FRAMES 个样本，各通道共用同一写入位置），
+// 音频线程中改变延迟只移动读取位置，不会重新分配。
+// 样本类型默认为 f32，双精度求和总线使用 f64。
+pub struct DelayLine<T = f32> {
+        ring: Vec<T>,
+        pos: usize,
+        delay_frames: usize,
+        channels: usize,
+}
+
+/// 可补偿的最大延迟（帧），须为 2 的幂；更长的延迟按此截断
+pub const MAX_DELAY_FRAMES: usize = 1 << 15;
+
+impl<T: Copy + Default> DelayLine<T> {
+        /// 为 `channels` 个通道分配环形缓冲（在音频线程之外调用）
+        pub fn new(channels: usize) -> Self {
+                Self {
+                        ring: vec![T::default(); channels * MAX_DELAY_FRAMES],
+                        pos: 0,
+                        delay_frames: 0,
+                        channels,
+                }
+        }
+
+        #[allow(dead_code)]
+        pub fn delay_frames(&self) -> usize {
+                self.delay_frames
+        }
+
+        /// 设置延迟（帧数）。只改变读取位置，历史样本保留
+        pub fn set_delay(&mut self, frames: usize) {
+                self.delay_frames = frames.min(MAX_DELAY_FRAMES - 1);
+        }
+
+        /// 就地延迟平面样本（`channels` 个通道依次排列）。
+        /// 延迟为 0 时仍写入环形缓冲，使之后增大延迟时读到的是真实的历史信号
+        pub fn process(&mut self, samples: &mut [T]) {
+                if self.channels == 0 {
+                        return;
+                }
+                let delay = self.delay_frames;
+                let mask = MAX_DELAY_FRAMES - 1;
+                let frames = samples.len() / self.channels;
+                for (channel, ring) in samples
+                        .chunks_exact_mut(frames.max(1))
+                        .zip(self.ring.chunks_exact_mut(MAX_DELAY_FRAMES))
+                {
+                        let mut pos = self.pos;
+                        for s in channel.iter_mut() {
+                                ring[pos] = *s;
+                                if delay > 0 {
+                                        *s = ring[(pos + MAX_DELAY_FRAMES - delay) & mask];
+                                }
+                                pos = (pos + 1) & mask;
+                        }
+                }
+                self.pos = (self.pos + frames) & mask;
+        }
+}
@@ -1,13 +1,80 @@
+use crate::audio::core::channel_layout::MAX_CHANNELS;
 use crate::audio::core::plugin::{AudioBuffer, Plugin, PluginEvent, PluginInfo, PluginParameter, PluginType};
+use crate::audio::plugins::mixer::loudness::{LoudnessMeter, LoudnessReport};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Mutex;
 use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use uuid::Uuid;
 
-// 电平计（Meter）电平的全局存储
-pub static METER_LEVELS: OnceLock<Mutex<HashMap<Uuid, f32>>> = OnceLock::new();
+// 电平计（Meter）读数的全局存储
+pub static METER_LEVELS: OnceLock<Mutex<HashMap<Uuid, MeterReading>>> = OnceLock::new();
 
-pub fn get_meter_levels() -> HashMap<Uuid, f32> {
+// 复位请求计数：电平计发现计数变化时清除峰值保持、削波指示、真峰值与响度测量；
+// 削波计数只清除削波指示
+static METER_RESET: AtomicU64 = AtomicU64::new(0);
+static CLIP_RESET: AtomicU64 = AtomicU64::new(0);
+
+// 当前的电平表动态特性（f32 的位模式），所有电平计共用
+static PEAK_HOLD_BITS: AtomicU32 = AtomicU32::new(0x3fc0_0000); // 1.5
+static FALL_RATE_BITS: AtomicU32 = AtomicU32::new(0x41a0_0000); // 20.0
+
+// 每通道 RMS 的积分时间（秒）
+const RMS_WINDOW_SECONDS: f32 = 0.3;
+
+/// 电平表的动态特性
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeterBallistics {
+        /// 峰值保持时间（秒），之后按回落速度下降；为负时一直保持到复位
+        pub peak_hold: f32,
+        /// 回落速度（dB/秒）；不大于 0 时立即回落到当前峰值
+        pub fall_rate: f32,
+}
+
+impl Default for MeterBallistics {
+        fn default() -> Self {
+                Self {
+                        peak_hold: 1.5,
+                        fall_rate: 20.0,
+                }
+        }
+}
+
+pub fn meter_ballistics() -> MeterBallistics {
+        MeterBallistics {
+                peak_hold: f32::from_bits(PEAK_HOLD_BITS.load(Ordering::Relaxed)),
+                fall_rate: f32::from_bits(FALL_RATE_BITS.load(Ordering::Relaxed)),
+        }
+}
+
+/// 设置所有电平计的动态特性（从下一个音频块起生效）
+pub fn set_meter_ballistics(ballistics: MeterBallistics) {
+        PEAK_HOLD_BITS.store(ballistics.peak_hold.to_bits(), Ordering::Relaxed);
+        FALL_RATE_BITS.store(ballistics.fall_rate.to_bits(), Ordering::Relaxed);
+}
+
+/// 一个电平计的读数。电平、峰值、RMS 与真峰值为线性值，按通道排列
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MeterReading {
+        /// 平滑后的整体 RMS（单值电平，供简单的电平条使用）
+        pub level: f32,
+        /// 按回落速度下降的峰值电平（电平条显示用）
+        pub levels: Vec<f32>,
+        /// 本块的采样峰值
+        pub peak: Vec<f32>,
+        /// 峰值保持
+        pub peak_hold: Vec<f32>,
+        /// 削波指示：出现超过 0 dBFS 的样本（绝对值大于 1.0，恰好 1.0 不算）后保持，直到复位
+        pub clip: Vec<bool>,
+        pub rms: Vec<f32>,
+        /// 自上次复位以来的真峰值（过采样）
+        pub true_peak: Vec<f32>,
+        pub loudness: LoudnessReport,
+}
+
+pub fn get_meter_levels() -> HashMap<Uuid, MeterReading> {
         METER_LEVELS
                 .get_or_init(|| Mutex::new(HashMap::new()))
                 .lock()
@@ -15,26 +82,118 @@ pub fn get_meter_levels() -> HashMap<Uuid, f32> {
                 .clone()
 }
 
+/// 复位所有电平计的峰值保持、削波指示、真峰值与响度测量（在各自的下一个音频块生效）
+pub fn reset_meters() {
+        METER_RESET.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 只清除所有电平计的削波指示
+pub fn clear_meter_clips() {
+        CLIP_RESET.fetch_add(1, Ordering::Relaxed);
+}
+
 pub struct LevelMeter {
         id: Uuid,
         current_level: f32,
+        levels: [f32; MAX_CHANNELS],
+        peak: [f32; MAX_CHANNELS],
+        peak_hold: [f32; MAX_CHANNELS],
+        // 峰值保持已持续的帧数
+        hold_age: [usize; MAX_CHANNELS],
+        mean_square: [f32; MAX_CHANNELS],
+        clip: [bool; MAX_CHANNELS],
+        // 创建时按 MAX_CHANNELS 预分配；音频块的采样率或通道数变化时原地重新配置
+        loudness: LoudnessMeter,
+        sample_rate: f32,
+        reset: u64,
+        clip_reset: u64,
 }
 
 impl LevelMeter {
         pub fn new() -> Self {
-                Self {
-                        id: Uuid::new_v4(),
-                        current_level: 0.0,
-                }
+                Self::with_id(Uuid::new_v4())
         }
 
         pub fn with_id(id: Uuid) -> Self {
-                Self { id, current_level: 0.0 }
+                Self {
+                        id,
+                        current_level: 0.0,
+                        levels: [0.0; MAX_CHANNELS],
+                        peak: [0.0; MAX_CHANNELS],
+                        peak_hold: [0.0; MAX_CHANNELS],
+                        hold_age: [0; MAX_CHANNELS],
+                        mean_square: [0.0; MAX_CHANNELS],
+                        clip: [false; MAX_CHANNELS],
+                        loudness: LoudnessMeter::new(0.0, MAX_CHANNELS),
+                        sample_rate: 0.0,
+                        reset: METER_RESET.load(Ordering::Relaxed),
+                        clip_reset: CLIP_RESET.load(Ordering::Relaxed),
+                }
         }
 
         pub fn get_id(&self) -> Uuid {
                 self.id
         }
+
+        fn clear(&mut self) {
+                self.peak_hold = [0.0; MAX_CHANNELS];
+                self.hold_age = [0; MAX_CHANNELS];
+                self.clip = [false; MAX_CHANNELS];
+                self.loudness.reset();
+        }
+
+        // 每通道的峰值电平、峰值保持、削波与 RMS
+        fn update_channels(&mut self, buffer: &AudioBuffer) {
+                let frames = buffer.frames();
+                let ballistics = meter_ballistics();
+                let seconds = frames as f32 / buffer.sample_rate.max(1.0);
+                // 本块内的回落系数（线性）
+                let fall = if ballistics.fall_rate > 0.0 {
+                        10f32.powf(-ballistics.fall_rate * seconds / 20.0)
+                } else {
+                        0.0
+                };
+                let hold = (ballistics.peak_hold * buffer.sample_rate) as usize;
+                let smoothing = (-seconds / RMS_WINDOW_SECONDS).exp();
+                for (c, channel) in buffer.iter_channels().take(MAX_CHANNELS).enumerate() {
+                        let peak = channel.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+                        let mean_square = channel.iter().map(|s| s * s).sum::<f32>() / frames as f32;
+                        self.peak[c] = peak;
+                        self.levels[c] = peak.max(self.levels[c] * fall);
+                        self.mean_square[c] = self.mean_square[c] * smoothing + mean_square * (1.0 - smoothing);
+                        // 恰好满刻度（1.0）仍可无损表示，只有超出满刻度的样本才锁定削波指示
+                        self.clip[c] |= peak > 1.0;
+                        if peak >= self.peak_hold[c] {
+                                self.peak_hold[c] = peak;
+                                self.hold_age[c] = 0;
+                        } else if ballistics.peak_hold >= 0.0 && self.hold_age[c] >= hold {
+                                self.peak_hold[c] = peak.max(self.peak_hold[c] * fall);
+                        } else {
+                                self.hold_age[c] += frames;
+                        }
+                }
+        }
+
+        fn write_reading(&self, channels: usize, reading: &mut MeterReading) {
+                let channels = channels.min(MAX_CHANNELS);
+                reading.level = self.current_level;
+                // 原地更新以复用各通道数组的内存
+                reading.levels.clear();
+                reading.levels.extend_from_slice(&self.levels[..channels]);
+                reading.peak.clear();
+                reading.peak.extend_from_slice(&self.peak[..channels]);
+                reading.peak_hold.clear();
+                reading.peak_hold.extend_from_slice(&self.peak_hold[..channels]);
+                reading.clip.clear();
+                reading.clip.extend_from_slice(&self.clip[..channels]);
+                reading.rms.clear();
+                reading.rms
+                        .extend(self.mean_square[..channels].iter().map(|ms| ms.sqrt()));
+                reading.true_peak.clear();
+                reading.true_peak
+                        .extend((0..channels).map(|c| self.loudness.true_peak(c)));
+                reading.loudness = self.loudness.report();
+        }
 }
 
 impl Plugin for LevelMeter {
@@ -59,23 +218,40 @@ impl Plugin for LevelMeter {
                 _events: &[PluginEvent],
                 _output_events: &mut Vec<PluginEvent>,
         ) {
-                let mut sum_sq = 0.0;
                 let len = buffer.samples.len();
+                if buffer.frames() == 0 {
+                        return;
+                }
 
-                if len > 0 {
-                        for sample in buffer.samples.iter() {
-                                sum_sq += sample * sample;
-                        }
-                        let rms = (sum_sq / len as f32).sqrt();
+                let reset = METER_RESET.load(Ordering::Relaxed);
+                if reset != self.reset {
+                        self.reset = reset;
+                        self.clear();
+                }
+                let clip_reset = CLIP_RESET.load(Ordering::Relaxed);
+                if clip_reset != self.clip_reset {
+                        self.clip_reset = clip_reset;
+                        self.clip = [false; MAX_CHANNELS];
+                }
 
-                        // 简单平滑处理
-                        self.current_level = self.current_level * 0.8 + rms * 0.2;
+                let sum_sq: f32 = buffer.samples.iter().map(|s| s * s).sum();
+                let rms = (sum_sq / len as f32).sqrt();
+                // 简单平滑处理
+                self.current_level = self.current_level * 0.8 + rms * 0.2;
 
-                        // 更新全局映射
-                        let map_mutex = METER_LEVELS.get_or_init(|| Mutex::new(HashMap::new()));
-                        if let Ok(mut map) = map_mutex.lock() {
-                                map.insert(self.id, self.current_level);
-                        }
+                self.update_channels(buffer);
+                let channels = buffer.channels.min(MAX_CHANNELS);
+                if self.sample_rate != buffer.sample_rate || self.loudness.channels() != channels {
+                        self.sample_rate = buffer.sample_rate;
+                        self.loudness.configure(buffer.sample_rate, channels);
+                }
+                self.loudness.process(&buffer.samples[..buffer.frames() * channels]);
+
+                // 更新全局映射；读取方（电平推送线程）持有锁时不等待，跳过本块，
+                // 保持与削波指示等状态保存在电平计内，下一块照常写入
+                let map_mutex = METER_LEVELS.get_or_init(|| Mutex::new(HashMap::new()));
+                if let Ok(mut map) = map_mutex.try_lock() {
+                        self.write_reading(buffer.channels, map.entry(self.id).or_default());
                 }
         }
 
@@ -0,0 +1,442 @@
+use crate::audio::core::channel_layout::{ChannelLayout, MAX_CHANNELS};
+use serde::Serialize;
+use std::f64::consts::PI;
+
+// 响度测量（ITU-R BS.1770-4 / EBU R128）：K 计权滤波后按 100 ms 子块累积各通道加权的均方值，
+// 得到瞬时（400 ms）、短期（3 s）与积分响度（-70 LUFS 绝对门限 + -10 LU 相对门限）、
+// 响度范围（EBU Tech 3342）以及过采样的真峰值。测量本身与音频图无关，目前由实时电平计使用（测量播放经过的音频）。
+
+// 门限（LUFS / LU）
+const ABSOLUTE_GATE: f64 = -70.0;
+const RELATIVE_GATE: f64 = -10.0;
+const RANGE_GATE: f64 = -20.0;
+// 门限处理用的直方图：-70 .. +10 LUFS，每格 0.1 LU（固定大小，处理中不分配内存）
+const HISTOGRAM_STEP: f64 = 0.1;
+const HISTOGRAM_BINS: usize = 800;
+// 瞬时 / 短期窗口包含的 100 ms 子块数
+const MOMENTARY_BLOCKS: usize = 4;
+const SHORT_TERM_BLOCKS: usize = 30;
+// 真峰值插值核每个相位的抽头数与最大的过采样倍数
+const TRUE_PEAK_TAPS: usize = 12;
+const MAX_OVERSAMPLING: usize = 4;
+
+/// 均方值 -> LUFS（静音为负无穷）
+fn to_lufs(power: f64) -> f64 {
+        -0.691 + 10.0 * power.log10()
+}
+
+fn finite(value: f64) -> Option<f32> {
+        value.is_finite().then_some(value as f32)
+}
+
+/// 一次测量的结果（LUFS / LU / dBTP）；尚无有效值（静音或时长不足）时为 None
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct LoudnessReport {
+        pub momentary: Option<f32>,
+        pub short_term: Option<f32>,
+        pub integrated: Option<f32>,
+        /// 响度范围（LRA）
+        pub range: Option<f32>,
+        pub max_momentary: Option<f32>,
+        pub max_short_term: Option<f32>,
+        /// 各通道中最大的真峰值
+        pub true_peak: Option<f32>,
+}
+
+#[derive(Clone, Copy)]
+struct Biquad {
+        b0: f64,
+        b1: f64,
+        b2: f64,
+        a1: f64,
+        a2: f64,
+}
+
+impl Biquad {
+        // 直接 II 型转置
+        fn process(&self, state: &mut [f64; 2], x: f64) -> f64 {
+                let y = self.b0 * x + state[0];
+                state[0] = self.b1 * x - self.a1 * y + state[1];
+                state[1] = self.b2 * x - self.a2 * y;
+                y
+        }
+}
+
+/// K 计权：高频搁架（头部声学效应）+ RLB 高通，按采样率由模拟原型重新计算系数
+fn k_weighting(sample_rate: f64) -> [Biquad; 2] {
+        let k = (PI * 1681.974450955533 / sample_rate).tan();
+        let q = 0.7071752369554196;
+        let vh = 10f64.powf(3.999843853973347 / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad {
+                b0: (vh + vb * k / q + k * k) / a0,
+                b1: 2.0 * (k * k - vh) / a0,
+                b2: (vh - vb * k / q + k * k) / a0,
+                a1: 2.0 * (k * k - 1.0) / a0,
+                a2: (1.0 - k / q + k * k) / a0,
+        };
+        let k = (PI * 38.13547087602444 / sample_rate).tan();
+        let q = 0.5003270373238773;
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad {
+                b0: 1.0,
+                b1: -2.0,
+                b2: 1.0,
+                a1: 2.0 * (k * k - 1.0) / a0,
+                a2: (1.0 - k / q + k * k) / a0,
+        };
+        [shelf, high_pass]
+}
+
+/// 通道权重：方位角在 60°..120° 之间的环绕通道为 1.41（+1.5 dB），LFE 与布局之外的通道不计入
+fn channel_weights(channels: usize) -> [f64; MAX_CHANNELS] {
+        let layout = ChannelLayout::from_channels(channels);
+        let mut weights = [0.0; MAX_CHANNELS];
+        for (weight, speaker) in weights.iter_mut().zip(layout.speakers()) {
+                *weight = match speaker.azimuth(layout).map(f32::abs) {
+                        None => 0.0,
+                        Some(azimuth) if (60.0..=120.0).contains(&azimuth) => 1.41,
+                        Some(_) => 1.0,
+                };
+        }
+        weights
+}
+
+// 子块响度的直方图，每格记录块数与均方值之和（门限内的平均值因此是精确的，只有门限位置量化到 0.1 LU）
+struct Histogram {
+        counts: Box<[u64; HISTOGRAM_BINS]>,
+        powers: Box<[f64; HISTOGRAM_BINS]>,
+}
+
+impl Histogram {
+        fn new() -> Self {
+                Self {
+                        counts: Box::new([0; HISTOGRAM_BINS]),
+                        powers: Box::new([0.0; HISTOGRAM_BINS]),
+                }
+        }
+
+        fn clear(&mut self) {
+                self.counts.fill(0);
+                self.powers.fill(0.0);
+        }
+
+        // 不低于 `lufs` 的第一格
+        fn bin(lufs: f64) -> usize {
+                (((lufs - ABSOLUTE_GATE) / HISTOGRAM_STEP).ceil().max(0.0) as usize).min(HISTOGRAM_BINS - 1)
+        }
+
+        fn bin_lufs(bin: usize) -> f64 {
+                ABSOLUTE_GATE + (bin as f64 + 0.5) * HISTOGRAM_STEP
+        }
+
+        /// 加入一个块；低于绝对门限的块丢弃
+        fn add(&mut self, power: f64) {
+                let lufs = to_lufs(power);
+                if lufs < ABSOLUTE_GATE {
+                        return;
+                }
+                let bin = (((lufs - ABSOLUTE_GATE) / HISTOGRAM_STEP) as usize).min(HISTOGRAM_BINS - 1);
+                self.counts[bin] += 1;
+                self.powers[bin] += power;
+        }
+
+        /// 从 `from` 格开始的块数与平均均方值
+        fn mean(&self, from: usize) -> Option<(u64, f64)> {
+                let count: u64 = self.counts[from..].iter().sum();
+                let power: f64 = self.powers[from..].iter().sum();
+                (count > 0).then(|| (count, power / count as f64))
+        }
+
+        /// 先以全部块的平均响度加 `relative` LU 作为相对门限，返回门限以上的起始格
+        fn relative_gate(&self, relative: f64) -> Option<usize> {
+                let (_, power) = self.mean(0)?;
+                Some(Self::bin(to_lufs(power) + relative))
+        }
+
+        /// 从 `from` 格开始累计块数达到 `fraction` 时所在格的响度
+        fn percentile(&self, from: usize, total: u64, fraction: f64) -> f64 {
+                let target = (total as f64 * fraction).ceil().max(1.0) as u64;
+                let mut seen = 0;
+                for (bin, count) in self.counts.iter().enumerate().skip(from) {
+                        seen += count;
+                        if seen >= target {
+                                return Self::bin_lufs(bin);
+                        }
+                }
+                Self::bin_lufs(HISTOGRAM_BINS - 1)
+        }
+}
+
+// 真峰值检测：多相 FIR 过采样（采样率低于 96 kHz 时 4 倍，低于 192 kHz 时 2 倍），取插值样本的最大绝对值
+struct TruePeak {
+        // 前 `factor` 个相位 × TRUE_PEAK_TAPS 个抽头有效（按最大倍数预留，改变采样率时原地重算）
+        kernel: [f32; MAX_OVERSAMPLING * TRUE_PEAK_TAPS],
+        factor: usize,
+        // 每个通道最近 TRUE_PEAK_TAPS 个输入样本（环形，与 `position` 共用写入位置）
+        history: [f32; MAX_CHANNELS * TRUE_PEAK_TAPS],
+        position: usize,
+        peaks: [f32; MAX_CHANNELS],
+}
+
+impl TruePeak {
+        fn new(sample_rate: f64) -> Self {
+                let mut true_peak = Self {
+                        kernel: [0.0; MAX_OVERSAMPLING * TRUE_PEAK_TAPS],
+                        factor: 1,
+                        history: [0.0; MAX_CHANNELS * TRUE_PEAK_TAPS],
+                        position: 0,
+                        peaks: [0.0; MAX_CHANNELS],
+                };
+                true_peak.set_sample_rate(sample_rate);
+                true_peak
+        }
+
+        // 按采样率选择过采样倍数并计算插值核（不分配内存）
+        fn set_sample_rate(&mut self, sample_rate: f64) {
+                let factor = if sample_rate < 96000.0 {
+                        4
+                } else if sample_rate < 192000.0 {
+                        2
+                } else {
+                        1
+                };
+                let length = factor * TRUE_PEAK_TAPS;
+                let center = (length - 1) as f64 / 2.0;
+                for (phase, kernel) in self.kernel.chunks_exact_mut(TRUE_PEAK_TAPS).take(factor).enumerate() {
+                        let mut row = [0.0f64; TRUE_PEAK_TAPS];
+                        for (tap, w) in row.iter_mut().enumerate() {
+                                let n = (tap * factor + phase) as f64;
+                                let x = (n - center) / factor as f64;
+                                let t = n / (length - 1) as f64;
+                                let window = 0.42 - 0.5 * (2.0 * PI * t).cos() + 0.08 * (4.0 * PI * t).cos();
+                                let sinc = if x == 0.0 {
+                                        1.0
+                                } else {
+                                        (PI * x).sin() / (PI * x)
+                                };
+                                *w = sinc * window.max(0.0);
+                        }
+                        // 每个相位的直流增益归一化为 1
+                        let sum: f64 = row.iter().sum();
+                        for (k, w) in kernel.iter_mut().zip(row) {
+                                *k = (w / sum) as f32;
+                        }
+                }
+                self.factor = factor;
+        }
+
+        fn clear(&mut self) {
+                self.history.fill(0.0);
+                self.peaks = [0.0; MAX_CHANNELS];
+        }
+
+        fn process(&mut self, samples: &[f32], channels: usize, frames: usize) {
+                for frame in 0..frames {
+                        let position = self.position;
+                        for c in 0..channels.min(MAX_CHANNELS) {
+                                let history = &mut self.history[c * TRUE_PEAK_TAPS..(c + 1) * TRUE_PEAK_TAPS];
+                                let sample = samples[c * frames + frame];
+                                history[position] = sample;
+                                let mut peak = self.peaks[c].max(sample.abs());
+                                for phase in self.kernel.chunks_exact(TRUE_PEAK_TAPS).take(self.factor) {
+                                        // 抽头 k 对应 k 个样本之前的输入
+                                        let mut sum = 0.0;
+                                        for (k, w) in phase.iter().enumerate() {
+                                                sum += w * history[(position + TRUE_PEAK_TAPS - k) % TRUE_PEAK_TAPS];
+                                        }
+                                        peak = peak.max(sum.abs());
+                                }
+                                self.peaks[c] = peak;
+                        }
+                        self.position = (position + 1) % TRUE_PEAK_TAPS;
+                }
+        }
+}
+
+/// BS.1770 响度计。输入为平面缓冲；最多 MAX_CHANNELS 个通道，状态全部是定长数组，
+/// 可以在音频线程上用 `configure` 改变采样率与通道数
+pub struct LoudnessMeter {
+        channels: usize,
+        weights: [f64; MAX_CHANNELS],
+        filters: [Biquad; 2],
+        states: [[[f64; 2]; 2]; MAX_CHANNELS],
+        // 100 ms 子块的帧数，以及当前子块已累积的帧数与加权平方和
+        block_frames: usize,
+        block_filled: usize,
+        block_sum: f64,
+        // 最近 SHORT_TERM_BLOCKS 个子块的均方值（环形）与已完成的子块总数
+        blocks: [f64; SHORT_TERM_BLOCKS],
+        block_count: usize,
+        momentary: f64,
+        short_term: f64,
+        max_momentary: f64,
+        max_short_term: f64,
+        // 积分响度的门限块（400 ms，75% 重叠）与响度范围的短期值
+        gating: Histogram,
+        range: Histogram,
+        true_peak: TruePeak,
+}
+
+impl LoudnessMeter {
+        pub fn new(sample_rate: f32, channels: usize) -> Self {
+                let sample_rate = sample_rate.max(1.0) as f64;
+                Self {
+                        channels: channels.min(MAX_CHANNELS),
+                        weights: channel_weights(channels),
+                        filters: k_weighting(sample_rate),
+                        states: [[[0.0; 2]; 2]; MAX_CHANNELS],
+                        block_frames: ((sample_rate / 10.0).round() as usize).max(1),
+                        block_filled: 0,
+                        block_sum: 0.0,
+                        blocks: [0.0; SHORT_TERM_BLOCKS],
+                        block_count: 0,
+                        momentary: f64::NEG_INFINITY,
+                        short_term: f64::NEG_INFINITY,
+                        max_momentary: f64::NEG_INFINITY,
+                        max_short_term: f64::NEG_INFINITY,
+                        gating: Histogram::new(),
+                        range: Histogram::new(),
+                        true_peak: TruePeak::new(sample_rate),
+                }
+        }
+
+        /// 改为新的采样率与通道数并重新开始测量（不分配内存）
+        pub fn configure(&mut self, sample_rate: f32, channels: usize) {
+                let sample_rate = sample_rate.max(1.0) as f64;
+                self.channels = channels.min(MAX_CHANNELS);
+                self.weights = channel_weights(self.channels);
+                self.filters = k_weighting(sample_rate);
+                self.block_frames = ((sample_rate / 10.0).round() as usize).max(1);
+                self.true_peak.set_sample_rate(sample_rate);
+                self.reset();
+        }
+
+        pub fn channels(&self) -> usize {
+                self.channels
+        }
+
+        /// 重新开始测量（积分响度、响度范围与最大值清零）
+        pub fn reset(&mut self) {
+                self.states = [[[0.0; 2]; 2]; MAX_CHANNELS];
+                self.block_filled = 0;
+                self.block_sum = 0.0;
+                self.blocks = [0.0; SHORT_TERM_BLOCKS];
+                self.block_count = 0;
+                self.momentary = f64::NEG_INFINITY;
+                self.short_term = f64::NEG_INFINITY;
+                self.max_momentary = f64::NEG_INFINITY;
+                self.max_short_term = f64::NEG_INFINITY;
+                self.gating.clear();
+                self.range.clear();
+                self.true_peak.clear();
+        }
+
+        /// 测量一个平面音频块（`channels` 个通道）
+        pub fn process(&mut self, samples: &[f32]) {
+                let channels = self.channels.min(MAX_CHANNELS);
+                if channels == 0 {
+                        return;
+                }
+                let frames = samples.len() / self.channels;
+                self.true_peak.process(samples, channels, frames);
+
+                let mut frame = 0;
+                while frame < frames {
+                        let len = (self.block_frames - self.block_filled).min(frames - frame);
+                        for c in 0..channels {
+                                let weight = self.weights[c];
+                                if weight == 0.0 {
+                                        continue;
+                                }
+                                let [shelf, high_pass] = self.filters;
+                                let [s0, s1] = &mut self.states[c];
+                                let mut sum = 0.0;
+                                for &x in &samples[c * frames + frame..c * frames + frame + len] {
+                                        let y = high_pass.process(s1, shelf.process(s0, x as f64));
+                                        sum += y * y;
+                                }
+                                self.block_sum += weight * sum;
+                        }
+                        self.block_filled += len;
+                        frame += len;
+                        if self.block_filled == self.block_frames {
+                                self.finish_block();
+                        }
+                }
+        }
+
+        fn window(&self, blocks: usize) -> f64 {
+                let sum: f64 = (1..=blocks)
+                        .map(|back| self.blocks[(self.block_count - back) % SHORT_TERM_BLOCKS])
+                        .sum();
+                sum / blocks as f64
+        }
+
+        // 一个 100 ms 子块结束：更新瞬时 / 短期响度并加入门限直方图
+        fn finish_block(&mut self) {
+                self.blocks[self.block_count % SHORT_TERM_BLOCKS] = self.block_sum / self.block_frames as f64;
+                self.block_count += 1;
+                self.block_filled = 0;
+                self.block_sum = 0.0;
+
+                if self.block_count >= MOMENTARY_BLOCKS {
+                        let power = self.window(MOMENTARY_BLOCKS);
+                        self.momentary = to_lufs(power);
+                        self.max_momentary = self.max_momentary.max(self.momentary);
+                        self.gating.add(power);
+                }
+                if self.block_count >= SHORT_TERM_BLOCKS {
+                        let power = self.window(SHORT_TERM_BLOCKS);
+                        self.short_term = to_lufs(power);
+                        self.max_short_term = self.max_short_term.max(self.short_term);
+                        self.range.add(power);
+                }
+        }
+
+        /// 瞬时响度（LUFS）
+        pub fn momentary(&self) -> Option<f32> {
+                finite(self.momentary)
+        }
+
+        /// 短期响度（LUFS）
+        pub fn short_term(&self) -> Option<f32> {
+                finite(self.short_term)
+        }
+
+        /// 积分响度（LUFS）
+        pub fn integrated(&self) -> Option<f32> {
+                let from = self.gating.relative_gate(RELATIVE_GATE)?;
+                let (_, power) = self.gating.mean(from)?;
+                finite(to_lufs(power))
+        }
+
+        /// 响度范围（LU）：相对门限 -20 LU 以上的短期响度第 10 与第 95 百分位之差
+        pub fn range(&self) -> Option<f32> {
+                let from = self.range.relative_gate(RANGE_GATE)?;
+                let (count, _) = self.range.mean(from)?;
+                let low = self.range.percentile(from, count, 0.10);
+                let high = self.range.percentile(from, count, 0.95);
+                Some((high - low) as f32)
+        }
+
+        /// 通道 `channel` 自开始测量以来的真峰值（线性）
+        pub fn true_peak(&self, channel: usize) -> f32 {
+                self.true_peak.peaks.get(channel).copied().unwrap_or(0.0)
+        }
+
+        /// 当前的完整测量结果（用于电平计显示）
+        pub fn report(&self) -> LoudnessReport {
+                let peak = self.true_peak.peaks.iter().fold(0.0f32, |m, p| m.max(*p));
+                LoudnessReport {
+                        momentary: self.momentary(),
+                        short_term: self.short_term(),
+                        integrated: self.integrated(),
+                        range: self.range(),
+                        max_momentary: finite(self.max_momentary),
+                        max_short_term: finite(self.max_short_term),
+                        true_peak: finite(20.0 * (peak as f64).log10()),
+                }
+        }
+}
@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+
+// 内置节拍器：按音序器报告的本块歌曲时间、速度与拍号在拍点上合成短促的 click，
+// 每小节第一拍（强拍）可用更高的音高与音量区分。由 MixerPlugin 在总轨之后混入主输出或写入独立输出。
+
+/// Click 音色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClickSound {
+        /// 正弦短音
+        #[default]
+        Beep,
+        /// 木鱼：快速衰减的正弦加上非谐波泛音
+        Woodblock,
+        /// 噪声短脉冲（类似闭镲）
+        Noise,
+}
+
+/// 节拍器输出
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetronomeOutput {
+        /// 混入主输出（不经过总轨推子与插入效果）
+        #[default]
+        Master,
+        /// 写入独立的 "Click" 输出端口；后端不提供附加端口时仍混入主输出
+        Dedicated,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetronomeSettings {
+        pub enabled: bool,
+        pub sound: ClickSound,
+        /// 线性增益（0..1）
+        pub level: f32,
+        /// 强拍使用更高的音高与音量
+        pub accent: bool,
+        /// 开始录音前的预备小节数
+        pub count_in_bars: u32,
+        /// 只在录音（含预备拍）时发声
+        pub record_only: bool,
+        pub output: MetronomeOutput,
+}
+
+impl Default for MetronomeSettings {
+        fn default() -> Self {
+                Self {
+                        enabled: false,
+                        sound: ClickSound::Beep,
+                        level: 0.5,
+                        accent: true,
+                        count_in_bars: 0,
+                        record_only: false,
+                        output: MetronomeOutput::Master,
+                }
+        }
+}
+
+/// 一个音频块对应的节拍信息（由音序器提供）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClickSpan {
+        /// 本块覆盖的歌曲时间 [start, end)（秒）；预备拍期间为录音起点之前的时间
+        pub start: f64,
+        pub end: f64,
+        /// 速度（每分钟四分音符数）
+        pub tempo: f64,
+        /// 拍号（分子, 分母）：每小节拍数与拍的时值
+        pub time_signature: (u32, u32),
+}
+
+impl ClickSpan {
+        /// 一拍的时长（秒），按拍号分母换算（6/8 拍的一拍为八分音符）
+        pub fn beat_length(&self) -> f64 {
+                60.0 / self.tempo.max(1.0) * 4.0 / self.time_signature.1.max(1) as f64
+        }
+}
+
+// 单个 click 的时长（秒）
+const CLICK_LENGTH: f32 = 0.05;
+// 非强拍相对强拍的音量
+const WEAK_GAIN: f32 = 0.6;
+
+struct ClickVoice {
+        accent: bool,
+        // 已发声的样本数
+        age: usize,
+}
+
+pub struct Metronome {
+        settings: MetronomeSettings,
+        voice: Option<ClickVoice>,
+        // 噪声音色的伪随机数状态（xorshift）
+        noise: u32,
+}
+
+impl Default for Metronome {
+        fn default() -> Self {
+                Self::new()
+        }
+}
+
+impl Metronome {
+        pub fn new() -> Self {
+                Self {
+                        settings: MetronomeSettings::default(),
+                        voice: None,
+                        noise: 0x1234_5678,
+                }
+        }
+
+        pub fn settings(&self) -> &MetronomeSettings {
+                &self.settings
+        }
+
+        pub fn set_settings(&mut self, settings: MetronomeSettings) {
+                self.settings = settings;
+        }
+
+        /// 是否应当发声：已启用，且不限于录音或正在录音
+        pub fn active(&self, recording: bool) -> bool {
+                self.settings.enabled && (!self.settings.record_only || recording)
+        }
+
+        // 本块内第一个拍点的序号（从歌曲开头计，预备拍为负）
+        fn first_beat(span: &ClickSpan) -> i64 {
+                (span.start / span.beat_length() - 1e-9).ceil() as i64
+        }
+
+        // 第 `index` 拍在本块内的帧偏移与是否为强拍；不在本块内时返回 None
+        fn beat_offset(span: &ClickSpan, frames: usize, index: i64) -> Option<(usize, bool)> {
+                let length = span.end - span.start;
+                let time = index as f64 * span.beat_length();
+                if length <= 0.0 || frames == 0 || time >= span.end {
+                        return None;
+                }
+                let offset = (((time - span.start) / length) * frames as f64) as usize;
+                let beats_per_bar = span.time_signature.0.max(1) as i64;
+                Some((offset.min(frames - 1), index.rem_euclid(beats_per_bar) == 0))
+        }
+
+        fn next_noise(&mut self) -> f32 {
+                self.noise ^= self.noise << 13;
+                self.noise ^= self.noise >> 17;
+                self.noise ^= self.noise << 5;
+                self.noise as f32 / u32::MAX as f32 * 2.0 - 1.0
+        }
+
+        fn sample(&mut self, accent: bool, age: usize, sample_rate: f32) -> f32 {
+                let t = age as f32 / sample_rate;
+                let accent = accent && self.settings.accent;
+                let gain = if accent { 1.0 } else { WEAK_GAIN };
+                let value = match self.settings.sound {
+                        ClickSound::Beep => {
+                                let freq = if accent { 1500.0 } else { 1000.0 };
+                                (TAU * freq * t).sin() * (-t / 0.012).exp()
+                        }
+                        ClickSound::Woodblock => {
+                                let freq = if accent { 1200.0 } else { 800.0 };
+                                ((TAU * freq * t).sin() + 0.4 * (TAU * freq * 2.7 * t).sin()) * (-t / 0.006).exp() / 1.4
+                        }
+                        ClickSound::Noise => {
+                                let decay = if accent { 0.010 } else { 0.006 };
+                                self.next_noise() * (-t / decay).exp()
+                        }
+                };
+                value * gain * self.settings.level
+        }
+
+        /// 把本块的 click 叠加到平面缓冲 `out`（所有通道相同）；`span` 为 None（停止）时只让正在发声的 click 结束
+        pub fn render(&mut self, out: &mut [f32], channels: usize, sample_rate: f32, span: Option<&ClickSpan>) {
+                let channels = channels.max(1);
+                let frames = out.len() / channels;
+                let length = (CLICK_LENGTH * sample_rate) as usize;
+                let mut index = span.map(Self::first_beat).unwrap_or(0);
+                let mut next = span.and_then(|s| Self::beat_offset(s, frames, index));
+                for frame in 0..frames {
+                        while let Some((offset, accent)) = next {
+                                if offset != frame {
+                                        break;
+                                }
+                                self.voice = Some(ClickVoice { accent, age: 0 });
+                                index += 1;
+                                next = span.and_then(|s| Self::beat_offset(s, frames, index));
+                        }
+                        let Some((accent, age)) = self.voice.as_ref().map(|v| (v.accent, v.age)) else {
+                                continue;
+                        };
+                        if age >= length {
+                                self.voice = None;
+                                continue;
+                        }
+                        let value = self.sample(accent, age, sample_rate);
+                        for channel in 0..channels {
+                                out[channel * frames + frame] += value;
+                        }
+                        if let Some(voice) = self.voice.as_mut() {
+                                voice.age += 1;
+                        }
+                }
+        }
+}
@@ -1,32 +1,118 @@
-use crate::audio::core::plugin::{AudioBuffer, Plugin, PluginEvent, PluginInfo, PluginParameter, PluginType};
+use crate::audio::analysis::tap::{AnalysisTap, tap_point};
+use crate::audio::core::channel_layout::{ChannelLayout, mix_planar, remix};
+use crate::audio::core::plugin::{
+        AudioBuffer, AudioPortConfig, IOConfig, NoteEvent, ParamAddress, Plugin, PluginEvent, PluginInfo,
+        PluginParameter, PluginType,
+};
+use crate::audio::core::smoothing::ParamRamper;
+use crate::audio::midi::clock::pop_clock_update;
+use crate::audio::plugins::mixer::delay_line::DelayLine;
+use crate::audio::plugins::mixer::metronome::{Metronome, MetronomeOutput, MetronomeSettings};
+use crate::audio::plugins::mixer::safety::{MasterSafety, NonFiniteGuard, SafetySettings};
+use crate::audio::plugins::mixer::summing::SummingBus;
 use crate::audio::plugins::mixer::track::MixerTrack;
 use crate::daw::sequencer::Sequencer;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+// 每块事件缓冲的预留容量
+const EVENT_CAPACITY: usize = 1024;
+
+// 旧版整数参数 ID：轨道为 `轨道下标 * 100 + 参数 ID`，乐器为 `10000 + 乐器下标 * 100 + 参数 ID`
+const LEGACY_INSTRUMENT_BASE: u32 = 10000;
+const LEGACY_STRIDE: u32 = 100;
+// 附加端口（轨道外部输入 / 推子后直出）固定为立体声；每个端口预留 MAX_AUX_FRAMES 帧，
+// 更长的块不提供附加端口
+const AUX_CHANNELS: usize = 2;
+const MAX_AUX_FRAMES: usize = 8192;
+const AUX_STRIDE: usize = AUX_CHANNELS * MAX_AUX_FRAMES;
+
 pub struct MixerPlugin {
         #[allow(dead_code)]
         id: Uuid,
         tracks: Vec<MixerTrack>,
         instruments: Vec<Arc<Mutex<Box<dyn Plugin>>>>,
+        // 乐器节点 ID（与 instruments 下标对应），用于分发 `NodeParameter` 事件
+        instrument_ids: Vec<Uuid>,
+        // 乐器参数斜坡（与 instruments 下标对应）
+        instrument_rampers: Vec<ParamRamper>,
+        // 乐器主输出端口的通道布局与输出分析抽头（与 instruments 下标对应）
+        instrument_layouts: Vec<ChannelLayout>,
+        instrument_taps: Vec<Arc<AnalysisTap>>,
+        // 乐器输出的非有限值保护（与 instruments 下标对应）
+        instrument_guards: Vec<NonFiniteGuard>,
         sequencer: Sequencer,
         scratch_buffer: Vec<f32>,
-        accumulator_buffer: Vec<f32>,
+        // 本块的节点参数事件（宿主事件 + 自动化）与单个乐器的事件，按块复用，避免在音频线程中分配
+        node_events: Vec<PluginEvent>,
+        inst_events: Vec<PluginEvent>,
+        // 各轨道输入的求和总线（可选双精度）
+        summing: SummingBus,
+        // 各轨道输入在 summing 中的起始位置（末尾额外一项为总长度）
+        track_offsets: Vec<usize>,
+        // 延迟补偿：每个乐器/轨道一条延迟线（与 instruments/tracks 下标对应），
+        // 以及直接路由到总轨的乐器信号所需的延迟线
+        instrument_latency: Vec<u32>,
+        instrument_delays: Vec<DelayLine>,
+        track_delays: Vec<DelayLine>,
+        master_direct_delay: DelayLine,
+        master_direct_delay_f64: DelayLine<f64>,
+        // 普通轨道（1..N）的附加端口：宿主写入的外部输入与推子后直出（立体声平面样本），
+        // 在 add_track 时按 AUX_STRIDE 为每个轨道预留，音频线程中只做索引
+        track_inputs: Vec<f32>,
+        track_outputs: Vec<f32>,
+        // 宿主本块写入附加输入的样本数（0 表示未写入）与最近一块直出的帧数
+        aux_input_len: usize,
+        aux_frames: usize,
+        metronome: Metronome,
+        // 节拍器的独立输出（最后一个附加输出端口 "Click"，立体声，创建时预留 AUX_STRIDE）；
+        // 宿主从未读取该端口时仍混入主输出
+        click_buffer: Vec<f32>,
+        click_port_used: AtomicBool,
+        // 把 Click 端口延迟限制器的前瞻，与经过安全级的主输出对齐
+        click_delay: DelayLine,
+        // 总输出安全级（限制器 / 隔直）
+        safety: MasterSafety,
 }
 impl MixerPlugin {
         pub fn new(num_tracks: usize) -> Self {
                 let mut tracks = Vec::new();
+                let mut track_delays = Vec::new();
                 for _ in 0..num_tracks {
-                        tracks.push(MixerTrack::new(None));
+                        tracks.push(MixerTrack::new(Uuid::new_v4(), None));
+                        track_delays.push(DelayLine::new(ChannelLayout::Stereo.channels()));
                 }
 
                 Self {
                         id: Uuid::new_v4(),
                         tracks,
                         instruments: Vec::new(),
+                        instrument_ids: Vec::new(),
+                        instrument_rampers: Vec::new(),
+                        instrument_layouts: Vec::new(),
+                        instrument_taps: Vec::new(),
+                        instrument_guards: Vec::new(),
                         sequencer: Sequencer::new(),
                         scratch_buffer: Vec::new(),
-                        accumulator_buffer: Vec::new(),
+                        node_events: Vec::with_capacity(EVENT_CAPACITY),
+                        inst_events: Vec::with_capacity(EVENT_CAPACITY),
+                        summing: SummingBus::new(),
+                        track_offsets: Vec::new(),
+                        instrument_latency: Vec::new(),
+                        instrument_delays: Vec::new(),
+                        track_delays,
+                        master_direct_delay: DelayLine::new(ChannelLayout::Stereo.channels()),
+                        master_direct_delay_f64: DelayLine::new(ChannelLayout::Stereo.channels()),
+                        track_inputs: Vec::new(),
+                        track_outputs: Vec::new(),
+                        aux_input_len: 0,
+                        aux_frames: 0,
+                        metronome: Metronome::new(),
+                        click_buffer: vec![0.0; AUX_STRIDE],
+                        click_port_used: AtomicBool::new(false),
+                        click_delay: DelayLine::new(AUX_CHANNELS),
+                        safety: MasterSafety::new(),
                 }
         }
 
@@ -34,16 +120,70 @@ impl MixerPlugin {
                 &mut self.sequencer
         }
 
-        pub fn add_track(&mut self, meter_id: Option<Uuid>) -> Uuid {
-                let track = MixerTrack::new(meter_id);
+        pub fn set_metronome(&mut self, settings: MetronomeSettings) {
+                self.metronome.set_settings(settings);
+        }
+
+        pub fn set_master_safety(&mut self, settings: SafetySettings) {
+                self.safety.set_settings(settings);
+        }
+
+        /// 以 f64 累加求和总线
+        pub fn set_double_precision(&mut self, enabled: bool) {
+                self.summing.set_double_precision(enabled);
+        }
+
+        /// 添加混音轨道，`id` 为轨道节点 ID；返回电平表 ID
+        pub fn add_track(&mut self, id: Uuid, meter_id: Option<Uuid>) -> Uuid {
+                let track = MixerTrack::new(id, meter_id);
                 let m_id = track.meter_id;
+                self.track_delays.push(DelayLine::new(track.layout.channels()));
+                // 总轨（第一个轨道）没有附加端口
+                if !self.tracks.is_empty() {
+                        self.track_inputs.resize(self.track_inputs.len() + AUX_STRIDE, 0.0);
+                        self.track_outputs.resize(self.track_outputs.len() + AUX_STRIDE, 0.0);
+                }
                 self.tracks.push(track);
                 m_id
         }
 
-        pub fn add_instrument(&mut self, plugin: Arc<Mutex<Box<dyn Plugin>>>) -> usize {
+        /// 设置轨道与其输出目标的通道布局（见 `MixerTrack::set_layout`），并按新的通道数重新分配延迟补偿线
+        pub fn set_track_layout(&mut self, index: usize, layout: ChannelLayout, output: ChannelLayout, pan_front: f32) {
+                let Some(track) = self.tracks.get_mut(index) else {
+                        return;
+                };
+                track.set_layout(layout, output, pan_front);
+                self.track_delays[index] = DelayLine::new(layout.channels());
+                if index == 0 {
+                        self.master_direct_delay = DelayLine::new(layout.channels());
+                        self.master_direct_delay_f64 = DelayLine::new(layout.channels());
+                }
+        }
+
+        /// 添加乐器，`id` 为乐器节点 ID（实例 ID）；返回乐器下标
+        pub fn add_instrument(&mut self, id: Uuid, plugin: Arc<Mutex<Box<dyn Plugin>>>) -> usize {
+                let (outputs, parameters, note_ids) = plugin
+                        .lock()
+                        .map(|p| {
+                                (
+                                        p.get_io_config().outputs,
+                                        p.get_parameters(),
+                                        p.supports_note_ids(),
+                                )
+                        })
+                        .unwrap_or((2, Vec::new(), false));
+                let layout = ChannelLayout::from_channels(outputs.max(1));
+                self.instrument_layouts.push(layout);
                 self.instruments.push(plugin);
-                self.instruments.len() - 1
+                self.instrument_ids.push(id);
+                self.instrument_taps.push(tap_point(id).unwrap_or_default());
+                self.instrument_guards.push(NonFiniteGuard::new(id));
+                self.instrument_rampers.push(ParamRamper::for_parameters(&parameters));
+                self.instrument_latency.push(0);
+                self.instrument_delays.push(DelayLine::new(layout.channels()));
+                let index = self.instruments.len() - 1;
+                self.sequencer.active_notes.set_note_ids(index, note_ids);
+                index
         }
 
         // 移除了 set_routing，因为它现在通过 Sequencer 动态处理
@@ -57,13 +197,80 @@ impl MixerPlugin {
         pub fn remove_track(&mut self, index: usize) {
                 if index < self.tracks.len() {
                         self.tracks.remove(index);
+                        self.track_delays.remove(index);
                 }
         }
 
-        #[allow(dead_code)]
         pub fn get_track_mut(&mut self, index: usize) -> Option<&mut MixerTrack> {
                 self.tracks.get_mut(index)
         }
+
+        /// 读取节点参数（乐器、轨道推子或插入效果）；节点不在本音频图中时返回 None
+        pub fn get_node_param(&self, address: ParamAddress) -> Option<f32> {
+                if let Some(inst_idx) = self.instrument_ids.iter().position(|id| *id == address.node) {
+                        return self.instruments[inst_idx]
+                                .lock()
+                                .ok()
+                                .map(|inst| inst.get_param(address.param_id));
+                }
+                self.tracks.iter().find_map(|track| track.get_node_param(address))
+        }
+
+        /// 立即设置节点参数（不经过参数斜坡）；节点不在本音频图中时返回 false。
+        /// 运行中的音频图应改为发送 `PluginEvent::NodeParameter`
+        pub fn set_node_param(&mut self, address: ParamAddress, value: f32) -> bool {
+                if let Some(inst_idx) = self.instrument_ids.iter().position(|id| *id == address.node) {
+                        if let Ok(mut inst) = self.instruments[inst_idx].lock() {
+                                inst.set_param(address.param_id, value);
+                        }
+                        return true;
+                }
+                self.tracks.iter_mut().any(|track| track.set_node_param(address, value))
+        }
+
+        /// 把旧版整数参数 ID 换算为当前音频图中的参数地址（下标越界时为 None）
+        pub fn legacy_address(&self, id: u32) -> Option<ParamAddress> {
+                if id >= LEGACY_INSTRUMENT_BASE {
+                        let id = id - LEGACY_INSTRUMENT_BASE;
+                        let node = self.instrument_ids.get((id / LEGACY_STRIDE) as usize)?;
+                        return Some(ParamAddress::new(*node, id % LEGACY_STRIDE));
+                }
+                let track = self.tracks.get((id / LEGACY_STRIDE) as usize)?;
+                Some(ParamAddress::new(track.id, id % LEGACY_STRIDE))
+        }
+
+        /// 计算各路径延迟并设置补偿延迟线，返回总输出延迟（采样数）。
+        ///
+        /// 路径为 乐器 -> 轨道 -> 总轨：
+        /// - 所有乐器对齐到最大的乐器延迟（乐器可同时路由到多个轨道，因此统一对齐）；
+        /// - 普通轨道（1..N）对齐到其中最大的轨道延迟，直接路由到总轨的乐器信号也补偿同样的延迟；
+        /// - 总输出延迟 = 最大乐器延迟 + 最大轨道延迟 + 总轨自身延迟。
+        ///
+        /// 各节点的延迟在其处理时（已持有实例锁）读取，因此延迟变化在下一个音频块生效；
+        /// 延迟线在构建音频图时按各路径自身的通道数分配，这里只改变延迟长度。
+        fn update_latency_compensation(&mut self) -> u32 {
+                let max_inst = self.instrument_latency.iter().copied().max().unwrap_or(0);
+                for (delay, latency) in self.instrument_delays.iter_mut().zip(self.instrument_latency.iter()) {
+                        delay.set_delay((max_inst - latency) as usize);
+                }
+
+                let mut max_track = 0;
+                for track in self.tracks.iter_mut().skip(1) {
+                        max_track = max_track.max(track.refresh_latency());
+                }
+                let master_latency = self.tracks.first_mut().map(|t| t.refresh_latency()).unwrap_or(0);
+                for (track, delay) in self.tracks.iter().zip(self.track_delays.iter_mut()).skip(1) {
+                        delay.set_delay((max_track - track.latency()) as usize);
+                }
+                // 只有当前累加精度对应的延迟线参与处理
+                if self.summing.double_precision() {
+                        self.master_direct_delay_f64.set_delay(max_track as usize);
+                } else {
+                        self.master_direct_delay.set_delay(max_track as usize);
+                }
+
+                max_inst + max_track + master_latency
+        }
 }
 
 impl Plugin for MixerPlugin {
@@ -84,25 +291,77 @@ impl Plugin for MixerPlugin {
                 Vec::new()
         }
 
+        // 主输出为总轨；每个普通轨道另有一对附加端口（外部输入 / 推子后直出），供多端口后端使用；
+        // 最后一个附加输出为节拍器的 Click 端口
+        fn get_io_config(&self) -> IOConfig {
+                let port = |name: &str, is_main: bool| AudioPortConfig {
+                        name: name.to_string(),
+                        channels: AUX_CHANNELS,
+                        is_main,
+                };
+                let track_ports: Vec<AudioPortConfig> =
+                        self.tracks.iter().skip(1).map(|t| port(&t.label, false)).collect();
+                let master = self
+                        .tracks
+                        .first()
+                        .map(|t| t.label.as_str())
+                        .filter(|l| !l.is_empty())
+                        .unwrap_or("Master");
+                let mut output_ports = vec![port(master, true)];
+                output_ports.extend(track_ports.iter().cloned());
+                output_ports.push(port("Click", false));
+                IOConfig {
+                        inputs: 0,
+                        outputs: 2,
+                        input_ports: track_ports,
+                        output_ports,
+                }
+        }
+
+        fn aux_input_mut(&mut self, index: usize, len: usize) -> Option<&mut [f32]> {
+                let count = self.tracks.len().saturating_sub(1);
+                if index >= count || len > AUX_STRIDE {
+                        return None;
+                }
+                self.aux_input_len = len;
+                self.track_inputs.get_mut(index * AUX_STRIDE..index * AUX_STRIDE + len)
+        }
+
+        fn aux_output(&self, index: usize) -> Option<&[f32]> {
+                let count = self.tracks.len().saturating_sub(1);
+                if index > count || self.aux_frames == 0 {
+                        return None;
+                }
+                if index == count {
+                        self.click_port_used.store(true, Ordering::Relaxed);
+                        return Some(&self.click_buffer[..AUX_CHANNELS * self.aux_frames]);
+                }
+                let start = index * AUX_STRIDE;
+                self.track_outputs.get(start..start + AUX_CHANNELS * self.aux_frames)
+        }
+
         fn process(&mut self, buffer: &mut AudioBuffer, events: &[PluginEvent], output_events: &mut Vec<PluginEvent>) {
                 let samples_len = buffer.samples.len();
-                let channels = buffer.channels;
+                let channels = buffer.channels.max(1);
                 let sample_rate = buffer.sample_rate;
+                let frames = samples_len / channels;
 
-                // 调整缓冲区大小
-                if self.scratch_buffer.len() != samples_len {
-                        self.scratch_buffer = vec![0.0; samples_len];
-                }
-                if self.accumulator_buffer.len() != samples_len {
-                        self.accumulator_buffer = vec![0.0; samples_len];
-                }
+                // 设备输出的通道布局；总轨按自身布局处理后混合到设备通道
+                let device_layout = ChannelLayout::from_channels(channels);
+                let device_matrix = |layout: ChannelLayout| device_layout.mix_matrix_from(layout);
 
                 // 清除主输出
                 for sample in buffer.samples.iter_mut() {
                         *sample = 0.0;
                 }
 
+                // 外部 MIDI Clock 的走带更新经无锁队列直接到达，不经过引擎的事件通道
+                while let Some(update) = pop_clock_update() {
+                        self.sequencer.follow_external(&update);
+                }
+
                 // 处理传输和 Clip 事件
+                let mut panic = false;
                 for event in events {
                         match event {
                                 PluginEvent::Transport {
@@ -112,257 +371,313 @@ impl Plugin for MixerPlugin {
                                 } => {
                                         self.sequencer.set_transport(*playing, *position, *tempo);
                                 }
+                                PluginEvent::AllNotesOff { panic: p } => {
+                                        self.sequencer.all_notes_off();
+                                        panic |= *p;
+                                }
+                                PluginEvent::Record { recording } => {
+                                        if *recording {
+                                                let bars = self.metronome.settings().count_in_bars;
+                                                self.sequencer.start_recording(bars);
+                                        } else {
+                                                self.sequencer.recording = false;
+                                        }
+                                }
+                                PluginEvent::Metronome(settings) => {
+                                        self.metronome.set_settings(settings.clone());
+                                }
+                                PluginEvent::MasterSafety(settings) => {
+                                        self.safety.set_settings(settings.clone());
+                                }
+                                PluginEvent::TimeSignature { numerator, denominator } => {
+                                        self.sequencer.time_signature = (*numerator, *denominator);
+                                }
+                                PluginEvent::InsertState {
+                                        node,
+                                        bypass,
+                                        post_fader,
+                                } => {
+                                        for track in self.tracks.iter_mut() {
+                                                if track.set_insert_state(*node, *bypass, *post_fader) {
+                                                        break;
+                                                }
+                                        }
+                                }
                                 _ => {}
                         }
                 }
 
+                // 延迟补偿：根据插件报告的延迟对齐并行路径，并把总输出延迟（含限制器前瞻）报告给传输（Sequencer）
+                let output_latency = self.update_latency_compensation() + self.safety.latency(sample_rate);
+                self.sequencer.set_output_latency(output_latency);
+
                 // 0. Run Sequencer to get Events and Routing for this block
-                let (seq_events, routing) = self.sequencer.process(samples_len);
+                self.sequencer.sample_rate = sample_rate;
+                let (mut seq_events, routing) = self.sequencer.process(frames);
+
+                // 节点参数事件（宿主发送的与自动化生成的）：乐器按节点 ID 转换后直接送达，轨道及其插入效果由 MixerTrack 自行分发
+                self.node_events.clear();
+                self.node_events.extend(events
+                        .iter()
+                        .filter(|e| matches!(e, PluginEvent::NodeParameter { .. }))
+                        .cloned());
+                self.node_events
+                        .extend(self.sequencer.automation_events().iter().cloned());
+                let node_events = &self.node_events;
+
+                // Panic：在音序器的 NoteOff 之后，向所有乐器的每个通道发送复位控制器
+                if panic {
+                        for inst_idx in 0..self.instruments.len() {
+                                let inst_events = seq_events.entry(inst_idx).or_default();
+                                for channel in 0..16 {
+                                        for controller in [120, 121, 123] {
+                                                inst_events.push(PluginEvent::Midi(NoteEvent::ControlChange {
+                                                        channel,
+                                                        controller,
+                                                        value: 0.0,
+                                                }));
+                                        }
+                                }
+                        }
+                }
 
                 let num_tracks = self.tracks.len();
                 let num_instruments = self.instruments.len();
 
-                // 准备轨道输入缓冲区
-                // 我们需要为每个轨道分配缓冲区以累加乐器输出
-                // 优化：我们可以使用单个缓冲池，但目前先采用分配/重置大小的方式
-                // 实际上，如果 N 是动态的，我们无法轻松地在不分配的情况下持有 N 个缓冲区。
-                // 但 self.tracks 在每次图（graph）重建时是固定的。
-                // 使用扁平向量：track_inputs[track_idx * samples_len ..]
-                let total_track_samples = num_tracks * samples_len;
-                if self.accumulator_buffer.len() < total_track_samples {
-                        self.accumulator_buffer = vec![0.0; total_track_samples];
-                } else {
-                        // 清空
-                        for x in self.accumulator_buffer.iter_mut() {
-                                *x = 0.0;
+                // 准备轨道输入缓冲区：扁平向量，每个轨道按自身布局占 frames * 通道数 个平面样本，
+                // 轨道 t 位于求和总线的 [track_offsets[t]..track_offsets[t + 1]]
+                self.track_offsets.clear();
+                let mut total_track_samples = 0;
+                for track in &self.tracks {
+                        self.track_offsets.push(total_track_samples);
+                        total_track_samples += frames * track.layout.channels();
+                }
+                self.track_offsets.push(total_track_samples);
+                self.summing.clear(total_track_samples);
+                // 临时缓冲按最宽的路径分配（乐器或轨道）
+                let widest = self
+                        .tracks
+                        .iter()
+                        .map(|t| t.layout.channels())
+                        .chain(self.instrument_layouts.iter().map(|l| l.channels()))
+                        .fold(channels, usize::max);
+                if self.scratch_buffer.len() < frames * widest {
+                        self.scratch_buffer = vec![0.0; frames * widest];
+                }
+
+                // 外部输入（多端口宿主写入的立体声附加输入）混入普通轨道的输入，读取后清零
+                let aux_input_len = std::mem::take(&mut self.aux_input_len);
+                if aux_input_len == AUX_CHANNELS * frames {
+                        for track_idx in 1..num_tracks {
+                                let start = (track_idx - 1) * AUX_STRIDE;
+                                let input = &mut self.track_inputs[start..start + aux_input_len];
+                                let layout = self.tracks[track_idx].layout;
+                                let (start, end) = (
+                                        self.track_offsets[track_idx],
+                                        self.track_offsets[track_idx + 1],
+                                );
+                                self.summing.add(start..end, |bus| {
+                                        mix_planar(
+                                                input,
+                                                channels,
+                                                bus,
+                                                layout.channels(),
+                                                &layout.mix_matrix_from(ChannelLayout::Stereo),
+                                        )
+                                });
+                                input.fill(0.0);
                         }
                 }
+                // 块长超过预留的直出缓冲时本块不提供直出
+                self.aux_frames = if frames <= MAX_AUX_FRAMES { frames } else { 0 };
 
                 // 1. Process Instruments (ONCE) and mix to Track Buffers
                 for inst_idx in 0..num_instruments {
                         // 合并音序器事件（Sequencer）和参数事件
-                        let mut inst_events: Vec<PluginEvent> = Vec::new();
+                        let inst_events = &mut self.inst_events;
+                        inst_events.clear();
 
                         // 添加音序器事件（音符）
                         if let Some(evts) = seq_events.get(&inst_idx) {
-                                inst_events.extend(evts.clone());
+                                inst_events.extend(evts.iter().cloned());
                         }
 
                         // 添加参数事件
-                        inst_events.extend(events.iter().filter_map(|e| match e {
-                                PluginEvent::Midi(_) => None,
-                                PluginEvent::Transport { .. } => None,
-                                PluginEvent::Custom(_) => None,
-                                PluginEvent::Parameter { id, value } => {
-                                        if *id >= 10000 {
-                                                let target_inst = ((*id - 10000) / 100) as usize;
-                                                if target_inst == inst_idx {
-                                                        return Some(PluginEvent::Parameter {
-                                                                id: (*id - 10000) % 100,
-                                                                value: *value,
-                                                        });
-                                                }
-                                        }
-                                        None
-                                }
-                        }));
+                        let inst_id = self.instrument_ids[inst_idx];
+                        inst_events.extend(node_events.iter().filter_map(|e| e.for_node(inst_id)));
 
                         let inst_arc = &self.instruments[inst_idx];
 
+                        // 乐器按其主输出端口的布局处理
+                        let inst_layout = self.instrument_layouts[inst_idx];
+                        let inst_samples = &mut self.scratch_buffer[..frames * inst_layout.channels()];
+
+                        // 乐器从静音开始写入，避免上一个乐器的输出被当作输入
+                        inst_samples.fill(0.0);
+
                         let mut inst_buffer = AudioBuffer {
-                                samples: &mut self.scratch_buffer,
-                                channels,
+                                samples: inst_samples,
+                                channels: inst_layout.channels(),
                                 sample_rate,
                         };
 
                         // 处理乐器
                         if let Ok(mut plugin) = inst_arc.try_lock() {
-                                plugin.process(&mut inst_buffer, &inst_events, output_events);
+                                // 延迟在同一次加锁中读取，不再为查询延迟单独加锁
+                                self.instrument_latency[inst_idx] = plugin.latency();
+                                self.instrument_rampers[inst_idx].process(
+                                        plugin.as_mut(),
+                                        &mut inst_buffer,
+                                        inst_events,
+                                        output_events,
+                                );
                         } else {
                                 // 如果无法锁定（例如正在保存状态），则输出静音
-                                for s in self.scratch_buffer.iter_mut() {
-                                        *s = 0.0;
-                                }
+                                inst_buffer.samples.fill(0.0);
                         }
+                        self.instrument_guards[inst_idx].check(inst_buffer.samples);
+                        self.instrument_taps[inst_idx].write(&inst_buffer);
 
-                        // Debug: Check if instrument produced sound
-                        let mut max_sample = 0.0f32;
-                        for s in self.scratch_buffer.iter() {
-                                if s.abs() > max_sample {
-                                        max_sample = s.abs();
-                                }
-                        }
-                        if max_sample > 0.0 {
-                                // println!("Mixer: Inst {} produced sound, max: {}", inst_idx, max_sample);
-                        }
+                        // 补偿乐器之间的延迟差
+                        self.instrument_delays[inst_idx].process(inst_buffer.samples);
 
-                        // 混音到目标轨道
+                        // 混音到目标轨道（布局不一致时上混 / 下混）
                         if let Some(target_tracks) = routing.get(&inst_idx) {
                                 for &track_idx in target_tracks {
                                         if track_idx < num_tracks {
-                                                let start = track_idx * samples_len;
-                                                let end = start + samples_len;
-                                                let track_slice = &mut self.accumulator_buffer[start..end];
-
-                                                for (i, sample) in self.scratch_buffer.iter().enumerate() {
-                                                        track_slice[i] += sample;
-                                                }
+                                                let start = self.track_offsets[track_idx];
+                                                let end = self.track_offsets[track_idx + 1];
+                                                let track_layout = self.tracks[track_idx].layout;
+                                                self.summing.add(start..end, |bus| {
+                                                        remix(inst_buffer.samples, inst_layout, bus, track_layout)
+                                                });
                                         }
                                 }
                         }
                 }
 
+                // 直接路由到总轨的乐器信号需要等待普通轨道的处理延迟
+                if num_tracks > 0 {
+                        let master = 0..self.track_offsets[1];
+                        if let Some(bus) = self.summing.single_mut(master.clone()) {
+                                self.master_direct_delay.process(bus);
+                        }
+                        if let Some(bus) = self.summing.double_mut(master) {
+                                self.master_direct_delay_f64.process(bus);
+                        }
+                }
+
                 // 2. Process Tracks
                 // 修改：先处理非总轨（1..N），将其输出累加到总轨（0）的输入中。
                 // 然后处理总轨（0），将其输出写入主缓冲区。
 
                 // A. 处理普通轨道 (1..N)
+                let master_len = self.track_offsets.get(1).copied().unwrap_or(0);
                 for track_idx in 1..num_tracks {
-                        let start = track_idx * samples_len;
-                        let end = start + samples_len;
+                        let start = self.track_offsets[track_idx];
+                        let end = self.track_offsets[track_idx + 1];
+                        let track_channels = self.tracks[track_idx].layout.channels();
 
                         // 将累积的输入复制到临时缓冲区以进行处理
-                        self.scratch_buffer
-                                .copy_from_slice(&self.accumulator_buffer[start..end]);
-
-                        let track_events: Vec<PluginEvent> = events
-                                .iter()
-                                .filter_map(|e| match e {
-                                        PluginEvent::Midi(_) => None,
-                                        PluginEvent::Transport { .. } => None,
-                                        PluginEvent::Custom(_) => None,
-                                        PluginEvent::Parameter { id, value } => {
-                                                if *id < 10000 {
-                                                        let target_track = (*id / 100) as usize;
-                                                        if target_track == track_idx {
-                                                                return Some(PluginEvent::Parameter {
-                                                                        id: *id % 100,
-                                                                        value: *value,
-                                                                });
-                                                        }
-                                                }
-                                                None
-                                        }
-                                })
-                                .collect();
+                        let track_samples = &mut self.scratch_buffer[..end - start];
+                        self.summing.read(start..end, track_samples);
 
                         if let Some(track) = self.tracks.get_mut(track_idx) {
                                 let mut track_buffer = AudioBuffer {
-                                        samples: &mut self.scratch_buffer,
-                                        channels,
+                                        samples: track_samples,
+                                        channels: track_channels,
                                         sample_rate,
                                 };
 
-                                track.process(&mut track_buffer, &track_events, output_events);
-
-                                // 将输出累加到总轨 (Track 0) 的输入缓冲区
-                                // 总轨输入位于 accumulator_buffer[0..samples_len]
-                                if num_tracks > 0 {
-                                        let master_input = &mut self.accumulator_buffer[0..samples_len];
-                                        for (i, sample) in self.scratch_buffer.iter().enumerate() {
-                                                master_input[i] += sample;
-                                        }
+                                track.process(&mut track_buffer, node_events, output_events);
+
+                                // 补偿轨道之间的延迟差，使并行轨道在总轨保持相位对齐
+                                self.track_delays[track_idx].process(track_buffer.samples);
+
+                                // 推子后直出（立体声附加输出端口）
+                                if self.aux_frames > 0 {
+                                        let start = (track_idx - 1) * AUX_STRIDE;
+                                        let direct = &mut self.track_outputs[start..start + AUX_CHANNELS * frames];
+                                        direct.fill(0.0);
+                                        mix_planar(
+                                                track_buffer.samples,
+                                                track_channels,
+                                                direct,
+                                                AUX_CHANNELS,
+                                                &ChannelLayout::Stereo.mix_matrix_from(track.layout),
+                                        );
                                 }
+
+                                // 将输出累加到总轨 (Track 0) 的输入缓冲区（按总轨布局上混 / 下混或环绕声像）
+                                let output = &*track_buffer.samples;
+                                self.summing.add(0..master_len, |bus| track.mix_output(output, bus));
                         }
                 }
 
                 // B. 处理总轨 (Track 0)
                 if num_tracks > 0 {
                         let track_idx = 0;
-                        let start = 0;
-                        let end = samples_len;
-
-                        // 此时 accumulator_buffer[0..samples_len] 包含了直接路由到总轨的乐器声音 + 其他轨道的输出
-                        self.scratch_buffer
-                                .copy_from_slice(&self.accumulator_buffer[start..end]);
-
-                        let track_events: Vec<PluginEvent> = events
-                                .iter()
-                                .filter_map(|e| match e {
-                                        PluginEvent::Midi(_) => None,
-                                        PluginEvent::Transport { .. } => None,
-                                        PluginEvent::Custom(_) => None,
-                                        PluginEvent::Parameter { id, value } => {
-                                                if *id < 10000 {
-                                                        let target_track = (*id / 100) as usize;
-                                                        if target_track == track_idx {
-                                                                return Some(PluginEvent::Parameter {
-                                                                        id: *id % 100,
-                                                                        value: *value,
-                                                                });
-                                                        }
-                                                }
-                                                None
-                                        }
-                                })
-                                .collect();
+                        let master_layout = self.tracks[track_idx].layout;
+
+                        // 此时求和总线的 [0..master_len] 包含了直接路由到总轨的乐器声音 + 其他轨道的输出
+                        let master_samples = &mut self.scratch_buffer[..master_len];
+                        self.summing.read(0..master_len, master_samples);
 
                         if let Some(track) = self.tracks.get_mut(track_idx) {
                                 let mut track_buffer = AudioBuffer {
-                                        samples: &mut self.scratch_buffer,
-                                        channels,
+                                        samples: master_samples,
+                                        channels: master_layout.channels(),
                                         sample_rate,
                                 };
 
-                                track.process(&mut track_buffer, &track_events, output_events);
+                                track.process(&mut track_buffer, node_events, output_events);
 
-                                // 将总轨输出写入主缓冲区，并进行硬削波（Hard Clip）限制在 0dB ([-1.0, 1.0])
-                                for (i, sample) in self.scratch_buffer.iter().enumerate() {
-                                        let mut out = *sample;
-                                        if out > 1.0 {
-                                                out = 1.0;
-                                        } else if out < -1.0 {
-                                                out = -1.0;
-                                        }
-                                        buffer.samples[i] += out;
-                                }
+                                // 将总轨输出混合到设备通道（安全级在节拍器之后统一处理）
+                                mix_planar(
+                                        track_buffer.samples,
+                                        master_layout.channels(),
+                                        buffer.samples,
+                                        channels,
+                                        &device_matrix(master_layout),
+                                );
                         }
                 }
-        }
 
-        fn get_param(&self, id: u32) -> f32 {
-                // 将全局混音器参数映射到轨道参数？
-                // ID 方案：TrackIndex * 100 + ParamID
-                // 乐器参数如何处理？
-                // 假设乐器参数为 10000 + InstIndex * 100 + ParamID
-
-                if id >= 10000 {
-                        let inst_idx = ((id - 10000) / 100) as usize;
-                        let param_id = (id - 10000) % 100;
-                        if let Some(inst_arc) = self.instruments.get(inst_idx) {
-                                if let Ok(inst) = inst_arc.lock() {
-                                        return inst.get_param(param_id);
-                                }
-                        }
-                        return 0.0;
+                // 3. 节拍器：不经过总轨推子与插入效果
+                let span = if self.metronome.active(self.sequencer.recording) {
+                        self.sequencer.click_span()
+                } else {
+                        None
+                };
+                let dedicated = self.metronome.settings().output == MetronomeOutput::Dedicated
+                        && self.click_port_used.load(Ordering::Relaxed)
+                        && self.aux_frames > 0;
+                let click = &mut self.click_buffer[..AUX_CHANNELS * self.aux_frames];
+                click.fill(0.0);
+                if dedicated {
+                        self.metronome.render(click, AUX_CHANNELS, sample_rate, span.as_ref());
+                        self.click_delay.set_delay(self.safety.latency(sample_rate) as usize);
+                        self.click_delay.process(click);
+                } else {
+                        self.metronome
+                                .render(buffer.samples, channels, sample_rate, span.as_ref());
                 }
 
-                let track_idx = (id / 100) as usize;
-                let param_id = id % 100;
+                // 4. 总输出安全级：清除非有限值，启用时经隔直与前瞻限制器保证不超过上限
+                self.safety.process(buffer.samples, channels, sample_rate);
+        }
 
-                if let Some(track) = self.tracks.get(track_idx) {
-                        return track.container.get_param(param_id);
-                }
-                0.0
+        // 兼容旧版整数参数 ID（见 `legacy_address`）；新代码应使用 `get_node_param` / `set_node_param`
+        // 或 `PluginEvent::NodeParameter`
+        fn get_param(&self, id: u32) -> f32 {
+                self.legacy_address(id)
+                        .and_then(|address| self.get_node_param(address))
+                        .unwrap_or(0.0)
         }
 
         fn set_param(&mut self, id: u32, value: f32) {
-                if id >= 10000 {
-                        let inst_idx = ((id - 10000) / 100) as usize;
-                        let param_id = (id - 10000) % 100;
-                        if let Some(inst_arc) = self.instruments.get_mut(inst_idx) {
-                                if let Ok(mut inst) = inst_arc.lock() {
-                                        inst.set_param(param_id, value);
-                                }
-                        }
-                        return;
-                }
-
-                let track_idx = (id / 100) as usize;
-                let param_id = id % 100;
-
-                if let Some(track) = self.tracks.get_mut(track_idx) {
-                        track.container.set_param(param_id, value);
+                if let Some(address) = self.legacy_address(id) {
+                        self.set_node_param(address, value);
                 }
         }
 }
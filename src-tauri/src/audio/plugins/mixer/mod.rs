@@ -1,3 +1,9 @@
+pub mod delay_line;
 pub mod level_meter;
+pub mod loudness;
+pub mod metronome;
 pub mod mixer_plugin;
+pub mod panner;
+pub mod safety;
+pub mod summing;
 pub mod track;
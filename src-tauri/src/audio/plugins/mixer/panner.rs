@@ -0,0 +1,116 @@
+use crate::audio::core::channel_layout::{ChannelLayout, MAX_CHANNELS, MixMatrix, Speaker, mix_planar, pan_gains};
+
+// 环绕声像：通道较少的轨道（单声道 / 立体声等）输出到环绕总线时，按二维声像位置分配到各扬声器。
+// 源的每个通道以其自身方位为偏移绕声像方位旋转（立体声源保持 ±30° 的宽度），LFE 直通到目标的 LFE。
+// 目标为立体声（单声道或环绕轨道输出到立体声总线）时只使用左右坐标：单声道源按等功率声像
+// （中置时两侧各 -3 dB，与上混矩阵一致），环绕源先按 ITU 下混再做与立体声轨道相同的平衡声像。
+// 增益在每个块内从上一块的值线性过渡到新值，位置变化不会产生拉链噪声。
+
+pub struct SurroundPanner {
+        source: ChannelLayout,
+        dest: ChannelLayout,
+        // 声像位置：x 为 -1 左 .. 1 右，y 为 -1 后 .. 1 前
+        x: f32,
+        y: f32,
+        // 上一块结束时的增益矩阵
+        gains: MixMatrix,
+}
+
+impl SurroundPanner {
+        pub fn new(source: ChannelLayout, dest: ChannelLayout, x: f32, y: f32) -> Self {
+                let mut panner = Self {
+                        source,
+                        dest,
+                        x: x.clamp(-1.0, 1.0),
+                        y: y.clamp(-1.0, 1.0),
+                        gains: [[0.0; MAX_CHANNELS]; MAX_CHANNELS],
+                };
+                panner.gains = panner.target_gains();
+                panner
+        }
+
+        pub fn x(&self) -> f32 {
+                self.x
+        }
+
+        pub fn y(&self) -> f32 {
+                self.y
+        }
+
+        pub fn set_x(&mut self, x: f32) {
+                self.x = x.clamp(-1.0, 1.0);
+        }
+
+        pub fn set_y(&mut self, y: f32) {
+                self.y = y.clamp(-1.0, 1.0);
+        }
+
+        /// 当前位置对应的增益矩阵（`[输出通道][输入通道]`）
+        pub fn target_gains(&self) -> MixMatrix {
+                if !self.dest.is_surround() {
+                        return self.stereo_gains();
+                }
+                let mut matrix = [[0.0; MAX_CHANNELS]; MAX_CHANNELS];
+                let lfe = self.dest.speakers().iter().position(|s| *s == Speaker::Lfe);
+                for (input, speaker) in self.source.speakers().iter().enumerate() {
+                        let Some(offset) = speaker.azimuth(self.source) else {
+                                if let Some(output) = lfe {
+                                        matrix[output][input] = 1.0;
+                                }
+                                continue;
+                        };
+                        let gains = pan_gains(self.dest, self.x, self.y, offset);
+                        for (output, gain) in gains.iter().enumerate() {
+                                matrix[output][input] = *gain;
+                        }
+                }
+                matrix
+        }
+
+        // 立体声（或单声道）目标：布局矩阵之上叠加左右声像
+        fn stereo_gains(&self) -> MixMatrix {
+                let mut matrix = self.dest.mix_matrix_from(self.source);
+                if self.dest != ChannelLayout::Stereo {
+                        return matrix;
+                }
+                if self.source == ChannelLayout::Mono {
+                        let angle = (self.x + 1.0) * std::f32::consts::FRAC_PI_4;
+                        matrix[0][0] = angle.cos();
+                        matrix[1][0] = angle.sin();
+                } else {
+                        let (left, right) = ((1.0 - self.x).min(1.0), (1.0 + self.x).min(1.0));
+                        let channels = self.source.channels();
+                        matrix[0][..channels].iter_mut().for_each(|g| *g *= left);
+                        matrix[1][..channels].iter_mut().for_each(|g| *g *= right);
+                }
+                matrix
+        }
+
+        /// 把源布局的平面 `input` 声像后叠加到目标布局的平面 `output`
+        pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+                let target = self.target_gains();
+                let (ins, outs) = (self.source.channels(), self.dest.channels());
+                if target == self.gains {
+                        mix_planar(input, ins, output, outs, &target);
+                        return;
+                }
+                let (in_frames, out_frames) = (input.len() / ins, output.len() / outs);
+                let frames = in_frames.min(out_frames);
+                let step = 1.0 / frames.max(1) as f32;
+                for o in 0..outs {
+                        let dst = &mut output[o * out_frames..o * out_frames + frames];
+                        for i in 0..ins {
+                                let (from, to) = (self.gains[o][i], target[o][i]);
+                                if from == 0.0 && to == 0.0 {
+                                        continue;
+                                }
+                                let src = &input[i * in_frames..i * in_frames + frames];
+                                for (n, (d, s)) in dst.iter_mut().zip(src).enumerate() {
+                                        let t = (n + 1) as f32 * step;
+                                        *d += (from + (to - from) * t) * s;
+                                }
+                        }
+                }
+                self.gains = target;
+        }
+}
@@ -0,0 +1,320 @@
+use crate::audio::core::channel_layout::MAX_CHANNELS;
+use crate::audio::core::threads;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use uuid::Uuid;
+
+// 总输出安全级：可选的隔直滤波 + 前瞻砖墙限制器，保证送往设备的样本不超过设定的上限；
+// 以及各节点输出的非有限值（NaN / Inf）保护：检测到后清零该节点的输出并保持静音，
+// 同时通过主线程泵向界面报告。限制器可以关闭，把超过 0 dBFS 的浮点信号原样送往浮点输出
+// （例如由其他应用录制的 JACK 端口；目前没有离线渲染），非有限值保护始终有效。
+// 所有缓冲在创建时按最长前瞻、最高采样率与 MAX_CHANNELS 预分配，音频线程上改变设置只更新系数与读取位置。
+
+// 上限、前瞻与释放时间的有效范围
+pub const CEILING_RANGE: std::ops::RangeInclusive<f32> = -24.0..=0.0;
+pub const LOOKAHEAD_RANGE: std::ops::RangeInclusive<f32> = 0.1..=10.0;
+pub const RELEASE_RANGE: std::ops::RangeInclusive<f32> = 1.0..=2000.0;
+
+// 隔直滤波器的截止频率（Hz）
+const DC_CUTOFF: f32 = 5.0;
+// 预分配所依据的最高采样率（与工程采样率的上限一致）
+const MAX_SAMPLE_RATE: f32 = 384000.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SafetySettings {
+        /// 启用限制器（关闭时总输出不做任何电平限制）
+        pub enabled: bool,
+        /// 输出上限（dBFS）
+        pub ceiling: f32,
+        /// 前瞻时间（毫秒），同时是限制器引入的延迟
+        pub lookahead: f32,
+        /// 释放时间（毫秒）
+        pub release: f32,
+        /// 在限制器之前去除直流偏移
+        pub dc_blocker: bool,
+}
+
+impl Default for SafetySettings {
+        fn default() -> Self {
+                Self {
+                        enabled: true,
+                        ceiling: -0.3,
+                        lookahead: 1.5,
+                        release: 100.0,
+                        dc_blocker: false,
+                }
+        }
+}
+
+impl SafetySettings {
+        pub fn validate(&self) -> Result<(), String> {
+                if !CEILING_RANGE.contains(&self.ceiling) {
+                        return Err(format!("Invalid limiter ceiling: {}", self.ceiling));
+                }
+                if !LOOKAHEAD_RANGE.contains(&self.lookahead) {
+                        return Err(format!("Invalid limiter lookahead: {}", self.lookahead));
+                }
+                if !RELEASE_RANGE.contains(&self.release) {
+                        return Err(format!("Invalid limiter release: {}", self.release));
+                }
+                Ok(())
+        }
+}
+
+/// 总输出安全级（在音频线程处理平面缓冲）。最多限制 MAX_CHANNELS 个通道，
+/// 更多的设备通道（混音器不会向其输出总线信号）只钳位到上限
+pub struct MasterSafety {
+        settings: SafetySettings,
+        // 以下系数按采样率与设置计算，二者变化时在音频线程上原地更新
+        sample_rate: f32,
+        // 前瞻帧数（小于 `capacity`）
+        lookahead: usize,
+        ceiling: f32,
+        release_coeff: f32,
+        dc_coeff: f32,
+        // 隔直滤波器每通道的上一个输入 / 输出
+        dc_state: [(f32, f32); MAX_CHANNELS],
+        // 每通道容量为 `capacity` 的延迟线（2 的幂），共用写入位置；改变前瞻只移动读取位置
+        capacity: usize,
+        delay: Vec<f32>,
+        // 最近 lookahead + 1 帧所需增益的单调队列（帧序号, 增益），用于求滑动最小值
+        minimum: VecDeque<(usize, f32)>,
+        frame: usize,
+        // 释放平滑后的增益包络的历史（环形，按帧序号索引），以及最近 lookahead 帧的总和
+        envelope: f32,
+        history: Vec<f32>,
+        average_sum: f64,
+}
+
+impl Default for MasterSafety {
+        fn default() -> Self {
+                Self::new()
+        }
+}
+
+impl MasterSafety {
+        /// 分配最长前瞻所需的全部缓冲（在音频线程之外调用）
+        pub fn new() -> Self {
+                let capacity = Self::lookahead_frames(*LOOKAHEAD_RANGE.end(), MAX_SAMPLE_RATE).next_power_of_two();
+                Self {
+                        settings: SafetySettings::default(),
+                        sample_rate: 0.0,
+                        lookahead: 1,
+                        ceiling: 1.0,
+                        release_coeff: 0.0,
+                        dc_coeff: 0.0,
+                        dc_state: [(0.0, 0.0); MAX_CHANNELS],
+                        capacity,
+                        delay: vec![0.0; MAX_CHANNELS * capacity],
+                        minimum: VecDeque::with_capacity(capacity + 2),
+                        frame: 0,
+                        envelope: 1.0,
+                        history: vec![1.0; capacity],
+                        average_sum: 1.0,
+                }
+        }
+
+        pub fn settings(&self) -> &SafetySettings {
+                &self.settings
+        }
+
+        pub fn set_settings(&mut self, settings: SafetySettings) {
+                if settings != self.settings {
+                        // 关闭期间延迟线没有更新，重新启用时从静音开始，避免放出过时的音频
+                        if settings.enabled && !self.settings.enabled {
+                                self.delay.fill(0.0);
+                                self.history.fill(1.0);
+                                self.minimum.clear();
+                                self.envelope = 1.0;
+                                self.dc_state = [(0.0, 0.0); MAX_CHANNELS];
+                        }
+                        self.settings = settings;
+                        // 下一个音频块按新设置更新系数
+                        self.sample_rate = 0.0;
+                }
+        }
+
+        /// 限制器引入的延迟（帧）；关闭时为 0
+        pub fn latency(&self, sample_rate: f32) -> u32 {
+                if self.settings.enabled {
+                        Self::lookahead_frames(self.settings.lookahead, sample_rate) as u32
+                } else {
+                        0
+                }
+        }
+
+        fn lookahead_frames(lookahead: f32, sample_rate: f32) -> usize {
+                ((lookahead / 1000.0 * sample_rate).round() as usize).max(1)
+        }
+
+        // 更新系数与前瞻长度（不分配）。延迟线与包络历史保留，改变前瞻不会丢弃已在延迟线中的音频
+        fn configure(&mut self, sample_rate: f32) {
+                self.sample_rate = sample_rate;
+                self.lookahead = Self::lookahead_frames(self.settings.lookahead, sample_rate).min(self.capacity - 1);
+                self.ceiling = 10f32.powf(self.settings.ceiling / 20.0);
+                self.release_coeff = (-1.0 / (self.settings.release / 1000.0 * sample_rate)).exp();
+                self.dc_coeff = 1.0 - std::f32::consts::TAU * DC_CUTOFF / sample_rate;
+                // 按新的窗口长度重新求包络历史的总和
+                let mask = self.capacity - 1;
+                self.average_sum = (1..=self.lookahead)
+                        .map(|back| self.history[self.frame.wrapping_sub(back) & mask] as f64)
+                        .sum();
+        }
+
+        /// 处理总输出（平面缓冲，`channels` 个通道）。关闭时只清除非有限值
+        pub fn process(&mut self, samples: &mut [f32], channels: usize, sample_rate: f32) {
+                // 非有限值会破坏限制器与滤波器的状态，整块静音
+                if samples.iter().any(|s| !s.is_finite()) {
+                        samples.fill(0.0);
+                }
+                if !self.settings.enabled || channels == 0 {
+                        return;
+                }
+                if self.sample_rate != sample_rate {
+                        self.configure(sample_rate);
+                }
+                let frames = samples.len() / channels;
+                let limited = channels.min(MAX_CHANNELS);
+                let lookahead = self.lookahead;
+                let mask = self.capacity - 1;
+
+                if self.settings.dc_blocker {
+                        for (channel, state) in samples.chunks_exact_mut(frames.max(1)).zip(self.dc_state.iter_mut()) {
+                                for sample in channel.iter_mut() {
+                                        let y = *sample - state.0 + self.dc_coeff * state.1;
+                                        *state = (*sample, y);
+                                        *sample = y;
+                                }
+                        }
+                }
+
+                for frame in 0..frames {
+                        // 本帧各通道峰值所需的增益
+                        let peak = (0..limited).fold(0.0f32, |m, c| m.max(samples[c * frames + frame].abs()));
+                        let required = if peak > self.ceiling {
+                                self.ceiling / peak
+                        } else {
+                                1.0
+                        };
+
+                        // 最近 lookahead + 1 帧的最小值：峰值进入延迟线时起，到它离开延迟线为止都不高于其所需增益
+                        while self.minimum.back().is_some_and(|(_, g)| *g >= required) {
+                                self.minimum.pop_back();
+                        }
+                        self.minimum.push_back((self.frame, required));
+                        while self.minimum.front().is_some_and(|(i, _)| i + lookahead < self.frame) {
+                                self.minimum.pop_front();
+                        }
+                        let target = self.minimum.front().map_or(1.0, |(_, g)| *g);
+
+                        // 增益下降立即跟随，回升按释放时间平滑
+                        self.envelope = if target < self.envelope {
+                                target
+                        } else {
+                                target - (target - self.envelope) * self.release_coeff
+                        };
+                        // 对包络做 lookahead 帧的滑动平均，使增益在峰值到达前平滑地降到位
+                        let oldest = self.history[self.frame.wrapping_sub(lookahead) & mask];
+                        self.average_sum += (self.envelope - oldest) as f64;
+                        self.history[self.frame & mask] = self.envelope;
+                        let gain = (self.average_sum / lookahead as f64) as f32;
+
+                        let write = self.frame & mask;
+                        let read = self.frame.wrapping_sub(lookahead) & mask;
+                        for (c, delay) in self.delay.chunks_exact_mut(self.capacity).take(limited).enumerate() {
+                                let index = c * frames + frame;
+                                delay[write] = samples[index];
+                                // 最后的钳位只吸收浮点舍入误差
+                                samples[index] = (delay[read] * gain).clamp(-self.ceiling, self.ceiling);
+                        }
+                        self.frame += 1;
+                }
+                for sample in samples[limited * frames..].iter_mut() {
+                        *sample = sample.clamp(-self.ceiling, self.ceiling);
+                }
+        }
+}
+
+// 被静音的节点报告（音频线程写入，主线程泵读取后发送事件）。
+// 容量在创建第一个保护时（音频线程之外）预留，音频线程只在容量内追加
+static NON_FINITE_REPORTS: OnceLock<Mutex<Vec<Uuid>>> = OnceLock::new();
+const NON_FINITE_REPORT_CAPACITY: usize = 256;
+
+fn non_finite_reports() -> &'static Mutex<Vec<Uuid>> {
+        NON_FINITE_REPORTS.get_or_init(|| Mutex::new(Vec::with_capacity(NON_FINITE_REPORT_CAPACITY)))
+}
+// 解除静音请求计数：各保护检测到计数变化时恢复节点输出
+static UNMUTE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 取出所有尚未报告的静音节点
+pub fn take_non_finite_reports() -> Vec<Uuid> {
+        // 取出元素但保留预留的容量，之后的报告不会在音频线程中重新分配
+        non_finite_reports()
+                .lock()
+                .map(|mut r| r.drain(..).collect())
+                .unwrap_or_default()
+}
+
+/// 恢复所有因输出非有限值而被静音的节点（在各自的下一个音频块生效）
+pub fn unmute_non_finite() {
+        UNMUTE_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 一个节点（乐器或插入效果）输出的非有限值保护。静音保持到音频图重建或 `unmute_non_finite`
+pub struct NonFiniteGuard {
+        node: Uuid,
+        muted: bool,
+        // 静音后是否已加入报告
+        reported: bool,
+        generation: u64,
+}
+
+impl NonFiniteGuard {
+        pub fn new(node: Uuid) -> Self {
+                non_finite_reports();
+                Self {
+                        node,
+                        muted: false,
+                        reported: false,
+                        generation: UNMUTE_GENERATION.load(Ordering::Relaxed),
+                }
+        }
+
+        /// 检查节点的输出；出现 NaN / Inf 时静音并报告。被静音时清零 `samples` 并返回 true
+        pub fn check(&mut self, samples: &mut [f32]) -> bool {
+                let generation = UNMUTE_GENERATION.load(Ordering::Relaxed);
+                if generation != self.generation {
+                        self.generation = generation;
+                        self.muted = false;
+                }
+                if !self.muted && samples.iter().any(|s| !s.is_finite()) {
+                        self.muted = true;
+                        self.reported = false;
+                }
+                if self.muted {
+                        samples.fill(0.0);
+                        if !self.reported {
+                                self.report();
+                        }
+                }
+                self.muted
+        }
+
+        // 音频线程不等待锁也不分配：报告被占用或已满时留到下一块再试
+        fn report(&mut self) {
+                let Ok(mut reports) = non_finite_reports().try_lock() else {
+                        return;
+                };
+                if !reports.contains(&self.node) {
+                        if reports.len() == reports.capacity() {
+                                return;
+                        }
+                        reports.push(self.node);
+                }
+                self.reported = true;
+                threads::request_main_thread_pump();
+        }
+}
@@ -0,0 +1,78 @@
+use std::ops::Range;
+
+// 混音台的求和总线：各轨道的输入（乐器、外部输入以及其它轨道的输出）在此累加。
+// 默认以 f32 累加；启用双精度时以 f64 累加，每一路信号先在 f32 暂存区生成再加入总线，
+// 多路信号相加的舍入误差不会随轨道数累积。轨道处理时再按 f32 读出。
+#[derive(Default)]
+pub struct SummingBus {
+        double_precision: bool,
+        single: Vec<f32>,
+        double: Vec<f64>,
+        staging: Vec<f32>,
+}
+
+impl SummingBus {
+        pub fn new() -> Self {
+                Self::default()
+        }
+
+        pub fn double_precision(&self) -> bool {
+                self.double_precision
+        }
+
+        /// 切换累加精度（在下一次 `clear` 时生效）
+        pub fn set_double_precision(&mut self, enabled: bool) {
+                self.double_precision = enabled;
+        }
+
+        /// 调整为 `len` 个样本并清零
+        pub fn clear(&mut self, len: usize) {
+                if self.double_precision {
+                        self.single = Vec::new();
+                        self.double.clear();
+                        self.double.resize(len, 0.0);
+                        if self.staging.len() < len {
+                                self.staging.resize(len, 0.0);
+                        }
+                } else {
+                        self.double = Vec::new();
+                        self.single.clear();
+                        self.single.resize(len, 0.0);
+                }
+        }
+
+        /// 叠加一路信号：`mix` 把信号叠加到传入的 f32 切片（对应总线的 `range`）
+        pub fn add(&mut self, range: Range<usize>, mix: impl FnOnce(&mut [f32])) {
+                if !self.double_precision {
+                        mix(&mut self.single[range]);
+                        return;
+                }
+                let staging = &mut self.staging[..range.len()];
+                staging.fill(0.0);
+                mix(staging);
+                for (sum, sample) in self.double[range].iter_mut().zip(staging.iter()) {
+                        *sum += *sample as f64;
+                }
+        }
+
+        /// 以 f32 读出 `range`
+        pub fn read(&self, range: Range<usize>, out: &mut [f32]) {
+                if self.double_precision {
+                        for (o, sum) in out.iter_mut().zip(&self.double[range]) {
+                                *o = *sum as f32;
+                        }
+                } else {
+                        out.copy_from_slice(&self.single[range]);
+                }
+        }
+
+        /// 单精度模式下 `range` 的可变切片
+        pub fn single_mut(&mut self, range: Range<usize>) -> Option<&mut [f32]> {
+                (!self.double_precision).then(|| &mut self.single[range])
+        }
+
+        /// 双精度模式下 `range` 的可变切片
+        pub fn double_mut(&mut self, range: Range<usize>) -> Option<&mut [f64]> {
+                self.double_precision.then(|| &mut self.double[range])
+        }
+}
@@ -1,21 +1,88 @@
+use crate::audio::analysis::tap::{AnalysisTap, tap_point};
+use crate::audio::core::channel_layout::{ChannelLayout, MixMatrix, mix_planar};
 use crate::audio::core::plugin::{
-        AudioBuffer, IOConfig, ParameterType, Plugin, PluginEvent, PluginInfo, PluginParameter, PluginType,
+        AudioBuffer, IOConfig, ParamAddress, ParameterType, Plugin, PluginEvent, PluginInfo, PluginParameter,
+        PluginType,
 };
+use crate::audio::core::smoothing::{ParamRamper, SmoothedValue, smoothing_config};
 use crate::audio::plugins::mixer::level_meter::LevelMeter;
+use crate::audio::plugins::mixer::panner::SurroundPanner;
+use crate::audio::plugins::mixer::safety::NonFiniteGuard;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
 pub struct MixerTrack {
-        #[allow(dead_code)]
+        // 轨道节点 ID（参数地址 `ParamAddress::node`）
         pub id: Uuid,
-        pub container: Box<dyn Plugin>,
+        // 轨道名称，用于多端口后端（如 JACK）的端口命名
+        pub label: String,
+        pub container: LocalContainer,
         pub meter_id: Uuid,
         #[allow(dead_code)]
         pub fader_id: Uuid,
+        // 插入效果链（按顺序处理；推子前/后由 post_fader 决定）
+        inserts: Vec<InsertSlot>,
+        // 每个插入槽位的参数斜坡、输出分析抽头与非有限值保护（与 inserts 下标对应）
+        insert_rampers: Vec<ParamRamper>,
+        insert_taps: Vec<Arc<AnalysisTap>>,
+        insert_guards: Vec<NonFiniteGuard>,
+        // 每个插入效果最近一次处理时报告的延迟（与 inserts 下标对应）
+        insert_latency: Vec<u32>,
+        // 每个插入效果排队的参数变化（`Parameter` 事件，同一参数只保留最新值）：旁通或实例被占用时暂存，
+        // 在下一次处理该插入效果时随其事件送达
+        insert_pending: Vec<Vec<PluginEvent>>,
+        meter: LevelMeter,
+        // 轨道输出（推子后）的分析抽头
+        tap: Arc<AnalysisTap>,
+        // 最近一次计算的轨道处理延迟（采样数）
+        latency: u32,
+        // 轨道自身的通道布局（轨道按此通道数处理），以及输出目标（总轨）的布局
+        pub layout: ChannelLayout,
+        output_layout: ChannelLayout,
+        output_matrix: MixMatrix,
+        // 输出到通道更多的环绕总线时的声像器；此时声像参数由它处理，推子的立体声平衡保持居中
+        panner: Option<SurroundPanner>,
+        // 发往推子容器 / 单个插入效果的参数事件，按块复用，避免在音频线程中分配
+        track_events: Vec<PluginEvent>,
+        slot_events: Vec<PluginEvent>,
+}
+
+// 每块事件缓冲的预留容量
+const EVENT_CAPACITY: usize = 256;
+
+// 把参数变化加入插入效果的队列：同一参数覆盖旧值，队列满时丢弃（预留容量内不会分配）
+fn queue_param(pending: &mut Vec<PluginEvent>, id: u32, value: f32) {
+        let queued = pending
+                .iter_mut()
+                .find(|event| matches!(event, PluginEvent::Parameter { id: queued, .. } if *queued == id));
+        if let Some(event) = queued {
+                *event = PluginEvent::Parameter { id, value };
+        } else if pending.len() < pending.capacity() {
+                pending.push(PluginEvent::Parameter { id, value });
+        }
+}
+
+// 队列中某个参数的最新值
+fn queued_param(pending: &[PluginEvent], param_id: u32) -> Option<f32> {
+        pending.iter().find_map(|event| match event {
+                PluginEvent::Parameter { id, value } if *id == param_id => Some(*value),
+                _ => None,
+        })
+}
+
+/// 混音轨道上的一个插入效果槽位。
+/// 实例与 AppState.plugin_instances 共享，因此可通过实例 ID 读写参数，并在重建音频图时保留状态。
+pub struct InsertSlot {
+        // 插入实例的节点 ID（与实例 ID 相同）
+        pub id: Uuid,
+        pub plugin: Arc<Mutex<Box<dyn Plugin>>>,
+        pub bypass: bool,
+        pub post_fader: bool,
 }
 
 // Minimal in-file container replacement to avoid depending on removed host builtin module.
-struct LocalContainer {
+pub struct LocalContainer {
         plugins: Vec<Box<dyn Plugin>>,
         param_map: HashMap<u32, (usize, u32)>,
         info: PluginInfo,
@@ -78,6 +145,10 @@ impl Plugin for LocalContainer {
                 IOConfig::default()
         }
 
+        fn latency(&self) -> u32 {
+                self.plugins.iter().map(|p| p.latency()).sum()
+        }
+
         fn process(&mut self, buffer: &mut AudioBuffer, events: &[PluginEvent], output_events: &mut Vec<PluginEvent>) {
                 // Apply parameter events directly
                 for event in events {
@@ -91,11 +162,7 @@ impl Plugin for LocalContainer {
                 }
 
                 // Pass MIDI-only events to child plugins
-                let midi_events: Vec<PluginEvent> = events
-                        .iter()
-                        .filter(|e| matches!(e, PluginEvent::Midi(_)))
-                        .cloned()
-                        .collect();
+                let midi_events: Vec<PluginEvent> = events.iter().filter(|e| e.as_midi().is_some()).cloned().collect();
 
                 for plugin in self.plugins.iter_mut() {
                         plugin.process(buffer, &midi_events, output_events);
@@ -118,16 +185,47 @@ impl Plugin for LocalContainer {
                         }
                 }
         }
+
+        fn reset(&mut self) {
+                for plugin in self.plugins.iter_mut() {
+                        plugin.reset();
+                }
+        }
+
+        fn param_value_to_text(&self, id: u32, value: f32) -> Option<String> {
+                let (plugin_idx, internal_id) = self.param_map.get(&id)?;
+                self.plugins.get(*plugin_idx)?.param_value_to_text(*internal_id, value)
+        }
+
+        fn param_text_to_value(&self, id: u32, text: &str) -> Option<f32> {
+                let (plugin_idx, internal_id) = self.param_map.get(&id)?;
+                self.plugins.get(*plugin_idx)?.param_text_to_value(*internal_id, text)
+        }
 }
 
-// Minimal gain plugin used by MixerTrack as a local replacement.
+/// 轨道参数 ID：推子增益（线性，0..1）
+pub const TRACK_PARAM_VOLUME: u32 = 0;
+/// 轨道参数 ID：声像（-1 左 .. 1 右，立体声按平衡方式处理；输出到环绕总线时为声像位置的左右坐标）
+pub const TRACK_PARAM_PAN: u32 = 1;
+/// 轨道参数 ID：环绕声像的前后坐标（-1 后 .. 1 前），仅在输出到环绕总线时生效
+pub const TRACK_PARAM_PAN_FRONT: u32 = 2;
+
+// Minimal gain/pan plugin used by MixerTrack as a local replacement.
+// 增益与声像的变化在宿主侧逐采样平滑，避免推子移动时的拉链噪声。
 struct NoopGain {
-        gain: f32,
+        gain: SmoothedValue,
+        pan: SmoothedValue,
+        // 最近一次处理的采样率（参数变化在 process 之前到达，用它计算渐变长度）
+        sample_rate: f32,
 }
 
 impl NoopGain {
         pub fn new() -> Self {
-                Self { gain: 0.5 }
+                Self {
+                        gain: SmoothedValue::new(0.5),
+                        pan: SmoothedValue::new(0.0),
+                        sample_rate: 44100.0,
+                }
         }
 }
 
@@ -144,24 +242,26 @@ impl Plugin for NoopGain {
         }
 
         fn get_parameters(&self) -> Vec<PluginParameter> {
-                vec![PluginParameter {
-                        id: 0,
-                        name: "Gain".to_string(),
-                        min_value: 0.0,
-                        max_value: 1.0,
-                        default_value: 0.5,
-                        value_type: ParameterType::Float,
-                }]
+                vec![
+                        PluginParameter::new(0, "Gain", 0.0, 1.0, 0.5, ParameterType::Float),
+                        PluginParameter::new(1, "Pan", -1.0, 1.0, 0.0, ParameterType::Float),
+                ]
         }
 
         fn get_state(&self) -> Vec<u8> {
-                self.gain.to_le_bytes().to_vec()
+                let mut state = self.gain.target().to_le_bytes().to_vec();
+                state.extend_from_slice(&self.pan.target().to_le_bytes());
+                state
         }
 
         fn set_state(&mut self, state: &[u8]) {
                 if state.len() >= 4 {
                         let bytes: [u8; 4] = state[0..4].try_into().unwrap();
-                        self.gain = f32::from_le_bytes(bytes);
+                        self.gain.reset(f32::from_le_bytes(bytes));
+                }
+                if state.len() >= 8 {
+                        let bytes: [u8; 4] = state[4..8].try_into().unwrap();
+                        self.pan.reset(f32::from_le_bytes(bytes));
                 }
         }
 
@@ -175,40 +275,58 @@ impl Plugin for NoopGain {
                 _events: &[PluginEvent],
                 _output_events: &mut Vec<PluginEvent>,
         ) {
-                for sample in buffer.samples.iter_mut() {
-                        *sample *= self.gain;
+                self.sample_rate = buffer.sample_rate;
+                let stereo = buffer.channels == 2;
+                let frames = buffer.frames();
+                // 逐通道处理：每个通道从块开始时的平滑状态出发，得到与其它通道相同的逐采样增益
+                for (channel, samples) in buffer.iter_channels_mut().enumerate() {
+                        let mut gain = self.gain.clone();
+                        let mut pan = self.pan.clone();
+                        for sample in samples.iter_mut() {
+                                let gain = gain.next_value();
+                                let pan = pan.next_value();
+                                // 平衡声像：中置时两侧均为单位增益，偏向一侧时衰减另一侧
+                                *sample *= match (stereo, channel) {
+                                        (false, _) => gain,
+                                        (true, 0) => gain * (1.0 - pan).min(1.0),
+                                        (true, _) => gain * (1.0 + pan).min(1.0),
+                                };
+                        }
                 }
+                self.gain.advance(frames as u32);
+                self.pan.advance(frames as u32);
         }
 
         fn get_param(&self, id: u32) -> f32 {
-                if id == 0 { self.gain } else { 0.0 }
+                match id {
+                        TRACK_PARAM_VOLUME => self.gain.target(),
+                        TRACK_PARAM_PAN => self.pan.target(),
+                        _ => 0.0,
+                }
         }
 
         fn set_param(&mut self, id: u32, value: f32) {
-                if id == 0 {
-                        self.gain = value;
+                let config = smoothing_config();
+                match id {
+                        TRACK_PARAM_VOLUME => self.gain.set_target(value.max(0.0), config, self.sample_rate),
+                        TRACK_PARAM_PAN => self.pan.set_target(value.clamp(-1.0, 1.0), config, self.sample_rate),
+                        _ => {}
                 }
         }
+
+        fn reset(&mut self) {
+                self.gain.reset(self.gain.target());
+                self.pan.reset(self.pan.target());
+        }
 }
 
 impl MixerTrack {
-        pub fn new(meter_id: Option<Uuid>) -> Self {
+        pub fn new(id: Uuid, meter_id: Option<Uuid>) -> Self {
                 let mut container = LocalContainer::new("Mixer Track", "com.mydaw.mixertrack");
 
-                // 添加电平计（Meter）的位置：前推子（Pre-fader）或后推子（Post-fader）？通常通道条是 Post-fader，但我们在此用于输入监控使用 Pre-fader。
-                // 用户要求：左边为电平计，右边为推子。通常电平计显示信号电平。
-                // 我们将电平计放在 Fader 之后以显示轨道的输出电平。
-
-                // 实际标准通常是：
-                // 插入效果 -> 推子 -> 电平计（Post-Fader）
-                // 或者
-                // 插入效果 -> 电平计（Pre-Fader） -> 推子
-
-                // 我们选择：推子 -> 电平计。
-
+                // 信号链：推子前插入效果 -> 推子 -> 推子后插入效果 -> 电平计（Post-Fader）。
+                // 电平计始终位于链的末端以显示轨道的输出电平，因此单独持有而不放入容器。
                 let fader = NoopGain::new();
-                // 我们需要获取 ID 来映射参数，但 GainFader 在 new() 中不直接暴露 ID。
-                // 直接添加即可。
                 let fader_idx = container.add_plugin(Box::new(fader));
 
                 let meter = if let Some(id) = meter_id {
@@ -217,26 +335,245 @@ impl MixerTrack {
                         LevelMeter::new()
                 };
                 let meter_id = meter.get_id();
-                let _meter_idx = container.add_plugin(Box::new(meter));
 
-                // 将推子增益（参数 0）映射到轨道参数 0
-                container.map_param(0, fader_idx, 0);
+                // 将推子增益与声像映射到轨道参数 0 / 1
+                container.map_param(TRACK_PARAM_VOLUME, fader_idx, 0);
+                container.map_param(TRACK_PARAM_PAN, fader_idx, 1);
 
                 Self {
-                        id: Uuid::new_v4(),
-                        container: Box::new(container),
+                        id,
+                        label: String::new(),
+                        container,
                         meter_id,
                         fader_id: Uuid::nil(), // 我们没有容易获取的 fader ID，但我们已将其映射到参数 0
+                        inserts: Vec::new(),
+                        insert_rampers: Vec::new(),
+                        insert_taps: Vec::new(),
+                        insert_guards: Vec::new(),
+                        insert_latency: Vec::new(),
+                        insert_pending: Vec::new(),
+                        meter,
+                        tap: tap_point(id).unwrap_or_default(),
+                        latency: 0,
+                        layout: ChannelLayout::Stereo,
+                        output_layout: ChannelLayout::Stereo,
+                        output_matrix: ChannelLayout::Stereo.mix_matrix_from(ChannelLayout::Stereo),
+                        panner: None,
+                        track_events: Vec::with_capacity(EVENT_CAPACITY),
+                        slot_events: Vec::with_capacity(EVENT_CAPACITY),
                 }
         }
 
-        #[allow(dead_code)]
+        /// 设置轨道与输出目标的通道布局（在 `set_levels` 之后调用）。
+        /// 目标为通道更多的环绕布局时启用环绕声像，当前的声像值作为左右坐标，`pan_front` 为前后坐标；
+        /// 单声道或环绕轨道输出到立体声目标时同样由声像器按左右坐标声像（推子自身只对立体声轨道做平衡）；
+        /// 其余情况按布局矩阵上混 / 下混
+        pub fn set_layout(&mut self, layout: ChannelLayout, output: ChannelLayout, pan_front: f32) {
+                self.layout = layout;
+                self.output_layout = output;
+                self.output_matrix = output.mix_matrix_from(layout);
+                self.panner = None;
+                let pans = if output.is_surround() {
+                        layout.channels() < output.channels()
+                } else {
+                        output == ChannelLayout::Stereo && layout != ChannelLayout::Stereo
+                };
+                if pans {
+                        let pan = self.container.get_param(TRACK_PARAM_PAN);
+                        self.panner = Some(SurroundPanner::new(layout, output, pan, pan_front));
+                        self.container.set_param(TRACK_PARAM_PAN, 0.0);
+                        self.container.reset();
+                }
+        }
+
+        /// 把轨道输出（轨道布局）叠加到输出目标（目标布局）的缓冲
+        pub fn mix_output(&mut self, input: &[f32], output: &mut [f32]) {
+                match self.panner.as_mut() {
+                        Some(panner) => panner.process(input, output),
+                        None => mix_planar(
+                                input,
+                                self.layout.channels(),
+                                output,
+                                self.output_layout.channels(),
+                                &self.output_matrix,
+                        ),
+                }
+        }
+
+        /// 立即设置推子增益与声像（不渐变），用于构建音频图时恢复轨道状态
+        pub fn set_levels(&mut self, volume: f32, pan: f32) {
+                self.container.set_param(TRACK_PARAM_VOLUME, volume);
+                self.container.set_param(TRACK_PARAM_PAN, pan);
+                self.container.reset();
+        }
+
+        /// 追加一个插入效果槽位（轨道信号作为其音频输入）
+        pub fn add_insert(&mut self, slot: InsertSlot) -> usize {
+                self.insert_taps.push(tap_point(slot.id).unwrap_or_default());
+                self.insert_guards.push(NonFiniteGuard::new(slot.id));
+                let parameters = slot.plugin.lock().map(|p| p.get_parameters()).unwrap_or_default();
+                self.insert_rampers.push(ParamRamper::for_parameters(&parameters));
+                self.inserts.push(slot);
+                self.insert_latency.push(0);
+                self.insert_pending.push(Vec::with_capacity(EVENT_CAPACITY));
+                self.inserts.len() - 1
+        }
+
+        /// 更新插入效果的旁通与推子前/后位置；`node` 不在本轨道时返回 false
+        pub fn set_insert_state(&mut self, node: Uuid, bypass: bool, post_fader: bool) -> bool {
+                match self.inserts.iter_mut().find(|s| s.id == node) {
+                        Some(slot) => {
+                                slot.bypass = bypass;
+                                slot.post_fader = post_fader;
+                                true
+                        }
+                        None => false,
+                }
+        }
+
+        /// 读取本轨道（推子）或其插入效果的参数；`address` 不属于本轨道时返回 None
+        pub fn get_node_param(&self, address: ParamAddress) -> Option<f32> {
+                if address.node == self.id {
+                        return Some(match (self.panner.as_ref(), address.param_id) {
+                                (Some(panner), TRACK_PARAM_PAN) => panner.x(),
+                                (Some(panner), TRACK_PARAM_PAN_FRONT) => panner.y(),
+                                _ => self.container.get_param(address.param_id),
+                        });
+                }
+                let index = self.inserts.iter().position(|s| s.id == address.node)?;
+                if let Some(value) = queued_param(&self.insert_pending[index], address.param_id) {
+                        return Some(value);
+                }
+                self.inserts[index]
+                        .plugin
+                        .lock()
+                        .ok()
+                        .map(|p| p.get_param(address.param_id))
+        }
+
+        /// 立即设置本轨道（推子）或其插入效果的参数；`address` 不属于本轨道时返回 false
+        pub fn set_node_param(&mut self, address: ParamAddress, value: f32) -> bool {
+                if address.node == self.id {
+                        match (self.panner.as_mut(), address.param_id) {
+                                (Some(panner), TRACK_PARAM_PAN) => panner.set_x(value),
+                                (Some(panner), TRACK_PARAM_PAN_FRONT) => panner.set_y(value),
+                                _ => self.container.set_param(address.param_id, value),
+                        }
+                        return true;
+                }
+                // 插入效果：未旁通且实例空闲时立即设置，否则排队到下一次处理（不等待实例锁）
+                let Some(index) = self.inserts.iter().position(|s| s.id == address.node) else {
+                        return false;
+                };
+                let slot = &self.inserts[index];
+                let pending = &mut self.insert_pending[index];
+                match slot.plugin.try_lock() {
+                        Ok(mut plugin) if !slot.bypass && pending.is_empty() => {
+                                plugin.set_param(address.param_id, value)
+                        }
+                        _ => queue_param(pending, address.param_id, value),
+                }
+                true
+        }
+
+        /// 重新计算轨道延迟：容器（推子）与所有未旁通插入效果的延迟之和。
+        /// 插入效果的延迟在其处理时（已持有实例锁）读取，这里不再加锁。
+        pub fn refresh_latency(&mut self) -> u32 {
+                let inserts = self
+                        .inserts
+                        .iter()
+                        .zip(self.insert_latency.iter())
+                        .filter(|(slot, _)| !slot.bypass)
+                        .map(|(_, latency)| *latency)
+                        .sum::<u32>();
+                self.latency = self.container.latency() + inserts;
+                self.latency
+        }
+
+        pub fn latency(&self) -> u32 {
+                self.latency
+        }
+
+        // 依次处理指定位置（推子前/后）的插入效果；旁通或实例被占用（例如主线程正在读取状态）时直通。
+        // 此时发往插入节点的参数事件进入该插入效果的队列，恢复处理时先于本块事件送达，以免参数停留在旧值。
+        fn process_inserts(
+                &mut self,
+                post_fader: bool,
+                buffer: &mut AudioBuffer,
+                events: &[PluginEvent],
+                output_events: &mut Vec<PluginEvent>,
+        ) {
+                let slot_events = &mut self.slot_events;
+                let slots = self
+                        .inserts
+                        .iter()
+                        .zip(self.insert_rampers.iter_mut())
+                        .zip(&self.insert_taps)
+                        .zip(self.insert_guards.iter_mut())
+                        .zip(self.insert_latency.iter_mut())
+                        .zip(self.insert_pending.iter_mut());
+                for (((((slot, ramper), tap), guard), latency), pending) in slots {
+                        if slot.post_fader != post_fader {
+                                continue;
+                        }
+                        let plugin = if slot.bypass {
+                                None
+                        } else {
+                                slot.plugin.try_lock().ok()
+                        };
+                        let Some(mut plugin) = plugin else {
+                                for event in events.iter().filter_map(|e| e.for_node(slot.id)) {
+                                        if let PluginEvent::Parameter { id, value } = event {
+                                                queue_param(pending, id, value);
+                                        }
+                                }
+                                continue;
+                        };
+                        slot_events.clear();
+                        slot_events.append(pending);
+                        slot_events.extend(events.iter().filter_map(|e| e.for_node(slot.id)));
+                        *latency = plugin.latency();
+                        ramper.process(plugin.as_mut(), buffer, slot_events, output_events);
+                        // 输出 NaN / Inf 的插入效果被静音（整个轨道随之无声）
+                        guard.check(buffer.samples);
+                        tap.write(buffer);
+                }
+        }
+
+        /// 处理一个音频块。`events` 中的 `NodeParameter` 事件按节点分发到推子容器或对应的插入效果
         pub fn process(
                 &mut self,
                 buffer: &mut AudioBuffer,
                 events: &[PluginEvent],
                 output_events: &mut Vec<PluginEvent>,
         ) {
-                self.container.process(buffer, events, output_events);
+                let track_events = &mut self.track_events;
+                track_events.clear();
+                track_events.extend(events.iter().filter_map(|e| e.for_node(self.id)));
+                // 环绕声像：声像参数交给声像器，不再作用于推子的立体声平衡
+                if let Some(panner) = self.panner.as_mut() {
+                        track_events.retain(|e| match e {
+                                PluginEvent::Parameter {
+                                        id: TRACK_PARAM_PAN,
+                                        value,
+                                } => {
+                                        panner.set_x(*value);
+                                        false
+                                }
+                                PluginEvent::Parameter {
+                                        id: TRACK_PARAM_PAN_FRONT,
+                                        value,
+                                } => {
+                                        panner.set_y(*value);
+                                        false
+                                }
+                                _ => true,
+                        });
+                }
+                self.process_inserts(false, buffer, events, output_events);
+                self.container.process(buffer, &self.track_events, output_events);
+                self.process_inserts(true, buffer, events, output_events);
+                self.meter.process(buffer, &[], output_events);
+                self.tap.write(buffer);
         }
 }
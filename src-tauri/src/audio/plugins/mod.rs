@@ -1,3 +1,5 @@
 pub mod clap;
 pub mod manager;
 pub mod mixer;
+pub mod presets;
+pub mod snapshots;
@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 插件预设：用户预设以 JSON 文件保存在 `<预设根目录>/<插件 unique_id>/<名称>-<散列>.json`，
+// 文件名中的散列由插件 ID 与原始名称计算，避免不同名称清理为相同文件名（例如 "a/b" 与 "a?b"、仅大小写不同）；
+// 显示名称以 JSON 中的 `name` 为准。
+// 出厂预设由插件 manifest.lua 的 `presets` 表声明（只读）。
+// 预设优先保存插件的二进制状态（get_state），插件不支持时回退为参数值列表。
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PresetParam {
+        pub id: u32,
+        pub value: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginPreset {
+        pub name: String,
+        /// 所属插件的 `PluginInfo::unique_id`
+        pub plugin_id: String,
+        #[serde(default)]
+        pub tags: Vec<String>,
+        #[serde(default)]
+        pub description: String,
+        /// true 表示来自 manifest 的出厂预设（不可改名/删除）
+        #[serde(default, skip_serializing)]
+        pub factory: bool,
+        /// 插件的二进制状态（为空时使用 params）
+        #[serde(default)]
+        pub state: Vec<u8>,
+        #[serde(default)]
+        pub params: Vec<PresetParam>,
+}
+
+/// 预设列表项：不含状态数据，供预设浏览器展示与搜索
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PresetInfo {
+        pub name: String,
+        pub plugin_id: String,
+        pub tags: Vec<String>,
+        pub description: String,
+        pub factory: bool,
+}
+
+impl PluginPreset {
+        pub fn info(&self) -> PresetInfo {
+                PresetInfo {
+                        name: self.name.clone(),
+                        plugin_id: self.plugin_id.clone(),
+                        tags: self.tags.clone(),
+                        description: self.description.clone(),
+                        factory: self.factory,
+                }
+        }
+
+        /// 搜索匹配：`query` 不区分大小写地匹配名称、描述或任一标签；
+        /// `tags` 中的每个标签都必须出现在预设标签里
+        pub fn matches(&self, query: &str, tags: &[String]) -> bool {
+                let has_tag = |t: &str| self.tags.iter().any(|own| own.eq_ignore_ascii_case(t));
+                if !tags.iter().all(|t| has_tag(t)) {
+                        return false;
+                }
+                let query = query.trim().to_lowercase();
+                if query.is_empty() {
+                        return true;
+                }
+                self.name.to_lowercase().contains(&query)
+                        || self.description.to_lowercase().contains(&query)
+                        || self.tags.iter().any(|t| t.to_lowercase().contains(&query))
+        }
+}
+
+/// 解析 manifest 中的出厂预设：
+/// `presets = { { name = "Quiet", tags = { "soft" }, description = "...", params = { { id = 0, value = -12.0 } } } }`
+pub fn parse_manifest_presets(plugin_id: &str, tbl: &mlua::Table) -> Vec<PluginPreset> {
+        let Ok(presets_tbl) = tbl.get::<mlua::Table>("presets") else {
+                return Vec::new();
+        };
+        let mut presets = Vec::new();
+        for p in presets_tbl.sequence_values::<mlua::Table>().flatten() {
+                let Ok(name) = p.get::<String>("name") else {
+                        continue;
+                };
+                let tags = p
+                        .get::<mlua::Table>("tags")
+                        .map(|t| t.sequence_values::<String>().flatten().collect())
+                        .unwrap_or_default();
+                let params = p
+                        .get::<mlua::Table>("params")
+                        .map(|t| {
+                                t.sequence_values::<mlua::Table>()
+                                        .flatten()
+                                        .filter_map(|e| {
+                                                Some(PresetParam {
+                                                        id: e.get::<u32>("id").ok()?,
+                                                        value: e.get::<f32>("value").ok()?,
+                                                })
+                                        })
+                                        .collect()
+                        })
+                        .unwrap_or_default();
+                presets.push(PluginPreset {
+                        name,
+                        plugin_id: plugin_id.to_string(),
+                        tags,
+                        description: p.get::<String>("description").unwrap_or_default(),
+                        factory: true,
+                        state: Vec::new(),
+                        params,
+                });
+        }
+        presets
+}
+
+// 名称中不适合作为文件名的字符替换为 '_'（结果可能重复，只用于可读部分）
+fn sanitize_file_name(name: &str) -> String {
+        let cleaned: String = name
+                .trim()
+                .chars()
+                .map(|c| {
+                        if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.') {
+                                c
+                        } else {
+                                '_'
+                        }
+                })
+                .collect();
+        let cleaned = cleaned.trim_matches('.').to_string();
+        if cleaned.is_empty() {
+                "_".to_string()
+        } else {
+                cleaned
+        }
+}
+
+// 64 位 FNV-1a：结果不随 Rust 版本变化，可用于持久化的文件名
+fn stable_hash(parts: &[&str]) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for (i, part) in parts.iter().enumerate() {
+                if i > 0 {
+                        hash = (hash ^ 0xff).wrapping_mul(0x0100_0000_01b3);
+                }
+                for byte in part.bytes() {
+                        hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+                }
+        }
+        hash
+}
+
+/// 用户预设目录
+pub struct PresetStore {
+        root: PathBuf,
+}
+
+impl PresetStore {
+        pub fn new(root: impl Into<PathBuf>) -> Self {
+                Self { root: root.into() }
+        }
+
+        fn plugin_dir(&self, plugin_id: &str) -> PathBuf {
+                self.root.join(sanitize_file_name(plugin_id))
+        }
+
+        fn preset_path(&self, plugin_id: &str, name: &str) -> PathBuf {
+                self.plugin_dir(plugin_id).join(format!(
+                        "{}-{:016x}.json",
+                        sanitize_file_name(name),
+                        stable_hash(&[plugin_id, name])
+                ))
+        }
+
+        // 旧版本的文件名（仅清理名称，可能与其它名称冲突）
+        fn legacy_preset_path(&self, plugin_id: &str, name: &str) -> PathBuf {
+                self.plugin_dir(plugin_id)
+                        .join(format!("{}.json", sanitize_file_name(name)))
+        }
+
+        // 已保存预设的文件：优先使用当前文件名，其次是内容名称一致的旧版文件
+        fn find_preset(&self, plugin_id: &str, name: &str) -> Option<(PathBuf, PluginPreset)> {
+                [
+                        self.preset_path(plugin_id, name),
+                        self.legacy_preset_path(plugin_id, name),
+                ]
+                .into_iter()
+                .find_map(|path| {
+                        let preset = Self::read_preset(&path)?;
+                        (preset.name == name && preset.plugin_id == plugin_id).then_some((path, preset))
+                })
+        }
+
+        fn read_preset(path: &Path) -> Option<PluginPreset> {
+                let content = fs::read(path).ok()?;
+                serde_json::from_slice(&content).ok()
+        }
+
+        /// 插件的全部用户预设（按名称排序）
+        pub fn list(&self, plugin_id: &str) -> Vec<PluginPreset> {
+                let Ok(entries) = fs::read_dir(self.plugin_dir(plugin_id)) else {
+                        return Vec::new();
+                };
+                let mut presets: Vec<PluginPreset> = entries
+                        .flatten()
+                        .map(|e| e.path())
+                        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                        .filter_map(|p| Self::read_preset(&p))
+                        .filter(|p| p.plugin_id == plugin_id)
+                        .collect();
+                presets.sort_by_key(|p| p.name.to_lowercase());
+                presets
+        }
+
+        pub fn get(&self, plugin_id: &str, name: &str) -> Option<PluginPreset> {
+                self.find_preset(plugin_id, name).map(|(_, preset)| preset)
+        }
+
+        /// 保存用户预设；`overwrite` 为 false 时同名预设已存在则报错
+        pub fn save(&self, preset: &PluginPreset, overwrite: bool) -> Result<(), String> {
+                if preset.name.trim().is_empty() {
+                        return Err("Preset name cannot be empty".to_string());
+                }
+                let existing = self.find_preset(&preset.plugin_id, &preset.name);
+                if !overwrite && existing.is_some() {
+                        return Err(format!("Preset '{}' already exists", preset.name));
+                }
+                fs::create_dir_all(self.plugin_dir(&preset.plugin_id))
+                        .map_err(|e| format!("Failed to create preset folder: {}", e))?;
+                let path = self.preset_path(&preset.plugin_id, &preset.name);
+                let json = serde_json::to_vec_pretty(preset).map_err(|e| e.to_string())?;
+                fs::write(&path, json).map_err(|e| format!("Failed to write preset: {}", e))?;
+                // 覆盖旧版文件名的预设时迁移到新文件名
+                if let Some((old_path, _)) = existing.filter(|(old_path, _)| *old_path != path) {
+                        let _ = fs::remove_file(old_path);
+                }
+                Ok(())
+        }
+
+        pub fn rename(&self, plugin_id: &str, name: &str, new_name: &str) -> Result<(), String> {
+                let (old_path, mut preset) = self
+                        .find_preset(plugin_id, name)
+                        .ok_or_else(|| format!("Preset '{}' not found", name))?;
+                if name == new_name {
+                        return Ok(());
+                }
+                preset.name = new_name.to_string();
+                self.save(&preset, false)?;
+                fs::remove_file(&old_path).map_err(|e| format!("Failed to remove old preset: {}", e))
+        }
+
+        pub fn delete(&self, plugin_id: &str, name: &str) -> Result<(), String> {
+                let (path, _) = self
+                        .find_preset(plugin_id, name)
+                        .ok_or_else(|| format!("Preset '{}' not found", name))?;
+                fs::remove_file(path).map_err(|e| format!("Failed to delete preset: {}", e))
+        }
+
+        pub fn set_metadata(
+                &self,
+                plugin_id: &str,
+                name: &str,
+                tags: Vec<String>,
+                description: String,
+        ) -> Result<(), String> {
+                let mut preset = self
+                        .get(plugin_id, name)
+                        .ok_or_else(|| format!("Preset '{}' not found", name))?;
+                preset.tags = tags;
+                preset.description = description;
+                self.save(&preset, true)
+        }
+}
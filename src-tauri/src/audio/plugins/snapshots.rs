@@ -0,0 +1,94 @@
+use crate::audio::core::plugin::{ParameterType, Plugin, PluginParameter};
+use crate::audio::plugins::presets::PresetParam;
+use serde::Serialize;
+
+// 实例快照：调音时在 A/B 或编号槽位之间切换。
+// 快照同时记录插件的二进制状态（若支持）与全部参数值：召回时优先恢复状态，
+// 在两个快照之间插值（morph）时只使用参数值。
+
+/// 快照槽位名：`"A"`、`"B"` 或编号（`"1"`、`"2"` ...），大小写不敏感
+pub fn normalize_slot(slot: &str) -> Result<String, String> {
+        let slot = slot.trim();
+        if slot.eq_ignore_ascii_case("a") || slot.eq_ignore_ascii_case("b") {
+                return Ok(slot.to_ascii_uppercase());
+        }
+        match slot.parse::<u32>() {
+                Ok(n) => Ok(n.to_string()),
+                Err(_) => Err(format!("Invalid snapshot slot: {}", slot)),
+        }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InstanceSnapshot {
+        #[serde(skip)]
+        pub state: Vec<u8>,
+        pub params: Vec<PresetParam>,
+}
+
+impl InstanceSnapshot {
+        /// 捕获实例当前状态；`params` 为实例的参数列表（含 manifest 回退）
+        pub fn capture(plugin: &dyn Plugin, params: &[PluginParameter]) -> Self {
+                Self {
+                        state: plugin.get_state(),
+                        params: params
+                                .iter()
+                                .filter(|p| !p.flags.read_only)
+                                .map(|p| PresetParam {
+                                        id: p.id,
+                                        value: plugin.get_param(p.id),
+                                })
+                                .collect(),
+                }
+        }
+
+        /// 恢复快照：有二进制状态时调用 `set_state`，否则逐个写回参数值
+        pub fn apply(&self, plugin: &mut dyn Plugin) {
+                if !self.state.is_empty() {
+                        plugin.set_state(&self.state);
+                        return;
+                }
+                for p in &self.params {
+                        plugin.set_param(p.id, p.value);
+                }
+        }
+
+        fn value(&self, id: u32) -> Option<f32> {
+                self.params.iter().find(|p| p.id == id).map(|p| p.value)
+        }
+}
+
+/// 在两个快照之间插值：`amount` 为 0 时等于 `from`，为 1 时等于 `to`。
+/// Float 参数在归一化行程上插值（遵循参数曲线），离散参数在中点切换；
+/// 只存在于一个快照中的参数保持该快照的值。
+pub fn morph(
+        from: &InstanceSnapshot,
+        to: &InstanceSnapshot,
+        params: &[PluginParameter],
+        amount: f32,
+) -> Vec<PresetParam> {
+        let amount = amount.clamp(0.0, 1.0);
+        params.iter()
+                .filter_map(|param| {
+                        let value = match (from.value(param.id), to.value(param.id)) {
+                                (Some(a), Some(b)) => match param.value_type {
+                                        ParameterType::Float => {
+                                                let na = param.to_normalized(a);
+                                                let nb = param.to_normalized(b);
+                                                param.from_normalized(na + (nb - na) * amount)
+                                        }
+                                        _ => {
+                                                if amount < 0.5 {
+                                                        a
+                                                } else {
+                                                        b
+                                                }
+                                        }
+                                },
+                                (Some(a), None) => a,
+                                (None, Some(b)) => b,
+                                (None, None) => return None,
+                        };
+                        Some(PresetParam { id: param.id, value })
+                })
+                .collect()
+}
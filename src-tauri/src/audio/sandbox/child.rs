@@ -0,0 +1,218 @@
+use super::SHARED_SAMPLES;
+use super::protocol::{
+        EVENT_RECORD_BYTES, Request, Response, SandboxKind, read_events, read_message, write_events, write_message,
+};
+use super::shm::{EVENT_BYTES, SharedBuffer};
+use crate::audio::core::ffi_plugin::FFIPlugin;
+use crate::audio::core::plugin::{AudioBuffer, Plugin};
+use crate::audio::core::threads;
+use crate::audio::plugins::clap::plugin::ClapPlugin;
+use crossbeam_channel::{RecvTimeoutError, bounded};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read};
+use std::os::unix::io::FromRawFd;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// 沙箱子进程：加载单个插件，在独立的音频线程中处理宿主通过共享内存提交的音频块，
+// 主线程处理控制管道上的请求与插件发起的主线程回调。
+// 插件崩溃只会终止本进程，宿主侧检测到管道关闭后以静音替代该插件。
+
+// 没有控制请求时检查主线程回调请求的间隔
+const PUMP_INTERVAL: Duration = Duration::from_millis(15);
+
+/// 子进程入口：`--plugin-sandbox <clap|local> <插件路径> <共享内存路径> <采样率> <门铃描述符>`，返回进程退出码
+pub fn run(args: &[String]) -> i32 {
+        let (Some(kind), Some(path), Some(shm_path), Some(sample_rate), Some(doorbell)) = (
+                args.first().and_then(|k| SandboxKind::parse(k)),
+                args.get(1),
+                args.get(2),
+                args.get(3).and_then(|s| s.parse::<f32>().ok()),
+                args.get(4).and_then(|s| s.parse::<i32>().ok()),
+        ) else {
+                eprintln!("Sandbox: invalid arguments {:?}", args);
+                return 2;
+        };
+        // 宿主为本进程创建的门铃管道读端
+        let doorbell = unsafe { File::from_raw_fd(doorbell) };
+
+        // 控制协议独占原始 stdout；插件自身的打印输出重定向到 stderr，避免破坏协议流
+        let mut writer = unsafe {
+                let proto_fd = libc::dup(1);
+                if proto_fd < 0 || libc::dup2(2, 1) < 0 {
+                        eprintln!("Sandbox: failed to set up control pipe");
+                        return 2;
+                }
+                BufWriter::new(File::from_raw_fd(proto_fd))
+        };
+
+        threads::register_main_thread();
+
+        let shm = match SharedBuffer::open(Path::new(shm_path)) {
+                Ok(shm) => shm,
+                Err(e) => {
+                        let _ = write_message(
+                                &mut writer,
+                                &Response::Error(format!("Failed to map shared buffer: {}", e)),
+                        );
+                        return 1;
+                }
+        };
+
+        let created: Result<Box<dyn Plugin>, String> = match kind {
+                SandboxKind::Clap => unsafe {
+                        ClapPlugin::new(path, sample_rate as f64).map(|p| Box::new(p) as Box<dyn Plugin>)
+                },
+                SandboxKind::Local => unsafe {
+                        FFIPlugin::new(path, sample_rate)
+                                .map(|p| Box::new(p) as Box<dyn Plugin>)
+                                .map_err(|e| e.to_string())
+                },
+        };
+        let plugin = match created {
+                Ok(p) => Arc::new(Mutex::new(p)),
+                Err(e) => {
+                        let _ = write_message(&mut writer, &Response::Error(e));
+                        return 1;
+                }
+        };
+
+        let ready = match plugin.lock() {
+                Ok(p) => Response::Ready {
+                        info: p.info(),
+                        io_config: p.get_io_config(),
+                        latency: p.latency(),
+                        note_ids: p.supports_note_ids(),
+                },
+                Err(_) => return 1,
+        };
+        if write_message(&mut writer, &ready).is_err() {
+                return 1;
+        }
+
+        let audio_plugin = plugin.clone();
+        if std::thread::Builder::new()
+                .name("plugin-sandbox-audio".to_string())
+                .spawn(move || run_audio(audio_plugin, shm, doorbell))
+                .is_err()
+        {
+                return 1;
+        }
+
+        // 读取线程：控制请求转发到主线程，使主线程在等待请求时也能处理主线程回调
+        let (tx, requests) = bounded::<Request>(16);
+        std::thread::spawn(move || {
+                let mut reader = BufReader::new(std::io::stdin());
+                loop {
+                        match read_message::<_, Request>(&mut reader) {
+                                Ok(Some(r)) => {
+                                        if tx.send(r).is_err() {
+                                                break;
+                                        }
+                                }
+                                // 宿主关闭了管道（正常退出或宿主已崩溃）
+                                Ok(None) => break,
+                                Err(e) => {
+                                        eprintln!("Sandbox: malformed request: {}", e);
+                                        break;
+                                }
+                        }
+                }
+        });
+
+        loop {
+                let request = match requests.recv_timeout(PUMP_INTERVAL) {
+                        Ok(r) => Some(r),
+                        Err(RecvTimeoutError::Timeout) => None,
+                        Err(RecvTimeoutError::Disconnected) => return 0,
+                };
+                let Ok(mut plugin) = plugin.lock() else {
+                        return 1;
+                };
+
+                // 处理插件发起的主线程回调
+                if threads::take_main_thread_pump_request() {
+                        plugin.on_main_thread();
+                }
+                let Some(request) = request else {
+                        continue;
+                };
+
+                let response = match request {
+                        Request::GetParameters => Response::Parameters(plugin.get_parameters()),
+                        Request::GetParam { id } => Response::Value(plugin.get_param(id)),
+                        Request::SetParam { id, value } => {
+                                plugin.set_param(id, value);
+                                Response::Ok
+                        }
+                        Request::ValueToText { id, value } => Response::Text(plugin.param_value_to_text(id, value)),
+                        Request::TextToValue { id, text } => Response::Parsed(plugin.param_text_to_value(id, &text)),
+                        Request::GetState => Response::State(plugin.get_state()),
+                        Request::SetState { data } => {
+                                plugin.set_state(&data);
+                                Response::Ok
+                        }
+                        Request::Shutdown => {
+                                let _ = write_message(&mut writer, &Response::Ok);
+                                return 0;
+                        }
+                };
+
+                if write_message(&mut writer, &response).is_err() {
+                        return 1;
+                }
+        }
+}
+
+// 音频线程：每次门铃响起时处理宿主新提交的块，处理完后发布 `done`；门铃关闭（宿主退出或释放插件）时结束
+fn run_audio(plugin: Arc<Mutex<Box<dyn Plugin>>>, mut shm: SharedBuffer, mut doorbell: File) {
+        threads::mark_audio_thread();
+        // 缓冲一次性按共享内存区域的上限预留，处理块时不再分配
+        let mut output_events = Vec::with_capacity(EVENT_BYTES / EVENT_RECORD_BYTES);
+        let mut events = Vec::with_capacity(EVENT_BYTES / EVENT_RECORD_BYTES);
+        let mut input = Vec::with_capacity(SHARED_SAMPLES);
+        let mut byte = [0u8; 1];
+        while matches!(doorbell.read(&mut byte), Ok(1)) {
+                let header = shm.header();
+                let request = header.request.load(Ordering::Acquire);
+                if request == header.done.load(Ordering::Relaxed) {
+                        continue;
+                }
+                let frames = header.frames.load(Ordering::Relaxed) as usize;
+                let channels = header.channels.load(Ordering::Relaxed) as usize;
+                let sample_rate = f32::from_bits(header.sample_rate.load(Ordering::Relaxed));
+                let used = (header.in_events.load(Ordering::Relaxed) as usize).min(EVENT_BYTES);
+                let len = (frames * channels).min(SHARED_SAMPLES);
+
+                events.clear();
+                read_events(&shm.in_events()[..used], |event| events.push(event));
+                input.clear();
+                input.extend_from_slice(&shm.input()[..len]);
+
+                let Ok(mut plugin) = plugin.lock() else {
+                        return;
+                };
+                let mut buffer = AudioBuffer {
+                        samples: &mut input,
+                        channels,
+                        sample_rate,
+                };
+                output_events.clear();
+                plugin.process(&mut buffer, &events, &mut output_events);
+                let latency = plugin.latency();
+                let dirty = plugin.take_state_dirty();
+                drop(plugin);
+
+                shm.output()[..len].copy_from_slice(&input);
+                let written = write_events(shm.out_events(), &output_events);
+                let header = shm.header();
+                header.out_events.store(written as u32, Ordering::Relaxed);
+                header.latency.store(latency, Ordering::Relaxed);
+                if dirty {
+                        header.state_dirty.store(1, Ordering::Relaxed);
+                }
+                header.done.store(request, Ordering::Release);
+        }
+}
@@ -0,0 +1,405 @@
+use super::SHARED_SAMPLES;
+use super::protocol::{
+        EVENT_RECORD_BYTES, Request, Response, SandboxKind, read_events, read_message, write_events, write_message,
+};
+use super::shm::{EVENT_BYTES, SharedBuffer};
+use crate::audio::core::plugin::{AudioBuffer, IOConfig, Plugin, PluginEvent, PluginInfo, PluginParameter};
+use crate::audio::core::threads;
+use crossbeam_channel::{Receiver, RecvTimeoutError, bounded};
+use std::io::{BufReader, BufWriter};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+// 宿主侧的沙箱插件代理：在子进程中运行真实插件，并实现 `Plugin` 接口。
+//
+// 音频线程从不等待子进程：每个块先取回子进程对上一块的处理结果，再提交本块输入并敲响门铃，
+// 因此沙箱插件比进程内运行多一个块的延迟（计入 `latency`）。子进程没能在一个块的时间内处理完时，
+// 本块输出静音，迟到的结果被丢弃，插件在之后的块中自行恢复。
+// 音频线程上的参数变化（斜坡、旁通后恢复的参数等）不走控制管道，而是排队随下一个块的事件写入共享内存。
+// 只有子进程退出或控制管道断开才视为崩溃：此后该插件输出静音，并通过主线程泵（`take_crash_report`）向 UI 报告一次失败。
+
+// 子进程启动（加载插件）的最长等待时间
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+// 主线程控制请求（参数/状态）的最长等待时间
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(2);
+// 音频线程参数队列的预留容量（同一参数只保留最新值）
+const PENDING_PARAM_CAPACITY: usize = EVENT_BYTES / EVENT_RECORD_BYTES / 8;
+
+struct Connection {
+        child: Child,
+        stdin: BufWriter<ChildStdin>,
+        responses: Receiver<Response>,
+}
+
+// 门铃管道：宿主每提交一个块写入 1 字节（非阻塞），子进程的音频线程阻塞读取
+fn doorbell_pipe() -> std::io::Result<(OwnedFd, OwnedFd)> {
+        let mut fds = [0; 2];
+        unsafe {
+                if libc::pipe(fds.as_mut_ptr()) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                }
+                let (read, write) = (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1]));
+                // 读端由子进程继承；写端只留在宿主中，并且写入时不能阻塞音频线程
+                libc::fcntl(write.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC);
+                libc::fcntl(write.as_raw_fd(), libc::F_SETFL, libc::O_NONBLOCK);
+                Ok((read, write))
+        }
+}
+
+/// 崩溃状态（由读取线程与调用方共享）
+struct CrashState {
+        crashed: AtomicBool,
+        message: Mutex<Option<String>>,
+}
+
+impl CrashState {
+        fn report(&self, message: String) {
+                if self.crashed.swap(true, Ordering::AcqRel) {
+                        return;
+                }
+                eprintln!("Sandbox: {}", message);
+                if let Ok(mut m) = self.message.lock() {
+                        *m = Some(message);
+                }
+                threads::request_main_thread_pump();
+        }
+}
+
+pub struct SandboxedPlugin {
+        // 控制连接；Drop 时取出并在后台关闭子进程
+        conn: Mutex<Option<Connection>>,
+        crash: Arc<CrashState>,
+        info: PluginInfo,
+        io_config: IOConfig,
+        note_ids: bool,
+        // 音频交接（只在音频线程访问）
+        shm: SharedBuffer,
+        doorbell: OwnedFd,
+        // 最近提交的块序号与样本数
+        submitted: u32,
+        submitted_len: usize,
+        // 子进程错过了上一个块的交付：它之后完成的结果已过时
+        stale: bool,
+        // 音频线程调用 `set_param` 排队的参数变化（`Parameter` 事件），随下一个提交的块送达
+        pending_params: Vec<PluginEvent>,
+        // 插件延迟 + 一个块的交接延迟
+        latency: AtomicU32,
+        state_dirty: bool,
+}
+
+impl SandboxedPlugin {
+        /// 启动子进程并加载插件；失败时返回子进程报告的错误
+        pub fn spawn(kind: SandboxKind, path: &str, sample_rate: f32) -> Result<Self, String> {
+                let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+                let shm_path = std::env::temp_dir().join(format!("mydaw-sandbox-{}.shm", Uuid::new_v4()));
+                let shm = SharedBuffer::create(&shm_path).map_err(|e| e.to_string())?;
+                let (doorbell_read, doorbell) = doorbell_pipe().map_err(|e| e.to_string())?;
+
+                let mut child = Command::new(exe)
+                        .arg(super::SANDBOX_FLAG)
+                        .arg(kind.as_str())
+                        .arg(path)
+                        .arg(&shm_path)
+                        .arg(sample_rate.to_string())
+                        .arg(doorbell_read.as_raw_fd().to_string())
+                        .stdin(Stdio::piped())
+                        .stdout(Stdio::piped())
+                        .stderr(Stdio::inherit())
+                        .spawn()
+                        .map_err(|e| format!("Failed to spawn sandbox process: {}", e))?;
+                // 读端已由子进程继承
+                drop(doorbell_read);
+
+                let stdin = child.stdin.take().ok_or("Sandbox stdin unavailable")?;
+                let stdout = child.stdout.take().ok_or("Sandbox stdout unavailable")?;
+
+                let crash = Arc::new(CrashState {
+                        crashed: AtomicBool::new(false),
+                        message: Mutex::new(None),
+                });
+
+                // 读取线程：把应答转发到通道；管道关闭即说明子进程已退出
+                let (tx, responses) = bounded::<Response>(16);
+                let reader_crash = crash.clone();
+                let name = path.to_string();
+                std::thread::Builder::new()
+                        .name("plugin-sandbox-reader".to_string())
+                        .spawn(move || {
+                                let mut reader = BufReader::new(stdout);
+                                loop {
+                                        match read_message::<_, Response>(&mut reader) {
+                                                Ok(Some(msg)) => {
+                                                        if tx.send(msg).is_err() {
+                                                                break;
+                                                        }
+                                                }
+                                                Ok(None) => {
+                                                        reader_crash.report(format!(
+                                                                "Plugin host process for {} exited",
+                                                                name
+                                                        ));
+                                                        break;
+                                                }
+                                                Err(e) => {
+                                                        reader_crash.report(format!(
+                                                                "Plugin host process for {} sent invalid data: {}",
+                                                                name, e
+                                                        ));
+                                                        break;
+                                                }
+                                        }
+                                }
+                        })
+                        .map_err(|e| e.to_string())?;
+
+                let mut conn = Connection {
+                        child,
+                        stdin: BufWriter::new(stdin),
+                        responses,
+                };
+
+                match conn.responses.recv_timeout(STARTUP_TIMEOUT) {
+                        Ok(Response::Ready {
+                                info,
+                                io_config,
+                                latency,
+                                note_ids,
+                        }) => Ok(Self {
+                                conn: Mutex::new(Some(conn)),
+                                crash,
+                                info,
+                                io_config,
+                                note_ids,
+                                shm,
+                                doorbell,
+                                submitted: 0,
+                                submitted_len: 0,
+                                stale: false,
+                                pending_params: Vec::with_capacity(PENDING_PARAM_CAPACITY),
+                                latency: AtomicU32::new(latency),
+                                state_dirty: false,
+                        }),
+                        Ok(Response::Error(e)) => {
+                                let _ = conn.child.wait();
+                                Err(e)
+                        }
+                        Ok(_) => {
+                                let _ = conn.child.kill();
+                                Err("Unexpected response from sandbox process".to_string())
+                        }
+                        Err(_) => {
+                                let _ = conn.child.kill();
+                                let _ = conn.child.wait();
+                                Err("Sandbox process failed to start".to_string())
+                        }
+                }
+        }
+
+        fn is_crashed(&self) -> bool {
+                self.crash.crashed.load(Ordering::Acquire)
+        }
+
+        // 发送请求并等待应答；失败时标记崩溃并终止子进程
+        fn exchange(&self, conn: &mut Connection, request: &Request, timeout: Duration) -> Option<Response> {
+                if self.is_crashed() {
+                        return None;
+                }
+                if let Err(e) = write_message(&mut conn.stdin, request) {
+                        self.crash.report(format!(
+                                "Lost connection to plugin {}: {}",
+                                self.info.name, e
+                        ));
+                        let _ = conn.child.kill();
+                        return None;
+                }
+                match conn.responses.recv_timeout(timeout) {
+                        Ok(response) => Some(response),
+                        Err(RecvTimeoutError::Timeout) => {
+                                self.crash
+                                        .report(format!("Plugin {} stopped responding", self.info.name));
+                                let _ = conn.child.kill();
+                                None
+                        }
+                        Err(RecvTimeoutError::Disconnected) => {
+                                self.crash.report(format!("Plugin {} crashed", self.info.name));
+                                None
+                        }
+                }
+        }
+
+        fn control(&self, request: Request) -> Option<Response> {
+                let mut conn = self.conn.lock().ok()?;
+                self.exchange(conn.as_mut()?, &request, CONTROL_TIMEOUT)
+        }
+}
+
+impl Drop for SandboxedPlugin {
+        fn drop(&mut self) {
+                // 门铃随本结构一起关闭，子进程的音频线程随之退出。
+                // 等待子进程退出可能需要数秒，放到后台线程进行，不阻塞调用方（可能是音频线程）
+                let Some(mut conn) = self.conn.get_mut().ok().and_then(|c| c.take()) else {
+                        return;
+                };
+                let crashed = self.is_crashed();
+                let _ = std::thread::Builder::new()
+                        .name("plugin-sandbox-shutdown".to_string())
+                        .spawn(move || {
+                                if !crashed && write_message(&mut conn.stdin, &Request::Shutdown).is_ok() {
+                                        let _ = conn.responses.recv_timeout(CONTROL_TIMEOUT);
+                                }
+                                let _ = conn.child.kill();
+                                let _ = conn.child.wait();
+                        });
+        }
+}
+
+impl Plugin for SandboxedPlugin {
+        fn info(&self) -> PluginInfo {
+                self.info.clone()
+        }
+
+        fn get_parameters(&self) -> Vec<PluginParameter> {
+                match self.control(Request::GetParameters) {
+                        Some(Response::Parameters(params)) => params,
+                        _ => Vec::new(),
+                }
+        }
+
+        fn get_state(&self) -> Vec<u8> {
+                match self.control(Request::GetState) {
+                        Some(Response::State(data)) => data,
+                        _ => Vec::new(),
+                }
+        }
+
+        fn set_state(&mut self, state: &[u8]) {
+                let _ = self.control(Request::SetState { data: state.to_vec() });
+        }
+
+        fn get_io_config(&self) -> IOConfig {
+                self.io_config.clone()
+        }
+
+        fn supports_note_ids(&self) -> bool {
+                self.note_ids
+        }
+
+        fn latency(&self) -> u32 {
+                self.latency.load(Ordering::Relaxed)
+        }
+
+        fn process(&mut self, buffer: &mut AudioBuffer, events: &[PluginEvent], output_events: &mut Vec<PluginEvent>) {
+                let len = buffer.samples.len();
+                if self.is_crashed() || len > SHARED_SAMPLES {
+                        self.pending_params.clear();
+                        buffer.samples.fill(0.0);
+                        return;
+                }
+                if self.shm.header().done.load(Ordering::Acquire) != self.submitted {
+                        // 子进程还没处理完上一块：不等待，本块输出静音，迟到的结果到达时丢弃；排队的参数留到下一块
+                        self.stale = true;
+                        buffer.samples.fill(0.0);
+                        return;
+                }
+
+                // 子进程空闲：先提交本块输入，再把上一块的输出写回缓冲（一个块的延迟）
+                self.shm.input()[..len].copy_from_slice(buffer.samples);
+                if !self.stale && self.submitted_len == len {
+                        buffer.samples.copy_from_slice(&self.shm.output()[..len]);
+                        let used = (self.shm.header().out_events.load(Ordering::Relaxed) as usize).min(EVENT_BYTES);
+                        read_events(&self.shm.out_events()[..used], |event| {
+                                output_events.push(event)
+                        });
+                } else {
+                        buffer.samples.fill(0.0);
+                }
+                self.stale = false;
+
+                let channels = buffer.channels.max(1);
+                // 排队的参数变化排在本块事件之前，块内事件中同一参数的变化随后生效
+                let in_events = write_events(
+                        self.shm.in_events(),
+                        self.pending_params.iter().chain(events),
+                );
+                self.pending_params.clear();
+                let header = self.shm.header();
+                let frames = (len / channels) as u32;
+                header.frames.store(frames, Ordering::Relaxed);
+                header.channels.store(channels as u32, Ordering::Relaxed);
+                header.sample_rate
+                        .store(buffer.sample_rate.to_bits(), Ordering::Relaxed);
+                header.in_events.store(in_events as u32, Ordering::Relaxed);
+                self.latency.store(
+                        header.latency.load(Ordering::Relaxed) + frames,
+                        Ordering::Relaxed,
+                );
+                if header.state_dirty.swap(0, Ordering::Relaxed) != 0 {
+                        self.state_dirty = true;
+                        threads::request_main_thread_pump();
+                }
+                self.submitted = self.submitted.wrapping_add(1);
+                self.submitted_len = len;
+                header.request.store(self.submitted, Ordering::Release);
+                // 门铃写满（不会发生：最多只有一个未处理的块）或子进程已退出时写入失败，均可忽略
+                unsafe {
+                        libc::write(
+                                self.doorbell.as_raw_fd(),
+                                [1u8].as_ptr() as *const libc::c_void,
+                                1,
+                        );
+                }
+        }
+
+        fn get_param(&self, id: u32) -> f32 {
+                match self.control(Request::GetParam { id }) {
+                        Some(Response::Value(v)) => v,
+                        _ => 0.0,
+                }
+        }
+
+        fn set_param(&mut self, id: u32, value: f32) {
+                if !threads::is_audio_thread() {
+                        let _ = self.control(Request::SetParam { id, value });
+                        return;
+                }
+                // 音频线程不能等待控制管道：排队随下一个块送达，同一参数只保留最新值；队列满时丢弃
+                let queued = self
+                        .pending_params
+                        .iter_mut()
+                        .find(|event| matches!(event, PluginEvent::Parameter { id: queued, .. } if *queued == id));
+                if let Some(event) = queued {
+                        *event = PluginEvent::Parameter { id, value };
+                } else if self.pending_params.len() < PENDING_PARAM_CAPACITY {
+                        self.pending_params.push(PluginEvent::Parameter { id, value });
+                }
+        }
+
+        fn param_value_to_text(&self, id: u32, value: f32) -> Option<String> {
+                match self.control(Request::ValueToText { id, value }) {
+                        Some(Response::Text(text)) => text,
+                        _ => None,
+                }
+        }
+
+        fn param_text_to_value(&self, id: u32, text: &str) -> Option<f32> {
+                match self.control(Request::TextToValue {
+                        id,
+                        text: text.to_string(),
+                }) {
+                        Some(Response::Parsed(value)) => value,
+                        _ => None,
+                }
+        }
+
+        fn take_state_dirty(&mut self) -> bool {
+                std::mem::take(&mut self.state_dirty)
+        }
+
+        fn take_crash_report(&mut self) -> Option<String> {
+                self.crash.message.lock().ok().and_then(|mut m| m.take())
+        }
+}
@@ -0,0 +1,51 @@
+/// 插件进程隔离（沙箱）：可选地把插件放到子宿主进程中运行，
+/// 通过共享内存交换音频、通过控制管道交换命令，插件崩溃不会拖垮 DAW 主进程。
+pub mod protocol;
+
+#[cfg(unix)]
+mod child;
+#[cfg(unix)]
+mod host;
+#[cfg(unix)]
+mod shm;
+
+use crate::audio::core::plugin::Plugin;
+use protocol::SandboxKind;
+
+/// 启动沙箱子进程时使用的命令行标志
+pub const SANDBOX_FLAG: &str = "--plugin-sandbox";
+
+// 共享音频缓冲容量（样本数）：足够容纳 8192 帧 × 8 通道
+#[cfg_attr(not(unix), allow(dead_code))]
+const SHARED_SAMPLES: usize = 8192 * 8;
+
+/// 若当前进程是以沙箱子进程方式启动的，则运行插件宿主循环并返回退出码；否则返回 None。
+/// 应在 `main` 的最开始调用。
+pub fn run_child_if_requested() -> Option<i32> {
+        let args: Vec<String> = std::env::args().collect();
+        if args.get(1).map(|a| a.as_str()) != Some(SANDBOX_FLAG) {
+                return None;
+        }
+        #[cfg(unix)]
+        {
+                Some(child::run(&args[2..]))
+        }
+        #[cfg(not(unix))]
+        {
+                eprintln!("Sandbox: plugin sandboxing is not supported on this platform");
+                Some(2)
+        }
+}
+
+/// 在沙箱子进程中创建插件
+pub fn spawn_sandboxed(kind: SandboxKind, path: &str, sample_rate: f32) -> Result<Box<dyn Plugin>, String> {
+        #[cfg(unix)]
+        {
+                host::SandboxedPlugin::spawn(kind, path, sample_rate).map(|p| Box::new(p) as Box<dyn Plugin>)
+        }
+        #[cfg(not(unix))]
+        {
+                let _ = (kind, path, sample_rate);
+                Err("Plugin sandboxing is not supported on this platform".to_string())
+        }
+}
@@ -0,0 +1,322 @@
+use crate::audio::core::plugin::{IOConfig, NoteEvent, NoteExpressionKind, PluginEvent, PluginInfo, PluginParameter};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, Write};
+
+// 沙箱宿主与子进程之间的控制协议：每条消息为一行 JSON（控制管道），只用于主线程的控制请求。
+// 音频块与块内事件不经过管道，而是通过共享内存交接（见 `shm`）；事件在共享内存中按固定长度的二进制记录存放，
+// 编码与解码都不分配内存，可在音频线程进行。
+
+/// 子进程加载插件所用的后端类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SandboxKind {
+        Clap,
+        Local,
+}
+
+impl SandboxKind {
+        pub fn as_str(&self) -> &'static str {
+                match self {
+                        SandboxKind::Clap => "clap",
+                        SandboxKind::Local => "local",
+                }
+        }
+
+        pub fn parse(s: &str) -> Option<Self> {
+                match s {
+                        "clap" => Some(SandboxKind::Clap),
+                        "local" => Some(SandboxKind::Local),
+                        _ => None,
+                }
+        }
+}
+
+/// 宿主 -> 子进程
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Request {
+        GetParameters,
+        GetParam { id: u32 },
+        SetParam { id: u32, value: f32 },
+        ValueToText { id: u32, value: f32 },
+        TextToValue { id: u32, text: String },
+        GetState,
+        SetState { data: Vec<u8> },
+        Shutdown,
+}
+
+/// 子进程 -> 宿主
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Response {
+        /// 插件加载完成，附带静态信息
+        Ready {
+                info: PluginInfo,
+                io_config: IOConfig,
+                latency: u32,
+                note_ids: bool,
+        },
+        Parameters(Vec<PluginParameter>),
+        Value(f32),
+        /// `ValueToText` 的结果（插件未提供格式化时为 None）
+        Text(Option<String>),
+        /// `TextToValue` 的结果（插件无法解析时为 None）
+        Parsed(Option<f32>),
+        State(Vec<u8>),
+        Ok,
+        Error(String),
+}
+
+pub fn write_message<W: Write, T: Serialize>(writer: &mut W, msg: &T) -> io::Result<()> {
+        let line = serde_json::to_string(msg).map_err(io::Error::other)?;
+        writer.write_all(line.as_bytes())?;
+        writer.write_all(b"\n")?;
+        writer.flush()
+}
+
+// 共享内存中每个事件记录的字节数
+pub const EVENT_RECORD_BYTES: usize = 32;
+
+// 事件记录的类型（首字节）
+const EVENT_MIDI: u8 = 1;
+const EVENT_MIDI_AT: u8 = 2;
+const EVENT_PARAMETER: u8 = 3;
+const EVENT_TRANSPORT: u8 = 4;
+const EVENT_ALL_NOTES_OFF: u8 = 5;
+const EVENT_RECORD: u8 = 6;
+const EVENT_TIME_SIGNATURE: u8 = 7;
+
+// MIDI 记录中的消息类型（第 2 字节）
+const NOTE_ON: u8 = 0;
+const NOTE_OFF: u8 = 1;
+const NOTE_EXPRESSION: u8 = 2;
+const CONTROL_CHANGE: u8 = 3;
+const PITCH_BEND: u8 = 4;
+const CHANNEL_PRESSURE: u8 = 5;
+const POLY_PRESSURE: u8 = 6;
+const PROGRAM_CHANGE: u8 = 7;
+
+fn put_u32(record: &mut [u8], at: usize, value: u32) {
+        record[at..at + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn get_u32(record: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes([record[at], record[at + 1], record[at + 2], record[at + 3]])
+}
+
+fn put_f64(record: &mut [u8], at: usize, value: f64) {
+        record[at..at + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+fn get_f64(record: &[u8], at: usize) -> f64 {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&record[at..at + 8]);
+        f64::from_le_bytes(bytes)
+}
+
+// MIDI 记录：[1] 消息类型 [2] 通道 [3] 音高/控制器/音色 [4..8] 块内帧 [8..12] 连续量（f32）
+// [12] 是否有 note_id [13] 表情类型 [16..20] note_id
+fn encode_note(record: &mut [u8], frame: u32, event: &NoteEvent) {
+        let (kind, data, value, note_id, expression) = match *event {
+                NoteEvent::NoteOn {
+                        note,
+                        velocity,
+                        note_id,
+                        ..
+                } => (NOTE_ON, note, velocity, note_id, 0),
+                NoteEvent::NoteOff { note, note_id, .. } => (NOTE_OFF, note, 0.0, note_id, 0),
+                NoteEvent::NoteExpression {
+                        note,
+                        note_id,
+                        expression,
+                        value,
+                        ..
+                } => {
+                        let expression = match expression {
+                                NoteExpressionKind::Pitch => 0,
+                                NoteExpressionKind::Pressure => 1,
+                                NoteExpressionKind::Brightness => 2,
+                        };
+                        (NOTE_EXPRESSION, note, value, note_id, expression)
+                }
+                NoteEvent::ControlChange { controller, value, .. } => (CONTROL_CHANGE, controller, value, None, 0),
+                NoteEvent::PitchBend { value, .. } => (PITCH_BEND, 0, value, None, 0),
+                NoteEvent::ChannelPressure { pressure, .. } => (CHANNEL_PRESSURE, 0, pressure, None, 0),
+                NoteEvent::PolyPressure { note, pressure, .. } => (POLY_PRESSURE, note, pressure, None, 0),
+                NoteEvent::ProgramChange { program, .. } => (PROGRAM_CHANGE, program, 0.0, None, 0),
+        };
+        record[1] = kind;
+        record[2] = event.channel();
+        record[3] = data;
+        put_u32(record, 4, frame);
+        put_u32(record, 8, value.to_bits());
+        record[12] = note_id.is_some() as u8;
+        record[13] = expression;
+        put_u32(record, 16, note_id.unwrap_or(0));
+}
+
+fn decode_note(record: &[u8]) -> Option<NoteEvent> {
+        let channel = record[2];
+        let data = record[3];
+        let value = f32::from_bits(get_u32(record, 8));
+        let note_id = (record[12] != 0).then(|| get_u32(record, 16));
+        Some(match record[1] {
+                NOTE_ON => NoteEvent::NoteOn {
+                        channel,
+                        note: data,
+                        velocity: value,
+                        note_id,
+                },
+                NOTE_OFF => NoteEvent::NoteOff {
+                        channel,
+                        note: data,
+                        note_id,
+                },
+                NOTE_EXPRESSION => NoteEvent::NoteExpression {
+                        channel,
+                        note: data,
+                        note_id,
+                        expression: match record[13] {
+                                0 => NoteExpressionKind::Pitch,
+                                1 => NoteExpressionKind::Pressure,
+                                2 => NoteExpressionKind::Brightness,
+                                _ => return None,
+                        },
+                        value,
+                },
+                CONTROL_CHANGE => NoteEvent::ControlChange {
+                        channel,
+                        controller: data,
+                        value,
+                },
+                PITCH_BEND => NoteEvent::PitchBend { channel, value },
+                CHANNEL_PRESSURE => NoteEvent::ChannelPressure {
+                        channel,
+                        pressure: value,
+                },
+                POLY_PRESSURE => NoteEvent::PolyPressure {
+                        channel,
+                        note: data,
+                        pressure: value,
+                },
+                PROGRAM_CHANGE => NoteEvent::ProgramChange { channel, program: data },
+                _ => return None,
+        })
+}
+
+/// 把事件编码为一条定长记录；只在宿主内部使用的事件（节点参数、节拍器 / 安全级设置、插入状态、
+/// 自定义消息）对插件没有意义，返回 false 且不写入
+pub fn encode_event(record: &mut [u8; EVENT_RECORD_BYTES], event: &PluginEvent) -> bool {
+        record.fill(0);
+        match event {
+                PluginEvent::Midi(note) => {
+                        record[0] = EVENT_MIDI;
+                        encode_note(record, 0, note);
+                }
+                PluginEvent::MidiAt { frame, event } => {
+                        record[0] = EVENT_MIDI_AT;
+                        encode_note(record, *frame, event);
+                }
+                PluginEvent::Parameter { id, value } => {
+                        record[0] = EVENT_PARAMETER;
+                        put_u32(record, 4, *id);
+                        put_u32(record, 8, value.to_bits());
+                }
+                PluginEvent::Transport {
+                        playing,
+                        position,
+                        tempo,
+                } => {
+                        record[0] = EVENT_TRANSPORT;
+                        record[1] = *playing as u8;
+                        record[2] = position.is_some() as u8;
+                        record[3] = tempo.is_some() as u8;
+                        put_f64(record, 8, position.unwrap_or(0.0));
+                        put_f64(record, 16, tempo.unwrap_or(0.0));
+                }
+                PluginEvent::AllNotesOff { panic } => {
+                        record[0] = EVENT_ALL_NOTES_OFF;
+                        record[1] = *panic as u8;
+                }
+                PluginEvent::Record { recording } => {
+                        record[0] = EVENT_RECORD;
+                        record[1] = *recording as u8;
+                }
+                PluginEvent::TimeSignature { numerator, denominator } => {
+                        record[0] = EVENT_TIME_SIGNATURE;
+                        put_u32(record, 4, *numerator);
+                        put_u32(record, 8, *denominator);
+                }
+                PluginEvent::NodeParameter { .. }
+                | PluginEvent::Metronome(_)
+                | PluginEvent::MasterSafety(_)
+                | PluginEvent::InsertState { .. }
+                | PluginEvent::Custom(_) => return false,
+        }
+        true
+}
+
+/// 解码 `encode_event` 写入的记录；类型未知时返回 None
+pub fn decode_event(record: &[u8]) -> Option<PluginEvent> {
+        if record.len() < EVENT_RECORD_BYTES {
+                return None;
+        }
+        Some(match record[0] {
+                EVENT_MIDI => PluginEvent::Midi(decode_note(record)?),
+                EVENT_MIDI_AT => PluginEvent::MidiAt {
+                        frame: get_u32(record, 4),
+                        event: decode_note(record)?,
+                },
+                EVENT_PARAMETER => PluginEvent::Parameter {
+                        id: get_u32(record, 4),
+                        value: f32::from_bits(get_u32(record, 8)),
+                },
+                EVENT_TRANSPORT => PluginEvent::Transport {
+                        playing: record[1] != 0,
+                        position: (record[2] != 0).then(|| get_f64(record, 8)),
+                        tempo: (record[3] != 0).then(|| get_f64(record, 16)),
+                },
+                EVENT_ALL_NOTES_OFF => PluginEvent::AllNotesOff { panic: record[1] != 0 },
+                EVENT_RECORD => PluginEvent::Record {
+                        recording: record[1] != 0,
+                },
+                EVENT_TIME_SIGNATURE => PluginEvent::TimeSignature {
+                        numerator: get_u32(record, 4),
+                        denominator: get_u32(record, 8),
+                },
+                _ => return None,
+        })
+}
+
+/// 把事件按定长记录写入共享内存的事件区，返回使用的字节数。
+/// 直接写入预分配的区域、不分配内存，可在音频线程调用；放不下的事件与宿主内部事件被丢弃
+pub fn write_events<'a>(area: &mut [u8], events: impl IntoIterator<Item = &'a PluginEvent>) -> usize {
+        let mut used = 0;
+        let mut record = [0u8; EVENT_RECORD_BYTES];
+        for event in events {
+                if used + EVENT_RECORD_BYTES > area.len() {
+                        break;
+                }
+                if encode_event(&mut record, event) {
+                        area[used..used + EVENT_RECORD_BYTES].copy_from_slice(&record);
+                        used += EVENT_RECORD_BYTES;
+                }
+        }
+        used
+}
+
+/// 读取 `write_events` 写入的事件（不分配内存）
+pub fn read_events(area: &[u8], mut f: impl FnMut(PluginEvent)) {
+        for record in area.chunks_exact(EVENT_RECORD_BYTES) {
+                if let Some(event) = decode_event(record) {
+                        f(event);
+                }
+        }
+}
+
+/// 读取下一条消息；对端关闭管道时返回 Ok(None)
+pub fn read_message<R: BufRead, T: DeserializeOwned>(reader: &mut R) -> io::Result<Option<T>> {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+        }
+        serde_json::from_str(&line).map(Some).map_err(io::Error::other)
+}
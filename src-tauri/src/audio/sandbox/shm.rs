@@ -0,0 +1,130 @@
+use std::fs::{File, OpenOptions};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicU32;
+
+use super::SHARED_SAMPLES;
+
+// 基于文件映射的共享内存：宿主创建并截断到固定大小，子进程映射同一文件。
+// 布局：块头（原子计数与块参数） | 输入样本 | 输出样本 | 输入事件 | 输出事件。
+// 音频交接不经过控制管道：宿主写入输入后发布 `request`，子进程处理完后发布 `done`；
+// 宿主只在 `done == request`（子进程空闲）时读写数据区，因此两端不会同时访问同一区域。
+
+// 每个方向的事件区大小（字节），事件按定长二进制记录存放（见 `protocol::write_events`）
+pub const EVENT_BYTES: usize = 64 * 1024;
+
+// 块头占用的字节数（向上对齐到缓存行）
+const HEADER_BYTES: usize = 64;
+
+/// 共享内存中的块头
+#[repr(C)]
+pub struct SharedHeader {
+        /// 宿主已提交的块序号
+        pub request: AtomicU32,
+        /// 子进程已处理完的块序号
+        pub done: AtomicU32,
+        /// 已提交块的帧数、通道数与采样率（f32 位模式）
+        pub frames: AtomicU32,
+        pub channels: AtomicU32,
+        pub sample_rate: AtomicU32,
+        /// 输入 / 输出事件区已使用的字节数
+        pub in_events: AtomicU32,
+        pub out_events: AtomicU32,
+        /// 插件报告的延迟（采样数）
+        pub latency: AtomicU32,
+        /// 插件状态已改变（子进程置位，宿主读取后清除）
+        pub state_dirty: AtomicU32,
+}
+
+const _: () = assert!(std::mem::size_of::<SharedHeader>() <= HEADER_BYTES);
+
+const INPUT_OFFSET: usize = HEADER_BYTES;
+const OUTPUT_OFFSET: usize = INPUT_OFFSET + SHARED_SAMPLES * std::mem::size_of::<f32>();
+const IN_EVENTS_OFFSET: usize = OUTPUT_OFFSET + SHARED_SAMPLES * std::mem::size_of::<f32>();
+const OUT_EVENTS_OFFSET: usize = IN_EVENTS_OFFSET + EVENT_BYTES;
+const TOTAL_BYTES: usize = OUT_EVENTS_OFFSET + EVENT_BYTES;
+
+pub struct SharedBuffer {
+        ptr: *mut u8,
+        _file: File,
+        // 仅创建方负责删除文件
+        owned_path: Option<PathBuf>,
+}
+
+unsafe impl Send for SharedBuffer {}
+unsafe impl Sync for SharedBuffer {}
+
+impl SharedBuffer {
+        /// 创建共享内存文件（内容为零，即两端的块序号都从 0 开始）
+        pub fn create(path: &Path) -> std::io::Result<Self> {
+                let file = OpenOptions::new().read(true).write(true).create_new(true).open(path)?;
+                file.set_len(TOTAL_BYTES as u64)?;
+                let mut shm = Self::map(file)?;
+                shm.owned_path = Some(path.to_path_buf());
+                Ok(shm)
+        }
+
+        /// 映射由宿主创建的共享内存文件
+        pub fn open(path: &Path) -> std::io::Result<Self> {
+                let file = OpenOptions::new().read(true).write(true).open(path)?;
+                Self::map(file)
+        }
+
+        fn map(file: File) -> std::io::Result<Self> {
+                let ptr = unsafe {
+                        libc::mmap(
+                                std::ptr::null_mut(),
+                                TOTAL_BYTES,
+                                libc::PROT_READ | libc::PROT_WRITE,
+                                libc::MAP_SHARED,
+                                file.as_raw_fd(),
+                                0,
+                        )
+                };
+                if ptr == libc::MAP_FAILED {
+                        return Err(std::io::Error::last_os_error());
+                }
+                Ok(Self {
+                        ptr: ptr as *mut u8,
+                        _file: file,
+                        owned_path: None,
+                })
+        }
+
+        pub fn header(&self) -> &SharedHeader {
+                // 映射按页对齐，块头位于起始处；原子类型允许两个进程并发访问
+                unsafe { &*(self.ptr as *const SharedHeader) }
+        }
+
+        // 数据区的访问由块序号的交接保证互斥（见文件开头），因此通过 &mut self 借出
+        fn region<T>(&mut self, offset: usize, len: usize) -> &mut [T] {
+                unsafe { std::slice::from_raw_parts_mut(self.ptr.add(offset) as *mut T, len) }
+        }
+
+        pub fn input(&mut self) -> &mut [f32] {
+                self.region(INPUT_OFFSET, SHARED_SAMPLES)
+        }
+
+        pub fn output(&mut self) -> &mut [f32] {
+                self.region(OUTPUT_OFFSET, SHARED_SAMPLES)
+        }
+
+        pub fn in_events(&mut self) -> &mut [u8] {
+                self.region(IN_EVENTS_OFFSET, EVENT_BYTES)
+        }
+
+        pub fn out_events(&mut self) -> &mut [u8] {
+                self.region(OUT_EVENTS_OFFSET, EVENT_BYTES)
+        }
+}
+
+impl Drop for SharedBuffer {
+        fn drop(&mut self) {
+                unsafe {
+                        libc::munmap(self.ptr as *mut libc::c_void, TOTAL_BYTES);
+                }
+                if let Some(path) = &self.owned_path {
+                        let _ = std::fs::remove_file(path);
+                }
+        }
+}
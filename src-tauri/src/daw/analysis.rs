@@ -0,0 +1,189 @@
+/// 分析会话：每个会话在后台线程以固定频率读取一个节点（混音轨道、乐器或插入效果）输出抽头的新样本，
+/// 计算频谱与波形快照，并通过 `analysis-frame` 事件发送给前端。会话被移除（drop）时线程结束。
+use crate::audio::analysis::scope::waveform_snapshot;
+use crate::audio::analysis::spectrum::{MAX_FFT_SIZE, MIN_FFT_SIZE, SpectrumAnalyzer};
+use crate::audio::analysis::tap::{AnalysisTap, SampleRing, tap_point};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+// 推送间隔（约 30 帧 / 秒）
+const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+// 波形快照的最大时长（秒）
+const MAX_WAVEFORM_SECONDS: f32 = 1.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalysisSettings {
+        /// FFT 尺寸（2 的幂，256..=16384）
+        pub fft_size: usize,
+        /// 频谱的指数平均系数（0 不平均，越接近 1 越平滑）
+        pub averaging: f32,
+        /// 对数频带数
+        pub bands: usize,
+        /// 最低显示频率（Hz）
+        pub min_frequency: f32,
+        /// 波形快照的点数（每点为区间的最小 / 最大值）
+        pub waveform_points: usize,
+        /// 波形快照覆盖的时长（秒）
+        pub waveform_length: f32,
+}
+
+impl Default for AnalysisSettings {
+        fn default() -> Self {
+                Self {
+                        fft_size: 4096,
+                        averaging: 0.6,
+                        bands: 96,
+                        min_frequency: 20.0,
+                        waveform_points: 512,
+                        waveform_length: 0.05,
+                }
+        }
+}
+
+impl AnalysisSettings {
+        pub fn validate(&self) -> Result<(), String> {
+                if !self.fft_size.is_power_of_two() || !(MIN_FFT_SIZE..=MAX_FFT_SIZE).contains(&self.fft_size) {
+                        return Err(format!("Invalid FFT size: {}", self.fft_size));
+                }
+                if !(0.0..1.0).contains(&self.averaging) {
+                        return Err(format!("Invalid spectrum averaging: {}", self.averaging));
+                }
+                if !(1..=1024).contains(&self.bands) {
+                        return Err(format!("Invalid band count: {}", self.bands));
+                }
+                if self.min_frequency.is_nan() || self.min_frequency <= 0.0 {
+                        return Err(format!("Invalid minimum frequency: {}", self.min_frequency));
+                }
+                if !(1..=4096).contains(&self.waveform_points) {
+                        return Err(format!(
+                                "Invalid waveform point count: {}",
+                                self.waveform_points
+                        ));
+                }
+                if !(f32::MIN_POSITIVE..=MAX_WAVEFORM_SECONDS).contains(&self.waveform_length) {
+                        return Err(format!("Invalid waveform length: {}", self.waveform_length));
+                }
+                Ok(())
+        }
+}
+
+/// `analysis-frame` 事件的内容
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisFrame {
+        /// 会话 ID
+        pub id: Uuid,
+        /// 被分析的节点
+        pub node: Uuid,
+        pub sample_rate: f32,
+        /// 各频带的中心频率（Hz）与电平（dBFS）
+        pub frequencies: Vec<f32>,
+        pub spectrum: Vec<f32>,
+        /// 波形快照：[最小值, 最大值]
+        pub waveform: Vec<[f32; 2]>,
+}
+
+pub struct AnalysisSession {
+        node: Uuid,
+        settings: Arc<Mutex<AnalysisSettings>>,
+        running: Arc<AtomicBool>,
+}
+
+impl AnalysisSession {
+        pub fn start(app: AppHandle, id: Uuid, node: Uuid, settings: AnalysisSettings) -> Result<Self, String> {
+                // 第一个会话为节点创建环形缓冲；线程结束时离开，最后一个会话离开时释放
+                let tap = tap_point(node)?;
+                let ring = tap.add_listener()?;
+                let settings = Arc::new(Mutex::new(settings));
+                let running = Arc::new(AtomicBool::new(true));
+                let (shared, flag, listener) = (settings.clone(), running.clone(), tap.clone());
+                let spawned = std::thread::Builder::new().name("analysis".to_string()).spawn(move || {
+                        run(app, id, node, &listener, &ring, shared, flag);
+                        let _ = listener.remove_listener();
+                });
+                if let Err(e) = spawned {
+                        let _ = tap.remove_listener();
+                        return Err(format!("Failed to start analysis: {}", e));
+                }
+                Ok(Self {
+                        node,
+                        settings,
+                        running,
+                })
+        }
+
+        pub fn node(&self) -> Uuid {
+                self.node
+        }
+
+        pub fn set_settings(&self, settings: AnalysisSettings) -> Result<(), String> {
+                *self.settings.lock().map_err(|_| "Failed to lock analysis settings")? = settings;
+                Ok(())
+        }
+}
+
+impl Drop for AnalysisSession {
+        fn drop(&mut self) {
+                self.running.store(false, Ordering::Relaxed);
+        }
+}
+
+fn run(
+        app: AppHandle,
+        id: Uuid,
+        node: Uuid,
+        tap: &AnalysisTap,
+        ring: &SampleRing,
+        settings: Arc<Mutex<AnalysisSettings>>,
+        running: Arc<AtomicBool>,
+) {
+        let mut position = ring.written();
+        let mut history: Vec<f32> = Vec::new();
+        let mut analyzer: Option<SpectrumAnalyzer> = None;
+        while running.load(Ordering::Relaxed) {
+                std::thread::sleep(FRAME_INTERVAL);
+                let Ok(settings) = settings.lock().map(|s| s.clone()) else {
+                        break;
+                };
+                let read = ring.read(position, &mut history);
+                // 没有新样本（引擎停止或节点不在音频图中）时不重复发送
+                if read == position {
+                        continue;
+                }
+                position = read;
+
+                let sample_rate = tap.sample_rate();
+                let waveform_length = (settings.waveform_length * sample_rate) as usize;
+                let keep = settings.fft_size.max(waveform_length);
+                if history.len() > keep {
+                        history.drain(..history.len() - keep);
+                }
+
+                let analyzer = match analyzer.as_mut() {
+                        Some(a) if a.size() == settings.fft_size => a,
+                        _ => analyzer.insert(SpectrumAnalyzer::new(settings.fft_size, settings.averaging)),
+                };
+                analyzer.set_averaging(settings.averaging);
+                analyzer.analyze(&history);
+                let (frequencies, spectrum) = analyzer.bands(sample_rate, settings.min_frequency, settings.bands);
+                let waveform = waveform_snapshot(
+                        &history[history.len().saturating_sub(waveform_length)..],
+                        settings.waveform_points,
+                );
+                let frame = AnalysisFrame {
+                        id,
+                        node,
+                        sample_rate,
+                        frequencies,
+                        spectrum,
+                        waveform,
+                };
+                if app.emit("analysis-frame", &frame).is_err() {
+                        break;
+                }
+        }
+}
@@ -0,0 +1,68 @@
+use crate::daw::analysis::{AnalysisSession, AnalysisSettings};
+use crate::daw::state::AppState;
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+// 频谱 / 示波器分析命令：分析对象为混音轨道的节点 ID，或乐器 / 插入效果的实例 ID。
+// 结果通过 `analysis-frame` 事件推送；会话只在本次运行中存在，不随工程保存。
+
+#[tauri::command]
+pub fn start_analysis(
+        app: AppHandle,
+        state: State<'_, AppState>,
+        node: Uuid,
+        settings: Option<AnalysisSettings>,
+) -> Result<Uuid, String> {
+        let settings = settings.unwrap_or_default();
+        settings.validate()?;
+        let is_track = {
+                let tracks = state.mixer_tracks.lock().map_err(|_| "Failed to lock tracks")?;
+                tracks.iter().any(|t| t.node_id == node)
+        };
+        if !is_track {
+                let instances = state.plugin_instances.lock().map_err(|_| "Failed to lock instances")?;
+                if !instances.contains_key(&node.to_string()) {
+                        return Err("Analysis target not found".to_string());
+                }
+        }
+        let id = Uuid::new_v4();
+        let session = AnalysisSession::start(app, id, node, settings)?;
+        let mut sessions = state
+                .analysis_sessions
+                .lock()
+                .map_err(|_| "Failed to lock analysis sessions")?;
+        sessions.insert(id, session);
+        Ok(id)
+}
+
+#[tauri::command]
+pub fn set_analysis_settings(state: State<'_, AppState>, id: Uuid, settings: AnalysisSettings) -> Result<(), String> {
+        settings.validate()?;
+        let sessions = state
+                .analysis_sessions
+                .lock()
+                .map_err(|_| "Failed to lock analysis sessions")?;
+        sessions.get(&id)
+                .ok_or("Analysis session not found")?
+                .set_settings(settings)
+}
+
+#[tauri::command]
+pub fn stop_analysis(state: State<'_, AppState>, id: Uuid) -> Result<(), String> {
+        let mut sessions = state
+                .analysis_sessions
+                .lock()
+                .map_err(|_| "Failed to lock analysis sessions")?;
+        sessions.remove(&id).ok_or("Analysis session not found")?;
+        Ok(())
+}
+
+/// 正在运行的分析会话：会话 ID -> 节点
+#[tauri::command]
+pub fn get_analysis_sessions(state: State<'_, AppState>) -> Result<Vec<(Uuid, Uuid)>, String> {
+        let sessions = state
+                .analysis_sessions
+                .lock()
+                .map_err(|_| "Failed to lock analysis sessions")?;
+        Ok(sessions.iter().map(|(id, session)| (*id, session.node())).collect())
+}
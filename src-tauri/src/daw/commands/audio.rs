@@ -0,0 +1,79 @@
+use crate::audio::engine::AudioBackend;
+use crate::daw::core::rebuild_engine;
+use crate::daw::model::ProcessingSettings;
+use crate::daw::settings::{load_json_setting, save_json_setting};
+use crate::daw::state::AppState;
+use tauri::{AppHandle, Manager, State};
+
+// 音频后端命令：选择 cpal（系统默认设备）或 JACK 及其端口 / 走带设置。
+// 设置保存在应用数据目录的 `audio.json`，启动时恢复。
+// 工程采样率与求和精度属于工程设置，随工程保存。
+
+/// 启动时恢复上次选择的后端（引擎尚未启动）
+pub fn load_audio_settings(app: &AppHandle) {
+        let Some(backend) = load_json_setting::<AudioBackend>(app, "audio.json") else {
+                return;
+        };
+        let state = app.state::<AppState>();
+        if let Ok(mut engine) = state.audio_engine.lock() {
+                engine.set_backend(backend);
+        }
+}
+
+#[tauri::command]
+pub fn get_audio_backend(state: State<'_, AppState>) -> Result<AudioBackend, String> {
+        let engine = state.audio_engine.lock().map_err(|_| "Failed to lock audio engine")?;
+        Ok(engine.backend().clone())
+}
+
+/// 切换后端；引擎正在运行时立即以新后端重建音频图。
+/// 新后端启动失败时恢复原后端设置，引擎保持停止，下次播放时以原后端启动。
+#[tauri::command]
+pub fn set_audio_backend(app: AppHandle, state: State<'_, AppState>, backend: AudioBackend) -> Result<(), String> {
+        let (previous, running) = {
+                let mut engine = state.audio_engine.lock().map_err(|_| "Failed to lock audio engine")?;
+                let previous = engine.backend().clone();
+                engine.set_backend(backend.clone());
+                (previous, engine.is_running())
+        };
+        let result = if running {
+                rebuild_engine(&state)
+        } else {
+                Ok(())
+        };
+        if let Err(e) = result {
+                let mut engine = state.audio_engine.lock().map_err(|_| "Failed to lock audio engine")?;
+                engine.set_backend(previous);
+                return Err(e);
+        }
+        save_json_setting(&app, "audio.json", &backend);
+        Ok(())
+}
+
+#[tauri::command]
+pub fn get_processing_settings(state: State<'_, AppState>) -> Result<ProcessingSettings, String> {
+        let processing = state
+                .processing
+                .lock()
+                .map_err(|_| "Failed to lock processing settings")?;
+        Ok(processing.clone())
+}
+
+/// 设置工程采样率与求和精度；引擎正在运行时立即重建音频图
+#[tauri::command]
+pub fn set_processing_settings(state: State<'_, AppState>, settings: ProcessingSettings) -> Result<(), String> {
+        if let Some(rate) = settings.sample_rate.filter(|rate| !(8000..=384000).contains(rate)) {
+                return Err(format!("Invalid sample rate: {}", rate));
+        }
+        {
+                let mut processing = state
+                        .processing
+                        .lock()
+                        .map_err(|_| "Failed to lock processing settings")?;
+                if *processing == settings {
+                        return Ok(());
+                }
+                *processing = settings;
+        }
+        rebuild_engine(&state)
+}
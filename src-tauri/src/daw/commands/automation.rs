@@ -0,0 +1,44 @@
+use crate::audio::core::automation::AutomationLane;
+use crate::audio::core::plugin::ParamAddress;
+use crate::daw::core::rebuild_engine;
+use crate::daw::state::AppState;
+use tauri::State;
+
+/// 参数自动化命令：曲线按参数地址（节点 UUID + 参数 ID）寻址，与 `set_node_parameter` 使用同一地址。
+/// 修改后重建音频图，由音序器在播放时发送 `NodeParameter` 事件。
+
+#[tauri::command]
+pub fn get_automation_lanes(state: State<'_, AppState>) -> Result<Vec<AutomationLane>, String> {
+        let lanes = state.automation.lock().map_err(|_| "Failed to lock automation")?;
+        Ok(lanes.clone())
+}
+
+/// 添加或替换某个参数的自动化曲线（点按时间排序）
+#[tauri::command]
+pub fn set_automation_lane(state: State<'_, AppState>, mut lane: AutomationLane) -> Result<(), String> {
+        if lane.points.iter().any(|p| !p.time.is_finite() || !p.value.is_finite()) {
+                return Err("Invalid automation point".to_string());
+        }
+        lane.points.sort_by(|a, b| a.time.total_cmp(&b.time));
+        {
+                let mut lanes = state.automation.lock().map_err(|_| "Failed to lock automation")?;
+                match lanes.iter_mut().find(|l| l.address == lane.address) {
+                        Some(existing) => *existing = lane,
+                        None => lanes.push(lane),
+                }
+        }
+        rebuild_engine(&state)
+}
+
+#[tauri::command]
+pub fn remove_automation_lane(state: State<'_, AppState>, address: ParamAddress) -> Result<(), String> {
+        {
+                let mut lanes = state.automation.lock().map_err(|_| "Failed to lock automation")?;
+                let before = lanes.len();
+                lanes.retain(|l| l.address != address);
+                if lanes.len() == before {
+                        return Err("Automation lane not found".to_string());
+                }
+        }
+        rebuild_engine(&state)
+}
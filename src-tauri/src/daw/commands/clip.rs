@@ -1,7 +1,7 @@
 /// Clip 命令：创建/更新/复制/查询/删除剪辑（修改 `AppState.clips` 并在必要时触发 `rebuild_engine`）
 use crate::AppState;
 use crate::daw::core::rebuild_engine;
-use crate::daw::model::{Clip, MusicalLength, Note, Position};
+use crate::daw::model::{Clip, ControllerEvent, MusicalLength, Note, Position};
 use std::collections::HashMap;
 use tauri::State;
 use uuid::Uuid;
@@ -35,6 +35,7 @@ pub fn add_clip(
                         start,
                         length,
                         notes: vec![],
+                        controllers: vec![],
                         content: crate::daw::model::ClipContent::Midi,
                         instrument_ids: vec![],
                         instrument_routes: HashMap::new(),
@@ -54,17 +55,19 @@ pub fn update_clip(
         track_id: Option<usize>,
         length: Option<MusicalLength>,
         notes: Option<Vec<Note>>,
+        controllers: Option<Vec<ControllerEvent>>,
         instrument_ids: Option<Vec<String>>,
         instrument_routes: Option<HashMap<String, usize>>,
 ) -> Result<(), String> {
         println!("ClipCommand: update_clip called for id: {}", id);
         println!(
-                "ClipCommand: Inputs - name: {:?}, start: {:?}, track_id: {:?}, length: {:?}, notes_count: {:?}, instrument_ids: {:?}, routes: {:?}",
+                "ClipCommand: Inputs - name: {:?}, start: {:?}, track_id: {:?}, length: {:?}, notes_count: {:?}, controllers_count: {:?}, instrument_ids: {:?}, routes: {:?}",
                 name,
                 start,
                 track_id,
                 length,
                 notes.as_ref().map(|n| n.len()),
+                controllers.as_ref().map(|c| c.len()),
                 instrument_ids,
                 instrument_routes
         );
@@ -85,8 +88,11 @@ pub fn update_clip(
                 let mut clips_to_update = Vec::new();
 
                 // 如果我们正在更新内容字段，我们将更新所有具有相同名称的 Clip
-                let is_content_update =
-                        notes.is_some() || length.is_some() || instrument_ids.is_some() || instrument_routes.is_some();
+                let is_content_update = notes.is_some()
+                        || controllers.is_some()
+                        || length.is_some()
+                        || instrument_ids.is_some()
+                        || instrument_routes.is_some();
 
                 if is_content_update || name.is_some() {
                         for (i, c) in clips.iter().enumerate() {
@@ -122,6 +128,9 @@ pub fn update_clip(
                                 if let Some(n) = &notes {
                                         clip.notes = n.clone();
                                 }
+                                if let Some(c) = &controllers {
+                                        clip.controllers = c.clone();
+                                }
                                 if let Some(i) = &instrument_ids {
                                         clip.instrument_ids = i.clone();
                                 }
@@ -165,6 +174,7 @@ pub fn copy_clip(
                         start: new_start,
                         length: original.length,
                         notes: original.notes,
+                        controllers: original.controllers,
                         content: original.content,
                         instrument_ids: original.instrument_ids,
                         instrument_routes: original.instrument_routes,
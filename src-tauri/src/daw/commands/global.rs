@@ -1,10 +1,15 @@
-use crate::audio::core::plugin::{NoteEvent, ParameterType, PluginEvent, PluginParameter};
-use crate::audio::plugins::mixer::level_meter::get_meter_levels;
+use crate::audio::core::plugin::{NoteEvent, ParamAddress, PluginEvent, PluginParameter};
+use crate::audio::core::smoothing::{SmoothingConfig, set_smoothing_config, smoothing_config};
+use crate::audio::engine::AudioEngine;
+use crate::audio::midi::mapping::MappingScope;
+use crate::audio::plugins::mixer::level_meter::{MeterReading, get_meter_levels, reset_meters};
+use crate::audio::plugins::mixer::track::{TRACK_PARAM_PAN, TRACK_PARAM_PAN_FRONT, TRACK_PARAM_VOLUME};
 /// 全局 Tauri 命令：播放控制、轨道/插件管理与项目保存/加载（通过 AppState/Engine 操作）
-use crate::daw::core::{create_audio_graph, rebuild_engine};
-use crate::daw::sequencer::{get_is_playing, get_playback_position};
+use crate::daw::core::{create_audio_graph, rebuild_engine, start_engine};
+use crate::daw::midi_control::external_clock_active;
+use crate::daw::sequencer::{get_is_playing, get_is_recording, get_output_latency, get_playback_position};
 use crate::daw::serialization::project::ProjectManager;
-use crate::daw::state::{AppState, MixerTrackData, PluginInstanceData};
+use crate::daw::state::{AppState, InsertSlotData, MixerTrackData, PluginInstanceData};
 use serde::Serialize;
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -23,11 +28,30 @@ pub fn get_playback_state() -> (bool, f64) {
         (get_is_playing(), get_playback_position())
 }
 
+/// 是否正在录音（含预备拍）
 #[tauri::command]
-pub fn get_meter_levels_cmd() -> HashMap<Uuid, f32> {
+pub fn get_recording_state() -> bool {
+        get_is_recording()
+}
+
+/// 音频图的总输出延迟（采样数，含插件延迟补偿）
+#[tauri::command]
+pub fn get_output_latency_cmd() -> u32 {
+        get_output_latency()
+}
+
+/// 各电平计的读数：每通道峰值 / 峰值保持 / RMS / 真峰值，以及瞬时、短期、积分响度与响度范围
+#[tauri::command]
+pub fn get_meter_levels_cmd() -> HashMap<Uuid, MeterReading> {
         get_meter_levels()
 }
 
+/// 复位峰值保持、真峰值与积分响度，重新开始测量
+#[tauri::command]
+pub fn reset_meters_cmd() {
+        reset_meters();
+}
+
 #[tauri::command]
 pub fn add_mixer_track(state: State<'_, AppState>) -> Result<(), String> {
         {
@@ -35,12 +59,16 @@ pub fn add_mixer_track(state: State<'_, AppState>) -> Result<(), String> {
                 let id = tracks.len();
                 tracks.push(MixerTrackData {
                         id,
+                        node_id: Uuid::new_v4(),
                         label: format!("Track {}", id + 1),
                         volume: 1.0,
                         pan: 0.0,
+                        pan_front: 1.0,
+                        layout: Default::default(),
                         mute: false,
                         solo: false,
                         meter_id: Some(Uuid::new_v4()), // 生成电平表 ID
+                        inserts: Vec::new(),
                 });
         }
         rebuild_engine(&state)?;
@@ -81,7 +109,7 @@ pub fn get_active_plugins(state: State<'_, AppState>) -> Result<Vec<PluginInstan
 }
 
 #[tauri::command]
-pub fn add_plugin_instance(state: State<'_, AppState>, name: String) -> Result<(), String> {
+pub fn add_plugin_instance(state: State<'_, AppState>, name: String, sandboxed: Option<bool>) -> Result<(), String> {
         {
                 let mut plugins = state.active_plugins.lock().map_err(|_| "Failed to lock plugins list")?;
 
@@ -90,6 +118,7 @@ pub fn add_plugin_instance(state: State<'_, AppState>, name: String) -> Result<(
                         name,
                         label: "New Instrument".to_string(),
                         routing_track_index: 0,
+                        sandboxed: sandboxed.unwrap_or(false),
                 });
         } // 解锁插件
 
@@ -133,61 +162,183 @@ pub fn toggle_audio(state: State<'_, AppState>) -> Result<bool, String> {
         } else {
                 let (root, _instances) = create_audio_graph(&state)?;
 
-                engine.start(root).map_err(|e| e.to_string())?;
+                start_engine(&state, &mut engine, root)?;
 
                 // 启动后发送测试音符以验证音频路径
                 engine.send_event(PluginEvent::Midi(NoteEvent::NoteOn {
+                        channel: 0,
                         note: 69,
                         velocity: 1.0,
+                        note_id: None,
                 })); // A4
 
                 Ok(true)
         }
 }
 
+/// 按稳定地址（节点 UUID + 参数 ID）发送参数变化；节点可以是乐器实例、混音轨道或插入效果
 #[tauri::command]
-pub fn update_parameter(state: State<'_, AppState>, param_id: u32, value: f32) -> Result<(), String> {
+pub fn set_node_parameter(state: State<'_, AppState>, address: ParamAddress, value: f32) -> Result<(), String> {
+        apply_node_parameter(&state, address, value)
+}
+
+/// 写入节点参数（供命令与 MIDI 控制映射共用）
+pub(crate) fn apply_node_parameter(state: &AppState, address: ParamAddress, value: f32) -> Result<(), String> {
+        // 混音轨道的音量/声像同时记录到轨道状态中，以便重建音频图或保存工程时保留
+        {
+                let mut tracks = state.mixer_tracks.lock().map_err(|_| "Failed to lock tracks")?;
+                if let Some(track) = tracks.iter_mut().find(|t| t.node_id == address.node) {
+                        match address.param_id {
+                                TRACK_PARAM_VOLUME => track.volume = value,
+                                TRACK_PARAM_PAN => track.pan = value,
+                                TRACK_PARAM_PAN_FRONT => track.pan_front = value,
+                                _ => {}
+                        }
+                }
+        }
+
         // 发送参数更新事件（若音频引擎运行）
         let engine = state.audio_engine.lock().map_err(|_| "Failed to lock audio engine")?;
 
         if engine.is_running() {
-                engine.send_event(PluginEvent::Parameter { id: param_id, value });
+                engine.send_event(PluginEvent::NodeParameter { address, value });
         }
         Ok(())
 }
 
+/// 兼容旧接口：参数 ID 采用旧的编码（`轨道 * 100 + 参数` / `10000 + 乐器 * 100 + 参数`），
+/// 在此转换为节点地址后按 `set_node_parameter` 发送。新代码应直接使用 `set_node_parameter`。
+#[tauri::command]
+pub fn update_parameter(state: State<'_, AppState>, param_id: u32, value: f32) -> Result<(), String> {
+        let address = legacy_param_address(&state, param_id)?;
+        set_node_parameter(state, address, value)
+}
+
+// 旧编码中的乐器下标按 active_plugins 的顺序解释
+fn legacy_param_address(state: &State<'_, AppState>, id: u32) -> Result<ParamAddress, String> {
+        if id >= 10000 {
+                let inst_idx = ((id - 10000) / 100) as usize;
+                let plugins = state.active_plugins.lock().map_err(|_| "Failed to lock plugins list")?;
+                let plugin = plugins.get(inst_idx).ok_or("Instrument not found")?;
+                let node = Uuid::parse_str(&plugin.id).map_err(|e| e.to_string())?;
+                return Ok(ParamAddress::new(node, (id - 10000) % 100));
+        }
+
+        let tracks = state.mixer_tracks.lock().map_err(|_| "Failed to lock tracks")?;
+        let track = tracks.get((id / 100) as usize).ok_or("Mixer track not found")?;
+        Ok(ParamAddress::new(track.node_id, id % 100))
+}
+
+/// 宿主参数平滑设置（推子/声像渐变时间与曲线，以及是否对插件参数生成斜坡）
+#[tauri::command]
+pub fn get_parameter_smoothing() -> SmoothingConfig {
+        smoothing_config()
+}
+
+#[tauri::command]
+pub fn set_parameter_smoothing(config: SmoothingConfig) {
+        set_smoothing_config(config);
+}
+
 #[derive(Serialize)]
 pub struct ParamsWithValues {
         pub params: Vec<crate::audio::core::plugin::PluginParameter>,
         pub values: Vec<f32>,
+        /// 当前值的显示文本（例如 "-6.0 dB"），与 values 一一对应
+        pub texts: Vec<String>,
 }
 
-#[tauri::command]
-pub fn get_instance_parameters(
-        state: State<'_, AppState>,
-        instance_id: String,
-) -> Result<Option<ParamsWithValues>, String> {
+pub(crate) type InstanceRef = std::sync::Arc<std::sync::Mutex<Box<dyn crate::audio::core::plugin::Plugin>>>;
+
+// 取出实例引用后立即释放实例表锁（重建音频图时按 插件管理器 -> 实例表 的顺序加锁）
+pub(crate) fn find_instance(state: &State<'_, AppState>, instance_id: &str) -> Result<Option<InstanceRef>, String> {
         let instances = state
                 .plugin_instances
                 .lock()
                 .map_err(|_| "Failed to lock plugin instances")?;
+        Ok(instances.get(instance_id).cloned())
+}
 
-        if let Some(inst_arc) = instances.get(&instance_id) {
-                if let Ok(inst) = inst_arc.lock() {
-                        let mut params = inst.get_parameters();
-                        if params.is_empty() {
-                                if let Some(fallback) = default_params_for_id(&inst.info().unique_id) {
-                                        params = fallback;
-                                }
-                        }
-                        let mut values = Vec::new();
-                        for p in &params {
-                                values.push(inst.get_param(p.id));
-                        }
-                        return Ok(Some(ParamsWithValues { params, values }));
-                }
+// 实例的参数列表：插件自身未报告参数时回退到 manifest 中声明的 `parameters`
+pub(crate) fn instance_parameters(
+        state: &State<'_, AppState>,
+        inst_arc: &InstanceRef,
+) -> Result<Vec<PluginParameter>, String> {
+        let (params, unique_id) = {
+                let inst = inst_arc.lock().map_err(|_| "Failed to lock plugin instance")?;
+                (inst.get_parameters(), inst.info().unique_id)
+        };
+        if !params.is_empty() {
+                return Ok(params);
         }
-        Ok(None)
+        let manager = state
+                .plugin_manager
+                .lock()
+                .map_err(|_| "Failed to lock plugin manager")?;
+        Ok(manager
+                .get_plugin_info(&unique_id)
+                .and_then(|info| info.parameters)
+                .unwrap_or_default())
+}
+
+// 插件自带的格式化优先，否则按参数元数据格式化
+fn param_display_text(inst: &dyn crate::audio::core::plugin::Plugin, param: &PluginParameter, value: f32) -> String {
+        inst.param_value_to_text(param.id, value)
+                .unwrap_or_else(|| param.format_value(value))
+}
+
+#[tauri::command]
+pub fn get_instance_parameters(
+        state: State<'_, AppState>,
+        instance_id: String,
+) -> Result<Option<ParamsWithValues>, String> {
+        let Some(inst_arc) = find_instance(&state, &instance_id)? else {
+                return Ok(None);
+        };
+        let params = instance_parameters(&state, &inst_arc)?;
+        let inst = inst_arc.lock().map_err(|_| "Failed to lock plugin instance")?;
+        let values: Vec<f32> = params.iter().map(|p| inst.get_param(p.id)).collect();
+        let texts = params
+                .iter()
+                .zip(values.iter())
+                .map(|(p, v)| param_display_text(inst.as_ref(), p, *v))
+                .collect();
+        Ok(Some(ParamsWithValues { params, values, texts }))
+}
+
+/// 把参数值格式化为显示文本（例如 "1.2 kHz"）
+#[tauri::command]
+pub fn format_instance_parameter(
+        state: State<'_, AppState>,
+        instance_id: String,
+        param_id: u32,
+        value: f32,
+) -> Result<String, String> {
+        let inst_arc = find_instance(&state, &instance_id)?.ok_or("Instance not found")?;
+        let params = instance_parameters(&state, &inst_arc)?;
+        let param = params.iter().find(|p| p.id == param_id).ok_or("Parameter not found")?;
+        let inst = inst_arc.lock().map_err(|_| "Failed to lock plugin instance")?;
+        Ok(param_display_text(inst.as_ref(), param, value))
+}
+
+/// 把用户输入的文本（例如 "-6 dB"、"1.2k"）解析为参数值
+#[tauri::command]
+pub fn parse_instance_parameter(
+        state: State<'_, AppState>,
+        instance_id: String,
+        param_id: u32,
+        text: String,
+) -> Result<f32, String> {
+        let inst_arc = find_instance(&state, &instance_id)?.ok_or("Instance not found")?;
+        let params = instance_parameters(&state, &inst_arc)?;
+        let param = params.iter().find(|p| p.id == param_id).ok_or("Parameter not found")?;
+        let inst = inst_arc.lock().map_err(|_| "Failed to lock plugin instance")?;
+        // 插件自身的解析结果同样限制到参数范围并对齐步进，与宿主解析的结果一致
+        inst.param_text_to_value(param_id, &text)
+                .or_else(|| param.parse_value(&text))
+                .filter(|value| value.is_finite())
+                .map(|value| param.quantize(value))
+                .ok_or_else(|| format!("Invalid value: {}", text))
 }
 
 #[tauri::command]
@@ -221,29 +372,6 @@ pub fn set_instance_parameter(
         Err("Instance not found".to_string())
 }
 
-fn default_params_for_id(id: &str) -> Option<Vec<PluginParameter>> {
-        match id {
-                "com.mydaw.gainfader" => Some(vec![PluginParameter {
-                        id: 0,
-                        name: "Gain (dB)".to_string(),
-                        min_value: -60.0,
-                        max_value: 12.0,
-                        default_value: 0.0,
-                        value_type: ParameterType::Float,
-                }]),
-                "com.mydaw.simplesynth" => Some(vec![PluginParameter {
-                        id: 0,
-                        name: "Frequency".to_string(),
-                        min_value: 20.0,
-                        max_value: 2000.0,
-                        default_value: 440.0,
-                        value_type: ParameterType::Float,
-                }]),
-                // Wave generator currently exposes no parameters
-                _ => None,
-        }
-}
-
 #[tauri::command]
 pub fn set_instrument_routing(state: State<'_, AppState>, inst_index: usize, track_index: usize) -> Result<(), String> {
         {
@@ -257,16 +385,28 @@ pub fn set_instrument_routing(state: State<'_, AppState>, inst_index: usize, tra
         Ok(())
 }
 
+// 从属模式下走带由外部 MIDI Clock 主机控制，本地的播放 / 暂停 / 停止 / 定位不生效
+fn defer_to_external_clock() -> Result<(), String> {
+        if external_clock_active() {
+                return Err("Transport is controlled by external MIDI clock".to_string());
+        }
+        Ok(())
+}
+
+/// 引擎未运行时构建音频图并启动（`play` / `record` 与跟随外部 MIDI Clock 时的主线程启动共用）
+pub(crate) fn ensure_engine_running(state: &State<'_, AppState>, engine: &mut AudioEngine) -> Result<(), String> {
+        if engine.is_running() {
+                return Ok(());
+        }
+        let (root, _instances) = create_audio_graph(state)?;
+        start_engine(state, engine, root)
+}
+
 #[tauri::command]
 pub fn play(state: State<'_, AppState>) -> Result<(), String> {
+        defer_to_external_clock()?;
         let mut engine = state.audio_engine.lock().map_err(|_| "Failed to lock audio engine")?;
-
-        if !engine.is_running() {
-                // 启动引擎
-                let (root, _instances) = create_audio_graph(&state)?;
-
-                engine.start(root).map_err(|e| e.to_string())?;
-        }
+        ensure_engine_running(&state, &mut engine)?;
 
         engine.send_event(PluginEvent::Transport {
                 playing: true,
@@ -275,8 +415,20 @@ pub fn play(state: State<'_, AppState>) -> Result<(), String> {
         });
         Ok(())
 }
+/// 开始录音：先播放节拍器设置的预备小节，然后从当前位置开始走带
+#[tauri::command]
+pub fn record(state: State<'_, AppState>) -> Result<(), String> {
+        defer_to_external_clock()?;
+        let mut engine = state.audio_engine.lock().map_err(|_| "Failed to lock audio engine")?;
+        ensure_engine_running(&state, &mut engine)?;
+
+        engine.send_event(PluginEvent::Record { recording: true });
+        Ok(())
+}
+
 #[tauri::command]
 pub fn pause(state: State<'_, AppState>) -> Result<(), String> {
+        defer_to_external_clock()?;
         let engine = state.audio_engine.lock().map_err(|_| "Failed to lock audio engine")?;
 
         if engine.is_running() {
@@ -291,6 +443,7 @@ pub fn pause(state: State<'_, AppState>) -> Result<(), String> {
 
 #[tauri::command]
 pub fn stop(state: State<'_, AppState>) -> Result<(), String> {
+        defer_to_external_clock()?;
         let engine = state.audio_engine.lock().map_err(|_| "Failed to lock audio engine")?;
 
         if engine.is_running() {
@@ -305,6 +458,7 @@ pub fn stop(state: State<'_, AppState>) -> Result<(), String> {
 
 #[tauri::command]
 pub fn seek(state: State<'_, AppState>, position: f64) -> Result<(), String> {
+        defer_to_external_clock()?;
         let engine = state.audio_engine.lock().map_err(|_| "Failed to lock audio engine")?;
 
         if engine.is_running() {
@@ -319,6 +473,19 @@ pub fn seek(state: State<'_, AppState>, position: f64) -> Result<(), String> {
         Ok(())
 }
 
+/// 释放所有发声的音符；`panic` 为 true 时还向每个乐器的所有通道发送 All Sound Off / All Notes Off
+#[tauri::command]
+pub fn all_notes_off(state: State<'_, AppState>, panic: Option<bool>) -> Result<(), String> {
+        let engine = state.audio_engine.lock().map_err(|_| "Failed to lock audio engine")?;
+
+        if engine.is_running() {
+                engine.send_event(PluginEvent::AllNotesOff {
+                        panic: panic.unwrap_or(false),
+                });
+        }
+        Ok(())
+}
+
 #[tauri::command]
 pub fn save_project_cmd(state: State<'_, AppState>, path: String) -> Result<(), String> {
         // 封装 ProjectManager 保存入口
@@ -360,6 +527,35 @@ pub fn load_project_cmd(state: State<'_, AppState>, path: String) -> Result<(),
         }
 
         {
+                // 片段内相对时间（秒）-> 小节 / 拍 / 十六分音符 / tick
+                const PPQ: f64 = 960.0;
+                let seconds_per_beat = 60.0 / schema.settings.bpm;
+                let (num, _den) = schema.settings.time_signature;
+                let position_at = |seconds: f64| {
+                        let total_ticks = (seconds / seconds_per_beat * PPQ).round() as u64;
+
+                        let ticks_per_beat = PPQ as u64;
+                        let ticks_per_bar = ticks_per_beat * num as u64;
+                        let ticks_per_16th = ticks_per_beat / 4;
+
+                        let bar = (total_ticks / ticks_per_bar) + 1;
+                        let rem_bar = total_ticks % ticks_per_bar;
+
+                        let beat = (rem_bar / ticks_per_beat) + 1;
+                        let rem_beat = rem_bar % ticks_per_beat;
+
+                        let sixteenth = (rem_beat / ticks_per_16th) + 1;
+                        let tick = (rem_beat % ticks_per_16th) as u32;
+
+                        crate::daw::model::Position {
+                                bar: bar as u32,
+                                beat: beat as u32,
+                                sixteenth: sixteenth as u32,
+                                tick,
+                                time: seconds,
+                        }
+                };
+
                 let mut clips = state.clips.lock().map_err(|_| "Lock error")?;
                 clips.clear();
                 for t in schema.tracks.iter() {
@@ -398,40 +594,12 @@ pub fn load_project_cmd(state: State<'_, AppState>, path: String) -> Result<(),
                                         notes: c.notes
                                                 .iter()
                                                 .map(|n| {
-                                                        let bpm = schema.settings.bpm;
-                                                        let (num, _den) = schema.settings.time_signature;
-                                                        let ppq = 960.0;
-
-                                                        let seconds_per_beat = 60.0 / bpm;
-                                                        let total_ticks =
-                                                                (n.start / seconds_per_beat * ppq).round() as u64;
-
-                                                        let ticks_per_beat = ppq as u64;
-                                                        let ticks_per_bar = ticks_per_beat * num as u64;
-                                                        let ticks_per_16th = ticks_per_beat / 4;
-
-                                                        let bar = (total_ticks / ticks_per_bar) + 1;
-                                                        let rem_bar = total_ticks % ticks_per_bar;
-
-                                                        let beat = (rem_bar / ticks_per_beat) + 1;
-                                                        let rem_beat = rem_bar % ticks_per_beat;
-
-                                                        let sixteenth = (rem_beat / ticks_per_16th) + 1;
-                                                        let tick = (rem_beat % ticks_per_16th) as u32;
-
                                                         let duration_ticks =
-                                                                (n.duration / seconds_per_beat * ppq).round() as u64;
-
+                                                                (n.duration / seconds_per_beat * PPQ).round() as u64;
                                                         crate::daw::model::Note {
                                                                 id: uuid::Uuid::new_v4().to_string(),
                                                                 note: n.note,
-                                                                start: crate::daw::model::Position {
-                                                                        bar: bar as u32,
-                                                                        beat: beat as u32,
-                                                                        sixteenth: sixteenth as u32,
-                                                                        tick,
-                                                                        time: n.start,
-                                                                },
+                                                                start: position_at(n.start),
                                                                 duration: crate::daw::model::MusicalLength {
                                                                         bars: 0,
                                                                         beats: 0,
@@ -441,9 +609,22 @@ pub fn load_project_cmd(state: State<'_, AppState>, path: String) -> Result<(),
                                                                         seconds: n.duration,
                                                                 },
                                                                 velocity: n.velocity,
+                                                                channel: n.channel,
+                                                                expressions: n.expressions.clone(),
                                                         }
                                                 })
                                                 .collect(),
+                                        controllers: c
+                                                .controllers
+                                                .iter()
+                                                .map(|e| crate::daw::model::ControllerEvent {
+                                                        id: uuid::Uuid::new_v4().to_string(),
+                                                        kind: e.kind,
+                                                        channel: e.channel,
+                                                        time: position_at(e.time),
+                                                        value: e.value,
+                                                })
+                                                .collect(),
                                         content,
                                         instrument_ids: c.instrument_ids.clone(),
                                         instrument_routes: c.instrument_routes.clone(),
@@ -462,6 +643,67 @@ pub fn load_project_cmd(state: State<'_, AppState>, path: String) -> Result<(),
                                 name: p.name.clone(),
                                 label: p.label.clone(),
                                 routing_track_index: p.routing_track_index,
+                                sandboxed: p.sandboxed,
+                        });
+                }
+        }
+
+        // MIDI 控制映射：替换为工程中的映射，保留全局映射
+        {
+                let mut midi = state.midi_control.lock().map_err(|_| "Lock error")?;
+                midi.mappings.retain(|m| m.scope == MappingScope::Global);
+                midi.mappings.extend(schema.midi_mappings.iter().cloned());
+        }
+
+        {
+                let mut automation = state.automation.lock().map_err(|_| "Lock error")?;
+                *automation = schema.automation.clone();
+        }
+
+        {
+                let (numerator, denominator) = schema.settings.time_signature;
+                let mut time_signature = state.time_signature.lock().map_err(|_| "Lock error")?;
+                *time_signature = crate::daw::model::TimeSignature { numerator, denominator };
+                let mut processing = state.processing.lock().map_err(|_| "Lock error")?;
+                *processing = crate::daw::model::ProcessingSettings {
+                        sample_rate: Some(schema.settings.sample_rate).filter(|rate| *rate > 0),
+                        double_precision: schema.settings.double_precision,
+                };
+        }
+
+        // 恢复混音轨道及其插入效果链；保存混音轨道之前的旧工程没有该字段，此时保留当前的混音布局
+        if !schema.mixer.tracks.is_empty() {
+                let mut strips = schema.mixer.tracks.clone();
+                strips.sort_by_key(|m| m.id);
+
+                let mut mixer_tracks = state.mixer_tracks.lock().map_err(|_| "Lock error")?;
+                mixer_tracks.clear();
+                for (i, m) in strips.iter().enumerate() {
+                        let inserts = m
+                                .plugin_instances
+                                .iter()
+                                .filter_map(|id| schema.mixer.inserts.iter().find(|ins| &ins.id == id))
+                                .map(|ins| InsertSlotData {
+                                        instance_id: ins.id.clone(),
+                                        name: ins.name.clone(),
+                                        label: ins.label.clone(),
+                                        bypass: ins.bypass,
+                                        post_fader: ins.post_fader,
+                                        sandboxed: ins.sandboxed,
+                                })
+                                .collect();
+                        mixer_tracks.push(MixerTrackData {
+                                id: i,
+                                node_id: m.node_id.unwrap_or_else(Uuid::new_v4),
+                                label: m.label.clone(),
+                                volume: m.volume,
+                                pan: m.pan,
+                                pan_front: m.pan_front,
+                                layout: m.layout,
+                                mute: m.mute,
+                                solo: m.solo,
+                                meter_id: Some(Uuid::new_v4()),
+                                inserts,
                         });
                 }
         }
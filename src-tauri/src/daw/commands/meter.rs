@@ -0,0 +1,36 @@
+use crate::audio::plugins::mixer::level_meter::{
+        MeterBallistics, clear_meter_clips, meter_ballistics, set_meter_ballistics,
+};
+use crate::daw::settings::{load_json_setting, save_json_setting};
+use tauri::AppHandle;
+
+// 电平表命令。动态特性（峰值保持与回落速度）保存在应用数据目录的 `meters.json`，启动时恢复；
+// 读数由 `meter_stream` 以固定频率推送给前端。
+
+/// 启动时恢复电平表的动态特性
+pub fn load_meter_settings(app: &AppHandle) {
+        if let Some(ballistics) = load_json_setting::<MeterBallistics>(app, "meters.json") {
+                set_meter_ballistics(ballistics);
+        }
+}
+
+#[tauri::command]
+pub fn get_meter_ballistics_cmd() -> MeterBallistics {
+        meter_ballistics()
+}
+
+#[tauri::command]
+pub fn set_meter_ballistics_cmd(app: AppHandle, ballistics: MeterBallistics) -> Result<(), String> {
+        if !ballistics.peak_hold.is_finite() || !ballistics.fall_rate.is_finite() {
+                return Err("Invalid meter ballistics".to_string());
+        }
+        set_meter_ballistics(ballistics);
+        save_json_setting(&app, "meters.json", &ballistics);
+        Ok(())
+}
+
+/// 清除所有电平计的削波指示（不影响响度测量）
+#[tauri::command]
+pub fn clear_meter_clips_cmd() {
+        clear_meter_clips();
+}
@@ -0,0 +1,77 @@
+use crate::audio::core::plugin::PluginEvent;
+use crate::audio::plugins::mixer::metronome::MetronomeSettings;
+use crate::daw::model::TimeSignature;
+use crate::daw::settings::{load_json_setting, save_json_setting};
+use crate::daw::state::AppState;
+use tauri::{AppHandle, Manager, State};
+
+// 节拍器与拍号命令。节拍器设置保存在应用数据目录的 `metronome.json`，启动时恢复；
+// 拍号属于工程，随工程保存。两者都立即发送给运行中的引擎，并在重建音频图时重新应用。
+
+/// 启动时恢复节拍器设置（引擎尚未启动）
+pub fn load_metronome_settings(app: &AppHandle) {
+        let Some(settings) = load_json_setting::<MetronomeSettings>(app, "metronome.json") else {
+                return;
+        };
+        let state = app.state::<AppState>();
+        if let Ok(mut metronome) = state.metronome.lock() {
+                *metronome = settings;
+        }
+}
+
+#[tauri::command]
+pub fn get_metronome(state: State<'_, AppState>) -> Result<MetronomeSettings, String> {
+        let metronome = state.metronome.lock().map_err(|_| "Failed to lock metronome")?;
+        Ok(metronome.clone())
+}
+
+#[tauri::command]
+pub fn set_metronome(app: AppHandle, state: State<'_, AppState>, settings: MetronomeSettings) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&settings.level) {
+                return Err(format!("Invalid metronome level: {}", settings.level));
+        }
+        {
+                let mut metronome = state.metronome.lock().map_err(|_| "Failed to lock metronome")?;
+                *metronome = settings.clone();
+        }
+        {
+                let engine = state.audio_engine.lock().map_err(|_| "Failed to lock audio engine")?;
+                if engine.is_running() {
+                        engine.send_event(PluginEvent::Metronome(settings.clone()));
+                }
+        }
+        save_json_setting(&app, "metronome.json", &settings);
+        Ok(())
+}
+
+#[tauri::command]
+pub fn get_time_signature(state: State<'_, AppState>) -> Result<TimeSignature, String> {
+        let time_signature = state
+                .time_signature
+                .lock()
+                .map_err(|_| "Failed to lock time signature")?;
+        Ok(time_signature.clone())
+}
+
+/// 设置工程拍号；分母须为 2 的幂（1..=32）
+#[tauri::command]
+pub fn set_time_signature(state: State<'_, AppState>, numerator: u32, denominator: u32) -> Result<(), String> {
+        if !(1..=64).contains(&numerator) || !denominator.is_power_of_two() || denominator > 32 {
+                return Err(format!(
+                        "Invalid time signature: {}/{}",
+                        numerator, denominator
+                ));
+        }
+        {
+                let mut time_signature = state
+                        .time_signature
+                        .lock()
+                        .map_err(|_| "Failed to lock time signature")?;
+                *time_signature = TimeSignature { numerator, denominator };
+        }
+        let engine = state.audio_engine.lock().map_err(|_| "Failed to lock audio engine")?;
+        if engine.is_running() {
+                engine.send_event(PluginEvent::TimeSignature { numerator, denominator });
+        }
+        Ok(())
+}
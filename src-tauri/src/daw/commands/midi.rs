@@ -0,0 +1,175 @@
+use crate::audio::midi::clock::MidiClockInputSettings;
+use crate::audio::midi::mapping::{MappingScope, MidiMapping};
+use crate::audio::midi::sync::{self, MidiSyncSettings};
+use crate::audio::midi::{MidiPortInfo, list_input_ports, list_output_ports};
+use crate::daw::midi_control::{LearnRequest, open_input, save_global_settings, set_clock_input};
+use crate::daw::state::AppState;
+use serde::Serialize;
+use tauri::State;
+use uuid::Uuid;
+
+// MIDI 控制命令：输入端口的打开/关闭、MIDI 学习与映射的增删改，以及走带同步输出 / MIDI Clock 从属模式设置。
+// 全局映射、打开的端口或同步设置变化时立即写回全局设置；工程映射随工程保存。
+
+#[derive(Serialize)]
+pub struct MidiInputStatus {
+        pub id: String,
+        pub name: String,
+        pub open: bool,
+}
+
+#[tauri::command]
+pub fn list_midi_inputs(state: State<'_, AppState>) -> Result<Vec<MidiInputStatus>, String> {
+        let midi = state.midi_control.lock().map_err(|_| "Failed to lock MIDI state")?;
+        Ok(list_input_ports()
+                .into_iter()
+                .map(|p| MidiInputStatus {
+                        open: midi.inputs.contains_key(&p.id),
+                        id: p.id,
+                        name: p.name,
+                })
+                .collect())
+}
+
+#[tauri::command]
+pub fn open_midi_input(app: tauri::AppHandle, state: State<'_, AppState>, port_id: String) -> Result<(), String> {
+        let mut midi = state.midi_control.lock().map_err(|_| "Failed to lock MIDI state")?;
+        open_input(&app, &mut midi, &port_id)?;
+        save_global_settings(&app, &midi);
+        Ok(())
+}
+
+#[tauri::command]
+pub fn close_midi_input(app: tauri::AppHandle, state: State<'_, AppState>, port_id: String) -> Result<(), String> {
+        let connection = {
+                let mut midi = state.midi_control.lock().map_err(|_| "Failed to lock MIDI state")?;
+                let connection = midi.inputs.remove(&port_id);
+                save_global_settings(&app, &midi);
+                connection
+        };
+        // 在释放锁之后断开：断开时会等待输入线程退出，而输入线程处理消息时需要该锁
+        drop(connection);
+        Ok(())
+}
+
+#[tauri::command]
+pub fn get_midi_mappings(state: State<'_, AppState>) -> Result<Vec<MidiMapping>, String> {
+        let midi = state.midi_control.lock().map_err(|_| "Failed to lock MIDI state")?;
+        Ok(midi.mappings.clone())
+}
+
+/// 进入学习状态：下一条控制器消息（CC / 弯音 / 触后）将绑定到 `request.target`，
+/// 完成后发出 `midi-learned` 事件（载荷为新映射）
+#[tauri::command]
+pub fn start_midi_learn(state: State<'_, AppState>, request: LearnRequest) -> Result<(), String> {
+        let mut midi = state.midi_control.lock().map_err(|_| "Failed to lock MIDI state")?;
+        midi.learn = Some(request);
+        Ok(())
+}
+
+#[tauri::command]
+pub fn cancel_midi_learn(state: State<'_, AppState>) -> Result<(), String> {
+        let mut midi = state.midi_control.lock().map_err(|_| "Failed to lock MIDI state")?;
+        midi.learn = None;
+        Ok(())
+}
+
+/// 手动添加映射（不经过学习）；返回映射 ID
+#[tauri::command]
+pub fn add_midi_mapping(
+        app: tauri::AppHandle,
+        state: State<'_, AppState>,
+        mapping: MidiMapping,
+) -> Result<Uuid, String> {
+        let mut midi = state.midi_control.lock().map_err(|_| "Failed to lock MIDI state")?;
+        let mut mapping = mapping;
+        mapping.id = Uuid::new_v4();
+        let id = mapping.id;
+        let global = mapping.scope == MappingScope::Global;
+        midi.mappings.push(mapping);
+        if global {
+                save_global_settings(&app, &midi);
+        }
+        Ok(id)
+}
+
+/// 修改映射的来源、目标、范围、接管模式或保存位置（按 ID 匹配）
+#[tauri::command]
+pub fn update_midi_mapping(
+        app: tauri::AppHandle,
+        state: State<'_, AppState>,
+        mapping: MidiMapping,
+) -> Result<(), String> {
+        let mut midi = state.midi_control.lock().map_err(|_| "Failed to lock MIDI state")?;
+        let existing = midi
+                .mappings
+                .iter_mut()
+                .find(|m| m.id == mapping.id)
+                .ok_or("MIDI mapping not found")?;
+        let was_global = existing.scope == MappingScope::Global;
+        *existing = mapping;
+        existing.reset_takeover();
+        if was_global || existing.scope == MappingScope::Global {
+                save_global_settings(&app, &midi);
+        }
+        Ok(())
+}
+
+#[tauri::command]
+pub fn remove_midi_mapping(app: tauri::AppHandle, state: State<'_, AppState>, id: Uuid) -> Result<(), String> {
+        let mut midi = state.midi_control.lock().map_err(|_| "Failed to lock MIDI state")?;
+        let index = midi
+                .mappings
+                .iter()
+                .position(|m| m.id == id)
+                .ok_or("MIDI mapping not found")?;
+        let removed = midi.mappings.remove(index);
+        if removed.scope == MappingScope::Global {
+                save_global_settings(&app, &midi);
+        }
+        Ok(())
+}
+
+#[tauri::command]
+pub fn list_midi_outputs() -> Vec<MidiPortInfo> {
+        list_output_ports()
+}
+
+#[tauri::command]
+pub fn get_midi_sync_settings(state: State<'_, AppState>) -> Result<MidiSyncSettings, String> {
+        let midi = state.midi_control.lock().map_err(|_| "Failed to lock MIDI state")?;
+        Ok(midi.sync.clone())
+}
+
+/// 设置走带同步输出（MIDI Clock / MTC）：`port` 为输出端口名，为空或两种输出都关闭时停止发送
+#[tauri::command]
+pub fn set_midi_sync_settings(
+        app: tauri::AppHandle,
+        state: State<'_, AppState>,
+        settings: MidiSyncSettings,
+) -> Result<(), String> {
+        sync::configure(&settings)?;
+        let mut midi = state.midi_control.lock().map_err(|_| "Failed to lock MIDI state")?;
+        midi.sync = settings;
+        save_global_settings(&app, &midi);
+        Ok(())
+}
+
+#[tauri::command]
+pub fn get_midi_clock_input(state: State<'_, AppState>) -> Result<MidiClockInputSettings, String> {
+        let midi = state.midi_control.lock().map_err(|_| "Failed to lock MIDI state")?;
+        Ok(midi.clock_input.clone())
+}
+
+/// 设置 MIDI Clock 从属模式：启用后由外部主机的 Clock / Start / Stop / Song Position Pointer 驱动走带
+#[tauri::command]
+pub fn set_midi_clock_input(
+        app: tauri::AppHandle,
+        state: State<'_, AppState>,
+        settings: MidiClockInputSettings,
+) -> Result<(), String> {
+        let mut midi = state.midi_control.lock().map_err(|_| "Failed to lock MIDI state")?;
+        set_clock_input(&mut midi, settings);
+        save_global_settings(&app, &midi);
+        Ok(())
+}
@@ -0,0 +1,169 @@
+use crate::audio::core::channel_layout::ChannelLayout;
+use crate::audio::core::plugin::PluginEvent;
+use crate::daw::core::rebuild_engine;
+use crate::daw::state::{AppState, InsertSlotData};
+use std::sync::{Arc, Mutex};
+use tauri::State;
+use uuid::Uuid;
+
+/// 混音轨道插入效果命令：添加 / 删除 / 排序 / 旁通 / 推子前后切换。
+/// 插入实例登记在 plugin_instances 中，参数读写沿用乐器的实例 ID 接口
+/// （`get_instance_parameters` / `set_instance_parameter`）。
+
+#[tauri::command]
+pub fn get_mixer_inserts(state: State<'_, AppState>, track_index: usize) -> Result<Vec<InsertSlotData>, String> {
+        let tracks = state.mixer_tracks.lock().map_err(|_| "Failed to lock tracks")?;
+        let track = tracks.get(track_index).ok_or("Mixer track not found")?;
+        Ok(track.inserts.clone())
+}
+
+#[tauri::command]
+pub fn add_mixer_insert(
+        state: State<'_, AppState>,
+        track_index: usize,
+        name: String,
+        post_fader: Option<bool>,
+        sandboxed: Option<bool>,
+) -> Result<String, String> {
+        let sandboxed = sandboxed.unwrap_or(false);
+        {
+                let tracks = state.mixer_tracks.lock().map_err(|_| "Failed to lock tracks")?;
+                if track_index >= tracks.len() {
+                        return Err("Mixer track not found".to_string());
+                }
+        }
+
+        // 先创建实例：既能尽早报告缺失的插件，也让重建音频图时直接复用该实例
+        let plugin = {
+                let manager = state
+                        .plugin_manager
+                        .lock()
+                        .map_err(|_| "Failed to lock plugin manager")?;
+                manager.create_instance(&name, sandboxed)
+                        .ok_or("Failed to create plugin")?
+        };
+        if !plugin.get_io_config().has_audio_input() {
+                return Err("Plugin has no audio input and cannot be used as an insert".to_string());
+        }
+        let label = plugin.info().name;
+
+        let instance_id = Uuid::new_v4().to_string();
+        {
+                let mut instances = state
+                        .plugin_instances
+                        .lock()
+                        .map_err(|_| "Failed to lock plugin instances")?;
+                instances.insert(instance_id.clone(), Arc::new(Mutex::new(plugin)));
+        }
+        {
+                let mut tracks = state.mixer_tracks.lock().map_err(|_| "Failed to lock tracks")?;
+                let track = tracks.get_mut(track_index).ok_or("Mixer track not found")?;
+                track.inserts.push(InsertSlotData {
+                        instance_id: instance_id.clone(),
+                        name,
+                        label,
+                        bypass: false,
+                        post_fader: post_fader.unwrap_or(false),
+                        sandboxed,
+                });
+        }
+        rebuild_engine(&state)?;
+        Ok(instance_id)
+}
+
+#[tauri::command]
+pub fn remove_mixer_insert(state: State<'_, AppState>, track_index: usize, slot: usize) -> Result<(), String> {
+        {
+                let mut tracks = state.mixer_tracks.lock().map_err(|_| "Failed to lock tracks")?;
+                let track = tracks.get_mut(track_index).ok_or("Mixer track not found")?;
+                if slot >= track.inserts.len() {
+                        return Err("Insert slot not found".to_string());
+                }
+                track.inserts.remove(slot);
+        }
+        // 重建后 plugin_instances 只包含仍被引用的实例
+        rebuild_engine(&state)?;
+        Ok(())
+}
+
+#[tauri::command]
+pub fn move_mixer_insert(state: State<'_, AppState>, track_index: usize, from: usize, to: usize) -> Result<(), String> {
+        {
+                let mut tracks = state.mixer_tracks.lock().map_err(|_| "Failed to lock tracks")?;
+                let track = tracks.get_mut(track_index).ok_or("Mixer track not found")?;
+                if from >= track.inserts.len() {
+                        return Err("Insert slot not found".to_string());
+                }
+                let insert = track.inserts.remove(from);
+                let to = to.min(track.inserts.len());
+                track.inserts.insert(to, insert);
+        }
+        rebuild_engine(&state)?;
+        Ok(())
+}
+
+#[tauri::command]
+pub fn set_mixer_insert_bypass(
+        state: State<'_, AppState>,
+        track_index: usize,
+        slot: usize,
+        bypass: bool,
+) -> Result<(), String> {
+        let insert = {
+                let mut tracks = state.mixer_tracks.lock().map_err(|_| "Failed to lock tracks")?;
+                let track = tracks.get_mut(track_index).ok_or("Mixer track not found")?;
+                let insert = track.inserts.get_mut(slot).ok_or("Insert slot not found")?;
+                insert.bypass = bypass;
+                insert.clone()
+        };
+        send_insert_state(&state, &insert)
+}
+
+#[tauri::command]
+pub fn set_mixer_insert_placement(
+        state: State<'_, AppState>,
+        track_index: usize,
+        slot: usize,
+        post_fader: bool,
+) -> Result<(), String> {
+        let insert = {
+                let mut tracks = state.mixer_tracks.lock().map_err(|_| "Failed to lock tracks")?;
+                let track = tracks.get_mut(track_index).ok_or("Mixer track not found")?;
+                let insert = track.inserts.get_mut(slot).ok_or("Insert slot not found")?;
+                insert.post_fader = post_fader;
+                insert.clone()
+        };
+        send_insert_state(&state, &insert)
+}
+
+// 旁通与推子前/后切换直接发给运行中的混音台，不重建音频图（避免音频中断）；
+// 引擎未运行时下次构建音频图会从 mixer_tracks 读取
+fn send_insert_state(state: &State<'_, AppState>, insert: &InsertSlotData) -> Result<(), String> {
+        let node = Uuid::parse_str(&insert.instance_id).map_err(|_| "Invalid insert instance ID")?;
+        let engine = state.audio_engine.lock().map_err(|_| "Failed to lock audio engine")?;
+        if engine.is_running() {
+                engine.send_event(PluginEvent::InsertState {
+                        node,
+                        bypass: insert.bypass,
+                        post_fader: insert.post_fader,
+                });
+        }
+        Ok(())
+}
+
+/// 设置混音轨道的通道布局并重建音频图。
+/// 总轨（0）的布局同时决定其它轨道的输出格式：轨道通道少于环绕总轨时使用环绕声像，其余情况按矩阵上混 / 下混。
+#[tauri::command]
+pub fn set_mixer_track_layout(
+        state: State<'_, AppState>,
+        track_index: usize,
+        layout: ChannelLayout,
+) -> Result<(), String> {
+        {
+                let mut tracks = state.mixer_tracks.lock().map_err(|_| "Failed to lock tracks")?;
+                let track = tracks.get_mut(track_index).ok_or("Mixer track not found")?;
+                track.layout = layout;
+        }
+        rebuild_engine(&state)?;
+        Ok(())
+}
@@ -1,9 +1,29 @@
 // 聚合所有 DAW 命令的子模块
+pub mod analysis;
+pub mod audio;
+pub mod automation;
 pub mod clip;
 pub mod global;
+pub mod meter;
+pub mod metronome;
+pub mod midi;
+pub mod mixer;
+pub mod preset;
+pub mod safety;
+pub mod snapshot;
 pub mod track;
 
 // 可选：对常用命令进行重新导出以便上层直接 `use daw::commands::*`
+pub use analysis::*;
+pub use audio::*;
+pub use automation::*;
 pub use clip::*;
 pub use global::*;
+pub use meter::*;
+pub use metronome::*;
+pub use midi::*;
+pub use mixer::*;
+pub use preset::*;
+pub use safety::*;
+pub use snapshot::*;
 pub use track::*;
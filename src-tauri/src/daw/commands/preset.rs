@@ -0,0 +1,198 @@
+use crate::audio::plugins::presets::{PluginPreset, PresetInfo, PresetParam, PresetStore};
+use crate::daw::commands::global::{find_instance, instance_parameters};
+use crate::daw::state::AppState;
+use tauri::{Emitter, Manager, State};
+
+// 插件预设命令：列出 / 保存 / 加载 / 改名 / 删除 / 标签与描述。
+// 用户预设保存在应用数据目录的 `presets/<unique_id>/` 下，出厂预设来自插件 manifest。
+
+fn preset_store(app: &tauri::AppHandle) -> Result<PresetStore, String> {
+        let dir = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+        Ok(PresetStore::new(dir.join("presets")))
+}
+
+// 实例对应的插件 unique_id：以创建实例时使用的 id 为准（乐器或插入效果），否则取插件自报的 id
+fn instance_plugin_id(state: &State<'_, AppState>, instance_id: &str) -> Result<String, String> {
+        {
+                let plugins = state.active_plugins.lock().map_err(|_| "Failed to lock plugins")?;
+                if let Some(p) = plugins.iter().find(|p| p.id == instance_id) {
+                        return Ok(p.name.clone());
+                }
+        }
+        {
+                let tracks = state.mixer_tracks.lock().map_err(|_| "Failed to lock tracks")?;
+                let insert = tracks
+                        .iter()
+                        .flat_map(|t| t.inserts.iter())
+                        .find(|i| i.instance_id == instance_id);
+                if let Some(insert) = insert {
+                        return Ok(insert.name.clone());
+                }
+        }
+        let inst_arc = find_instance(state, instance_id)?.ok_or("Instance not found")?;
+        let inst = inst_arc.lock().map_err(|_| "Failed to lock plugin instance")?;
+        Ok(inst.info().unique_id)
+}
+
+fn factory_presets(state: &State<'_, AppState>, plugin_id: &str) -> Result<Vec<PluginPreset>, String> {
+        let manager = state
+                .plugin_manager
+                .lock()
+                .map_err(|_| "Failed to lock plugin manager")?;
+        Ok(manager.get_factory_presets(plugin_id))
+}
+
+/// 预设列表（出厂在前），可按关键字与标签过滤
+#[tauri::command]
+pub fn list_plugin_presets(
+        app: tauri::AppHandle,
+        state: State<'_, AppState>,
+        plugin_id: String,
+        query: Option<String>,
+        tags: Option<Vec<String>>,
+) -> Result<Vec<PresetInfo>, String> {
+        let query = query.unwrap_or_default();
+        let tags = tags.unwrap_or_default();
+        let mut presets = factory_presets(&state, &plugin_id)?;
+        presets.extend(preset_store(&app)?.list(&plugin_id));
+        Ok(presets
+                .iter()
+                .filter(|p| p.matches(&query, &tags))
+                .map(PluginPreset::info)
+                .collect())
+}
+
+/// 插件全部预设中出现过的标签（去重、排序），供预设浏览器的标签筛选使用
+#[tauri::command]
+pub fn get_plugin_preset_tags(
+        app: tauri::AppHandle,
+        state: State<'_, AppState>,
+        plugin_id: String,
+) -> Result<Vec<String>, String> {
+        let mut presets = factory_presets(&state, &plugin_id)?;
+        presets.extend(preset_store(&app)?.list(&plugin_id));
+        let mut tags: Vec<String> = presets.into_iter().flat_map(|p| p.tags).collect();
+        tags.sort_by_key(|t| t.to_lowercase());
+        tags.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        Ok(tags)
+}
+
+/// 把实例当前的声音保存为用户预设
+#[tauri::command]
+pub fn save_plugin_preset(
+        app: tauri::AppHandle,
+        state: State<'_, AppState>,
+        instance_id: String,
+        name: String,
+        tags: Option<Vec<String>>,
+        description: Option<String>,
+        overwrite: Option<bool>,
+) -> Result<PresetInfo, String> {
+        let plugin_id = instance_plugin_id(&state, &instance_id)?;
+        let inst_arc = find_instance(&state, &instance_id)?.ok_or("Instance not found")?;
+        let params = instance_parameters(&state, &inst_arc)?;
+        let (state_blob, values) = {
+                let inst = inst_arc.lock().map_err(|_| "Failed to lock plugin instance")?;
+                let values: Vec<PresetParam> = params
+                        .iter()
+                        .filter(|p| !p.flags.read_only)
+                        .map(|p| PresetParam {
+                                id: p.id,
+                                value: inst.get_param(p.id),
+                        })
+                        .collect();
+                (inst.get_state(), values)
+        };
+        // 插件不提供二进制状态时回退为参数值
+        let preset = PluginPreset {
+                name: name.trim().to_string(),
+                plugin_id,
+                tags: tags.unwrap_or_default(),
+                description: description.unwrap_or_default(),
+                factory: false,
+                params: if state_blob.is_empty() {
+                        values
+                } else {
+                        Vec::new()
+                },
+                state: state_blob,
+        };
+        preset_store(&app)?.save(&preset, overwrite.unwrap_or(false))?;
+        Ok(preset.info())
+}
+
+/// 把预设应用到实例；`factory` 为 true 时从出厂预设中查找
+#[tauri::command]
+pub fn load_plugin_preset(
+        app: tauri::AppHandle,
+        state: State<'_, AppState>,
+        instance_id: String,
+        name: String,
+        factory: Option<bool>,
+) -> Result<(), String> {
+        let plugin_id = instance_plugin_id(&state, &instance_id)?;
+        let preset = if factory.unwrap_or(false) {
+                factory_presets(&state, &plugin_id)?
+                        .into_iter()
+                        .find(|p| p.name == name)
+        } else {
+                preset_store(&app)?.get(&plugin_id, &name)
+        }
+        .ok_or_else(|| format!("Preset '{}' not found", name))?;
+
+        let inst_arc = find_instance(&state, &instance_id)?.ok_or("Instance not found")?;
+        {
+                let mut inst = inst_arc.lock().map_err(|_| "Failed to lock plugin instance")?;
+                if !preset.state.is_empty() {
+                        inst.set_state(&preset.state);
+                } else {
+                        for p in &preset.params {
+                                inst.set_param(p.id, p.value);
+                        }
+                }
+        }
+
+        // 通知前端刷新该实例的参数显示
+        let payload = serde_json::json!({
+                "instanceId": instance_id,
+                "pluginId": plugin_id,
+                "name": preset.name,
+                "factory": preset.factory,
+        });
+        let _ = app.emit("plugin-preset-loaded", payload);
+        Ok(())
+}
+
+#[tauri::command]
+pub fn rename_plugin_preset(
+        app: tauri::AppHandle,
+        plugin_id: String,
+        name: String,
+        new_name: String,
+) -> Result<(), String> {
+        let new_name = new_name.trim();
+        if new_name.is_empty() {
+                return Err("Preset name cannot be empty".to_string());
+        }
+        preset_store(&app)?.rename(&plugin_id, &name, new_name)
+}
+
+#[tauri::command]
+pub fn delete_plugin_preset(app: tauri::AppHandle, plugin_id: String, name: String) -> Result<(), String> {
+        preset_store(&app)?.delete(&plugin_id, &name)
+}
+
+/// 更新用户预设的标签与描述
+#[tauri::command]
+pub fn set_plugin_preset_metadata(
+        app: tauri::AppHandle,
+        plugin_id: String,
+        name: String,
+        tags: Vec<String>,
+        description: Option<String>,
+) -> Result<(), String> {
+        preset_store(&app)?.set_metadata(&plugin_id, &name, tags, description.unwrap_or_default())
+}
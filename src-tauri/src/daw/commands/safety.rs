@@ -0,0 +1,51 @@
+use crate::audio::core::plugin::PluginEvent;
+use crate::audio::plugins::mixer::safety::{SafetySettings, unmute_non_finite};
+use crate::daw::settings::{load_json_setting, save_json_setting};
+use crate::daw::state::AppState;
+use tauri::{AppHandle, Manager, State};
+
+// 总输出安全级命令。设置保存在应用数据目录的 `safety.json`，启动时恢复，
+// 修改后立即发送给运行中的引擎，并在重建音频图时重新应用。
+
+/// 启动时恢复安全级设置（引擎尚未启动）
+pub fn load_safety_settings(app: &AppHandle) {
+        let Some(settings) =
+                load_json_setting::<SafetySettings>(app, "safety.json").filter(|settings| settings.validate().is_ok())
+        else {
+                return;
+        };
+        let state = app.state::<AppState>();
+        if let Ok(mut safety) = state.master_safety.lock() {
+                *safety = settings;
+        }
+}
+
+#[tauri::command]
+pub fn get_master_safety(state: State<'_, AppState>) -> Result<SafetySettings, String> {
+        let safety = state.master_safety.lock().map_err(|_| "Failed to lock master safety")?;
+        Ok(safety.clone())
+}
+
+/// 设置总输出限制器（上限 / 前瞻 / 释放 / 隔直）；关闭后总输出不再限制电平
+#[tauri::command]
+pub fn set_master_safety(app: AppHandle, state: State<'_, AppState>, settings: SafetySettings) -> Result<(), String> {
+        settings.validate()?;
+        {
+                let mut safety = state.master_safety.lock().map_err(|_| "Failed to lock master safety")?;
+                *safety = settings.clone();
+        }
+        {
+                let engine = state.audio_engine.lock().map_err(|_| "Failed to lock audio engine")?;
+                if engine.is_running() {
+                        engine.send_event(PluginEvent::MasterSafety(settings.clone()));
+                }
+        }
+        save_json_setting(&app, "safety.json", &settings);
+        Ok(())
+}
+
+/// 恢复因输出 NaN / Inf 而被静音的乐器与插入效果
+#[tauri::command]
+pub fn unmute_plugins() {
+        unmute_non_finite();
+}
@@ -0,0 +1,142 @@
+use crate::audio::plugins::snapshots::{InstanceSnapshot, morph, normalize_slot};
+use crate::daw::commands::global::{find_instance, instance_parameters};
+use crate::daw::state::AppState;
+use tauri::{Emitter, State};
+
+// 实例快照命令：存储 / 召回 / 复制（A→B）/ 清除 / 在两个快照间插值。
+// 快照按实例 ID 保存在 AppState 中，实例被删除后随音频图重建一并丢弃。
+
+fn get_snapshot(state: &State<'_, AppState>, instance_id: &str, slot: &str) -> Result<InstanceSnapshot, String> {
+        let snapshots = state
+                .instance_snapshots
+                .lock()
+                .map_err(|_| "Failed to lock instance snapshots")?;
+        snapshots
+                .get(instance_id)
+                .and_then(|slots| slots.get(slot))
+                .cloned()
+                .ok_or_else(|| format!("Snapshot {} is empty", slot))
+}
+
+// 通知前端刷新该实例的参数显示
+fn emit_recalled(app: &tauri::AppHandle, instance_id: &str, slot: &str) {
+        let payload = serde_json::json!({
+                "instanceId": instance_id,
+                "slot": slot,
+        });
+        let _ = app.emit("plugin-snapshot-recalled", payload);
+}
+
+/// 实例已存储的快照槽位（A、B 在前，编号按数值排序）
+#[tauri::command]
+pub fn list_instance_snapshots(state: State<'_, AppState>, instance_id: String) -> Result<Vec<String>, String> {
+        let snapshots = state
+                .instance_snapshots
+                .lock()
+                .map_err(|_| "Failed to lock instance snapshots")?;
+        let mut slots: Vec<String> = snapshots
+                .get(&instance_id)
+                .map(|slots| slots.keys().cloned().collect())
+                .unwrap_or_default();
+        slots.sort_by_key(|s| (s.parse::<u32>().ok(), s.clone()));
+        Ok(slots)
+}
+
+/// 把实例当前的状态与参数值存入槽位
+#[tauri::command]
+pub fn store_instance_snapshot(state: State<'_, AppState>, instance_id: String, slot: String) -> Result<(), String> {
+        let slot = normalize_slot(&slot)?;
+        let inst_arc = find_instance(&state, &instance_id)?.ok_or("Instance not found")?;
+        let params = instance_parameters(&state, &inst_arc)?;
+        let snapshot = {
+                let inst = inst_arc.lock().map_err(|_| "Failed to lock plugin instance")?;
+                InstanceSnapshot::capture(inst.as_ref(), &params)
+        };
+        let mut snapshots = state
+                .instance_snapshots
+                .lock()
+                .map_err(|_| "Failed to lock instance snapshots")?;
+        snapshots.entry(instance_id).or_default().insert(slot, snapshot);
+        Ok(())
+}
+
+#[tauri::command]
+pub fn recall_instance_snapshot(
+        app: tauri::AppHandle,
+        state: State<'_, AppState>,
+        instance_id: String,
+        slot: String,
+) -> Result<(), String> {
+        let slot = normalize_slot(&slot)?;
+        let snapshot = get_snapshot(&state, &instance_id, &slot)?;
+        let inst_arc = find_instance(&state, &instance_id)?.ok_or("Instance not found")?;
+        {
+                let mut inst = inst_arc.lock().map_err(|_| "Failed to lock plugin instance")?;
+                snapshot.apply(inst.as_mut());
+        }
+        emit_recalled(&app, &instance_id, &slot);
+        Ok(())
+}
+
+/// 复制快照槽位（例如 A→B）
+#[tauri::command]
+pub fn copy_instance_snapshot(
+        state: State<'_, AppState>,
+        instance_id: String,
+        from: String,
+        to: String,
+) -> Result<(), String> {
+        let from = normalize_slot(&from)?;
+        let to = normalize_slot(&to)?;
+        let snapshot = get_snapshot(&state, &instance_id, &from)?;
+        let mut snapshots = state
+                .instance_snapshots
+                .lock()
+                .map_err(|_| "Failed to lock instance snapshots")?;
+        snapshots.entry(instance_id).or_default().insert(to, snapshot);
+        Ok(())
+}
+
+#[tauri::command]
+pub fn clear_instance_snapshot(state: State<'_, AppState>, instance_id: String, slot: String) -> Result<(), String> {
+        let slot = normalize_slot(&slot)?;
+        let mut snapshots = state
+                .instance_snapshots
+                .lock()
+                .map_err(|_| "Failed to lock instance snapshots")?;
+        if let Some(slots) = snapshots.get_mut(&instance_id) {
+                slots.remove(&slot);
+        }
+        Ok(())
+}
+
+/// 在两个快照的参数值之间插值并写入实例（`amount` 0..1，0 = from，1 = to）
+#[tauri::command]
+pub fn morph_instance_snapshots(
+        app: tauri::AppHandle,
+        state: State<'_, AppState>,
+        instance_id: String,
+        from: String,
+        to: String,
+        amount: f32,
+) -> Result<(), String> {
+        let from = get_snapshot(&state, &instance_id, &normalize_slot(&from)?)?;
+        let to = get_snapshot(&state, &instance_id, &normalize_slot(&to)?)?;
+        let inst_arc = find_instance(&state, &instance_id)?.ok_or("Instance not found")?;
+        let params = instance_parameters(&state, &inst_arc)?;
+        let values = morph(&from, &to, &params, amount);
+        {
+                let mut inst = inst_arc.lock().map_err(|_| "Failed to lock plugin instance")?;
+                for p in &values {
+                        inst.set_param(p.id, p.value);
+                }
+        }
+        let _ = app.emit(
+                "plugin-snapshot-morphed",
+                serde_json::json!({
+                        "instanceId": instance_id,
+                        "amount": amount.clamp(0.0, 1.0),
+                }),
+        );
+        Ok(())
+}
@@ -2,12 +2,20 @@
 /// `create_audio_graph` 返回 root 插件（通常为 Mixer）和实例映射（UUID -> Plugin 实例）。
 use super::state::AppState;
 use crate::audio::core::plugin::Plugin;
+use crate::audio::engine::AudioEngine;
 use crate::audio::plugins::mixer::mixer_plugin::MixerPlugin;
+use crate::audio::plugins::mixer::track::InsertSlot;
 
-use crate::daw::sequencer::{get_is_playing, get_playback_position};
+use crate::daw::sequencer::{get_is_playing, get_is_recording, get_playback_position};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use tauri::State;
+use uuid::Uuid;
+
+// 实例 ID 即节点 ID；无法解析的 ID 无法作为参数地址，对应实例不会加入音频图
+fn node_id_for(instance_id: &str) -> Result<Uuid, String> {
+        Uuid::parse_str(instance_id).map_err(|_| format!("Invalid plugin instance ID: {}", instance_id))
+}
 
 pub fn create_audio_graph(
         state: &State<'_, AppState>,
@@ -24,28 +32,95 @@ pub fn create_audio_graph(
 
         let mut mixer = MixerPlugin::new(0);
 
-        // 根据 MixerTrack 创建 Mixer 路径（用于电平表映射）
-        for track_data in tracks.iter() {
-                mixer.add_track(track_data.meter_id);
-        }
+        let processing = state
+                .processing
+                .lock()
+                .map_err(|_| "Failed to lock processing settings")?
+                .clone();
 
-        // 创建并注册插件实例到 Mixer（机架）
-        let manager = state
+        // 创建并注册插件实例到 Mixer（机架）；新实例按工程采样率创建（跟随设备时沿用上一次的采样率）
+        let mut manager = state
                 .plugin_manager
                 .lock()
                 .map_err(|_| "Failed to lock plugin manager")?;
+        if let Some(rate) = processing.sample_rate {
+                manager.set_sample_rate(rate as f32);
+        }
 
         // 建立 UUID -> 实例索引映射
         let mut inst_uuid_to_index = std::collections::HashMap::new();
         let mut inst_uuid_to_instance = HashMap::new();
 
+        // 已存在的实例（插入效果跨重建复用，以保留其内部状态）
+        let existing_instances = state
+                .plugin_instances
+                .lock()
+                .map_err(|_| "Failed to lock plugin instances")?
+                .clone();
+
+        // 根据 MixerTrack 创建 Mixer 路径（用于电平表映射）及其插入效果链
+        let master_layout = tracks.first().map(|t| t.layout).unwrap_or_default();
+        for (track_idx, track_data) in tracks.iter().enumerate() {
+                mixer.add_track(track_data.node_id, track_data.meter_id);
+                if let Some(track) = mixer.get_track_mut(track_idx) {
+                        track.set_levels(track_data.volume, track_data.pan);
+                        track.label = track_data.label.clone();
+                }
+                // 普通轨道输出到总轨；总轨的输出由 Mixer 混合到设备通道
+                let output = if track_idx == 0 {
+                        track_data.layout
+                } else {
+                        master_layout
+                };
+                mixer.set_track_layout(track_idx, track_data.layout, output, track_data.pan_front);
+
+                for insert in track_data.inserts.iter() {
+                        let node = match node_id_for(&insert.instance_id) {
+                                Ok(node) => node,
+                                Err(e) => {
+                                        println!("Core: Skipping insert on mixer track {}: {}", track_idx, e);
+                                        continue;
+                                }
+                        };
+                        let instance = match existing_instances.get(&insert.instance_id) {
+                                Some(inst) => Some(inst.clone()),
+                                None => manager
+                                        .create_instance(&insert.name, insert.sandboxed)
+                                        .map(|p| Arc::new(Mutex::new(p))),
+                        };
+                        let Some(instance) = instance else {
+                                println!(
+                                        "Core: Failed to create insert {} ({}) on mixer track {}",
+                                        insert.instance_id, insert.name, track_idx
+                                );
+                                continue;
+                        };
+                        inst_uuid_to_instance.insert(insert.instance_id.clone(), instance.clone());
+                        if let Some(track) = mixer.get_track_mut(track_idx) {
+                                track.add_insert(InsertSlot {
+                                        id: node,
+                                        plugin: instance,
+                                        bypass: insert.bypass,
+                                        post_fader: insert.post_fader,
+                                });
+                        }
+                }
+        }
+
         println!("Core: Building Audio Graph");
         for (_i, p_data) in plugins.iter().enumerate() {
-                let plugin_opt = if let Some(plugin) = manager.create_plugin(&p_data.name) {
+                let node = match node_id_for(&p_data.id) {
+                        Ok(node) => node,
+                        Err(e) => {
+                                println!("Core: Skipping plugin {}: {}", p_data.name, e);
+                                continue;
+                        }
+                };
+                let plugin_opt = if let Some(plugin) = manager.create_instance(&p_data.name, p_data.sandboxed) {
                         Some(plugin)
                 } else if p_data.name == "SimpleSynth" {
                         // 兼容旧版本的后备方案
-                        manager.create_plugin("com.mydaw.simplesynth")
+                        manager.create_instance("com.mydaw.simplesynth", p_data.sandboxed)
                 } else {
                         None
                 };
@@ -53,7 +128,7 @@ pub fn create_audio_graph(
                 let inst_idx = if let Some(plugin) = plugin_opt {
                         let wrapped = Arc::new(Mutex::new(plugin));
                         inst_uuid_to_instance.insert(p_data.id.clone(), wrapped.clone());
-                        Some(mixer.add_instrument(wrapped))
+                        Some(mixer.add_instrument(node, wrapped))
                 } else {
                         None
                 };
@@ -87,7 +162,32 @@ pub fn create_audio_graph(
                 track_routing.insert(track.id, track.target_mixer_track_id);
         }
 
+        mixer.set_metronome(state.metronome.lock().map_err(|_| "Failed to lock metronome")?.clone());
+        mixer.set_master_safety(
+                state.master_safety
+                        .lock()
+                        .map_err(|_| "Failed to lock master safety")?
+                        .clone(),
+        );
+        mixer.set_double_precision(processing.double_precision);
+        let time_signature = state
+                .time_signature
+                .lock()
+                .map_err(|_| "Failed to lock time signature")?
+                .clone();
+
         let sequencer = mixer.get_sequencer_mut();
+        sequencer.time_signature = (time_signature.numerator, time_signature.denominator);
+        // 重建时保持录音状态（预备拍不会重新开始）
+        sequencer.recording = get_is_recording();
+        sequencer.set_automation(
+                state.automation
+                        .lock()
+                        .map_err(|_| "Failed to lock automation")?
+                        .clone(),
+        );
+        // 音符 ID 在整个音频图内唯一（同一片段的多个副本也各自编号）
+        let mut next_note_id: u32 = 0;
         for clip in clips.iter() {
                 println!(
                         "Core: Processing Clip {}. Raw Instrument IDs: {:?}",
@@ -142,13 +242,35 @@ pub fn create_audio_graph(
                         notes: clip
                                 .notes
                                 .iter()
-                                .map(|n| crate::audio::core::clip::Note {
-                                        relative_start: n.start.time,
-                                        duration: n.duration.seconds,
-                                        note: n.note,
-                                        velocity: n.velocity,
+                                .map(|n| {
+                                        next_note_id = next_note_id.wrapping_add(1);
+                                        let mut expressions = n.expressions.clone();
+                                        expressions.sort_by(|a, b| a.time.total_cmp(&b.time));
+                                        crate::audio::core::clip::Note {
+                                                id: next_note_id,
+                                                relative_start: n.start.time,
+                                                duration: n.duration.seconds,
+                                                note: n.note,
+                                                velocity: n.velocity,
+                                                channel: n.channel,
+                                                expressions,
+                                        }
                                 })
                                 .collect(),
+                        controllers: {
+                                let mut controllers: Vec<_> = clip
+                                        .controllers
+                                        .iter()
+                                        .map(|c| crate::audio::core::clip::ControllerEvent {
+                                                relative_time: c.time.time,
+                                                channel: c.channel,
+                                                kind: c.kind,
+                                                value: c.value,
+                                        })
+                                        .collect();
+                                controllers.sort_by(|a, b| a.relative_time.total_cmp(&b.relative_time));
+                                controllers
+                        },
                 };
                 sequencer.add_clip(audio_clip);
         }
@@ -156,6 +278,21 @@ pub fn create_audio_graph(
         Ok((Box::new(mixer), inst_uuid_to_instance))
 }
 
+/// 按当前的工程采样率启动引擎（所有启动路径共用，保证引擎与图使用同一采样率）
+pub(crate) fn start_engine(
+        state: &State<'_, AppState>,
+        engine: &mut AudioEngine,
+        root: Box<dyn Plugin>,
+) -> Result<(), String> {
+        let sample_rate = state
+                .processing
+                .lock()
+                .map_err(|_| "Failed to lock processing settings")?
+                .sample_rate;
+        engine.set_sample_rate(sample_rate);
+        engine.start(root).map_err(|e| e.to_string())
+}
+
 pub fn rebuild_engine(state: &State<'_, AppState>) -> Result<(), String> {
         let mut engine = state.audio_engine.lock().map_err(|_| "Failed to lock audio engine")?;
 
@@ -178,6 +315,13 @@ pub fn rebuild_engine(state: &State<'_, AppState>) -> Result<(), String> {
                         .lock()
                         .map_err(|_| "Failed to lock plugin instances")?;
                 *state_instances = instances;
+
+                // 丢弃已删除实例的快照
+                let mut snapshots = state
+                        .instance_snapshots
+                        .lock()
+                        .map_err(|_| "Failed to lock instance snapshots")?;
+                snapshots.retain(|id, _| state_instances.contains_key(id));
         }
 
         // 如果之前正在运行或我们希望在重建时保持状态，请恢复状态
@@ -185,7 +329,7 @@ pub fn rebuild_engine(state: &State<'_, AppState>) -> Result<(), String> {
         // 发送事件更安全/更清晰。
 
         if was_running {
-                engine.start(root).map_err(|e| e.to_string())?;
+                start_engine(state, &mut engine, root)?;
 
                 // 恢复传输状态（transport）
                 use crate::audio::core::plugin::PluginEvent;
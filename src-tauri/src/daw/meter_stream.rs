@@ -0,0 +1,29 @@
+/// 电平推送：后台线程以固定频率读取所有电平计的读数，有变化时通过 `meter-levels` 事件发送给前端，
+/// 代替前端轮询 `get_meter_levels_cmd`。
+use crate::audio::plugins::mixer::level_meter::get_meter_levels;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+// 推送间隔（约 30 次 / 秒）
+const STREAM_INTERVAL: Duration = Duration::from_millis(33);
+
+pub fn spawn_meter_stream(app: AppHandle) {
+        std::thread::Builder::new()
+                .name("meter-stream".to_string())
+                .spawn(move || {
+                        let mut last = None;
+                        loop {
+                                std::thread::sleep(STREAM_INTERVAL);
+                                let levels = get_meter_levels();
+                                // 引擎停止后读数不再变化，不重复发送
+                                if last.as_ref() == Some(&levels) {
+                                        continue;
+                                }
+                                if app.emit("meter-levels", &levels).is_err() {
+                                        break;
+                                }
+                                last = Some(levels);
+                        }
+                })
+                .expect("failed to spawn meter stream");
+}
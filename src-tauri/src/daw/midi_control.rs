@@ -0,0 +1,354 @@
+/// MIDI 控制：打开的硬件输入、控制映射与 MIDI 学习。
+/// 输入线程收到消息后在此分发：学习状态下把消息绑定到待学习的目标，否则按映射写入参数或触发走带动作。
+/// 启用 MIDI Clock 从属模式时，同步消息交给 `MidiClockFollower`，由外部主机驱动音序器。
+/// 全局映射、打开的输入端口与走带同步设置保存在应用数据目录的 `midi.json`，工程映射随工程保存。
+use crate::audio::core::plugin::{ParamAddress, PluginParameter};
+use crate::audio::core::threads;
+use crate::audio::midi::clock::{
+        ClockUpdate, MidiClockFollower, MidiClockInputSettings, monotonic_seconds, push_clock_update,
+};
+use crate::audio::midi::mapping::{
+        ControlInput, MappingScope, MappingTarget, MidiControlSource, MidiMapping, TakeoverMode, TransportAction,
+};
+use crate::audio::midi::sync::{self, MidiSyncSettings};
+use crate::audio::midi::{MidiInputConnection, MidiMessage, connect_input, list_input_ports};
+use crate::audio::plugins::mixer::track::{TRACK_PARAM_PAN, TRACK_PARAM_VOLUME};
+use crate::daw::commands::global::{
+        apply_node_parameter, ensure_engine_running, find_instance, instance_parameters, pause, play, stop,
+};
+use crate::daw::sequencer::get_is_playing;
+use crate::daw::settings::{load_json_setting, save_json_setting};
+use crate::daw::state::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// 等待下一条控制器消息的学习请求
+#[derive(Debug, Clone, Deserialize)]
+pub struct LearnRequest {
+        pub target: MappingTarget,
+        #[serde(default)]
+        pub takeover: TakeoverMode,
+        #[serde(default)]
+        pub scope: MappingScope,
+        #[serde(default)]
+        pub min: Option<f32>,
+        #[serde(default)]
+        pub max: Option<f32>,
+}
+
+#[derive(Default)]
+pub struct MidiControlState {
+        pub mappings: Vec<MidiMapping>,
+        pub learn: Option<LearnRequest>,
+        // 端口 ID -> 打开的连接
+        pub inputs: HashMap<String, MidiInputConnection>,
+        // 走带同步输出设置（输出连接本身由 `audio::midi::sync` 持有）
+        pub sync: MidiSyncSettings,
+        // MIDI Clock 从属模式
+        pub clock_input: MidiClockInputSettings,
+        pub clock_follower: MidiClockFollower,
+}
+
+// 外部 MIDI Clock 主机正在驱动走带；走带命令读取它时不必锁定 MIDI 状态
+static EXTERNAL_CLOCK_ACTIVE: AtomicBool = AtomicBool::new(false);
+// 外部主机开始走带，等待主线程启动引擎
+static EXTERNAL_START_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// 走带由外部 MIDI Clock 主机控制（从属模式已启用且主机正在走带）
+pub fn external_clock_active() -> bool {
+        EXTERNAL_CLOCK_ACTIVE.load(Ordering::Relaxed)
+}
+
+/// 应用从属模式设置；关闭时立即把走带控制交还本地
+pub fn set_clock_input(midi: &mut MidiControlState, settings: MidiClockInputSettings) {
+        if !settings.enabled {
+                midi.clock_follower = MidiClockFollower::new();
+                EXTERNAL_CLOCK_ACTIVE.store(false, Ordering::Relaxed);
+        }
+        midi.clock_input = settings;
+}
+
+// 全局设置文件内容
+#[derive(Default, Serialize, Deserialize)]
+struct MidiSettings {
+        /// 启动时自动打开的输入端口（按端口名）
+        #[serde(default)]
+        inputs: Vec<String>,
+        #[serde(default)]
+        mappings: Vec<MidiMapping>,
+        #[serde(default)]
+        sync: MidiSyncSettings,
+        #[serde(default)]
+        clock_input: MidiClockInputSettings,
+}
+
+/// 保存全局映射与打开的输入端口
+pub fn save_global_settings(app: &AppHandle, midi: &MidiControlState) {
+        let settings = MidiSettings {
+                inputs: midi.inputs.values().map(|c| c.port.name.clone()).collect(),
+                mappings: midi
+                        .mappings
+                        .iter()
+                        .filter(|m| m.scope == MappingScope::Global)
+                        .cloned()
+                        .collect(),
+                sync: midi.sync.clone(),
+                clock_input: midi.clock_input.clone(),
+        };
+        save_json_setting(app, "midi.json", &settings);
+}
+
+/// 启动时加载全局映射并重新打开上次使用的输入端口
+pub fn load_global_settings(app: &AppHandle) {
+        let settings: MidiSettings = load_json_setting(app, "midi.json").unwrap_or_default();
+        let state = app.state::<AppState>();
+        let Ok(mut midi) = state.midi_control.lock() else {
+                return;
+        };
+        midi.mappings.extend(settings.mappings.into_iter().map(|mut m| {
+                m.scope = MappingScope::Global;
+                m
+        }));
+        for port in list_input_ports()
+                .into_iter()
+                .filter(|p| settings.inputs.contains(&p.name))
+        {
+                if let Err(e) = open_input(app, &mut midi, &port.id) {
+                        println!("MIDI: {}", e);
+                }
+        }
+        // 设置保留（即使端口暂时不可用），以便之后重新应用
+        if let Err(e) = sync::configure(&settings.sync) {
+                println!("MIDI sync: {}", e);
+        }
+        midi.sync = settings.sync;
+        set_clock_input(&mut midi, settings.clock_input);
+}
+
+/// 打开输入端口（已打开时不做任何事）
+pub fn open_input(app: &AppHandle, midi: &mut MidiControlState, port_id: &str) -> Result<(), String> {
+        if midi.inputs.contains_key(port_id) {
+                return Ok(());
+        }
+        let handle = app.clone();
+        let port_name = list_input_ports()
+                .into_iter()
+                .find(|p| p.id == port_id)
+                .map(|p| p.name)
+                .unwrap_or_default();
+        let connection = connect_input(port_id, move |msg| handle_message(&handle, &port_name, msg))?;
+        midi.inputs.insert(port_id.to_string(), connection);
+        Ok(())
+}
+
+// 插件参数目标：参数元数据与当前值
+fn plugin_param(state: &State<'_, AppState>, instance_id: &str, param_id: u32) -> Option<(PluginParameter, f32)> {
+        let inst_arc = find_instance(state, instance_id).ok()??;
+        let param = instance_parameters(state, &inst_arc)
+                .ok()?
+                .into_iter()
+                .find(|p| p.id == param_id)?;
+        let value = inst_arc.lock().ok()?.get_param(param_id);
+        Some((param, value))
+}
+
+fn track_levels(state: &AppState, node: uuid::Uuid) -> Option<(f32, f32)> {
+        let tracks = state.mixer_tracks.lock().ok()?;
+        tracks.iter().find(|t| t.node_id == node).map(|t| (t.volume, t.pan))
+}
+
+// 目标当前的归一化值
+fn current_normalized(state: &State<'_, AppState>, target: &MappingTarget) -> Option<f32> {
+        match target {
+                MappingTarget::PluginParameter { instance_id, param_id } => {
+                        let (param, value) = plugin_param(state, instance_id, *param_id)?;
+                        Some(param.to_normalized(value))
+                }
+                MappingTarget::MixerVolume { node } => track_levels(state, *node).map(|(v, _)| v.clamp(0.0, 1.0)),
+                MappingTarget::MixerPan { node } => track_levels(state, *node).map(|(_, p)| (p + 1.0) / 2.0),
+                MappingTarget::Transport { .. } => None,
+        }
+}
+
+// 把归一化值写入目标并通知前端
+fn apply_normalized(app: &AppHandle, state: &State<'_, AppState>, target: &MappingTarget, normalized: f32) {
+        match target {
+                MappingTarget::PluginParameter { instance_id, param_id } => {
+                        let Some((param, _)) = plugin_param(state, instance_id, *param_id) else {
+                                return;
+                        };
+                        let value = param.from_normalized(normalized);
+                        let Ok(Some(inst_arc)) = find_instance(state, instance_id) else {
+                                return;
+                        };
+                        if let Ok(mut inst) = inst_arc.lock() {
+                                inst.set_param(*param_id, value);
+                        }
+                        let _ = app.emit(
+                                "plugin-parameter-changed",
+                                serde_json::json!({
+                                        "instanceId": instance_id,
+                                        "paramId": param_id,
+                                        "value": value,
+                                }),
+                        );
+                }
+                MappingTarget::MixerVolume { node } | MappingTarget::MixerPan { node } => {
+                        let (param_id, value) = match target {
+                                MappingTarget::MixerVolume { .. } => (TRACK_PARAM_VOLUME, normalized),
+                                _ => (TRACK_PARAM_PAN, normalized * 2.0 - 1.0),
+                        };
+                        if apply_node_parameter(state, ParamAddress::new(*node, param_id), value).is_ok() {
+                                let _ = app.emit(
+                                        "node-parameter-changed",
+                                        serde_json::json!({
+                                                "node": node,
+                                                "paramId": param_id,
+                                                "value": value,
+                                        }),
+                                );
+                        }
+                }
+                MappingTarget::Transport { .. } => {}
+        }
+}
+
+fn run_transport(app: &AppHandle, action: TransportAction) {
+        let result = match action {
+                TransportAction::Play => play(app.state::<AppState>()),
+                TransportAction::Pause => pause(app.state::<AppState>()),
+                TransportAction::Stop => stop(app.state::<AppState>()),
+                TransportAction::TogglePlay if get_is_playing() => pause(app.state::<AppState>()),
+                TransportAction::TogglePlay => play(app.state::<AppState>()),
+        };
+        if let Err(e) = result {
+                println!("MIDI: transport action failed: {}", e);
+        }
+}
+
+// 把外部主机的走带更新经无锁队列送到音序器（不锁音频引擎）。
+// 主机开始走带（Start / Continue 之后的第一个 Clock）时请求主线程确保引擎在运行
+fn follow_external_clock(update: ClockUpdate) {
+        push_clock_update(update);
+        if update.playing && update.relocate {
+                EXTERNAL_START_REQUESTED.store(true, Ordering::Release);
+                threads::request_main_thread_pump();
+        }
+}
+
+/// 主线程泵调用：外部主机开始走带而引擎未运行时，按与 `play` 相同的路径启动引擎；
+/// 走带状态随后由队列中的时钟更新驱动
+pub(crate) fn start_for_external_clock(state: &State<'_, AppState>) {
+        if !EXTERNAL_START_REQUESTED.swap(false, Ordering::AcqRel) {
+                return;
+        }
+        let started = state
+                .audio_engine
+                .lock()
+                .map_err(|_| "Failed to lock audio engine".to_string())
+                .and_then(|mut engine| ensure_engine_running(state, &mut engine));
+        if let Err(e) = started {
+                println!("MIDI clock: failed to start engine: {}", e);
+        }
+}
+
+// 映射命中后在 MIDI 状态锁之外执行的动作
+enum MappedAction {
+        Transport(TransportAction),
+        Parameter { target: MappingTarget, input: ControlInput },
+}
+
+// 输入线程回调。
+// MIDI 状态锁只在读写映射 / 学习 / 时钟状态时短暂持有，读取与写入目标（混音轨道、插件实例、音频引擎）时不持有，
+// 避免与先锁轨道 / 实例、再锁 MIDI 状态的路径（例如保存工程）形成锁顺序反转。
+fn handle_message(app: &AppHandle, port: &str, msg: MidiMessage) {
+        let state = app.state::<AppState>();
+        let actions = {
+                let Ok(mut midi) = state.midi_control.lock() else {
+                        return;
+                };
+
+                // 走带同步消息（Clock / Start / Stop / Continue / Song Position Pointer）不参与学习与映射
+                if matches!(
+                        msg,
+                        MidiMessage::Clock
+                                | MidiMessage::Start
+                                | MidiMessage::Stop
+                                | MidiMessage::Continue
+                                | MidiMessage::SongPosition(_)
+                ) {
+                        if !midi.clock_input.accepts(port) {
+                                return;
+                        }
+                        let update = midi.clock_follower.handle(&msg, monotonic_seconds());
+                        EXTERNAL_CLOCK_ACTIVE.store(midi.clock_follower.is_running(), Ordering::Relaxed);
+                        drop(midi);
+                        if let Some(update) = update {
+                                follow_external_clock(update);
+                        }
+                        return;
+                }
+
+                if let Some(request) = midi.learn.clone() {
+                        let Some(source) = MidiControlSource::learn(port, &msg) else {
+                                return;
+                        };
+                        let mut mapping = MidiMapping::new(source, request.target);
+                        mapping.takeover = request.takeover;
+                        mapping.scope = request.scope;
+                        mapping.min = request.min.unwrap_or(mapping.min);
+                        mapping.max = request.max.unwrap_or(mapping.max);
+                        // 一个目标只保留一个映射：重新学习即替换
+                        midi.mappings.retain(|m| m.target != mapping.target);
+                        midi.mappings.push(mapping.clone());
+                        midi.learn = None;
+                        if mapping.scope == MappingScope::Global {
+                                save_global_settings(app, &midi);
+                        }
+                        let _ = app.emit("midi-learned", &mapping);
+                        return;
+                }
+
+                let mut actions = Vec::new();
+                for mapping in midi.mappings.iter_mut() {
+                        let Some(input) = mapping.source.matches(port, &msg) else {
+                                continue;
+                        };
+                        match mapping.target.clone() {
+                                MappingTarget::Transport { action } => {
+                                        if mapping.trigger(input) {
+                                                actions.push(MappedAction::Transport(action));
+                                        }
+                                }
+                                target => actions.push(MappedAction::Parameter { target, input }),
+                        }
+                }
+                actions
+        };
+
+        for action in actions {
+                match action {
+                        MappedAction::Transport(action) => run_transport(app, action),
+                        MappedAction::Parameter { target, input } => {
+                                let Some(current) = current_normalized(&state, &target) else {
+                                        continue;
+                                };
+                                // 接管状态保存在映射中：只为计算新值重新短暂加锁（映射按目标唯一）
+                                let value = {
+                                        let Ok(mut midi) = state.midi_control.lock() else {
+                                                return;
+                                        };
+                                        midi.mappings
+                                                .iter_mut()
+                                                .find(|m| m.target == target)
+                                                .and_then(|m| m.resolve(input, current))
+                                };
+                                if let Some(value) = value {
+                                        apply_normalized(app, &state, &target, value.clamp(0.0, 1.0));
+                                }
+                        }
+                }
+        }
+}
@@ -1,7 +1,12 @@
-// DAW 子模块汇总：包含剪辑命令、全局命令、音频图构建、模型、音序器、序列化与状态定义
+// DAW 子模块汇总：包含剪辑命令、全局命令、音频图构建、模型、音序器、序列化、应用设置与状态定义
+pub mod analysis;
 pub mod commands;
 pub mod core;
+pub mod meter_stream;
+pub mod midi_control;
 pub mod model;
+pub mod pump;
 pub mod sequencer;
 pub mod serialization;
+pub mod settings;
 pub mod state;
@@ -1,13 +1,31 @@
+use crate::audio::core::clip::{ControllerKind, ExpressionPoint};
 use serde::{Deserialize, Serialize};
 
 // DAW 数据模型：位置、时长、音符、片段（Clip）、编排轨道等
 #[derive(Clone, Debug, Serialize, Deserialize)]
-#[allow(dead_code)]
 pub struct TimeSignature {
         pub numerator: u32,
         pub denominator: u32,
 }
 
+impl Default for TimeSignature {
+        fn default() -> Self {
+                Self {
+                        numerator: 4,
+                        denominator: 4,
+                }
+        }
+}
+
+/// 工程处理设置：采样率（`None` 跟随输出设备，不同时在设备边界重采样）与求和总线精度
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessingSettings {
+        pub sample_rate: Option<u32>,
+        /// 混音求和总线以 f64 累加
+        pub double_precision: bool,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Position {
@@ -37,6 +55,25 @@ pub struct Note {
         pub start: Position,
         pub duration: MusicalLength,
         pub velocity: f32,
+        // MIDI 通道（0-15）
+        #[serde(default)]
+        pub channel: u8,
+        // 单音符表情曲线（时间相对于音符起点，秒）
+        #[serde(default)]
+        pub expressions: Vec<ExpressionPoint>,
+}
+
+/// 片段内的控制器事件：CC、弯音、通道 / 单音符触后、音色切换
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControllerEvent {
+        pub id: String,
+        pub kind: ControllerKind,
+        #[serde(default)]
+        pub channel: u8,
+        pub time: Position,
+        // CC / 触后为 0.0 - 1.0，弯音为 -1.0 - 1.0，音色切换为音色号（0-127）
+        pub value: f32,
 }
 
 use std::collections::HashMap;
@@ -59,6 +96,8 @@ pub struct Clip {
         pub start: Position,
         pub length: MusicalLength,
         pub notes: Vec<Note>,
+        #[serde(default)]
+        pub controllers: Vec<ControllerEvent>,
         pub content: ClipContent,
         // 使用的乐器 UUID 列表
         pub instrument_ids: Vec<String>,
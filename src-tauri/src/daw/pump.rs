@@ -0,0 +1,71 @@
+/// 主线程泵：插件（例如 CLAP 的 request_callback / params.rescan / state.mark_dirty）请求主线程回调时，
+/// 由后台线程察觉请求并把维护工作调度到 Tauri 主线程执行，逐个调用插件的 `on_main_thread`。
+use crate::audio::core::threads;
+use crate::audio::plugins::mixer::safety::take_non_finite_reports;
+use crate::daw::midi_control::start_for_external_clock;
+use crate::daw::state::AppState;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+// 轮询间隔：足够短以便及时响应插件请求，又不会给主线程带来可感知的负担
+const PUMP_INTERVAL: Duration = Duration::from_millis(15);
+
+pub fn spawn_main_thread_pump(app: AppHandle) {
+        std::thread::Builder::new()
+                .name("main-thread-pump".to_string())
+                .spawn(move || {
+                        loop {
+                                std::thread::sleep(PUMP_INTERVAL);
+                                if !threads::take_main_thread_pump_request() {
+                                        continue;
+                                }
+                                let handle = app.clone();
+                                if app.run_on_main_thread(move || pump_once(&handle)).is_err() {
+                                        // 事件循环已退出
+                                        break;
+                                }
+                        }
+                })
+                .expect("failed to spawn main thread pump");
+}
+
+fn pump_once(app: &AppHandle) {
+        // 输出 NaN / Inf 而被静音的节点（乐器或插入效果）
+        for node in take_non_finite_reports() {
+                let _ = app.emit(
+                        "plugin-non-finite",
+                        serde_json::json!({ "instanceId": node }),
+                );
+        }
+
+        let state = app.state::<AppState>();
+        start_for_external_clock(&state);
+
+        let instances = match state.plugin_instances.lock() {
+                Ok(i) => i.clone(),
+                Err(_) => return,
+        };
+
+        for (id, instance) in instances.iter() {
+                let (dirty, crash) = match instance.lock() {
+                        Ok(mut inst) => {
+                                inst.on_main_thread();
+                                (inst.take_state_dirty(), inst.take_crash_report())
+                        }
+                        Err(_) => (false, None),
+                };
+                if let Some(message) = crash {
+                        // 沙箱插件崩溃：实例已被静音替代，通知 UI
+                        let _ = app.emit(
+                                "plugin-crashed",
+                                serde_json::json!({ "instanceId": id, "message": message }),
+                        );
+                }
+                if dirty {
+                        let _ = app.emit(
+                                "plugin-state-dirty",
+                                serde_json::json!({ "instanceId": id }),
+                        );
+                }
+        }
+}
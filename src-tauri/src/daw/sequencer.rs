@@ -1,11 +1,22 @@
+use crate::audio::core::automation::AutomationLane;
 use crate::audio::core::clip::Clip;
-use crate::audio::core::plugin::{NoteEvent, PluginEvent};
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
+use crate::audio::core::plugin::{NoteEvent, NoteExpressionKind, PluginEvent};
+use crate::audio::midi::clock::{ClockUpdate, monotonic_seconds};
+use crate::audio::midi::sync;
+use crate::audio::plugins::mixer::metronome::ClickSpan;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 
 // 全局原子量：以 f64 的位模式存储播放位置，避免在回调中使用 Mutex
 pub static PLAYBACK_POSITION_BITS: AtomicU64 = AtomicU64::new(0);
 pub static IS_PLAYING: AtomicU64 = AtomicU64::new(0); // 0 = false, 1 = true
+// 当前音频图的总输出延迟（采样数，包含延迟补偿）
+pub static OUTPUT_LATENCY_SAMPLES: AtomicU32 = AtomicU32::new(0);
+// 当前速度（BPM，f64 位模式），供 JACK 时基回调等音频图之外的线程读取
+pub static TEMPO_BITS: AtomicU64 = AtomicU64::new(0x405e_0000_0000_0000); // 120.0
+pub static IS_RECORDING: AtomicU64 = AtomicU64::new(0); // 0 = false, 1 = true（含预备拍）
+// 音频设备的输出延迟（秒，f64 位模式）：从音频回调开始到该块实际播放的时间，由音频后端每块更新
+pub static DEVICE_LATENCY_BITS: AtomicU64 = AtomicU64::new(0);
 
 pub fn get_playback_position() -> f64 {
         f64::from_bits(PLAYBACK_POSITION_BITS.load(Ordering::Relaxed))
@@ -13,6 +24,130 @@ pub fn get_playback_position() -> f64 {
 pub fn get_is_playing() -> bool {
         IS_PLAYING.load(Ordering::Relaxed) == 1
 }
+pub fn get_output_latency() -> u32 {
+        OUTPUT_LATENCY_SAMPLES.load(Ordering::Relaxed)
+}
+pub fn get_tempo() -> f64 {
+        f64::from_bits(TEMPO_BITS.load(Ordering::Relaxed))
+}
+pub fn get_is_recording() -> bool {
+        IS_RECORDING.load(Ordering::Relaxed) == 1
+}
+pub fn get_device_latency() -> f64 {
+        f64::from_bits(DEVICE_LATENCY_BITS.load(Ordering::Relaxed))
+}
+pub fn set_device_latency(seconds: f64) {
+        DEVICE_LATENCY_BITS.store(seconds.max(0.0).to_bits(), Ordering::Relaxed);
+}
+
+// 跟随外部 MIDI Clock 时：相位误差超过该值（秒）直接跳转，否则在 PHASE_CORRECTION_TIME 内通过播放速率追上
+const MAX_EXTERNAL_DRIFT: f64 = 0.1;
+const PHASE_CORRECTION_TIME: f64 = 0.5;
+// 相位校正对播放速率的最大调整比例
+const MAX_RATE_CORRECTION: f64 = 0.1;
+// 单音符表情在块内的采样间隔（帧）：每个子块起点取一次插值，以 `MidiAt` 携带块内帧偏移发送
+pub const EXPRESSION_STEP_FRAMES: usize = 32;
+
+#[derive(Debug, Clone, Copy)]
+struct SoundingNote {
+        id: u32,
+        channel: u8,
+        note: u8,
+        // 音符已结束，但同一 (通道, 音高) 上仍有其它音符在发声，NoteOff 被推迟
+        released: bool,
+}
+
+/// 正在发声的音符表（按乐器、按音符 ID 跟踪）。
+/// 支持 note_id 的乐器（见 `Plugin::supports_note_ids`）按音符 ID 立即释放，重叠的同音高音符互不影响，
+/// MPE 成员通道也随之释放。不支持的乐器只能按 (通道, 音高) 区分发声：同音高重叠的音符按引用计数处理，
+/// 先结束的音符只标记为已释放，直到最后一个同音高音符结束时才为它们一并发送 NoteOff，避免被提前切断。
+#[derive(Debug, Default)]
+pub struct NoteTracker {
+        notes: HashMap<usize, Vec<SoundingNote>>,
+        // 支持 note_id 的乐器
+        note_id_instruments: HashSet<usize>,
+}
+
+impl NoteTracker {
+        /// 设置乐器是否支持 note_id（在构建音频图时调用）
+        pub fn set_note_ids(&mut self, inst_id: usize, supported: bool) {
+                if supported {
+                        self.note_id_instruments.insert(inst_id);
+                } else {
+                        self.note_id_instruments.remove(&inst_id);
+                }
+        }
+
+        pub fn note_on(&mut self, inst_id: usize, channel: u8, note: u8, id: u32) {
+                self.notes.entry(inst_id).or_default().push(SoundingNote {
+                        id,
+                        channel,
+                        note,
+                        released: false,
+                });
+        }
+
+        /// 结束音符；只为确实发出过 NoteOn 的音符生成 NoteOff
+        pub fn note_off(&mut self, inst_id: usize, id: u32, events: &mut Vec<PluginEvent>) {
+                let Some(list) = self.notes.get_mut(&inst_id) else {
+                        return;
+                };
+                let Some(index) = list.iter().position(|n| n.id == id && !n.released) else {
+                        return;
+                };
+                let (channel, note) = (list[index].channel, list[index].note);
+                if self.note_id_instruments.contains(&inst_id) {
+                        list.swap_remove(index);
+                        events.push(PluginEvent::Midi(NoteEvent::NoteOff {
+                                channel,
+                                note,
+                                note_id: Some(id),
+                        }));
+                        return;
+                }
+                list[index].released = true;
+                let held = list
+                        .iter()
+                        .any(|n| n.channel == channel && n.note == note && !n.released);
+                if held {
+                        return;
+                }
+                list.retain(|n| {
+                        if n.channel != channel || n.note != note {
+                                return true;
+                        }
+                        events.push(PluginEvent::Midi(NoteEvent::NoteOff {
+                                channel,
+                                note,
+                                note_id: Some(n.id),
+                        }));
+                        false
+                });
+        }
+
+        /// 为所有发声中的音符生成 NoteOff 并清空
+        pub fn release_all(&mut self, events: &mut HashMap<usize, Vec<PluginEvent>>) {
+                for (inst_id, notes) in self.notes.drain() {
+                        let inst_events = events.entry(inst_id).or_default();
+                        for n in notes {
+                                inst_events.push(PluginEvent::Midi(NoteEvent::NoteOff {
+                                        channel: n.channel,
+                                        note: n.note,
+                                        note_id: Some(n.id),
+                                }));
+                        }
+                }
+        }
+
+        /// 发声中（含推迟释放）的音符数量
+        pub fn len(&self) -> usize {
+                self.notes.values().map(Vec::len).sum()
+        }
+
+        pub fn is_empty(&self) -> bool {
+                self.len() == 0
+        }
+}
 
 // 简单的音序器：维护 Clips、播放时间与活动音符，并按块生成插件事件与路由映射
 pub struct Sequencer {
@@ -21,7 +156,31 @@ pub struct Sequencer {
         pub current_time: f64,
         pub tempo: f64,
         pub playing: bool,
-        pub active_notes: HashMap<usize, Vec<u8>>,
+        pub active_notes: NoteTracker,
+        // 定位（seek）或全部音符关闭请求后，在下一块开头释放所有发声的音符
+        release_pending: bool,
+        // 从音序器事件到实际听到声音之间的延迟（采样数），由 Mixer 的延迟补偿计算得出
+        pub output_latency: u32,
+        // 播放速率：本地走带为 1.0，跟随外部 MIDI Clock 时为主机速度 / 工程速度（含相位校正）
+        pub playback_rate: f64,
+        // 拍号（分子, 分母），用于节拍器与预备拍长度
+        pub time_signature: (u32, u32),
+        pub recording: bool,
+        // 录音预备拍的剩余时长（秒）；期间走带保持不动
+        count_in: f64,
+        // 本块覆盖的歌曲时间（预备拍期间为录音起点之前的时间），停止时为 None
+        block_span: Option<(f64, f64)>,
+        // 参数自动化曲线、每条曲线最近发送的值（NaN 表示尚未发送），以及本块生成的 `NodeParameter` 事件。
+        // 事件缓冲在 `set_automation` 中按曲线数量预留，音频线程中只清空和写入
+        automation: Vec<AutomationLane>,
+        automation_sent: Vec<f32>,
+        automation_events: Vec<PluginEvent>,
+}
+
+impl Default for Sequencer {
+        fn default() -> Self {
+                Self::new()
+        }
 }
 
 impl Sequencer {
@@ -32,21 +191,142 @@ impl Sequencer {
                         current_time: 0.0,
                         tempo: 120.0,
                         playing: false,
-                        active_notes: HashMap::new(),
+                        active_notes: NoteTracker::default(),
+                        release_pending: false,
+                        output_latency: 0,
+                        playback_rate: 1.0,
+                        time_signature: (4, 4),
+                        recording: false,
+                        count_in: 0.0,
+                        block_span: None,
+                        automation: Vec::new(),
+                        automation_sent: Vec::new(),
+                        automation_events: Vec::new(),
                 }
         }
 
+        /// 设置参数自动化曲线（构建音频图时调用）
+        pub fn set_automation(&mut self, lanes: Vec<AutomationLane>) {
+                self.automation_sent = vec![f32::NAN; lanes.len()];
+                self.automation_events = Vec::with_capacity(lanes.len());
+                self.automation = lanes;
+        }
+
+        /// 本块由自动化生成的节点参数事件（在 `process` 之后读取）
+        pub fn automation_events(&self) -> &[PluginEvent] {
+                &self.automation_events
+        }
+
         // 设置传输状态（播放/位置/节拍）
         pub fn set_transport(&mut self, playing: bool, position: Option<f64>, tempo: Option<f64>) {
                 self.playing = playing;
+                self.playback_rate = 1.0;
+                if !playing {
+                        self.recording = false;
+                        self.count_in = 0.0;
+                }
                 if let Some(pos) = position {
+                        // 跳转后原先发声的音符不会再收到各自的 NoteOff
                         self.current_time = pos;
+                        self.release_pending = true;
                 }
                 if let Some(t) = tempo {
                         self.tempo = t;
                 }
         }
 
+        /// 开始录音：先播放 `count_in_bars` 小节预备拍，再从当前位置开始走带；
+        /// 已在播放时直接从当前位置开始录音（不停下走带播放预备拍）
+        pub fn start_recording(&mut self, count_in_bars: u32) {
+                if self.playing {
+                        self.recording = true;
+                        return;
+                }
+                let (numerator, denominator) = self.time_signature;
+                let beat = 60.0 / self.tempo * 4.0 / denominator.max(1) as f64;
+                self.count_in = count_in_bars as f64 * numerator.max(1) as f64 * beat;
+                self.recording = true;
+                self.playing = true;
+                self.playback_rate = 1.0;
+        }
+
+        /// 本块的节拍信息，供节拍器使用；停止时为 None
+        pub fn click_span(&self) -> Option<ClickSpan> {
+                self.block_span.map(|(start, end)| ClickSpan {
+                        start,
+                        end,
+                        tempo: self.tempo,
+                        time_signature: self.time_signature,
+                })
+        }
+
+        // 循环长度：所有 Clip 的最大结束时间，至少 8 小节
+        fn loop_length(&self) -> f64 {
+                let max_end = self
+                        .clips
+                        .iter()
+                        .map(|clip| clip.start_time + clip.duration)
+                        .fold(0.0, f64::max);
+                max_end.max(8.0 * 4.0 * (60.0 / self.tempo))
+        }
+
+        /// 跟随外部 MIDI Clock 主机：主机位置按工程速度换算为秒（在循环内回绕），
+        /// 误差较小时调整播放速率平滑追上，定位或误差过大时直接跳转
+        pub fn follow_external(&mut self, update: &ClockUpdate) {
+                let ratio = update.tempo.map_or(self.playback_rate, |t| t / self.tempo);
+                let loop_length = self.loop_length();
+                // 从收到消息到本块开始经过的时间
+                let elapsed = if update.playing {
+                        (monotonic_seconds() - update.received).max(0.0)
+                } else {
+                        0.0
+                };
+                let target = (update.beats * 60.0 / self.tempo + elapsed * ratio).rem_euclid(loop_length);
+
+                if !update.playing {
+                        self.playing = false;
+                        self.playback_rate = 1.0;
+                        if update.relocate {
+                                self.current_time = target;
+                                self.release_pending = true;
+                        }
+                        return;
+                }
+
+                // 误差按循环长度取最短方向，循环回绕处不会误判为大幅漂移
+                let error =
+                        (target - self.current_time + loop_length / 2.0).rem_euclid(loop_length) - loop_length / 2.0;
+                if update.relocate || !self.playing || error.abs() > MAX_EXTERNAL_DRIFT {
+                        if (target - self.current_time).abs() > f64::EPSILON {
+                                self.current_time = target;
+                                self.release_pending = true;
+                        }
+                        self.playback_rate = ratio;
+                } else {
+                        self.playback_rate = (ratio + error / PHASE_CORRECTION_TIME).clamp(
+                                ratio * (1.0 - MAX_RATE_CORRECTION),
+                                ratio * (1.0 + MAX_RATE_CORRECTION),
+                        );
+                }
+                self.playing = true;
+        }
+
+        // 由 Mixer 报告音频图的总输出延迟
+        pub fn set_output_latency(&mut self, samples: u32) {
+                self.output_latency = samples;
+                OUTPUT_LATENCY_SAMPLES.store(samples, Ordering::Relaxed);
+        }
+
+        /// 输出延迟对应的秒数
+        pub fn output_latency_seconds(&self) -> f64 {
+                self.output_latency as f64 / self.sample_rate as f64
+        }
+
+        /// 在下一块开头为所有发声的音符发送 NoteOff
+        pub fn all_notes_off(&mut self) {
+                self.release_pending = true;
+        }
+
         pub fn add_clip(&mut self, clip: Clip) {
                 self.clips.push(clip);
         }
@@ -58,24 +338,28 @@ impl Sequencer {
                 let mut events = HashMap::new();
                 let mut routing = HashMap::new();
 
-                let duration = samples as f64 / self.sample_rate as f64;
+                // 歌曲时间中的块长度（跟随外部 MIDI Clock 时按播放速率伸缩）
+                let duration = samples as f64 / self.sample_rate as f64 * self.playback_rate;
 
-                // 计算循环长度（取所有 Clip 的最大结束时间或至少 8 小节）
-                let mut max_end = 0.0;
-                for clip in &self.clips {
-                        let end = clip.start_time + clip.duration;
-                        if end > max_end {
-                                max_end = end;
+                let loop_length = self.loop_length();
+                // 每帧与每个表情子块对应的歌曲时间
+                let step = duration / samples.max(1) as f64;
+                let step_duration = step * EXPRESSION_STEP_FRAMES as f64;
+
+                // 预备拍：走带保持不动，只推进录音起点之前的时间；预备拍在本块内结束时从本块开始走带
+                let mut playing = self.playing;
+                let mut count_in_span = None;
+                if playing && self.count_in > 0.0 {
+                        let start = self.current_time - self.count_in;
+                        count_in_span = Some((start, start + duration));
+                        self.count_in -= duration;
+                        playing = self.count_in <= 0.0;
+                        if playing {
+                                self.count_in = 0.0;
                         }
                 }
-                let min_length = 8.0 * 4.0 * (60.0 / self.tempo);
-                let loop_length = if max_end > min_length {
-                        max_end
-                } else {
-                        min_length
-                };
 
-                let mut end_time = if self.playing {
+                let mut end_time = if playing {
                         self.current_time + duration
                 } else {
                         self.current_time
@@ -83,24 +367,42 @@ impl Sequencer {
 
                 // 处理循环回绕
                 let mut looped = false;
-                if self.playing && end_time >= loop_length {
+                if playing && end_time >= loop_length {
                         end_time = loop_length;
                         looped = true;
                 }
 
-                // 非播放状态：发送所有活动音符的 NoteOff
-                if !self.playing {
-                        for (inst_id, notes) in self.active_notes.drain() {
-                                let inst_events = events.entry(inst_id).or_insert(Vec::new());
-                                for note in notes {
-                                        inst_events.push(PluginEvent::Midi(NoteEvent::NoteOff { note }));
+                // 定位后自动化值可能不连续，重新发送所有曲线的当前值
+                if self.release_pending {
+                        self.automation_sent.fill(f32::NAN);
+                }
+
+                // 参数自动化：播放时在块起点取值，只在值变化时发送（参数斜坡负责块内平滑）
+                self.automation_events.clear();
+                if playing {
+                        for (lane, sent) in self.automation.iter().zip(self.automation_sent.iter_mut()) {
+                                let Some(value) = lane.value_at(self.current_time) else {
+                                        continue;
+                                };
+                                if value != *sent {
+                                        *sent = value;
+                                        self.automation_events.push(PluginEvent::NodeParameter {
+                                                address: lane.address,
+                                                value,
+                                        });
                                 }
                         }
                 }
 
+                // 停止 / 暂停、定位或全部音符关闭：释放所有发声的音符
+                if !playing || self.release_pending {
+                        self.active_notes.release_all(&mut events);
+                        self.release_pending = false;
+                }
+
                 // 遍历 Clips，收集路由与事件（仅在播放时生成 NoteOn/NoteOff）
                 for clip in &self.clips {
-                        let is_active = if self.playing {
+                        let is_active = if playing {
                                 clip.start_time < end_time && (clip.start_time + clip.duration) > self.current_time
                         } else {
                                 self.current_time >= clip.start_time
@@ -120,11 +422,19 @@ impl Sequencer {
                                         }
                                 }
 
-                                if self.playing {
+                                if playing {
                                         for &inst_id in &clip.instrument_ids {
                                                 let inst_events = events.entry(inst_id).or_insert(Vec::new());
-                                                let active_list =
-                                                        self.active_notes.entry(inst_id).or_insert(Vec::new());
+
+                                                // 控制器事件先于同一块内的音符发送（例如音色切换应在音符之前生效）
+                                                for controller in &clip.controllers {
+                                                        let time_abs = clip.start_time + controller.relative_time;
+                                                        if time_abs >= self.current_time && time_abs < end_time {
+                                                                inst_events.push(PluginEvent::Midi(
+                                                                        controller.to_note_event(),
+                                                                ));
+                                                        }
+                                                }
 
                                                 for note in &clip.notes {
                                                         let note_start_abs = clip.start_time + note.relative_start;
@@ -136,24 +446,87 @@ impl Sequencer {
                                                         {
                                                                 inst_events.push(PluginEvent::Midi(
                                                                         NoteEvent::NoteOn {
+                                                                                channel: note.channel,
                                                                                 note: note.note,
                                                                                 velocity: note.velocity,
+                                                                                note_id: Some(note.id),
                                                                         },
                                                                 ));
-                                                                active_list.push(note.note);
+                                                                self.active_notes.note_on(
+                                                                        inst_id,
+                                                                        note.channel,
+                                                                        note.note,
+                                                                        note.id,
+                                                                );
+                                                        }
+
+                                                        // 单音符表情：每 EXPRESSION_STEP_FRAMES 帧在子块起点取一次插值（块内未变化的值不重复发送）；
+                                                        // 曲线结束后的第一个子块仍会发送一次，保证终点值送达
+                                                        if note_start_abs < end_time
+                                                                && note_end_abs >= self.current_time
+                                                        {
+                                                                for kind in [
+                                                                        NoteExpressionKind::Pitch,
+                                                                        NoteExpressionKind::Pressure,
+                                                                        NoteExpressionKind::Brightness,
+                                                                ] {
+                                                                        let Some((first, last)) =
+                                                                                note.expression_span(kind)
+                                                                        else {
+                                                                                continue;
+                                                                        };
+                                                                        let (first_abs, last_abs) = (
+                                                                                note_start_abs + first,
+                                                                                note_start_abs + last,
+                                                                        );
+                                                                        let mut previous = None;
+                                                                        for frame in (0..samples)
+                                                                                .step_by(EXPRESSION_STEP_FRAMES)
+                                                                        {
+                                                                                let at_abs = self.current_time
+                                                                                        + frame as f64 * step;
+                                                                                if first_abs >= at_abs + step_duration
+                                                                                        || last_abs
+                                                                                                <= at_abs
+                                                                                                        - step_duration
+                                                                                        || at_abs >= end_time
+                                                                                {
+                                                                                        continue;
+                                                                                }
+                                                                                let at = at_abs
+                                                                                        .clamp(first_abs, last_abs)
+                                                                                        - note_start_abs;
+                                                                                let Some(value) =
+                                                                                        note.expression_at(kind, at)
+                                                                                else {
+                                                                                        continue;
+                                                                                };
+                                                                                if previous == Some(value) {
+                                                                                        continue;
+                                                                                }
+                                                                                previous = Some(value);
+                                                                                inst_events.push(PluginEvent::MidiAt {
+                                                                                        frame: frame as u32,
+                                                                                        event: NoteEvent::NoteExpression {
+                                                                                                channel: note.channel,
+                                                                                                note: note.note,
+                                                                                                note_id: Some(note.id),
+                                                                                                expression: kind,
+                                                                                                value,
+                                                                                        },
+                                                                                });
+                                                                        }
+                                                                }
                                                         }
 
                                                         // NoteOff
                                                         if note_end_abs >= self.current_time && note_end_abs < end_time
                                                         {
-                                                                inst_events.push(PluginEvent::Midi(
-                                                                        NoteEvent::NoteOff { note: note.note },
-                                                                ));
-                                                                if let Some(pos) =
-                                                                        active_list.iter().position(|&n| n == note.note)
-                                                                {
-                                                                        active_list.remove(pos);
-                                                                }
+                                                                self.active_notes.note_off(
+                                                                        inst_id,
+                                                                        note.id,
+                                                                        inst_events,
+                                                                );
                                                         }
 
                                                         // 循环边界处强制终止仍在播放的音符
@@ -161,14 +534,11 @@ impl Sequencer {
                                                                 && note_start_abs < end_time
                                                                 && note_end_abs >= end_time
                                                         {
-                                                                inst_events.push(PluginEvent::Midi(
-                                                                        NoteEvent::NoteOff { note: note.note },
-                                                                ));
-                                                                if let Some(pos) =
-                                                                        active_list.iter().position(|&n| n == note.note)
-                                                                {
-                                                                        active_list.remove(pos);
-                                                                }
+                                                                self.active_notes.note_off(
+                                                                        inst_id,
+                                                                        note.id,
+                                                                        inst_events,
+                                                                );
                                                         }
                                                 }
                                         }
@@ -176,7 +546,23 @@ impl Sequencer {
                         }
                 }
 
-                if self.playing {
+                // MIDI 走带同步输出（未启用时立即返回）
+                sync::process_block(
+                        self.current_time,
+                        end_time,
+                        playing,
+                        self.tempo * self.playback_rate,
+                        // 消息与本块音频同时被听到：音频图延迟 + 设备输出延迟
+                        self.output_latency_seconds() + get_device_latency(),
+                );
+
+                self.block_span = if playing {
+                        Some((self.current_time, end_time))
+                } else {
+                        count_in_span
+                };
+
+                if playing {
                         if looped {
                                 self.current_time = 0.0
                         } else {
@@ -187,6 +573,8 @@ impl Sequencer {
                 // 更新全局播放状态
                 PLAYBACK_POSITION_BITS.store(self.current_time.to_bits(), Ordering::Relaxed);
                 IS_PLAYING.store(if self.playing { 1 } else { 0 }, Ordering::Relaxed);
+                TEMPO_BITS.store(self.tempo.to_bits(), Ordering::Relaxed);
+                IS_RECORDING.store(if self.recording { 1 } else { 0 }, Ordering::Relaxed);
 
                 (events, routing)
         }
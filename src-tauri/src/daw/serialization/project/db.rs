@@ -3,7 +3,7 @@ use rusqlite::{Connection, params};
 use std::path::Path;
 
 use crate::daw::model::Clip;
-use crate::daw::state::PluginInstanceData;
+use crate::daw::state::{MixerTrackData, PluginInstanceData};
 
 pub fn init_db(path: &Path) -> Result<Connection> {
         if let Some(parent) = path.parent() {
@@ -27,10 +27,29 @@ pub fn init_db(path: &Path) -> Result<Connection> {
             start REAL,
             duration REAL,
             velocity REAL,
+            channel INTEGER NOT NULL DEFAULT 0,
+            expressions TEXT,
             PRIMARY KEY (clip_id, note_index)
         )",
                 [],
         )?;
+        // 旧工程的 notes 表缺少后来加入的列：补上（列已存在时报错，忽略即可）
+        let _ = conn.execute("ALTER TABLE notes ADD COLUMN channel INTEGER NOT NULL DEFAULT 0", []);
+        let _ = conn.execute("ALTER TABLE notes ADD COLUMN expressions TEXT", []);
+
+        // 控制器事件：kind 为 ControllerKind 的 JSON
+        conn.execute(
+                "CREATE TABLE IF NOT EXISTS controllers (
+            clip_id TEXT,
+            event_index INTEGER,
+            kind TEXT,
+            channel INTEGER,
+            time REAL,
+            value REAL,
+            PRIMARY KEY (clip_id, event_index)
+        )",
+                [],
+        )?;
 
         Ok(conn)
 }
@@ -38,14 +57,21 @@ pub fn init_db(path: &Path) -> Result<Connection> {
 pub fn save_plugin_states(
         conn: &mut Connection,
         plugins: &Vec<PluginInstanceData>,
+        mixer_tracks: &[MixerTrackData],
         instances: &std::collections::HashMap<
                 String,
                 std::sync::Arc<std::sync::Mutex<Box<dyn crate::audio::core::plugin::Plugin>>>,
         >,
 ) -> Result<()> {
         let tx = conn.transaction()?;
-        for plugin in plugins.iter() {
-                let mut state_blob = if let Some(instance) = instances.get(&plugin.id) {
+        // 乐器实例与混音轨道插入效果实例的状态都按实例 id 保存
+        let entries = plugins.iter().map(|p| (&p.id, &p.name)).chain(
+                mixer_tracks
+                        .iter()
+                        .flat_map(|t| t.inserts.iter().map(|i| (&i.instance_id, &i.name))),
+        );
+        for (plugin_id, plugin_name) in entries {
+                let mut state_blob = if let Some(instance) = instances.get(plugin_id) {
                         if let Ok(inst) = instance.lock() {
                                 inst.get_state()
                         } else {
@@ -57,7 +83,7 @@ pub fn save_plugin_states(
 
                 // 如果插件没有提供二进制 state（空），尝试通过参数集合序列化回退保存
                 if state_blob.is_empty() {
-                        if let Some(instance) = instances.get(&plugin.id) {
+                        if let Some(instance) = instances.get(plugin_id) {
                                 if let Ok(inst) = instance.lock() {
                                         let params = inst.get_parameters();
                                         if !params.is_empty() {
@@ -79,7 +105,7 @@ pub fn save_plugin_states(
 
                 tx.execute(
                         "INSERT OR REPLACE INTO plugins (id, name, state) VALUES (?1, ?2, ?3)",
-                        params![plugin.id, plugin.name, state_blob],
+                        params![plugin_id, plugin_name, state_blob],
                 )?;
         }
         tx.commit()?;
@@ -89,12 +115,28 @@ pub fn save_plugin_states(
 pub fn save_notes(conn: &mut Connection, clips: &Vec<Clip>) -> Result<()> {
         let tx = conn.transaction()?;
         for clip in clips.iter() {
+                // 先清空该片段已保存的事件，避免删除的音符 / 控制器残留
+                tx.execute("DELETE FROM notes WHERE clip_id = ?1", params![clip.id])?;
+                tx.execute("DELETE FROM controllers WHERE clip_id = ?1", params![clip.id])?;
                 for (idx, note) in clip.notes.iter().enumerate() {
+                        // 表情曲线以 JSON 保存，没有表情的音符存 NULL
+                        let expressions = if note.expressions.is_empty() {
+                                None
+                        } else {
+                                Some(serde_json::to_string(&note.expressions)?)
+                        };
                         tx.execute(
-                "INSERT OR REPLACE INTO notes (clip_id, note_index, note, start, duration, velocity) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-                params![clip.id, idx as i64, note.note, note.start.time, note.duration.seconds, note.velocity],
+                "INSERT OR REPLACE INTO notes (clip_id, note_index, note, start, duration, velocity, channel, expressions) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![clip.id, idx as i64, note.note, note.start.time, note.duration.seconds, note.velocity, note.channel, expressions],
             )?;
                 }
+                for (idx, event) in clip.controllers.iter().enumerate() {
+                        let kind = serde_json::to_string(&event.kind)?;
+                        tx.execute(
+                                "INSERT OR REPLACE INTO controllers (clip_id, event_index, kind, channel, time, value) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                                params![clip.id, idx as i64, kind, event.channel, event.time.time, event.value],
+                        )?;
+                }
         }
         tx.commit()?;
         Ok(())
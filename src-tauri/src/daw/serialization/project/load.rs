@@ -1,9 +1,12 @@
 use anyhow::Result;
-use mlua::{Lua, Table};
-use rusqlite::{Connection, params};
+use mlua::{Lua, LuaSerdeExt, Table};
+use rusqlite::params;
 use std::fs;
 use std::path::Path;
+use uuid::Uuid;
 
+use crate::audio::core::automation::AutomationLane;
+use crate::audio::midi::mapping::{MappingScope, MidiMapping};
 use crate::daw::serialization::schema::*;
 
 pub fn load_project(path: &Path) -> Result<ProjectSchema> {
@@ -14,12 +17,15 @@ pub fn load_project(path: &Path) -> Result<ProjectSchema> {
         let globals = lua.globals();
 
         lua.load(r#"
-        _G.project_data = { meta = {}, tracks = {}, clips = {}, mixer = {}, plugins = {} }
+        _G.project_data = { meta = {}, tracks = {}, clips = {}, mixer = {}, plugins = {}, inserts = {}, midi_mappings = {}, automation = {} }
         function project(t) _G.project_data.meta = t end
         function track(t) table.insert(_G.project_data.tracks, t) end
         function clip(t) table.insert(_G.project_data.clips, t) end
         function mixer_strip(t) table.insert(_G.project_data.mixer, t) end
         function plugin(t) table.insert(_G.project_data.plugins, t) end
+        function insert(t) table.insert(_G.project_data.inserts, t) end
+        function midi_mapping(t) table.insert(_G.project_data.midi_mappings, t) end
+        function automation(t) table.insert(_G.project_data.automation, t) end
     "#)
                 .exec()
                 .map_err(|e| anyhow::anyhow!(e.to_string()))?;
@@ -34,10 +40,15 @@ pub fn load_project(path: &Path) -> Result<ProjectSchema> {
         let meta: Table = project_data.get("meta").map_err(|e| anyhow::anyhow!(e.to_string()))?;
         let tracks_tbl: Vec<Table> = project_data.get("tracks").map_err(|e| anyhow::anyhow!(e.to_string()))?;
         let clips_tbl: Vec<Table> = project_data.get("clips").map_err(|e| anyhow::anyhow!(e.to_string()))?;
-        let _mixer_tbl: Vec<Table> = project_data.get("mixer").map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let mixer_tbl: Vec<Table> = project_data.get("mixer").map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let inserts_tbl: Vec<Table> = project_data
+                .get("inserts")
+                .map_err(|e| anyhow::anyhow!(e.to_string()))?;
         let plugins_tbl: Vec<Table> = project_data
                 .get("plugins")
                 .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let midi_mappings_tbl: Vec<Table> = project_data.get("midi_mappings").unwrap_or_default();
+        let automation_tbl: Vec<Table> = project_data.get("automation").unwrap_or_default();
 
         let mut schema = ProjectSchema {
                 meta: ProjectMetadata {
@@ -56,15 +67,32 @@ pub fn load_project(path: &Path) -> Result<ProjectSchema> {
                                 .get("bpm")
                                 .map_err(|e| anyhow::anyhow!(e.to_string()))
                                 .unwrap_or(120.0),
-                        sample_rate: meta
-                                .get("sample_rate")
-                                .map_err(|e| anyhow::anyhow!(e.to_string()))
-                                .unwrap_or(44100),
-                        time_signature: (4, 4),
+                        // 旧工程（没有 double_precision 字段）总是写入 44100，并不是用户选择的采样率，按跟随设备处理
+                        sample_rate: match meta.get::<Option<bool>>("double_precision") {
+                                Ok(Some(_)) => meta.get("sample_rate").unwrap_or(0),
+                                _ => 0,
+                        },
+                        // 旧工程没有保存拍号，按 4/4 处理
+                        time_signature: meta
+                                .get::<Vec<u32>>("time_signature")
+                                .ok()
+                                .and_then(|ts| match ts[..] {
+                                        [numerator, denominator] if numerator > 0 && denominator > 0 => {
+                                                Some((numerator, denominator))
+                                        }
+                                        _ => None,
+                                })
+                                .unwrap_or((4, 4)),
+                        double_precision: meta.get("double_precision").unwrap_or(false),
                 },
                 tracks: vec![],
-                mixer: MixerSchema { tracks: vec![] },
+                mixer: MixerSchema {
+                        tracks: vec![],
+                        inserts: vec![],
+                },
                 plugins: vec![],
+                midi_mappings: vec![],
+                automation: vec![],
         };
 
         for t in tracks_tbl {
@@ -88,13 +116,83 @@ pub fn load_project(path: &Path) -> Result<ProjectSchema> {
                         routing_track_index: plugin.get("routing_track").unwrap_or(0),
                         format: plugin.get("format").unwrap_or("Internal".to_string()),
                         state_blob_id: None,
+                        sandboxed: plugin.get("sandboxed").unwrap_or(false),
                 });
         }
 
+        for m in mixer_tbl {
+                let id: usize = m.get("id").map_err(|e| anyhow::anyhow!(e.to_string()))?;
+                let ids_tbl: Option<Table> = m.get("plugin_instances").ok();
+                let mut plugin_instances = Vec::new();
+                if let Some(tbl) = ids_tbl {
+                        for (_, inst_id) in tbl.pairs::<usize, String>().flatten() {
+                                plugin_instances.push(inst_id);
+                        }
+                }
+                let default_label = if id == 0 {
+                        "Master".to_string()
+                } else {
+                        format!("Track {}", id)
+                };
+                let node_id: Option<String> = m.get("node_id").ok();
+                schema.mixer.tracks.push(MixerTrackSchema {
+                        id,
+                        node_id: node_id.and_then(|s| Uuid::parse_str(&s).ok()),
+                        label: m.get("label").unwrap_or(default_label),
+                        volume: m.get("volume").unwrap_or(1.0),
+                        pan: m.get("pan").unwrap_or(0.0),
+                        pan_front: m.get("pan_front").unwrap_or(1.0),
+                        layout: m
+                                .get::<String>("layout")
+                                .ok()
+                                .and_then(|l| serde_json::from_value(serde_json::Value::String(l)).ok())
+                                .unwrap_or_default(),
+                        mute: m.get("mute").unwrap_or(false),
+                        solo: m.get("solo").unwrap_or(false),
+                        plugin_instances,
+                });
+        }
+
+        for i in inserts_tbl {
+                schema.mixer.inserts.push(InsertSchema {
+                        id: i.get("id").map_err(|e| anyhow::anyhow!(e.to_string()))?,
+                        name: i.get("name").map_err(|e| anyhow::anyhow!(e.to_string()))?,
+                        label: i
+                                .get("label")
+                                .unwrap_or_else(|_| i.get("name").unwrap_or("Unknown".to_string())),
+                        bypass: i.get("bypass").unwrap_or(false),
+                        post_fader: i.get("post_fader").unwrap_or(false),
+                        sandboxed: i.get("sandboxed").unwrap_or(false),
+                });
+        }
+
+        for m in midi_mappings_tbl {
+                match lua.from_value::<MidiMapping>(mlua::Value::Table(m)) {
+                        Ok(mut mapping) => {
+                                mapping.scope = MappingScope::Project;
+                                schema.midi_mappings.push(mapping);
+                        }
+                        Err(e) => println!("Skipping invalid midi_mapping: {}", e),
+                }
+        }
+
+        for a in automation_tbl {
+                match lua.from_value::<AutomationLane>(mlua::Value::Table(a)) {
+                        Ok(lane) => schema.automation.push(lane),
+                        Err(e) => println!("Skipping invalid automation: {}", e),
+                }
+        }
+
         // load notes from data.db
         let db_path = path.join("data.db");
-        let conn = Connection::open(&db_path)?;
-        let mut stmt = conn.prepare("SELECT note, start, duration, velocity FROM notes WHERE clip_id = ?1")?;
+        // init_db 同时为旧工程补齐 channel 列与 controllers 表
+        let conn = super::db::init_db(&db_path)?;
+        let mut stmt = conn.prepare(
+                "SELECT note, start, duration, velocity, channel, expressions FROM notes WHERE clip_id = ?1",
+        )?;
+        let mut controller_stmt = conn.prepare(
+                "SELECT kind, channel, time, value FROM controllers WHERE clip_id = ?1 ORDER BY event_index",
+        )?;
 
         for c in clips_tbl {
                 let track_id: usize = c.get("track_id").map_err(|e| anyhow::anyhow!(e.to_string()))?;
@@ -106,7 +204,11 @@ pub fn load_project(path: &Path) -> Result<ProjectSchema> {
                                 start: row.get(1)?,
                                 duration: row.get(2)?,
                                 velocity: row.get(3)?,
-                                channel: 0,
+                                channel: row.get(4)?,
+                                expressions: row
+                                        .get::<_, Option<String>>(5)?
+                                        .and_then(|json| serde_json::from_str(&json).ok())
+                                        .unwrap_or_default(),
                         })
                 })?;
 
@@ -115,6 +217,28 @@ pub fn load_project(path: &Path) -> Result<ProjectSchema> {
                         notes.push(note?);
                 }
 
+                let controller_iter = controller_stmt.query_map(params![clip_id], |row| {
+                        Ok((
+                                row.get::<_, String>(0)?,
+                                row.get::<_, u8>(1)?,
+                                row.get::<_, f64>(2)?,
+                                row.get::<_, f32>(3)?,
+                        ))
+                })?;
+                let mut controllers = Vec::new();
+                for row in controller_iter {
+                        let (kind, channel, time, value) = row?;
+                        match serde_json::from_str(&kind) {
+                                Ok(kind) => controllers.push(ControllerSchema {
+                                        kind,
+                                        channel,
+                                        time,
+                                        value,
+                                }),
+                                Err(e) => println!("Skipping invalid controller event in clip {}: {}", clip_id, e),
+                        }
+                }
+
                 let type_str: String = c.get("type").unwrap_or("midi".to_string());
                 let audio_path: String = c.get("audio_path").unwrap_or("".to_string());
 
@@ -167,6 +291,7 @@ pub fn load_project(path: &Path) -> Result<ProjectSchema> {
                                 content_type,
                                 note_count: notes.len(),
                                 notes,
+                                controllers,
                                 instrument_ids,
                                 instrument_routes,
                         });
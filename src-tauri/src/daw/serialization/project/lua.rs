@@ -1,3 +1,6 @@
+use crate::audio::core::automation::AutomationLane;
+use crate::audio::midi::mapping::MidiMapping;
+use crate::daw::serialization::schema::ProjectSettings;
 use crate::daw::model::{ArrangementTrack, Clip};
 use std::fs;
 use std::path::Path;
@@ -7,6 +10,8 @@ pub fn generate_lua_script(
         clips: &Vec<Clip>,
         mixer_tracks: &Vec<crate::daw::state::MixerTrackData>,
         plugins: &Vec<crate::daw::state::PluginInstanceData>,
+        midi_mappings: &[MidiMapping],
+        settings: &ProjectSettings,
         project_path: &Path,
 ) -> String {
         let mut script = String::new();
@@ -15,14 +20,22 @@ pub fn generate_lua_script(
 
         script.push_str("project {\n");
         script.push_str("  name = \"Untitled Project\",\n");
-        script.push_str("  bpm = 120.0,\n");
-        script.push_str("  sample_rate = 44100\n");
+        script.push_str(&format!("  bpm = {:.1},\n", settings.bpm));
+        script.push_str(&format!(
+                "  time_signature = {{ {}, {} }},\n",
+                settings.time_signature.0, settings.time_signature.1
+        ));
+        // 未设置工程采样率时跟随输出设备，不写入
+        if settings.sample_rate > 0 {
+                script.push_str(&format!("  sample_rate = {},\n", settings.sample_rate));
+        }
+        script.push_str(&format!("  double_precision = {}\n", settings.double_precision));
         script.push_str("}\n\n");
 
         for plugin in plugins {
                 script.push_str(&format!(
-            "plugin {{\n  id = \"{}\",\n  name = \"{}\",\n  label = \"{}\",\n  routing_track = {},\n  format = \"Internal\"\n}}\n\n",
-            plugin.id, plugin.name, plugin.label, plugin.routing_track_index
+            "plugin {{\n  id = \"{}\",\n  name = \"{}\",\n  label = \"{}\",\n  routing_track = {},\n  format = \"Internal\",\n  sandboxed = {}\n}}\n\n",
+            plugin.id, plugin.name, plugin.label, plugin.routing_track_index, plugin.sandboxed
         ));
         }
 
@@ -77,9 +90,68 @@ pub fn generate_lua_script(
         }
 
         for mixer in mixer_tracks {
-                script.push_str(&format!("mixer_strip {{\n  id = {},\n  volume = {:.2},\n  pan = {:.2},\n  mute = {},\n  solo = {}\n}}\n\n",
-            mixer.id, mixer.volume, mixer.pan, mixer.mute, mixer.solo));
+                for insert in &mixer.inserts {
+                        script.push_str(&format!(
+                                "insert {{\n  id = \"{}\",\n  name = \"{}\",\n  label = \"{}\",\n  bypass = {},\n  post_fader = {},\n  sandboxed = {}\n}}\n\n",
+                                insert.instance_id, insert.name, insert.label, insert.bypass, insert.post_fader, insert.sandboxed
+                        ));
+                }
+
+                let insert_ids_str = mixer
+                        .inserts
+                        .iter()
+                        .map(|i| format!("\"{}\"", i.instance_id))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                let layout = serde_json::to_value(mixer.layout)
+                        .ok()
+                        .and_then(|v| v.as_str().map(str::to_string))
+                        .unwrap_or_default();
+                script.push_str(&format!("mixer_strip {{\n  id = {},\n  node_id = \"{}\",\n  label = \"{}\",\n  volume = {:.2},\n  pan = {:.2},\n  pan_front = {:.2},\n  layout = \"{}\",\n  mute = {},\n  solo = {},\n  plugin_instances = {{{}}}\n}}\n\n",
+            mixer.id, mixer.node_id, mixer.label, mixer.volume, mixer.pan, mixer.pan_front, layout, mixer.mute, mixer.solo, insert_ids_str));
+        }
+
+        for mapping in midi_mappings {
+                if let Ok(value) = serde_json::to_value(mapping) {
+                        script.push_str(&format!("midi_mapping {}\n\n", lua_literal(&value, 0)));
+                }
         }
 
         script
 }
+
+/// 参数自动化曲线的 Lua 片段（追加在工程脚本末尾）
+pub fn generate_automation_lua(lanes: &[AutomationLane]) -> String {
+        let mut script = String::new();
+        for lane in lanes {
+                if let Ok(value) = serde_json::to_value(lane) {
+                        script.push_str(&format!("automation {}\n\n", lua_literal(&value, 0)));
+                }
+        }
+        script
+}
+
+// 把 JSON 值写成 Lua 字面量（对象 -> 带键的表，数组 -> 序列表，null 省略）
+fn lua_literal(value: &serde_json::Value, indent: usize) -> String {
+        use serde_json::Value;
+        let pad = "  ".repeat(indent + 1);
+        match value {
+                Value::Null => "nil".to_string(),
+                Value::Bool(b) => b.to_string(),
+                Value::Number(n) => n.to_string(),
+                Value::String(s) => format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")),
+                Value::Array(items) => {
+                        let items: Vec<String> = items.iter().map(|v| lua_literal(v, indent + 1)).collect();
+                        format!("{{ {} }}", items.join(", "))
+                }
+                Value::Object(fields) => {
+                        let fields: Vec<String> = fields
+                                .iter()
+                                .filter(|(_, v)| !v.is_null())
+                                .map(|(k, v)| format!("{}{} = {}", pad, k, lua_literal(v, indent + 1)))
+                                .collect();
+                        format!("{{\n{}\n{}}}", fields.join(",\n"), "  ".repeat(indent))
+                }
+        }
+}
@@ -2,10 +2,12 @@ use anyhow::Result;
 use std::fs;
 use std::path::Path;
 
+use crate::audio::midi::mapping::{MappingScope, MidiMapping};
+use crate::daw::serialization::schema::ProjectSettings;
 use crate::daw::state::AppState;
 
 use super::db as db_helpers;
-use super::lua::generate_lua_script;
+use super::lua::{generate_automation_lua, generate_lua_script};
 use super::save_plugins as plugin_helpers;
 
 pub fn save_project(state: &AppState, project_path: &Path) -> Result<()> {
@@ -13,6 +15,18 @@ pub fn save_project(state: &AppState, project_path: &Path) -> Result<()> {
                 fs::create_dir_all(project_path)?;
         }
 
+        // 只有工程范围的 MIDI 映射随工程保存（全局映射保存在应用数据目录）；
+        // 在锁定轨道 / 实例之前复制，MIDI 状态锁不与其它锁嵌套
+        let midi_mappings: Vec<MidiMapping> = state
+                .midi_control
+                .lock()
+                .unwrap()
+                .mappings
+                .iter()
+                .filter(|m| m.scope == MappingScope::Project)
+                .cloned()
+                .collect();
+
         let db_path = project_path.join("data.db");
         let mut conn = db_helpers::init_db(&db_path)?;
 
@@ -22,12 +36,31 @@ pub fn save_project(state: &AppState, project_path: &Path) -> Result<()> {
         let plugins = state.active_plugins.lock().unwrap();
 
         let instances = state.plugin_instances.lock().unwrap();
-        db_helpers::save_plugin_states(&mut conn, &plugins, &*instances)?;
+        db_helpers::save_plugin_states(&mut conn, &plugins, &mixer_tracks, &*instances)?;
         db_helpers::save_notes(&mut conn, &clips)?;
 
         plugin_helpers::copy_plugins_into_project(state, project_path);
 
-        let lua_script = generate_lua_script(&tracks, &clips, &mixer_tracks, &plugins, project_path);
+        let automation = state.automation.lock().unwrap().clone();
+        let time_signature = state.time_signature.lock().unwrap().clone();
+        let processing = state.processing.lock().unwrap().clone();
+        let settings = ProjectSettings {
+                bpm: 120.0,
+                sample_rate: processing.sample_rate.unwrap_or(0),
+                time_signature: (time_signature.numerator, time_signature.denominator),
+                double_precision: processing.double_precision,
+        };
+
+        let mut lua_script = generate_lua_script(
+                &tracks,
+                &clips,
+                &mixer_tracks,
+                &plugins,
+                &midi_mappings,
+                &settings,
+                project_path,
+        );
+        lua_script.push_str(&generate_automation_lua(&automation));
         fs::write(project_path.join("project.lua"), lua_script)?;
 
         Ok(())
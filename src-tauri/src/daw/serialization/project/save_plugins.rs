@@ -4,7 +4,7 @@ use mlua::Table;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use crate::audio::plugins::manager::PluginSource;
+use crate::audio::plugins::manager::{PluginManager, PluginSource};
 use crate::daw::state::AppState;
 
 // 本文件负责在保存项目时将当前激活的插件复制到项目目录。
@@ -24,82 +24,99 @@ pub fn copy_plugins_into_project(state: &AppState, project_path: &Path) {
                 fs::create_dir_all(&plugins_dir).ok();
         }
 
-        let plugins = state.active_plugins.lock().unwrap();
-        for plugin in plugins.iter() {
-                match plugin_mgr.get_plugin_source(&plugin.id) {
-                        // 本分支处理本地（Local）插件：可能是带 manifest 的文件夹（官方样式），
-                        // 也可能是单个库文件（此时我们把其拷贝到 local 并生成一个简单 manifest）。
-                        PluginSource::Local(lib_path) => {
-                                if let Some(folder) = find_plugin_folder_with_manifest(&lib_path) {
-                                        let manifest_path = folder.join("manifest.lua");
-                                        let should_copy = manifest_copy_flag(&manifest_path);
+        // 乐器与混音轨道插入效果：`name` 字段即插件 unique_id
+        let mut sources: Vec<String> = Vec::new();
+        {
+                let plugins = state.active_plugins.lock().unwrap();
+                sources.extend(plugins.iter().map(|p| p.name.clone()));
+        }
+        {
+                let mixer_tracks = state.mixer_tracks.lock().unwrap();
+                sources.extend(mixer_tracks.iter().flat_map(|t| t.inserts.iter().map(|i| i.name.clone())));
+        }
+        sources.sort();
+        sources.dedup();
 
-                                        if should_copy {
-                                                let folder_name = folder
-                                                        .file_name()
-                                                        .map(|s| s.to_string_lossy().to_string())
-                                                        .unwrap_or(plugin.id.clone());
-                                                let dest = plugins_dir.join("official").join(folder_name);
-                                                if let Err(e) = copy_dir_all(&folder, &dest) {
-                                                        println!("Failed to copy plugin folder: {}", e);
-                                                }
-                                                let _ = copy_children_from_manifest(&folder, &plugins_dir);
-                                        }
-                                } else {
-                                        // no manifest: copy binary into local
-                                        let dest_dir = plugins_dir.join("local").join(&plugin.id);
-                                        if let Err(e) = fs::create_dir_all(&dest_dir) {
-                                                println!("Failed to create plugin dest dir: {}", e);
-                                        }
-                                        if let Some(fname) = lib_path.file_name() {
-                                                let _ = fs::copy(&lib_path, dest_dir.join(fname));
-                                                let manifest = format!(
-                                                        "return {{ id = \"{}\", name = \"{}\", backend = {{ type = \"local\", path = \"{}\" }} }}\n",
-                                                        plugin.id,
-                                                        plugin.name,
-                                                        fname.to_string_lossy()
-                                                );
-                                                let _ = fs::write(dest_dir.join("manifest.lua"), manifest);
+        for unique_id in sources.iter() {
+                copy_plugin(&plugin_mgr, &plugins_dir, unique_id);
+        }
+}
+
+/// 按插件来源复制单个插件（Local/Clap/Builtin）。
+fn copy_plugin(plugin_mgr: &PluginManager, plugins_dir: &Path, unique_id: &str) {
+        match plugin_mgr.get_plugin_source(unique_id) {
+                // 本分支处理本地（Local）插件：可能是带 manifest 的文件夹（官方样式），
+                // 也可能是单个库文件（此时我们把其拷贝到 local 并生成一个简单 manifest）。
+                PluginSource::Local(lib_path) => {
+                        if let Some(folder) = find_plugin_folder_with_manifest(&lib_path) {
+                                let manifest_path = folder.join("manifest.lua");
+                                let should_copy = manifest_copy_flag(&manifest_path);
+
+                                if should_copy {
+                                        let folder_name = folder
+                                                .file_name()
+                                                .map(|s| s.to_string_lossy().to_string())
+                                                .unwrap_or(unique_id.to_string());
+                                        let dest = plugins_dir.join("official").join(folder_name);
+                                        if let Err(e) = copy_dir_all(&folder, &dest) {
+                                                println!("Failed to copy plugin folder: {}", e);
                                         }
+                                        let _ = copy_children_from_manifest(&folder, plugins_dir);
                                 }
-                        }
-                        // 处理 CLAP 插件：如果是文件夹则递归复制，否则直接复制到目标路径。
-                        PluginSource::Clap(p) => {
-                                let folder_name = p
-                                        .file_name()
-                                        .map(|s| s.to_string_lossy().to_string())
-                                        .unwrap_or(plugin.id.clone());
-                                let dest = plugins_dir.join("clap").join(folder_name);
-                                if p.is_dir() {
-                                        if let Err(e) = copy_dir_all(&p, &dest) {
-                                                println!("Failed to copy clap plugin: {}", e);
-                                        }
-                                } else {
-                                        if let Some(parent) = dest.parent() {
-                                                let _ = fs::create_dir_all(parent);
-                                        }
-                                        let _ = fs::copy(&p, &dest);
+                        } else {
+                                // no manifest: copy binary into local
+                                let dest_dir = plugins_dir.join("local").join(unique_id);
+                                if let Err(e) = fs::create_dir_all(&dest_dir) {
+                                        println!("Failed to create plugin dest dir: {}", e);
+                                }
+                                if let Some(fname) = lib_path.file_name() {
+                                        let _ = fs::copy(&lib_path, dest_dir.join(fname));
+                                        let manifest = format!(
+                                                "return {{ id = \"{}\", name = \"{}\", backend = {{ type = \"local\", path = \"{}\" }} }}\n",
+                                                unique_id,
+                                                unique_id,
+                                                fname.to_string_lossy()
+                                        );
+                                        let _ = fs::write(dest_dir.join("manifest.lua"), manifest);
                                 }
                         }
-                        // 内置插件：创建一个只包含 backend 信息的 manifest 写入 official/<id>/manifest.lua
-                        PluginSource::Builtin(module_opt) => {
-                                let dest_folder = plugins_dir.join("official").join(&plugin.id);
-                                if let Err(e) = fs::create_dir_all(&dest_folder) {
-                                        println!("Failed to create builtin plugin folder: {}", e);
+                }
+                // 处理 CLAP 插件：如果是文件夹则递归复制，否则直接复制到目标路径。
+                PluginSource::Clap(p) => {
+                        let folder_name = p
+                                .file_name()
+                                .map(|s| s.to_string_lossy().to_string())
+                                .unwrap_or(unique_id.to_string());
+                        let dest = plugins_dir.join("clap").join(folder_name);
+                        if p.is_dir() {
+                                if let Err(e) = copy_dir_all(&p, &dest) {
+                                        println!("Failed to copy clap plugin: {}", e);
+                                }
+                        } else {
+                                if let Some(parent) = dest.parent() {
+                                        let _ = fs::create_dir_all(parent);
                                 }
-                                let module_field = if let Some(m) = module_opt {
-                                        format!("module = \"{}\"", m)
-                                } else {
-                                        String::new()
-                                };
-                                let manifest = format!(
-                                        "return {{ id = \"{}\", name = \"{}\", backend = {{ type = \"builtin\", {} }} }}\n",
-                                        plugin.id, plugin.name, module_field
-                                );
-                                let _ = fs::write(dest_folder.join("manifest.lua"), manifest);
+                                let _ = fs::copy(&p, &dest);
+                        }
+                }
+                // 内置插件：创建一个只包含 backend 信息的 manifest 写入 official/<id>/manifest.lua
+                PluginSource::Builtin(module_opt) => {
+                        let dest_folder = plugins_dir.join("official").join(unique_id);
+                        if let Err(e) = fs::create_dir_all(&dest_folder) {
+                                println!("Failed to create builtin plugin folder: {}", e);
                         }
-                        PluginSource::Unknown => {}
+                        let module_field = if let Some(m) = module_opt {
+                                format!("module = \"{}\"", m)
+                        } else {
+                                String::new()
+                        };
+                        let manifest = format!(
+                                "return {{ id = \"{}\", name = \"{}\", backend = {{ type = \"builtin\", {} }} }}\n",
+                                unique_id, unique_id, module_field
+                        );
+                        let _ = fs::write(dest_folder.join("manifest.lua"), manifest);
                 }
+                PluginSource::Unknown => {}
         }
 }
 
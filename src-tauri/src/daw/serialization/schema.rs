@@ -1,4 +1,9 @@
+use crate::audio::core::automation::AutomationLane;
+use crate::audio::core::channel_layout::ChannelLayout;
+use crate::audio::core::clip::{ControllerKind, ExpressionPoint};
+use crate::audio::midi::mapping::MidiMapping;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 /// 项目序列化类型集合
 ///
@@ -12,6 +17,12 @@ pub struct ProjectSchema {
         pub tracks: Vec<TrackSchema>,
         pub mixer: MixerSchema,
         pub plugins: Vec<PluginSchema>,
+        /// 随工程保存的 MIDI 控制映射
+        #[serde(default)]
+        pub midi_mappings: Vec<MidiMapping>,
+        /// 参数自动化曲线
+        #[serde(default)]
+        pub automation: Vec<AutomationLane>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -36,10 +47,13 @@ pub struct ProjectMetadata {
 pub struct ProjectSettings {
         /// 当前 BPM（节拍/分钟）
         pub bpm: f64,
-        /// 采样率（Hz）
+        /// 采样率（Hz）；0 表示跟随输出设备
         pub sample_rate: u32,
         /// 拍号（分子, 分母），例如 (4,4)
         pub time_signature: (u32, u32),
+        /// 混音求和总线以 f64 累加
+        #[serde(default)]
+        pub double_precision: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -103,6 +117,9 @@ pub struct ClipSchema {
         /// - 当内容为 MIDI 时，`notes` 会包含对应的 NoteSchema。
         pub note_count: usize,
         pub notes: Vec<NoteSchema>,
+        /// 控制器事件（CC、弯音、触后、音色切换），与音符一样存储在 data.db
+        #[serde(default)]
+        pub controllers: Vec<ControllerSchema>,
         /// 该 clip 使用的 instrument id 列表
         pub instrument_ids: Vec<String>,
         /// instrument routing（map instrument_id -> target track id）
@@ -131,12 +148,30 @@ pub struct NoteSchema {
         pub velocity: f32,
         /// MIDI 通道（0-15）
         pub channel: u8,
+        /// 单音符表情曲线（音高 / 压力 / 明亮度）
+        #[serde(default)]
+        pub expressions: Vec<ExpressionPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// 控制器事件的序列化结构（相对于 clip 起始点的时间）
+pub struct ControllerSchema {
+        pub kind: ControllerKind,
+        /// MIDI 通道（0-15）
+        pub channel: u8,
+        /// 相对于 clip 起始点的时间（秒）
+        pub time: f64,
+        /// CC / 触后为 0.0 - 1.0，弯音为 -1.0 - 1.0，音色切换为音色号
+        pub value: f32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 /// 混音器序列化结构：包含全部混音器轨道信息
 pub struct MixerSchema {
         pub tracks: Vec<MixerTrackSchema>,
+        /// 所有轨道上的插入效果定义（由 `MixerTrackSchema::plugin_instances` 按 id 引用）
+        #[serde(default)]
+        pub inserts: Vec<InsertSchema>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -144,18 +179,52 @@ pub struct MixerSchema {
 pub struct MixerTrackSchema {
         /// 混音轨道 id
         pub id: usize,
+        /// 稳定的节点 ID（参数地址使用；旧工程中缺失时加载后重新生成）
+        #[serde(default)]
+        pub node_id: Option<Uuid>,
+        /// 轨道显示名称
+        #[serde(default)]
+        pub label: String,
         /// 音量（-1.0 - 1.0 或 0.0 - 1.0，视实现而定）
         pub volume: f32,
         /// 声像（-1.0 左，1.0 右）
         pub pan: f32,
+        /// 环绕声像的前后坐标（-1.0 后，1.0 前）
+        #[serde(default = "default_pan_front")]
+        pub pan_front: f32,
+        /// 通道布局（旧工程缺失时为立体声）
+        #[serde(default)]
+        pub layout: ChannelLayout,
         /// 静音标志
         pub mute: bool,
         /// solo 标志
         pub solo: bool,
-        /// 该混音轨道上插入的 plugin instance id 列表
+        /// 该混音轨道上插入的 plugin instance id 列表（按处理顺序）
         pub plugin_instances: Vec<String>,
 }
 
+fn default_pan_front() -> f32 {
+        1.0
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// 插入效果实例的序列化结构（状态 blob 与乐器实例一样按 id 存于 `plugins` 表）
+pub struct InsertSchema {
+        /// 插件实例 ID
+        pub id: String,
+        /// 插件 unique_id（用于重建实例）
+        pub name: String,
+        /// 插件 label（UI 显示名）
+        pub label: String,
+        /// 旁通标志
+        pub bypass: bool,
+        /// 是否位于推子之后
+        pub post_fader: bool,
+        /// 是否在沙箱子进程中运行
+        #[serde(default)]
+        pub sandboxed: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 /// 插件在项目中的元数据（用于生成 `project.lua` 并在 `data.db` 中查找对应状态 blob）
 pub struct PluginSchema {
@@ -171,4 +240,7 @@ pub struct PluginSchema {
         pub format: String,
         /// 在 SQLite `plugins` 表中对应的状态 blob 的 id（如有）
         pub state_blob_id: Option<i64>,
+        /// 是否在沙箱子进程中运行
+        #[serde(default)]
+        pub sandboxed: bool,
 }
@@ -0,0 +1,41 @@
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+// 应用级设置文件：以 JSON 保存在应用数据目录（`audio.json`、`meters.json`、`metronome.json`、
+// `safety.json`、`midi.json` 等），启动时恢复。读取失败或格式不符时按未保存处理。
+
+fn settings_path(app: &AppHandle, file: &str) -> Option<PathBuf> {
+        app.path().app_data_dir().ok().map(|dir| dir.join(file))
+}
+
+/// 读取应用数据目录下的设置文件 `file`；文件不存在或无法解析时为 None
+pub fn load_json_setting<T: DeserializeOwned>(app: &AppHandle, file: &str) -> Option<T> {
+        let content = std::fs::read(settings_path(app, file)?).ok()?;
+        match serde_json::from_slice(&content) {
+                Ok(value) => Some(value),
+                Err(e) => {
+                        println!("Settings: ignoring invalid {}: {}", file, e);
+                        None
+                }
+        }
+}
+
+/// 把设置写入应用数据目录下的 `file`（失败只记录日志）
+pub fn save_json_setting<T: Serialize + ?Sized>(app: &AppHandle, file: &str, value: &T) {
+        let Some(path) = settings_path(app, file) else {
+                return;
+        };
+        if let Some(dir) = path.parent() {
+                let _ = std::fs::create_dir_all(dir);
+        }
+        match serde_json::to_vec_pretty(value) {
+                Ok(json) => {
+                        if let Err(e) = std::fs::write(&path, json) {
+                                println!("Settings: failed to save {}: {}", path.display(), e);
+                        }
+                }
+                Err(e) => println!("Settings: failed to serialize {}: {}", file, e),
+        }
+}
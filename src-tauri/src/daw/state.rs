@@ -1,7 +1,14 @@
+use crate::audio::core::automation::AutomationLane;
+use crate::audio::core::channel_layout::ChannelLayout;
 use crate::audio::core::plugin::Plugin;
 use crate::audio::engine::AudioEngine;
 use crate::audio::plugins::manager::PluginManager;
-use crate::daw::model::{ArrangementTrack, Clip};
+use crate::audio::plugins::mixer::metronome::MetronomeSettings;
+use crate::audio::plugins::mixer::safety::SafetySettings;
+use crate::audio::plugins::snapshots::InstanceSnapshot;
+use crate::daw::analysis::AnalysisSession;
+use crate::daw::midi_control::MidiControlState;
+use crate::daw::model::{ArrangementTrack, Clip, ProcessingSettings, TimeSignature};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -14,18 +21,42 @@ pub struct PluginInstanceData {
         pub name: String,
         pub label: String,
         pub routing_track_index: usize,
+        // 是否在沙箱子进程中运行
+        #[serde(default)]
+        pub sandboxed: bool,
+}
+
+// 混音轨道上的插入效果槽位（实例本身登记在 plugin_instances 中，键为 instance_id）
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InsertSlotData {
+        pub instance_id: String,
+        // 插件 unique_id，用于重建实例
+        pub name: String,
+        pub label: String,
+        pub bypass: bool,
+        // true 表示位于推子之后
+        pub post_fader: bool,
+        #[serde(default)]
+        pub sandboxed: bool,
 }
 
 // 混音轨道在 UI/状态中的表示（用于显示与电平映射）
 #[derive(Clone, Serialize)]
 pub struct MixerTrackData {
         pub id: usize,
+        // 稳定的节点 ID：参数地址（`ParamAddress`）以此定位轨道，不随轨道顺序变化
+        pub node_id: Uuid,
         pub label: String,
         pub volume: f32,
         pub pan: f32,
+        // 环绕声像的前后坐标（1 = 前），仅在输出到环绕总线时使用
+        pub pan_front: f32,
+        // 轨道 / 总线的通道布局；总轨（0）的布局决定其它轨道的输出目标
+        pub layout: ChannelLayout,
         pub mute: bool,
         pub solo: bool,
         pub meter_id: Option<Uuid>,
+        pub inserts: Vec<InsertSlotData>,
 }
 
 // 应用全局状态：持有音频引擎、插件管理器、轨道、片段与实例引用等（多线程通过 Mutex/Arc 保护）
@@ -40,4 +71,19 @@ pub struct AppState {
         pub plugin_instances: Mutex<HashMap<String, Arc<Mutex<Box<dyn Plugin>>>>>,
         // 未应用到实例的插件序列化状态（加载项目时暂存）
         pub pending_plugin_states: Mutex<HashMap<String, Vec<u8>>>,
+        // 实例快照：实例 ID -> 槽位（"A"/"B"/编号）-> 快照，仅在会话内保存
+        pub instance_snapshots: Mutex<HashMap<String, HashMap<String, InstanceSnapshot>>>,
+        // MIDI 控制：打开的输入端口、控制映射与学习状态
+        pub midi_control: Mutex<MidiControlState>,
+        // 节拍器设置（全局，保存在应用数据目录）与工程拍号，重建音频图时应用到 Mixer
+        pub metronome: Mutex<MetronomeSettings>,
+        pub time_signature: Mutex<TimeSignature>,
+        // 总输出安全级（限制器）设置（全局，保存在应用数据目录），重建音频图时应用到 Mixer
+        pub master_safety: Mutex<SafetySettings>,
+        // 工程采样率与求和精度，重建音频图 / 启动引擎时应用
+        pub processing: Mutex<ProcessingSettings>,
+        // 参数自动化曲线（按参数地址），随工程保存，重建音频图时交给音序器
+        pub automation: Mutex<Vec<AutomationLane>>,
+        // 频谱 / 示波器分析会话（仅在本次运行中存在）
+        pub analysis_sessions: Mutex<HashMap<Uuid, AnalysisSession>>,
 }
@@ -1,5 +1,5 @@
 pub mod audio;
-mod daw;
+pub mod daw;
 
 use crate::audio::engine::AudioEngine;
 use crate::audio::plugins::manager::PluginManager;
@@ -59,28 +59,39 @@ fn log_msg(msg: String) {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+        // 记录 UI 主线程，供插件宿主的线程检查使用
+        crate::audio::core::threads::register_main_thread();
+
         // 初始化默认混音轨道（0 = Master，后续为常规轨道）
         let mut tracks = Vec::new();
         // Track 0 为 Master
         tracks.push(MixerTrackData {
                 id: 0,
+                node_id: Uuid::new_v4(),
                 label: "Master".to_string(),
                 volume: 1.0,
                 pan: 0.0,
+                pan_front: 1.0,
+                layout: Default::default(),
                 mute: false,
                 solo: false,
                 meter_id: Some(Uuid::new_v4()),
+                inserts: Vec::new(),
         });
 
         for i in 1..5 {
                 tracks.push(MixerTrackData {
                         id: i,
+                        node_id: Uuid::new_v4(),
                         label: format!("Track {}", i),
                         volume: 1.0,
                         pan: 0.0,
+                        pan_front: 1.0,
+                        layout: Default::default(),
                         mute: false,
                         solo: false,
                         meter_id: Some(Uuid::new_v4()),
+                        inserts: Vec::new(),
                 });
         }
 
@@ -91,6 +102,7 @@ pub fn run() {
                 name: "com.mydaw.simplesynth".to_string(),
                 label: "Grand Piano".to_string(),
                 routing_track_index: 0,
+                sandboxed: false,
         });
 
         // 初始化编排轨道（创建 4 个，默认路由到对应的 Mixer Track 1-4）
@@ -117,6 +129,24 @@ pub fn run() {
                         clips: Mutex::new(Vec::new()),
                         plugin_instances: Mutex::new(std::collections::HashMap::new()),
                         pending_plugin_states: Mutex::new(std::collections::HashMap::new()),
+                        instance_snapshots: Mutex::new(std::collections::HashMap::new()),
+                        midi_control: Mutex::new(Default::default()),
+                        metronome: Mutex::new(Default::default()),
+                        time_signature: Mutex::new(Default::default()),
+                        master_safety: Mutex::new(Default::default()),
+                        processing: Mutex::new(Default::default()),
+                        automation: Mutex::new(Vec::new()),
+                        analysis_sessions: Mutex::new(std::collections::HashMap::new()),
+                })
+                .setup(|app| {
+                        daw::pump::spawn_main_thread_pump(app.handle().clone());
+                        daw::meter_stream::spawn_meter_stream(app.handle().clone());
+                        daw::commands::audio::load_audio_settings(app.handle());
+                        daw::commands::metronome::load_metronome_settings(app.handle());
+                        daw::commands::meter::load_meter_settings(app.handle());
+                        daw::commands::safety::load_safety_settings(app.handle());
+                        daw::midi_control::load_global_settings(app.handle());
+                        Ok(())
                 })
                 .plugin(tauri_plugin_opener::init())
                 .plugin(tauri_plugin_dialog::init())
@@ -124,15 +154,76 @@ pub fn run() {
                         greet,
                         toggle_audio,
                         update_parameter,
+                        set_node_parameter,
+                        get_automation_lanes,
+                        set_automation_lane,
+                        remove_automation_lane,
+                        get_parameter_smoothing,
+                        set_parameter_smoothing,
                         get_instance_parameters,
                         set_instance_parameter,
+                        format_instance_parameter,
+                        parse_instance_parameter,
+                        list_plugin_presets,
+                        get_plugin_preset_tags,
+                        save_plugin_preset,
+                        load_plugin_preset,
+                        rename_plugin_preset,
+                        delete_plugin_preset,
+                        set_plugin_preset_metadata,
+                        list_instance_snapshots,
+                        store_instance_snapshot,
+                        recall_instance_snapshot,
+                        copy_instance_snapshot,
+                        clear_instance_snapshot,
+                        morph_instance_snapshots,
+                        list_midi_inputs,
+                        open_midi_input,
+                        close_midi_input,
+                        get_midi_mappings,
+                        start_midi_learn,
+                        cancel_midi_learn,
+                        add_midi_mapping,
+                        update_midi_mapping,
+                        remove_midi_mapping,
+                        list_midi_outputs,
+                        get_midi_sync_settings,
+                        set_midi_sync_settings,
+                        get_midi_clock_input,
+                        set_midi_clock_input,
+                        get_audio_backend,
+                        set_audio_backend,
+                        get_processing_settings,
+                        set_processing_settings,
+                        get_metronome,
+                        set_metronome,
+                        get_time_signature,
+                        set_time_signature,
                         add_plugin_instance,
                         remove_plugin_instance,
                         update_plugin_label,
                         get_meter_levels_cmd,
+                        reset_meters_cmd,
+                        get_meter_ballistics_cmd,
+                        set_meter_ballistics_cmd,
+                        clear_meter_clips_cmd,
+                        get_master_safety,
+                        set_master_safety,
+                        unmute_plugins,
+                        start_analysis,
+                        set_analysis_settings,
+                        stop_analysis,
+                        get_analysis_sessions,
                         add_mixer_track,
                         remove_mixer_track,
                         get_mixer_tracks,
+                        get_mixer_inserts,
+                        add_mixer_insert,
+                        remove_mixer_insert,
+                        move_mixer_insert,
+                        set_mixer_insert_bypass,
+                        set_mixer_insert_placement,
+                        set_mixer_track_layout,
                         set_instrument_routing,
                         get_active_plugins,
                         add_clip,
@@ -143,9 +234,13 @@ pub fn run() {
                         remove_clip,
                         play,
                         get_playback_state,
+                        get_recording_state,
+                        record,
+                        get_output_latency_cmd,
                         pause,
                         stop,
                         seek,
+                        all_notes_off,
                         get_available_plugins,
                         get_plugin_parameters,
                         import_plugin,
@@ -2,6 +2,11 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+        // 以插件沙箱子进程方式启动时，只运行插件宿主循环
+        if let Some(code) = my_daw_lib::audio::sandbox::run_child_if_requested() {
+                std::process::exit(code);
+        }
+
         // Linux: 禁用 WebKit DMA-BUF 渲染以规避 Wayland 的 Error 71 问题
         #[cfg(target_os = "linux")]
         unsafe {
@@ -0,0 +1,101 @@
+use my_daw_lib::audio::analysis::scope::waveform_snapshot;
+use my_daw_lib::audio::analysis::spectrum::SpectrumAnalyzer;
+use my_daw_lib::audio::analysis::tap::{SampleRing, tap_point};
+use my_daw_lib::audio::core::plugin::AudioBuffer;
+use std::f32::consts::TAU;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// Analysis taps: the lock-free sample ring, FFT spectra and waveform snapshots.
+
+#[test]
+fn ring_skips_overwritten_samples() {
+        let ring = SampleRing::new(8);
+        let mut out = Vec::new();
+        ring.push([1.0, 2.0, 3.0]);
+        let position = ring.read(0, &mut out);
+        assert_eq!((position, out.as_slice()), (3, [1.0, 2.0, 3.0].as_slice()));
+
+        // the reader fell behind by more than the capacity: only the newest 8 samples remain
+        ring.push((4..=14).map(|n| n as f32));
+        out.clear();
+        assert_eq!(ring.read(position, &mut out), 14);
+        assert_eq!(out, (7..=14).map(|n| n as f32).collect::<Vec<_>>());
+}
+
+#[test]
+fn tap_ring_lives_only_while_listened_to() {
+        let node = Uuid::new_v4();
+        let tap = tap_point(node).unwrap();
+        // the graph and the analysis sessions share one tap per node
+        assert!(Arc::ptr_eq(&tap, &tap_point(node).unwrap()));
+
+        let mut samples = [0.5, 1.5, -1.0, 1.0];
+        let write = |samples: &mut [f32]| {
+                tap.write(&AudioBuffer {
+                        samples,
+                        channels: 2,
+                        sample_rate: 48000.0,
+                })
+        };
+        write(&mut samples);
+        let first = tap.add_listener().unwrap();
+        let second = tap.add_listener().unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(first.written(), 0);
+        write(&mut samples);
+        let mut out = Vec::new();
+        first.read(0, &mut out);
+        assert_eq!(out, [-0.25, 1.25]);
+
+        // the ring is released when the last listener leaves
+        tap.remove_listener().unwrap();
+        assert_eq!(Arc::strong_count(&first), 3);
+        tap.remove_listener().unwrap();
+        assert_eq!(Arc::strong_count(&first), 2);
+        write(&mut samples);
+        assert_eq!(first.written(), 2);
+
+        // once nothing holds the tap a later lookup starts afresh
+        let weak = Arc::downgrade(&tap);
+        drop(tap);
+        assert!(weak.upgrade().is_none());
+        assert!(!tap_point(node).unwrap().has_listeners());
+}
+
+#[test]
+fn sine_peaks_in_its_band() {
+        let rate = 48000.0;
+        let samples: Vec<f32> = (0..8192)
+                .map(|n| 0.5 * (TAU * 1000.0 * n as f32 / rate).sin())
+                .collect();
+        let mut analyzer = SpectrumAnalyzer::new(4096, 0.0);
+        analyzer.analyze(&samples);
+        let (frequencies, levels) = analyzer.bands(rate, 20.0, 96);
+        assert_eq!(frequencies.len(), 96);
+        assert!(frequencies.windows(2).all(|f| f[0] < f[1]));
+
+        let (peak, level) = levels.iter().enumerate().fold(
+                (0, f32::MIN),
+                |m, (i, l)| if *l > m.1 { (i, *l) } else { m },
+        );
+        // -6 dBFS within the Hann window's scalloping loss
+        assert!((level + 6.02).abs() < 1.5, "{}", level);
+        assert!(
+                (frequencies[peak] / 1000.0).log2().abs() < 0.1,
+                "{}",
+                frequencies[peak]
+        );
+        let far = frequencies.iter().position(|f| *f > 8000.0).unwrap();
+        assert!(levels[far] < -60.0, "{}", levels[far]);
+}
+
+#[test]
+fn waveform_snapshot_keeps_extremes() {
+        let samples = [0.0, 1.0, -0.5, 0.25, -1.0, 0.5];
+        assert_eq!(
+                waveform_snapshot(&samples, 2),
+                vec![[-0.5, 1.0], [-1.0, 0.5]]
+        );
+        assert_eq!(waveform_snapshot(&samples[..1], 4), vec![[0.0, 0.0]]);
+}
@@ -0,0 +1,73 @@
+use my_daw_lib::audio::core::automation::{AutomationLane, AutomationPoint};
+use my_daw_lib::audio::core::plugin::{ParamAddress, PluginEvent};
+use my_daw_lib::daw::sequencer::Sequencer;
+use uuid::Uuid;
+
+// Parameter automation lanes addressed by node UUID + parameter ID.
+
+fn lane(address: ParamAddress, points: &[(f64, f32)]) -> AutomationLane {
+        AutomationLane {
+                address,
+                points: points
+                        .iter()
+                        .map(|&(time, value)| AutomationPoint { time, value })
+                        .collect(),
+        }
+}
+
+fn values(sequencer: &Sequencer) -> Vec<(ParamAddress, f32)> {
+        sequencer
+                .automation_events()
+                .iter()
+                .cloned()
+                .filter_map(|e| match e {
+                        PluginEvent::NodeParameter { address, value } => Some((address, value)),
+                        _ => None,
+                })
+                .collect()
+}
+
+#[test]
+fn lane_interpolates_and_holds_end_points() {
+        let lane = lane(
+                ParamAddress::new(Uuid::new_v4(), 3),
+                &[(1.0, 0.0), (2.0, 1.0)],
+        );
+        assert_eq!(lane.value_at(0.0), Some(0.0));
+        assert_eq!(lane.value_at(1.5), Some(0.5));
+        assert_eq!(lane.value_at(5.0), Some(1.0));
+        assert_eq!(
+                AutomationLane {
+                        address: lane.address,
+                        points: vec![]
+                }
+                .value_at(1.0),
+                None
+        );
+}
+
+#[test]
+fn sequencer_sends_automation_only_while_playing_and_on_change() {
+        let ramp = ParamAddress::new(Uuid::new_v4(), 0);
+        let constant = ParamAddress::new(Uuid::new_v4(), 250);
+        let mut sequencer = Sequencer::new();
+        sequencer.sample_rate = 1000.0;
+        sequencer.set_automation(vec![
+                lane(ramp, &[(0.0, 0.0), (1.0, 1.0)]),
+                lane(constant, &[(0.0, 0.5)]),
+        ]);
+
+        sequencer.process(100);
+        assert!(values(&sequencer).is_empty());
+
+        sequencer.set_transport(true, Some(0.0), None);
+        sequencer.process(100);
+        assert_eq!(values(&sequencer), vec![(ramp, 0.0), (constant, 0.5)]);
+        sequencer.process(100);
+        assert_eq!(values(&sequencer), vec![(ramp, 0.1)]);
+
+        // 定位后重新发送所有曲线的当前值
+        sequencer.set_transport(true, Some(0.5), None);
+        sequencer.process(100);
+        assert_eq!(values(&sequencer), vec![(ramp, 0.5), (constant, 0.5)]);
+}
@@ -0,0 +1,151 @@
+use my_daw_lib::audio::core::channel_layout::{ChannelLayout, pan_gains, remix};
+use my_daw_lib::audio::plugins::mixer::panner::SurroundPanner;
+
+// Channel layouts: up/downmix matrices between mismatched nodes and surround panning.
+
+const MINUS_3DB: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+}
+
+#[test]
+fn layouts_round_trip_channel_counts() {
+        for layout in ChannelLayout::ALL {
+                assert_eq!(ChannelLayout::from_channels(layout.channels()), layout);
+        }
+        assert_eq!(ChannelLayout::Surround51.channels(), 6);
+        assert_eq!(ChannelLayout::from_channels(3), ChannelLayout::Stereo);
+        assert_eq!(
+                serde_json::to_string(&ChannelLayout::Surround71).unwrap(),
+                "\"7.1\""
+        );
+}
+
+#[test]
+fn mono_and_stereo_conversions() {
+        let mut stereo = [0.0; 4];
+        remix(
+                &[1.0, 0.5],
+                ChannelLayout::Mono,
+                &mut stereo,
+                ChannelLayout::Stereo,
+        );
+        // planar: L0 L1 R0 R1
+        assert!(close(stereo[0], MINUS_3DB) && close(stereo[2], MINUS_3DB));
+        assert!(close(stereo[1], 0.5 * MINUS_3DB) && close(stereo[3], 0.5 * MINUS_3DB));
+
+        // remix accumulates into the output
+        let mut mono = [1.0];
+        remix(
+                &[0.2, 0.6],
+                ChannelLayout::Stereo,
+                &mut mono,
+                ChannelLayout::Mono,
+        );
+        assert!(close(mono[0], 1.4));
+
+        // stereo into 5.1 stays on the front pair
+        let mut surround = [0.0; 6];
+        remix(
+                &[1.0, 2.0],
+                ChannelLayout::Stereo,
+                &mut surround,
+                ChannelLayout::Surround51,
+        );
+        assert_eq!(surround, [1.0, 2.0, 0.0, 0.0, 0.0, 0.0]);
+}
+
+#[test]
+fn surround_downmix_follows_itu() {
+        let matrix = ChannelLayout::Stereo.mix_matrix_from(ChannelLayout::Surround51);
+        // L R C LFE Ls Rs -> Lo = L + 0.707 C + 0.707 Ls, LFE dropped
+        let left = &matrix[0][..6];
+        assert!(close(left[0], 1.0) && close(left[1], 0.0) && close(left[2], MINUS_3DB));
+        assert!(close(left[3], 0.0) && close(left[4], MINUS_3DB) && close(left[5], 0.0));
+
+        // 7.1 -> 5.1: side surrounds map directly, rear surrounds fold in at -3 dB
+        let matrix = ChannelLayout::Surround51.mix_matrix_from(ChannelLayout::Surround71);
+        assert!(close(matrix[4][6], 1.0) && close(matrix[4][4], MINUS_3DB) && close(matrix[3][3], 1.0));
+        // quad rear pair -> 5.1 surrounds
+        let matrix = ChannelLayout::Surround51.mix_matrix_from(ChannelLayout::Quad);
+        assert!(close(matrix[4][2], 1.0) && close(matrix[5][3], 1.0));
+}
+
+#[test]
+fn pan_gains_are_power_preserving() {
+        for layout in [
+                ChannelLayout::Quad,
+                ChannelLayout::Surround51,
+                ChannelLayout::Surround71,
+        ] {
+                for (x, y) in [(0.0, 1.0), (-1.0, 0.0), (0.7, -0.7), (0.2, 0.1), (0.0, 0.0)] {
+                        let gains = pan_gains(layout, x, y, 0.0);
+                        let power: f32 = gains.iter().map(|g| g * g).sum();
+                        assert!(close(power, 1.0), "{:?} ({}, {}): {}", layout, x, y, power);
+                }
+        }
+
+        // front centre goes to the centre speaker only; LFE is never panned
+        let gains = pan_gains(ChannelLayout::Surround51, 0.0, 1.0, 0.0);
+        assert!(close(gains[2], 1.0) && close(gains[0], 0.0) && gains[3] == 0.0);
+
+        // hard left sits between L (-30°) and Ls (-110°)
+        let gains = pan_gains(ChannelLayout::Surround51, -1.0, 0.0, 0.0);
+        assert!(gains[0] > 0.0 && gains[4] > 0.0 && close(gains[1], 0.0) && close(gains[2], 0.0));
+        assert!(gains[4] > gains[0]);
+
+        // the centre of the room spreads evenly
+        let gains = pan_gains(ChannelLayout::Quad, 0.0, 0.0, 0.0);
+        assert!(gains[..4].iter().all(|g| close(*g, 0.5)));
+}
+
+#[test]
+fn stereo_track_panned_front_keeps_its_image() {
+        let mut panner = SurroundPanner::new(ChannelLayout::Stereo, ChannelLayout::Surround51, 0.0, 1.0);
+        let mut out = [0.0; 6];
+        panner.process(&[1.0, 0.5], &mut out);
+        assert!(close(out[0], 1.0) && close(out[1], 0.5));
+        assert!(out[2..].iter().all(|s| s.abs() < 1e-4));
+
+        // moving the panner ramps towards the rear over the block
+        panner.set_y(-1.0);
+        let input = vec![1.0; 2 * 64];
+        let mut out = vec![0.0; 6 * 64];
+        panner.process(&input, &mut out);
+        let last = |channel: usize| out[channel * 64 + 63];
+        assert!(last(0).abs() < 1e-3 && last(4) > 0.5 && last(5) > 0.5);
+        assert!(out[0] > 0.9);
+}
+
+#[test]
+fn mono_track_pans_into_stereo() {
+        let pan = |x: f32| {
+                let mut panner = SurroundPanner::new(ChannelLayout::Mono, ChannelLayout::Stereo, x, 0.0);
+                let mut out = [0.0; 2];
+                panner.process(&[1.0], &mut out);
+                out
+        };
+        // centre sits at -3 dB like the upmix, the ends go hard to one side, power stays constant
+        let center = pan(0.0);
+        assert!(close(center[0], MINUS_3DB) && close(center[1], MINUS_3DB));
+        let left = pan(-1.0);
+        assert!(close(left[0], 1.0) && close(left[1], 0.0));
+        let right = pan(1.0);
+        assert!(close(right[0], 0.0) && close(right[1], 1.0));
+        for x in [-0.7, -0.2, 0.4, 0.9] {
+                let [l, r] = pan(x);
+                assert!(close(l * l + r * r, 1.0));
+        }
+}
+
+#[test]
+fn surround_track_balances_into_stereo() {
+        let mut panner = SurroundPanner::new(ChannelLayout::Surround51, ChannelLayout::Stereo, 0.0, 0.0);
+        let downmix = ChannelLayout::Stereo.mix_matrix_from(ChannelLayout::Surround51);
+        assert_eq!(panner.target_gains(), downmix);
+        panner.set_x(1.0);
+        let gains = panner.target_gains();
+        assert!(gains[0].iter().all(|g| *g == 0.0));
+        assert_eq!(gains[1], downmix[1]);
+}
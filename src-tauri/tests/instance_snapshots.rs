@@ -0,0 +1,41 @@
+use my_daw_lib::audio::core::plugin::{ParameterTaper, ParameterType, PluginParameter};
+use my_daw_lib::audio::plugins::presets::PresetParam;
+use my_daw_lib::audio::plugins::snapshots::{InstanceSnapshot, morph, normalize_slot};
+
+// Morphing between two snapshots follows each parameter's taper for floats and
+// switches discrete parameters at the midpoint.
+
+fn snapshot(values: &[(u32, f32)]) -> InstanceSnapshot {
+        InstanceSnapshot {
+                state: Vec::new(),
+                params: values.iter().map(|&(id, value)| PresetParam { id, value }).collect(),
+        }
+}
+
+#[test]
+fn morph_float_and_discrete_params() {
+        let mut freq = PluginParameter::new(0, "Frequency", 20.0, 20000.0, 440.0, ParameterType::Float);
+        freq.taper = ParameterTaper::Logarithmic;
+        let gain = PluginParameter::new(1, "Gain", -60.0, 12.0, 0.0, ParameterType::Float);
+        let on = PluginParameter::new(2, "On", 0.0, 1.0, 0.0, ParameterType::Bool);
+        let params = [freq, gain, on];
+
+        let a = snapshot(&[(0, 100.0), (1, -12.0), (2, 0.0)]);
+        let b = snapshot(&[(0, 10000.0), (1, 0.0), (2, 1.0)]);
+
+        let half = morph(&a, &b, &params, 0.5);
+        assert!((half[0].value - 1000.0).abs() < 1.0);
+        assert!((half[1].value + 6.0).abs() < 1e-4);
+        assert_eq!(half[2].value, 1.0);
+
+        let start = morph(&a, &b, &params, -1.0);
+        assert_eq!(start[1].value, -12.0);
+        assert_eq!(start[2].value, 0.0);
+}
+
+#[test]
+fn slot_names() {
+        assert_eq!(normalize_slot("a"), Ok("A".to_string()));
+        assert_eq!(normalize_slot(" 03 "), Ok("3".to_string()));
+        assert!(normalize_slot("C").is_err());
+}
@@ -0,0 +1,74 @@
+use my_daw_lib::audio::engine::AudioBackend;
+use my_daw_lib::audio::jack::{JackSettings, JackTransportMode, unique_port_names};
+
+// JACK backend settings and port naming (no JACK server needed).
+
+#[test]
+fn port_names_are_unique_and_valid() {
+        let names = vec![
+                "Master".to_string(),
+                "Drums".to_string(),
+                "Drums".to_string(),
+                "Bus: FX".to_string(),
+                " ".to_string(),
+        ];
+        assert_eq!(
+                unique_port_names(&names),
+                vec!["Master", "Drums", "Drums 2", "Bus- FX", "Port"]
+        );
+}
+
+#[test]
+fn backend_settings_round_trip() {
+        let backend: AudioBackend = serde_json::from_str(r#"{"type":"jack","transport":"follow"}"#).unwrap();
+        let AudioBackend::Jack(settings) = &backend else {
+                panic!("expected jack backend");
+        };
+        assert_eq!(settings.client_name, JackSettings::default().client_name);
+        assert!(settings.auto_connect);
+        assert_eq!(settings.transport, JackTransportMode::Follow);
+
+        let json = serde_json::to_string(&backend).unwrap();
+        assert_eq!(
+                serde_json::from_str::<AudioBackend>(&json).unwrap(),
+                backend
+        );
+        assert_eq!(
+                serde_json::from_str::<AudioBackend>(r#"{"type":"cpal"}"#).unwrap(),
+                AudioBackend::Cpal
+        );
+}
+
+#[test]
+fn mixer_exposes_track_ports() {
+        use my_daw_lib::audio::core::plugin::{AudioBuffer, Plugin};
+        use my_daw_lib::audio::plugins::mixer::mixer_plugin::MixerPlugin;
+        use uuid::Uuid;
+
+        let mut mixer = MixerPlugin::new(0);
+        for label in ["Master", "Vocals"] {
+                mixer.add_track(Uuid::new_v4(), None);
+                let index = mixer.get_io_config().output_ports.len() - 1;
+                mixer.get_track_mut(index).unwrap().label = label.to_string();
+        }
+        let io = mixer.get_io_config();
+        assert_eq!(io.output_ports.len(), 2);
+        assert!(io.output_ports[0].is_main && io.output_ports[0].name == "Master");
+        assert_eq!(io.input_ports[0].name, "Vocals");
+
+        // external input on the track reaches its direct output and the master
+        let len = 64 * 2;
+        mixer.aux_input_mut(0, len).unwrap().fill(0.25);
+        assert!(mixer.aux_input_mut(1, len).is_none());
+        let mut samples = vec![0.0; len];
+        let mut buffer = AudioBuffer {
+                samples: &mut samples,
+                channels: 2,
+                sample_rate: 48000.0,
+        };
+        mixer.process(&mut buffer, &[], &mut Vec::new());
+        let direct = mixer.aux_output(0).unwrap();
+        assert_eq!(direct.len(), len);
+        assert!(direct.iter().any(|s| *s != 0.0));
+        assert!(samples.iter().any(|s| *s != 0.0));
+}
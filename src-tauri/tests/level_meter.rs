@@ -0,0 +1,100 @@
+use my_daw_lib::audio::core::plugin::{AudioBuffer, Plugin};
+use my_daw_lib::audio::plugins::mixer::level_meter::{
+        LevelMeter, MeterBallistics, MeterReading, clear_meter_clips, get_meter_levels, set_meter_ballistics,
+};
+use std::sync::Mutex;
+
+// Level meter ballistics (peak hold, fall rate) and the clip indicator, driven through `process`.
+
+// 1 kHz so that a 100-frame block is exactly 0.1 s
+const RATE: f32 = 1000.0;
+const BLOCK: usize = 100;
+
+// The ballistics are global and readings share one map; the tests run one at a time
+static SERIAL: Mutex<()> = Mutex::new(());
+
+// Processes one mono block with the given peak and returns the published reading
+fn block(meter: &mut LevelMeter, peak: f32) -> MeterReading {
+        let mut samples = vec![0.0; BLOCK];
+        samples[BLOCK / 2] = peak;
+        let mut buffer = AudioBuffer {
+                samples: &mut samples,
+                channels: 1,
+                sample_rate: RATE,
+        };
+        meter.process(&mut buffer, &[], &mut Vec::new());
+        get_meter_levels().remove(&meter.get_id()).unwrap()
+}
+
+fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+}
+
+#[test]
+fn peak_hold_expires_then_falls() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        set_meter_ballistics(MeterBallistics {
+                peak_hold: 0.5,
+                fall_rate: 20.0,
+        });
+        let mut meter = LevelMeter::new();
+        block(&mut meter, 0.5);
+        // 20 dB/s over 0.1 s blocks: 2 dB per block
+        let fall = 10f32.powf(-0.1);
+        let mut reading = MeterReading::default();
+        for n in 1..=5 {
+                reading = block(&mut meter, 0.0);
+                assert!(
+                        close(reading.levels[0], 0.5 * fall.powi(n)),
+                        "{:?}",
+                        reading.levels
+                );
+        }
+        // held for 0.5 s, then falls at the same rate as the level
+        assert_eq!(reading.peak_hold[0], 0.5);
+        let reading = block(&mut meter, 0.0);
+        assert!(close(reading.peak_hold[0], 0.5 * fall));
+        let reading = block(&mut meter, 0.0);
+        assert!(close(reading.peak_hold[0], 0.5 * fall * fall));
+
+        // a negative hold time keeps the peak until reset
+        set_meter_ballistics(MeterBallistics {
+                peak_hold: -1.0,
+                fall_rate: 20.0,
+        });
+        let mut meter = LevelMeter::new();
+        block(&mut meter, 0.8);
+        for _ in 0..50 {
+                block(&mut meter, 0.1);
+        }
+        let reading = block(&mut meter, 0.0);
+        assert_eq!(reading.peak_hold[0], 0.8);
+
+        // no fall rate: the level drops straight to the current peak
+        set_meter_ballistics(MeterBallistics {
+                peak_hold: 0.5,
+                fall_rate: 0.0,
+        });
+        let mut meter = LevelMeter::new();
+        block(&mut meter, 0.8);
+        let reading = block(&mut meter, 0.25);
+        assert_eq!(reading.levels[0], 0.25);
+        set_meter_ballistics(MeterBallistics::default());
+}
+
+#[test]
+fn clip_latches_above_full_scale_until_cleared() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let mut meter = LevelMeter::new();
+        // exactly full scale is not a clip
+        assert!(!block(&mut meter, 1.0).clip[0]);
+        assert!(!block(&mut meter, -1.0).clip[0]);
+        assert!(block(&mut meter, 1.001).clip[0]);
+        // latched through quieter blocks
+        assert!(block(&mut meter, 0.1).clip[0]);
+        clear_meter_clips();
+        let reading = block(&mut meter, 0.1);
+        assert!(!reading.clip[0]);
+        // clearing clips keeps the held peak
+        assert!(reading.peak_hold[0] > 1.0);
+}
@@ -0,0 +1,65 @@
+use my_daw_lib::audio::plugins::mixer::loudness::LoudnessMeter;
+use std::f32::consts::TAU;
+
+// BS.1770 loudness and true-peak measurement (reference cases from EBU Tech 3341).
+
+const RATE: f32 = 48000.0;
+
+// Feeds `seconds` of a stereo sine (`gain` dBFS, `freq` Hz, starting `phase` rad) in 480-frame blocks
+fn feed(meter: &mut LoudnessMeter, gain: f32, freq: f32, phase: f32, seconds: f32) {
+        let amplitude = 10f32.powf(gain / 20.0);
+        let frames = 480;
+        let mut block = vec![0.0; 2 * frames];
+        for n in 0..(seconds * RATE) as usize / frames {
+                for i in 0..frames {
+                        let t = (n * frames + i) as f32 / RATE;
+                        let sample = amplitude * (TAU * freq * t + phase).sin();
+                        block[i] = sample;
+                        block[frames + i] = sample;
+                }
+                meter.process(&block);
+        }
+}
+
+#[test]
+fn stereo_sine_at_minus_23_dbfs_reads_minus_23_lufs() {
+        let mut meter = LoudnessMeter::new(RATE, 2);
+        feed(&mut meter, -23.0, 1000.0, 0.0, 20.0);
+        let report = meter.report();
+        for value in [report.momentary, report.short_term, report.integrated] {
+                let value = value.unwrap();
+                assert!((value + 23.0).abs() < 0.1, "{}", value);
+        }
+        assert!(report.range.unwrap() < 0.2);
+
+        // silence is gated out of the integrated value, then reset starts over
+        feed(&mut meter, -200.0, 1000.0, 0.0, 10.0);
+        assert!((meter.integrated().unwrap() + 23.0).abs() < 0.1);
+        meter.reset();
+        assert_eq!(meter.integrated(), None);
+}
+
+#[test]
+fn true_peak_finds_inter_sample_peaks() {
+        // a quarter-rate sine sampled 45 degrees off its peaks: samples reach only -3 dBFS
+        let mut meter = LoudnessMeter::new(RATE, 2);
+        feed(&mut meter, 0.0, RATE / 4.0, TAU / 8.0, 1.0);
+        let peak = 20.0 * meter.true_peak(0).log10();
+        assert!(peak.abs() < 0.5, "{}", peak);
+        let report = meter.report().true_peak.unwrap();
+        assert!((report - peak).abs() < 1e-3);
+}
+
+#[test]
+fn reconfigured_meter_matches_a_new_one() {
+        // the level meter builds one meter up front and reconfigures it in place on the audio thread
+        let mut reconfigured = LoudnessMeter::new(44100.0, 8);
+        feed(&mut reconfigured, -10.0, 500.0, 0.0, 1.0);
+        reconfigured.configure(RATE, 2);
+        assert_eq!(reconfigured.integrated(), None);
+        let mut fresh = LoudnessMeter::new(RATE, 2);
+        for meter in [&mut reconfigured, &mut fresh] {
+                feed(meter, 0.0, RATE / 4.0, TAU / 8.0, 5.0);
+        }
+        assert_eq!(reconfigured.report(), fresh.report());
+}
@@ -0,0 +1,103 @@
+use my_daw_lib::audio::plugins::mixer::safety::{
+        MasterSafety, NonFiniteGuard, SafetySettings, take_non_finite_reports, unmute_non_finite,
+};
+use std::f32::consts::TAU;
+use uuid::Uuid;
+
+// Master safety stage: lookahead limiter and NaN / Inf protection.
+
+const RATE: f32 = 48000.0;
+
+// Runs a stereo sine of `amplitude` through the stage in 256-frame blocks and returns the output of the last block
+fn run(safety: &mut MasterSafety, amplitude: f32, blocks: usize) -> Vec<f32> {
+        let mut block = vec![0.0; 512];
+        for n in 0..blocks {
+                for i in 0..256 {
+                        let sample = amplitude * (TAU * 440.0 * (n * 256 + i) as f32 / RATE).sin();
+                        block[i] = sample;
+                        block[256 + i] = sample;
+                }
+                safety.process(&mut block, 2, RATE);
+        }
+        block
+}
+
+#[test]
+fn limiter_holds_the_ceiling() {
+        let mut safety = MasterSafety::new();
+        assert_eq!(safety.latency(RATE), 72);
+        let out = run(&mut safety, 4.0, 40);
+        let ceiling = 10f32.powf(-0.3 / 20.0);
+        let peak = out.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak <= ceiling && peak > ceiling * 0.9, "{}", peak);
+
+        // signals below the ceiling pass unchanged (delayed by the lookahead)
+        let mut quiet = MasterSafety::new();
+        let out = run(&mut quiet, 0.5, 4);
+        let expected = 0.5 * (TAU * 440.0 * (3 * 256 + 100 - 72) as f32 / RATE).sin();
+        assert!((out[100] - expected).abs() < 1e-4);
+
+        // disabled: no limiting and no latency, but non-finite blocks are still muted
+        let mut off = MasterSafety::new();
+        off.set_settings(SafetySettings {
+                enabled: false,
+                ..Default::default()
+        });
+        assert_eq!(off.latency(RATE), 0);
+        let mut block = [2.0, f32::NAN];
+        off.process(&mut block, 2, RATE);
+        assert_eq!(block, [0.0, 0.0]);
+        let mut block = [2.0, -3.0];
+        off.process(&mut block, 2, RATE);
+        assert_eq!(block, [2.0, -3.0]);
+}
+
+#[test]
+fn non_finite_output_mutes_and_reports_the_node() {
+        let node = Uuid::new_v4();
+        let mut guard = NonFiniteGuard::new(node);
+        let mut block = [0.5, f32::INFINITY];
+        assert!(guard.check(&mut block));
+        assert_eq!(block, [0.0, 0.0]);
+        assert!(take_non_finite_reports().contains(&node));
+
+        // stays muted until unmuted
+        let mut block = [0.5, 0.5];
+        assert!(guard.check(&mut block));
+        assert_eq!(block, [0.0, 0.0]);
+        unmute_non_finite();
+        let mut block = [0.5, 0.5];
+        assert!(!guard.check(&mut block));
+        assert_eq!(block, [0.5, 0.5]);
+}
+
+#[test]
+fn changing_the_lookahead_keeps_the_delayed_audio() {
+        let mut safety = MasterSafety::new();
+        ramp(&mut safety, 0);
+        let before = ramp(&mut safety, 256);
+        assert!(close_ramp(&before, 256 - 72));
+
+        // 1.5 ms -> 3 ms: the delay grows without zeroing what is already in the line
+        safety.set_settings(SafetySettings {
+                lookahead: 3.0,
+                ..Default::default()
+        });
+        assert_eq!(safety.latency(RATE), 144);
+        let after = ramp(&mut safety, 512);
+        assert!(close_ramp(&after, 512 - 144));
+}
+
+// Runs 256 samples of a slow mono ramp starting at sample `from` through the stage
+fn ramp(safety: &mut MasterSafety, from: usize) -> Vec<f32> {
+        let mut block: Vec<f32> = (0..256).map(|i| ((from + i) % 1000) as f32 * 1e-4).collect();
+        safety.process(&mut block, 1, RATE);
+        block
+}
+
+// The block holds the ramp starting at sample `from`
+fn close_ramp(block: &[f32], from: usize) -> bool {
+        block.iter()
+                .enumerate()
+                .all(|(i, s)| (s - ((from + i) % 1000) as f32 * 1e-4).abs() < 1e-6)
+}
@@ -0,0 +1,140 @@
+use my_daw_lib::audio::plugins::mixer::metronome::{ClickSpan, Metronome, MetronomeSettings};
+use my_daw_lib::daw::sequencer::Sequencer;
+
+// Metronome: click placement from the time signature, accented downbeats, count-in and record-only.
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+// Renders `span` in one mono block and returns (onset in seconds from span start, peak) per click
+fn clicks(settings: MetronomeSettings, span: ClickSpan) -> Vec<(f64, f32)> {
+        let mut metronome = Metronome::new();
+        metronome.set_settings(settings);
+        let frames = ((span.end - span.start) * SAMPLE_RATE as f64) as usize;
+        let mut out = vec![0.0; frames];
+        metronome.render(&mut out, 1, SAMPLE_RATE, Some(&span));
+
+        let mut found: Vec<(f64, f32)> = Vec::new();
+        let mut silent = 0;
+        for (i, s) in out.iter().enumerate() {
+                if *s == 0.0 {
+                        silent += 1;
+                        continue;
+                }
+                if silent > 100 || found.is_empty() {
+                        found.push((i as f64 / SAMPLE_RATE as f64, 0.0));
+                }
+                silent = 0;
+                let last = found.last_mut().unwrap();
+                last.1 = last.1.max(s.abs());
+        }
+        found
+}
+
+fn enabled() -> MetronomeSettings {
+        MetronomeSettings {
+                enabled: true,
+                ..Default::default()
+        }
+}
+
+fn assert_onsets(found: &[(f64, f32)], expected: &[f64]) {
+        assert_eq!(found.len(), expected.len(), "{:?}", found);
+        for ((onset, _), e) in found.iter().zip(expected) {
+                assert!((onset - e).abs() < 0.001, "{} vs {}", onset, e);
+        }
+}
+
+#[test]
+fn accents_first_beat_of_each_bar() {
+        let span = ClickSpan {
+                start: 0.0,
+                end: 3.0,
+                tempo: 120.0,
+                time_signature: (3, 4),
+        };
+        let found = clicks(enabled(), span);
+        assert_onsets(&found, &[0.0, 0.5, 1.0, 1.5, 2.0, 2.5]);
+        let accents: Vec<bool> = found.iter().map(|(_, peak)| *peak > 0.4).collect();
+        assert_eq!(accents, [true, false, false, true, false, false]);
+
+        // without accent every click has the same level
+        let flat = clicks(
+                MetronomeSettings {
+                        accent: false,
+                        ..enabled()
+                },
+                span,
+        );
+        assert!(flat.iter().all(|(_, peak)| *peak < 0.4));
+}
+
+#[test]
+fn compound_meter_clicks_on_eighth_notes() {
+        // 6/8 at 120 BPM: an eighth note lasts 0.25 s, bars are 1.5 s
+        let found = clicks(
+                enabled(),
+                ClickSpan {
+                        start: 0.0,
+                        end: 3.0,
+                        tempo: 120.0,
+                        time_signature: (6, 8),
+                },
+        );
+        assert_eq!(found.len(), 12);
+        let accented: Vec<(f64, f32)> = found.into_iter().filter(|(_, peak)| *peak > 0.4).collect();
+        assert_onsets(&accented, &[0.0, 1.5]);
+}
+
+#[test]
+fn count_in_clicks_before_song_start() {
+        // one bar of 4/4 count-in before recording at 0: span covers negative song time
+        let found = clicks(
+                enabled(),
+                ClickSpan {
+                        start: -2.0,
+                        end: 0.0,
+                        tempo: 120.0,
+                        time_signature: (4, 4),
+                },
+        );
+        assert_onsets(&found, &[0.0, 0.5, 1.0, 1.5]);
+        assert!(found[0].1 > 0.4 && found[1].1 < 0.4);
+}
+
+#[test]
+fn record_only_and_disabled() {
+        let mut metronome = Metronome::new();
+        assert!(!metronome.active(false));
+        metronome.set_settings(MetronomeSettings {
+                record_only: true,
+                ..enabled()
+        });
+        assert!(!metronome.active(false));
+        assert!(metronome.active(true));
+
+        // no span (stopped): silence
+        let mut out = vec![0.0; 1024];
+        metronome.render(&mut out, 2, SAMPLE_RATE, None);
+        assert!(out.iter().all(|s| *s == 0.0));
+}
+
+#[test]
+fn count_in_only_when_starting_from_stop() {
+        // 1 kHz，4/4，120 BPM：一小节预备拍 = 2 秒
+        let mut stopped = Sequencer::new();
+        stopped.sample_rate = 1000.0;
+        stopped.start_recording(1);
+        stopped.process(100);
+        assert_eq!(stopped.current_time, 0.0);
+        assert!(stopped.recording);
+
+        // 播放中开始录音：走带不停，直接录音
+        let mut rolling = Sequencer::new();
+        rolling.sample_rate = 1000.0;
+        rolling.set_transport(true, Some(0.0), None);
+        rolling.process(100);
+        rolling.start_recording(1);
+        rolling.process(100);
+        assert!((rolling.current_time - 0.2).abs() < 1e-9);
+        assert!(rolling.recording);
+}
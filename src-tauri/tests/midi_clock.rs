@@ -0,0 +1,104 @@
+use my_daw_lib::audio::midi::MidiMessage;
+use my_daw_lib::audio::midi::clock::{
+        ClockUpdate, MidiClockFollower, MidiClockInputSettings, pop_clock_update, push_clock_update,
+};
+
+// MIDI clock slave: start/continue arming, song position and jitter-smoothed tempo.
+
+// 120 BPM = 24 clocks per 0.5 s
+const INTERVAL: f64 = 0.5 / 24.0;
+
+#[test]
+fn start_waits_for_first_clock() {
+        let mut f = MidiClockFollower::new();
+        assert_eq!(f.handle(&MidiMessage::Start, 0.0), None);
+        assert!(f.is_running());
+
+        let first = f.handle(&MidiMessage::Clock, 0.01).unwrap();
+        assert!(first.playing && first.relocate);
+        assert_eq!(first.beats, 0.0);
+
+        let mut last = first;
+        for i in 1..=24 {
+                last = f.handle(&MidiMessage::Clock, 0.01 + i as f64 * INTERVAL).unwrap();
+                assert!(!last.relocate);
+        }
+        assert_eq!(last.beats, 1.0);
+
+        let stop = f.handle(&MidiMessage::Stop, 0.6).unwrap();
+        assert!(!stop.playing);
+        assert!(!f.is_running());
+        // clocks while stopped keep the tempo estimate but don't move the transport
+        assert_eq!(f.handle(&MidiMessage::Clock, 0.62), None);
+}
+
+#[test]
+fn song_position_then_continue() {
+        let mut f = MidiClockFollower::new();
+        let located = f.handle(&MidiMessage::SongPosition(8), 0.0).unwrap();
+        assert!(!located.playing && located.relocate);
+        assert_eq!(located.beats, 2.0);
+
+        f.handle(&MidiMessage::Continue, 0.1);
+        let resumed = f.handle(&MidiMessage::Clock, 0.11).unwrap();
+        assert!(resumed.playing && resumed.relocate);
+        assert_eq!(resumed.beats, 2.0);
+}
+
+#[test]
+fn tempo_is_smoothed_against_jitter() {
+        let mut f = MidiClockFollower::new();
+        f.handle(&MidiMessage::Start, 0.0);
+        let mut time = 0.0;
+        for i in 0..480 {
+                // alternate ±2 ms of jitter around the nominal interval
+                let jitter = if i % 2 == 0 { 0.002 } else { -0.002 };
+                time += INTERVAL;
+                f.handle(&MidiMessage::Clock, time + jitter);
+        }
+        let tempo = f.tempo().unwrap();
+        assert!((tempo - 120.0).abs() < 1.0, "tempo {}", tempo);
+
+        // a long gap drops the estimate instead of reporting a bogus tempo
+        f.handle(&MidiMessage::Clock, time + 1.0);
+        assert_eq!(f.tempo(), None);
+}
+
+#[test]
+fn input_settings_filter_ports() {
+        let any = MidiClockInputSettings {
+                enabled: true,
+                port: None,
+        };
+        assert!(any.accepts("Sequencer:Out"));
+        let one = MidiClockInputSettings {
+                enabled: true,
+                port: Some("Sequencer:Out".to_string()),
+        };
+        assert!(one.accepts("Sequencer:Out"));
+        assert!(!one.accepts("Keyboard"));
+        assert!(!MidiClockInputSettings::default().accepts("Sequencer:Out"));
+}
+
+#[test]
+fn clock_queue_drops_oldest_updates_when_full() {
+        // 没有消费者（引擎未运行）时持续推入：队列保留最新的更新
+        let update = |beats| ClockUpdate {
+                playing: true,
+                beats,
+                tempo: None,
+                relocate: false,
+                received: 0.0,
+        };
+        for i in 0..1000 {
+                push_clock_update(update(i as f64));
+        }
+        let mut last = None;
+        let mut count = 0;
+        while let Some(update) = pop_clock_update() {
+                last = Some(update.beats);
+                count += 1;
+        }
+        assert!(count < 1000);
+        assert_eq!(last, Some(999.0));
+}
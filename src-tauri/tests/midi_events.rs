@@ -0,0 +1,99 @@
+use my_daw_lib::audio::core::clip::{ControllerEvent, ControllerKind};
+use my_daw_lib::audio::core::plugin::NoteEvent;
+
+// Channel messages in the plugin event model and their MIDI 1.0 encoding.
+
+#[test]
+fn note_events_carry_channel() {
+        let on = NoteEvent::NoteOn {
+                channel: 9,
+                note: 36,
+                velocity: 1.0,
+                note_id: None,
+        };
+        assert_eq!(on.channel(), 9);
+        assert_eq!(on.to_midi_bytes(), [0x99, 36, 127]);
+        assert_eq!(
+                NoteEvent::NoteOff {
+                        channel: 9,
+                        note: 36,
+                        note_id: None,
+                }
+                .to_midi_bytes(),
+                [0x89, 36, 0]
+        );
+        // 力度为 0 的 NoteOn 仍然是 NoteOn
+        let quiet = NoteEvent::NoteOn {
+                channel: 0,
+                note: 60,
+                velocity: 0.0,
+                note_id: None,
+        };
+        assert_eq!(quiet.to_midi_bytes()[2], 1);
+}
+
+#[test]
+fn controller_events_encode() {
+        let cc = NoteEvent::ControlChange {
+                channel: 1,
+                controller: 74,
+                value: 0.5,
+        };
+        assert_eq!(cc.to_midi_bytes(), [0xb1, 74, 64]);
+
+        let center = NoteEvent::PitchBend { channel: 0, value: 0.0 };
+        assert_eq!(center.to_midi_bytes(), [0xe0, 0x00, 0x40]);
+        let up = NoteEvent::PitchBend { channel: 0, value: 1.0 };
+        assert_eq!(up.to_midi_bytes(), [0xe0, 0x7f, 0x7f]);
+        let down = NoteEvent::PitchBend {
+                channel: 0,
+                value: -1.0,
+        };
+        assert_eq!(down.to_midi_bytes(), [0xe0, 0x00, 0x00]);
+
+        let pressure = NoteEvent::ChannelPressure {
+                channel: 2,
+                pressure: 1.0,
+        };
+        assert_eq!(pressure.to_midi_bytes(), [0xd2, 127, 0]);
+        let poly = NoteEvent::PolyPressure {
+                channel: 2,
+                note: 60,
+                pressure: 0.0,
+        };
+        assert_eq!(poly.to_midi_bytes(), [0xa2, 60, 0]);
+        let program = NoteEvent::ProgramChange { channel: 3, program: 5 };
+        assert_eq!(program.to_midi_bytes(), [0xc3, 5, 0]);
+}
+
+#[test]
+fn clip_controller_events_become_plugin_events() {
+        let event = |kind, value| ControllerEvent {
+                relative_time: 0.0,
+                channel: 4,
+                kind,
+                value,
+        };
+        assert_eq!(
+                event(ControllerKind::ControlChange { controller: 1 }, 0.25).to_note_event(),
+                NoteEvent::ControlChange {
+                        channel: 4,
+                        controller: 1,
+                        value: 0.25,
+                }
+        );
+        assert_eq!(
+                event(ControllerKind::PitchBend, -0.5).to_note_event(),
+                NoteEvent::PitchBend {
+                        channel: 4,
+                        value: -0.5,
+                }
+        );
+        assert_eq!(
+                event(ControllerKind::ProgramChange, 12.0).to_note_event(),
+                NoteEvent::ProgramChange {
+                        channel: 4,
+                        program: 12,
+                }
+        );
+}
@@ -0,0 +1,103 @@
+use my_daw_lib::audio::midi::MidiMessage;
+use my_daw_lib::audio::midi::mapping::{
+        MappingTarget, MidiControlKind, MidiControlSource, MidiMapping, TakeoverMode, TransportAction,
+};
+
+// MIDI learn sources, range scaling and the three takeover modes.
+
+fn cc(value: u8) -> MidiMessage {
+        MidiMessage::ControlChange {
+                channel: 0,
+                controller: 7,
+                value,
+        }
+}
+
+fn mapping(takeover: TakeoverMode) -> MidiMapping {
+        let source = MidiControlSource::learn("Controller:Port 1", &cc(0)).unwrap();
+        assert_eq!(
+                source.kind,
+                MidiControlKind::ControlChange { controller: 7 }
+        );
+        let mut m = MidiMapping::new(
+                source,
+                MappingTarget::PluginParameter {
+                        instance_id: "synth".to_string(),
+                        param_id: 0,
+                },
+        );
+        m.takeover = takeover;
+        m
+}
+
+#[test]
+fn parse_and_match() {
+        assert_eq!(
+                MidiMessage::parse(&[0xe1, 0x00, 0x40]),
+                Some(MidiMessage::PitchBend {
+                        channel: 1,
+                        value: 8192
+                })
+        );
+        assert!(matches!(
+                MidiMessage::parse(&[0x90, 60, 0]),
+                Some(MidiMessage::NoteOff { .. })
+        ));
+
+        let m = mapping(TakeoverMode::Jump);
+        assert!(m.source.matches("Controller:Port 1", &cc(64)).is_some());
+        assert!(m.source.matches("Other:Port", &cc(64)).is_none());
+        let other_cc = MidiMessage::ControlChange {
+                channel: 0,
+                controller: 8,
+                value: 64,
+        };
+        assert!(m.source.matches("Controller:Port 1", &other_cc).is_none());
+}
+
+#[test]
+fn jump_scales_to_range() {
+        let mut m = mapping(TakeoverMode::Jump);
+        m.min = 0.25;
+        m.max = 0.75;
+        let input = m.source.matches("Controller:Port 1", &cc(127)).unwrap();
+        assert_eq!(m.resolve(input, 0.0), Some(0.75));
+}
+
+#[test]
+fn pickup_waits_until_crossing() {
+        let mut m = mapping(TakeoverMode::Pickup);
+        let port = "Controller:Port 1";
+        // 目标位于 0.5，控制器从低处开始移动：未越过前不写入
+        assert_eq!(
+                m.resolve(m.source.matches(port, &cc(10)).unwrap(), 0.5),
+                None
+        );
+        assert_eq!(
+                m.resolve(m.source.matches(port, &cc(40)).unwrap(), 0.5),
+                None
+        );
+        let crossed = m.resolve(m.source.matches(port, &cc(80)).unwrap(), 0.5).unwrap();
+        assert!((crossed - 80.0 / 127.0).abs() < 1e-6);
+        // 已追上后直接跟随
+        assert!(m.resolve(m.source.matches(port, &cc(20)).unwrap(), crossed).is_some());
+}
+
+#[test]
+fn relative_and_transport() {
+        let mut m = mapping(TakeoverMode::Relative);
+        let port = "Controller:Port 1";
+        let up = m.resolve(m.source.matches(port, &cc(2)).unwrap(), 0.5).unwrap();
+        assert!((up - (0.5 + 2.0 / 128.0)).abs() < 1e-6);
+        let down = m.resolve(m.source.matches(port, &cc(127)).unwrap(), up).unwrap();
+        assert!((down - 0.5 - 1.0 / 128.0).abs() < 1e-6);
+
+        let mut play = mapping(TakeoverMode::Jump);
+        play.target = MappingTarget::Transport {
+                action: TransportAction::Play,
+        };
+        assert!(play.trigger(play.source.matches(port, &cc(127)).unwrap()));
+        assert!(!play.trigger(play.source.matches(port, &cc(127)).unwrap()));
+        assert!(!play.trigger(play.source.matches(port, &cc(0)).unwrap()));
+        assert!(play.trigger(play.source.matches(port, &cc(100)).unwrap()));
+}
@@ -0,0 +1,89 @@
+use my_daw_lib::audio::midi::MidiMessage;
+use my_daw_lib::audio::midi::sync::{MidiSyncGenerator, MidiSyncSettings, MtcFrameRate};
+
+// Transport sync output: clock/start/continue generation and MTC quarter frames.
+
+fn generator(clock: bool, timecode: bool) -> MidiSyncGenerator {
+        MidiSyncGenerator::new(&MidiSyncSettings {
+                port: Some("Out".to_string()),
+                clock,
+                timecode,
+                frame_rate: MtcFrameRate::Fps25,
+                latency_offset_ms: 0.0,
+        })
+}
+
+fn run(generator: &mut MidiSyncGenerator, start: f64, end: f64, playing: bool) -> Vec<(f64, MidiMessage)> {
+        let mut out = Vec::new();
+        generator.process(start, end, playing, 120.0, |offset, msg| {
+                out.push((offset, msg))
+        });
+        out
+}
+
+#[test]
+fn clock_starts_from_zero_at_24_ppqn() {
+        let mut g = generator(true, false);
+        // 120 BPM: one quarter note = 0.5 s
+        let events = run(&mut g, 0.0, 0.5, true);
+        assert_eq!(events[0], (0.0, MidiMessage::Start));
+        let clocks: Vec<f64> = events
+                .iter()
+                .filter(|(_, m)| *m == MidiMessage::Clock)
+                .map(|(t, _)| *t)
+                .collect();
+        assert_eq!(clocks.len(), 24);
+        assert!((clocks[1] - 0.5 / 24.0).abs() < 1e-9);
+
+        // next block continues without another Start
+        let events = run(&mut g, 0.5, 1.0, true);
+        assert_eq!(events.len(), 24);
+        assert!(events.iter().all(|(_, m)| *m == MidiMessage::Clock));
+
+        let events = run(&mut g, 1.0, 1.0, false);
+        assert_eq!(events[0].1, MidiMessage::Stop);
+        assert_eq!(events[1].1, MidiMessage::SongPosition(8));
+}
+
+#[test]
+fn continue_aligns_to_next_sixteenth() {
+        let mut g = generator(true, false);
+        // one sixteenth = 0.125 s; start slightly after the 4th sixteenth
+        let events = run(&mut g, 0.51, 0.75, true);
+        assert_eq!(events[0].1, MidiMessage::SongPosition(5));
+        assert_eq!(events[1].1, MidiMessage::Continue);
+        let (offset, msg) = events[2];
+        assert_eq!(msg, MidiMessage::Clock);
+        assert!((offset - (0.625 - 0.51)).abs() < 1e-9);
+
+        // jump while playing: Stop, then re-position
+        let events = run(&mut g, 0.0, 0.1, true);
+        assert_eq!(events[0].1, MidiMessage::Stop);
+        assert_eq!(events[1].1, MidiMessage::Start);
+}
+
+#[test]
+fn timecode_quarter_frames() {
+        let mut g = generator(false, true);
+        // 25 fps: 100 quarter frames per second; position 1h 0m 1s
+        let events = run(&mut g, 3601.0, 3601.075, true);
+        assert_eq!(
+                events[0].1,
+                MidiMessage::TimecodeFullFrame {
+                        hours: 1,
+                        minutes: 0,
+                        seconds: 1,
+                        frames: 0,
+                        rate: 1,
+                }
+        );
+        let pieces: Vec<u8> = events[1..]
+                .iter()
+                .map(|(_, m)| match m {
+                        MidiMessage::QuarterFrame(data) => *data,
+                        other => panic!("unexpected {:?}", other),
+                })
+                .collect();
+        assert_eq!(pieces, vec![0x00, 0x10, 0x21, 0x30, 0x40, 0x50, 0x61, 0x72]);
+        assert_eq!(MidiMessage::QuarterFrame(0x72).to_bytes(), vec![0xf1, 0x72]);
+}
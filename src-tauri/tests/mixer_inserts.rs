@@ -0,0 +1,243 @@
+use my_daw_lib::audio::core::plugin::{
+        AudioBuffer, IOConfig, ParamAddress, ParameterType, Plugin, PluginEvent, PluginInfo, PluginParameter,
+        PluginType,
+};
+use my_daw_lib::audio::engine::AudioEngine;
+use my_daw_lib::audio::plugins::manager::PluginManager;
+use my_daw_lib::audio::plugins::mixer::mixer_plugin::MixerPlugin;
+use my_daw_lib::audio::plugins::mixer::track::InsertSlot;
+use my_daw_lib::daw::serialization::project::ProjectManager;
+use my_daw_lib::daw::state::{AppState, InsertSlotData, MixerTrackData};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+// Insert chains on mixer tracks: processing order, bypass and project round trips.
+
+type Instance = Arc<Mutex<Box<dyn Plugin>>>;
+
+// Multiplies by `scale` (parameter 0), then adds `offset` (parameter 1); the state holds both values
+struct Affine {
+        scale: f32,
+        offset: f32,
+}
+
+impl Plugin for Affine {
+        fn info(&self) -> PluginInfo {
+                PluginInfo {
+                        name: "Affine".to_string(),
+                        vendor: String::new(),
+                        url: String::new(),
+                        plugin_type: PluginType::Native,
+                        unique_id: "test.affine".to_string(),
+                        parameters: None,
+                }
+        }
+
+        fn get_parameters(&self) -> Vec<PluginParameter> {
+                vec![
+                        PluginParameter::new(0, "Scale", 0.0, 4.0, 1.0, ParameterType::Float),
+                        PluginParameter::new(1, "Offset", -1.0, 1.0, 0.0, ParameterType::Float),
+                ]
+        }
+
+        fn get_state(&self) -> Vec<u8> {
+                let mut state = self.scale.to_le_bytes().to_vec();
+                state.extend_from_slice(&self.offset.to_le_bytes());
+                state
+        }
+
+        fn set_state(&mut self, state: &[u8]) {
+                self.scale = f32::from_le_bytes(state[0..4].try_into().unwrap());
+                self.offset = f32::from_le_bytes(state[4..8].try_into().unwrap());
+        }
+
+        fn get_io_config(&self) -> IOConfig {
+                IOConfig::default()
+        }
+
+        fn process(&mut self, buffer: &mut AudioBuffer, events: &[PluginEvent], _output: &mut Vec<PluginEvent>) {
+                for event in events {
+                        if let PluginEvent::Parameter { id, value } = event {
+                                self.set_param(*id, *value);
+                        }
+                }
+                for sample in buffer.samples.iter_mut() {
+                        *sample = *sample * self.scale + self.offset;
+                }
+        }
+
+        fn get_param(&self, id: u32) -> f32 {
+                match id {
+                        0 => self.scale,
+                        _ => self.offset,
+                }
+        }
+
+        fn set_param(&mut self, id: u32, value: f32) {
+                match id {
+                        0 => self.scale = value,
+                        _ => self.offset = value,
+                }
+        }
+}
+
+fn affine(scale: f32, offset: f32) -> Instance {
+        Arc::new(Mutex::new(Box::new(Affine { scale, offset })))
+}
+
+// Master plus one track at unity gain, with `inserts` as (node, instance, bypass) on the track
+fn mixer_with_inserts(inserts: &[(Uuid, Instance, bool)]) -> MixerPlugin {
+        let mut mixer = MixerPlugin::new(0);
+        for index in 0..2 {
+                mixer.add_track(Uuid::new_v4(), None);
+                mixer.get_track_mut(index).unwrap().set_levels(1.0, 0.0);
+        }
+        let track = mixer.get_track_mut(1).unwrap();
+        for (id, plugin, bypass) in inserts {
+                track.add_insert(InsertSlot {
+                        id: *id,
+                        plugin: plugin.clone(),
+                        bypass: *bypass,
+                        post_fader: false,
+                });
+        }
+        mixer
+}
+
+// Feeds a constant into the track's external input and returns its direct (post-fader) output
+fn run(mixer: &mut MixerPlugin, input: f32, events: &[PluginEvent]) -> Vec<f32> {
+        let len = 64 * 2;
+        mixer.aux_input_mut(0, len).unwrap().fill(input);
+        let mut samples = vec![0.0; len];
+        let mut buffer = AudioBuffer {
+                samples: &mut samples,
+                channels: 2,
+                sample_rate: 48000.0,
+        };
+        mixer.process(&mut buffer, events, &mut Vec::new());
+        mixer.aux_output(0).unwrap().to_vec()
+}
+
+#[test]
+fn inserts_process_in_chain_order() {
+        let (scale, offset) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut mixer = mixer_with_inserts(&[
+                (scale, affine(2.0, 0.0), false),
+                (offset, affine(1.0, 0.05), false),
+        ]);
+        // (0.1 * 2) + 0.05, not (0.1 + 0.05) * 2
+        let output = run(&mut mixer, 0.1, &[]);
+        assert!(output.iter().all(|s| (*s - 0.25).abs() < 1e-6));
+}
+
+#[test]
+fn bypassed_insert_passes_audio_through_and_keeps_parameter_changes() {
+        let node = Uuid::new_v4();
+        let plugin = affine(2.0, 0.0);
+        let mut mixer = mixer_with_inserts(&[(node, plugin.clone(), true)]);
+        let output = run(&mut mixer, 0.1, &[]);
+        assert!(output.iter().all(|s| *s == 0.1));
+
+        // a change sent while bypassed waits for the insert instead of reaching the plugin
+        let address = ParamAddress::new(node, 0);
+        let output = run(
+                &mut mixer,
+                0.1,
+                &[PluginEvent::NodeParameter { address, value: 3.0 }],
+        );
+        assert!(output.iter().all(|s| *s == 0.1));
+        assert_eq!(plugin.lock().unwrap().get_param(0), 2.0);
+        assert_eq!(mixer.get_node_param(address), Some(3.0));
+
+        // it takes effect when the insert resumes
+        let resume = PluginEvent::InsertState {
+                node,
+                bypass: false,
+                post_fader: false,
+        };
+        let output = run(&mut mixer, 0.1, &[resume]);
+        assert!(output.iter().all(|s| (*s - 0.3).abs() < 1e-6));
+        assert_eq!(plugin.lock().unwrap().get_param(0), 3.0);
+}
+
+#[test]
+fn project_round_trip_keeps_insert_slots_and_parameter_state() {
+        let slots = [
+                ("first", 2.0, 0.0, true, false),
+                ("second", 0.5, 0.25, false, true),
+        ];
+        let mut instances: HashMap<String, Instance> = HashMap::new();
+        let mut inserts = Vec::new();
+        for (label, scale, offset, bypass, post_fader) in slots {
+                let id = Uuid::new_v4().to_string();
+                instances.insert(id.clone(), affine(scale, offset));
+                inserts.push(InsertSlotData {
+                        instance_id: id,
+                        name: "test.affine".to_string(),
+                        label: label.to_string(),
+                        bypass,
+                        post_fader,
+                        sandboxed: false,
+                });
+        }
+        let track = |id: usize, inserts: Vec<InsertSlotData>| MixerTrackData {
+                id,
+                node_id: Uuid::new_v4(),
+                label: format!("Track {}", id),
+                volume: 1.0,
+                pan: 0.0,
+                pan_front: 1.0,
+                layout: Default::default(),
+                mute: false,
+                solo: false,
+                meter_id: None,
+                inserts,
+        };
+        let state = AppState {
+                audio_engine: Mutex::new(AudioEngine::new()),
+                plugin_manager: Mutex::new(PluginManager::new()),
+                active_plugins: Mutex::new(Vec::new()),
+                mixer_tracks: Mutex::new(vec![track(0, Vec::new()), track(1, inserts.clone())]),
+                arrangement_tracks: Mutex::new(Vec::new()),
+                clips: Mutex::new(Vec::new()),
+                plugin_instances: Mutex::new(instances),
+                pending_plugin_states: Mutex::new(HashMap::new()),
+                instance_snapshots: Mutex::new(HashMap::new()),
+                midi_control: Mutex::new(Default::default()),
+                metronome: Mutex::new(Default::default()),
+                time_signature: Mutex::new(Default::default()),
+                master_safety: Mutex::new(Default::default()),
+                processing: Mutex::new(Default::default()),
+                automation: Mutex::new(Vec::new()),
+                analysis_sessions: Mutex::new(HashMap::new()),
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("project");
+        ProjectManager::save_project(&state, &path).unwrap();
+        let schema = ProjectManager::load_project(&path).unwrap();
+        let states = ProjectManager::load_plugin_states(&path).unwrap();
+
+        // slot list in processing order, with bypass / position flags
+        let strip = schema.mixer.tracks.iter().find(|t| t.id == 1).unwrap();
+        let ids: Vec<&String> = inserts.iter().map(|i| &i.instance_id).collect();
+        assert_eq!(strip.plugin_instances.iter().collect::<Vec<_>>(), ids);
+        for (saved, loaded) in inserts.iter().zip(&strip.plugin_instances) {
+                let insert = schema.mixer.inserts.iter().find(|i| &i.id == loaded).unwrap();
+                assert_eq!(insert.name, saved.name);
+                assert_eq!(insert.label, saved.label);
+                assert_eq!(insert.bypass, saved.bypass);
+                assert_eq!(insert.post_fader, saved.post_fader);
+        }
+
+        // each insert's state restores its parameters on a fresh instance
+        for (saved, (_, scale, offset, _, _)) in inserts.iter().zip(slots) {
+                let mut fresh = Affine {
+                        scale: 1.0,
+                        offset: 0.0,
+                };
+                fresh.set_state(&states[&saved.instance_id]);
+                assert_eq!((fresh.get_param(0), fresh.get_param(1)), (scale, offset));
+        }
+}
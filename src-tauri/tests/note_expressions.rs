@@ -0,0 +1,178 @@
+use my_daw_lib::audio::core::clip::{Clip, ExpressionPoint, Note};
+use my_daw_lib::audio::core::plugin::{NoteEvent, NoteExpressionKind, PluginEvent};
+use my_daw_lib::audio::midi::mpe::MpeEncoder;
+use my_daw_lib::daw::sequencer::{EXPRESSION_STEP_FRAMES, Sequencer};
+use std::collections::HashMap;
+
+// Per-note expression curves and MPE channel-per-note encoding.
+
+fn note_with(expressions: Vec<ExpressionPoint>) -> Note {
+        Note {
+                id: 1,
+                relative_start: 0.0,
+                duration: 1.0,
+                note: 60,
+                velocity: 1.0,
+                channel: 0,
+                expressions,
+        }
+}
+
+#[test]
+fn expression_curves_interpolate() {
+        let point = |kind, time, value| ExpressionPoint { kind, time, value };
+        let note = note_with(vec![
+                point(NoteExpressionKind::Pitch, 0.0, 0.0),
+                point(NoteExpressionKind::Pressure, 0.1, 0.3),
+                point(NoteExpressionKind::Pitch, 0.5, 2.0),
+        ]);
+        assert_eq!(
+                note.expression_span(NoteExpressionKind::Pitch),
+                Some((0.0, 0.5))
+        );
+        assert_eq!(note.expression_span(NoteExpressionKind::Brightness), None);
+        assert_eq!(
+                note.expression_at(NoteExpressionKind::Pitch, 0.25),
+                Some(1.0)
+        );
+        // 曲线两端之外保持端点值
+        assert_eq!(
+                note.expression_at(NoteExpressionKind::Pitch, 0.9),
+                Some(2.0)
+        );
+        assert_eq!(
+                note.expression_at(NoteExpressionKind::Pressure, 0.0),
+                Some(0.3)
+        );
+}
+
+fn encode(mpe: &mut MpeEncoder, event: NoteEvent) -> Vec<[u8; 3]> {
+        let mut out = Vec::new();
+        mpe.encode(&event, |bytes| out.push(bytes));
+        out
+}
+
+fn note_on(note: u8, note_id: u32) -> NoteEvent {
+        NoteEvent::NoteOn {
+                channel: 0,
+                note,
+                velocity: 1.0,
+                note_id: Some(note_id),
+        }
+}
+
+#[test]
+fn mpe_assigns_a_member_channel_per_note() {
+        let mut mpe = MpeEncoder::new();
+        let first = encode(&mut mpe, note_on(60, 1));
+        // 第一次编码先发送区配置（主通道上的 RPN 6）
+        assert_eq!(
+                &first[..3],
+                &[[0xb0, 101, 0], [0xb0, 100, 6], [0xb0, 6, 15]]
+        );
+        assert_eq!(first.last(), Some(&[0x91, 60, 127]));
+
+        // 同音高的第二个音符（不同 ID）使用另一个成员通道
+        let second = encode(&mut mpe, note_on(60, 2));
+        assert_eq!(second.last(), Some(&[0x92, 60, 127]));
+
+        // 表情只作用于对应音符的通道：+48 半音为满弯音，明亮度为 CC74
+        let bend = encode(
+                &mut mpe,
+                NoteEvent::NoteExpression {
+                        channel: 0,
+                        note: 60,
+                        note_id: Some(2),
+                        expression: NoteExpressionKind::Pitch,
+                        value: 48.0,
+                },
+        );
+        assert_eq!(bend, vec![[0xe2, 0x7f, 0x7f]]);
+        let brightness = encode(
+                &mut mpe,
+                NoteEvent::NoteExpression {
+                        channel: 0,
+                        note: 60,
+                        note_id: Some(1),
+                        expression: NoteExpressionKind::Brightness,
+                        value: 1.0,
+                },
+        );
+        assert_eq!(brightness, vec![[0xb1, 74, 127]]);
+
+        let off = encode(
+                &mut mpe,
+                NoteEvent::NoteOff {
+                        channel: 0,
+                        note: 60,
+                        note_id: Some(1),
+                },
+        );
+        assert_eq!(off, vec![[0x81, 60, 0]]);
+
+        // 普通控制器发往主通道
+        let cc = encode(
+                &mut mpe,
+                NoteEvent::ControlChange {
+                        channel: 5,
+                        controller: 64,
+                        value: 1.0,
+                },
+        );
+        assert_eq!(cc, vec![[0xb0, 64, 127]]);
+}
+
+#[test]
+fn sequencer_sends_expressions_with_frame_offsets() {
+        let point = |time, value| ExpressionPoint {
+                kind: NoteExpressionKind::Pitch,
+                time,
+                value,
+        };
+        let mut sequencer = Sequencer::new();
+        sequencer.sample_rate = 1000.0;
+        sequencer.clips.push(Clip {
+                id: "clip".to_string(),
+                name: String::new(),
+                start_time: 0.0,
+                duration: 1.0,
+                instrument_ids: vec![0],
+                instrument_routes: HashMap::new(),
+                notes: vec![note_with(vec![point(0.0, 0.0), point(0.128, 1.28)])],
+                controllers: Vec::new(),
+        });
+        sequencer.set_transport(true, Some(0.0), None);
+
+        // 1 kHz 下 128 帧 = 0.128 秒：每个子块起点取一次插值（10 半音 / 秒）
+        let (events, _) = sequencer.process(128);
+        let timed: Vec<(u32, f32)> = events[&0]
+                .iter()
+                .filter_map(|e| match e {
+                        PluginEvent::MidiAt {
+                                frame,
+                                event: NoteEvent::NoteExpression { value, .. },
+                        } => Some((*frame, *value)),
+                        _ => None,
+                })
+                .collect();
+        let frames: Vec<u32> = timed.iter().map(|(frame, _)| *frame).collect();
+        assert_eq!(
+                frames,
+                (0..128).step_by(EXPRESSION_STEP_FRAMES)
+                        .map(|f| f as u32)
+                        .collect::<Vec<_>>()
+        );
+        for (frame, value) in timed {
+                assert!((value - frame as f32 / 100.0).abs() < 1e-4);
+        }
+
+        // 曲线终点在下一块的第一个子块送达，之后不再重复发送
+        let (events, _) = sequencer.process(128);
+        let tail: Vec<&PluginEvent> = events[&0]
+                .iter()
+                .filter(|e| matches!(e, PluginEvent::MidiAt { .. }))
+                .collect();
+        assert_eq!(tail.len(), 1);
+        let (events, _) = sequencer.process(128);
+        assert!(!events[&0].iter().any(|e| matches!(e, PluginEvent::MidiAt { .. })));
+}
@@ -0,0 +1,187 @@
+use my_daw_lib::audio::core::plugin::{
+        AudioBuffer, NoteEvent, ParameterType, Plugin, PluginEvent, PluginInfo, PluginParameter, PluginType,
+};
+use my_daw_lib::audio::core::smoothing::{
+        ParamRamper, RAMP_CHUNK_FRAMES, SmoothingConfig, SmoothingMode, set_smoothing_config,
+};
+
+// Host-side parameter ramps: sub-block splitting and ramp length.
+
+// Writes the current value of parameter 0 into every sample and records each process call
+#[derive(Default)]
+struct Recorder {
+        value: f32,
+        calls: Vec<(usize, f32, usize)>,
+}
+
+impl Plugin for Recorder {
+        fn info(&self) -> PluginInfo {
+                PluginInfo {
+                        name: "Recorder".to_string(),
+                        vendor: String::new(),
+                        url: String::new(),
+                        plugin_type: PluginType::Native,
+                        unique_id: "test.recorder".to_string(),
+                        parameters: None,
+                }
+        }
+
+        fn get_parameters(&self) -> Vec<PluginParameter> {
+                vec![PluginParameter::new(
+                        0,
+                        "Gain",
+                        0.0,
+                        1.0,
+                        0.0,
+                        ParameterType::Float,
+                )]
+        }
+
+        fn process(&mut self, buffer: &mut AudioBuffer, events: &[PluginEvent], _output: &mut Vec<PluginEvent>) {
+                for event in events {
+                        if let PluginEvent::Parameter { id: 0, value } = event {
+                                self.value = *value;
+                        }
+                }
+                buffer.samples.fill(self.value);
+                self.calls.push((buffer.frames(), self.value, events.len()));
+        }
+
+        fn get_param(&self, _id: u32) -> f32 {
+                self.value
+        }
+
+        fn set_param(&mut self, _id: u32, value: f32) {
+                self.value = value;
+        }
+}
+
+fn run(ramper: &mut ParamRamper, plugin: &mut Recorder, frames: usize, events: &[PluginEvent]) -> Vec<f32> {
+        let mut samples = vec![0.0; frames * 2];
+        let mut buffer = AudioBuffer {
+                samples: &mut samples,
+                channels: 2,
+                sample_rate: 64000.0,
+        };
+        ramper.process(plugin, &mut buffer, events, &mut Vec::new());
+        samples
+}
+
+// Both tests depend on the same global smoothing configuration
+fn enable_ramps() {
+        set_smoothing_config(SmoothingConfig {
+                mode: SmoothingMode::Linear,
+                time_ms: 1.0,
+                ramp_plugin_params: true,
+        });
+}
+
+#[test]
+fn ramp_splits_block_at_chunk_boundaries() {
+        enable_ramps();
+        let mut plugin = Recorder::default();
+        let mut ramper = ParamRamper::for_parameters(&plugin.get_parameters());
+        let set = |value| PluginEvent::Parameter { id: 0, value };
+
+        // 首个变化直接送达，整块一次处理
+        run(&mut ramper, &mut plugin, 128, &[set(0.0)]);
+        assert_eq!(plugin.calls, vec![(128, 0.0, 1)]);
+
+        // 1 ms @ 64 kHz = 64 帧的斜坡：两个子块渐变，剩余部分仍按子块处理；其它事件只随第一个子块送达
+        plugin.calls.clear();
+        let note = PluginEvent::Midi(NoteEvent::NoteOn {
+                channel: 0,
+                note: 60,
+                velocity: 1.0,
+                note_id: None,
+        });
+        let samples = run(&mut ramper, &mut plugin, 128, &[set(1.0), note]);
+        assert_eq!(
+                plugin.calls,
+                vec![
+                        (RAMP_CHUNK_FRAMES, 0.5, 1),
+                        (RAMP_CHUNK_FRAMES, 1.0, 0),
+                        (RAMP_CHUNK_FRAMES, 1.0, 0),
+                        (RAMP_CHUNK_FRAMES, 1.0, 0)
+                ]
+        );
+        // 子块写回各自的位置（每个通道）
+        for channel in samples.chunks(128) {
+                assert!(channel[..RAMP_CHUNK_FRAMES].iter().all(|s| *s == 0.5));
+                assert!(channel[RAMP_CHUNK_FRAMES..].iter().all(|s| *s == 1.0));
+        }
+}
+
+#[test]
+fn undeclared_parameters_bypass_the_ramp() {
+        enable_ramps();
+        let mut plugin = Recorder::default();
+        let mut ramper = ParamRamper::for_parameters(&[]);
+        let set = |value| PluginEvent::Parameter { id: 0, value };
+        run(&mut ramper, &mut plugin, 64, &[set(0.0)]);
+        run(&mut ramper, &mut plugin, 64, &[set(1.0)]);
+        assert_eq!(plugin.calls, vec![(64, 0.0, 1), (64, 1.0, 1)]);
+}
+
+#[test]
+fn timed_events_reach_the_chunk_containing_their_frame() {
+        enable_ramps();
+        let mut plugin = Recorder::default();
+        let mut ramper = ParamRamper::for_parameters(&plugin.get_parameters());
+        let set = |value| PluginEvent::Parameter { id: 0, value };
+        run(&mut ramper, &mut plugin, 128, &[set(0.0)]);
+
+        let at = |frame| PluginEvent::MidiAt {
+                frame,
+                event: NoteEvent::NoteOff {
+                        channel: 0,
+                        note: 60,
+                        note_id: None,
+                },
+        };
+        plugin.calls.clear();
+        let offset = RAMP_CHUNK_FRAMES as u32;
+        run(
+                &mut ramper,
+                &mut plugin,
+                128,
+                &[set(1.0), at(offset + 8), at(100)],
+        );
+        // 第二个子块收到一个事件，第四个子块收到块内帧 100 的事件
+        let counts: Vec<usize> = plugin.calls.iter().map(|c| c.2).collect();
+        assert_eq!(counts, vec![0, 1, 0, 1]);
+}
+
+#[test]
+fn ramp_ending_mid_block_keeps_chunks_within_the_reserved_buffer() {
+        enable_ramps();
+        let mut plugin = Recorder::default();
+        let mut ramper = ParamRamper::for_parameters(&plugin.get_parameters());
+        let capacity = ramper.chunk_capacity();
+        let set = |value| PluginEvent::Parameter { id: 0, value };
+        run(&mut ramper, &mut plugin, 128, &[set(0.0)]);
+
+        // the 64-frame ramp starts in a 40-frame block and ends 24 frames into the next 200-frame block
+        run(&mut ramper, &mut plugin, 40, &[set(1.0)]);
+        plugin.calls.clear();
+        let samples = run(&mut ramper, &mut plugin, 200, &[]);
+        let frames: Vec<usize> = plugin.calls.iter().map(|c| c.0).collect();
+        assert_eq!(frames, vec![32, 32, 32, 32, 32, 32, 8]);
+        for channel in samples.chunks(200) {
+                assert!(channel.iter().all(|s| *s == 1.0));
+        }
+
+        // more channels than reserved: shorter chunks in the same buffer
+        run(&mut ramper, &mut plugin, 128, &[set(0.0)]);
+        plugin.calls.clear();
+        let mut samples = vec![0.0; 12 * 100];
+        let mut buffer = AudioBuffer {
+                samples: &mut samples,
+                channels: 12,
+                sample_rate: 64000.0,
+        };
+        ramper.process(&mut plugin, &mut buffer, &[set(0.5)], &mut Vec::new());
+        assert!(plugin.calls.iter().all(|c| c.0 <= 256 / 12));
+        assert_eq!(plugin.calls.iter().map(|c| c.0).sum::<usize>(), 100);
+        assert_eq!(ramper.chunk_capacity(), capacity);
+}
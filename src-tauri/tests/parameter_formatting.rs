@@ -0,0 +1,45 @@
+use my_daw_lib::audio::core::plugin::{ParameterTaper, ParameterType, PluginParameter};
+
+// Round-trip checks for the host-side parameter formatting used when a plugin
+// does not provide its own value<->text callbacks.
+
+#[test]
+fn format_and_parse_with_units() {
+        let mut gain = PluginParameter::new(0, "Gain", -60.0, 12.0, 0.0, ParameterType::Float);
+        gain.unit = "dB".to_string();
+        assert_eq!(gain.format_value(-6.0), "-6.0 dB");
+        assert_eq!(gain.parse_value("-6 dB"), Some(-6.0));
+        assert_eq!(gain.parse_value("-inf"), Some(-60.0));
+        assert_eq!(gain.parse_value("100"), Some(12.0));
+
+        let mut freq = PluginParameter::new(1, "Frequency", 20.0, 20000.0, 440.0, ParameterType::Float);
+        freq.unit = "Hz".to_string();
+        freq.taper = ParameterTaper::Logarithmic;
+        assert_eq!(freq.format_value(1200.0), "1.2 kHz");
+        assert_eq!(freq.format_value(440.0), "440 Hz");
+        assert_eq!(freq.parse_value("1.2k"), Some(1200.0));
+        assert!((freq.from_normalized(freq.to_normalized(1000.0)) - 1000.0).abs() < 0.5);
+
+        let mode = PluginParameter::new(
+                2,
+                "Mode",
+                0.0,
+                2.0,
+                0.0,
+                ParameterType::Enum(vec![
+                        "Low".to_string(),
+                        "Mid".to_string(),
+                        "High".to_string(),
+                ]),
+        );
+        assert_eq!(mode.format_value(1.0), "Mid");
+        assert_eq!(mode.parse_value("high"), Some(2.0));
+        assert_eq!(mode.from_normalized(0.4), 1.0);
+        // 插件返回的解析结果同样按范围与步进对齐
+        assert_eq!(mode.quantize(7.0), 2.0);
+        assert_eq!(mode.quantize(0.6), 1.0);
+
+        let voices = PluginParameter::new(3, "Voices", 1.0, 16.0, 8.0, ParameterType::Int);
+        assert_eq!(voices.parse_value("2.6"), Some(3.0));
+        assert_eq!(voices.quantize(-4.0), 1.0);
+}
@@ -0,0 +1,158 @@
+use my_daw_lib::audio::core::buffer::{PlanarBuffer, deinterleave, interleave};
+use my_daw_lib::audio::core::plugin::{
+        AudioBuffer, IOConfig, Plugin, PluginEvent, PluginInfo, PluginParameter, PluginType,
+};
+use my_daw_lib::audio::plugins::mixer::delay_line::DelayLine;
+use my_daw_lib::audio::plugins::mixer::mixer_plugin::MixerPlugin;
+use my_daw_lib::audio::plugins::mixer::safety::SafetySettings;
+use my_daw_lib::audio::plugins::mixer::track::InsertSlot;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+// Planar processing buffers and the interleaved conversion layer.
+
+#[test]
+fn interleaved_round_trip() {
+        let interleaved = [0.0, 10.0, 1.0, 11.0, 2.0, 12.0];
+        let mut buffer = PlanarBuffer::default();
+        buffer.read_interleaved(&interleaved, 2);
+        assert_eq!((buffer.channels(), buffer.frames()), (2, 3));
+        assert_eq!(buffer.channel(0), [0.0, 1.0, 2.0]);
+        assert_eq!(buffer.channel(1), [10.0, 11.0, 12.0]);
+
+        let audio = buffer.as_audio_buffer(48000.0);
+        assert_eq!(audio.frames(), 3);
+        assert_eq!(audio.channel(1), [10.0, 11.0, 12.0]);
+        assert!(audio.channel(2).is_empty());
+
+        let mut out = [0.0; 6];
+        buffer.write_interleaved(&mut out);
+        assert_eq!(out, interleaved);
+
+        let mut planar = [0.0; 6];
+        deinterleave(&interleaved, &mut planar, 3);
+        let mut back = [0.0; 6];
+        interleave(&planar, &mut back, 3);
+        assert_eq!(back, interleaved);
+}
+
+#[test]
+fn delay_line_delays_each_channel() {
+        let mut delay = DelayLine::new(2);
+        delay.set_delay(2);
+        let mut block = [1.0, 2.0, 3.0, 10.0, 20.0, 30.0];
+        delay.process(&mut block);
+        assert_eq!(block, [0.0, 0.0, 1.0, 0.0, 0.0, 10.0]);
+        let mut block = [4.0, 5.0, 40.0, 50.0];
+        delay.process(&mut block);
+        assert_eq!(block, [2.0, 3.0, 20.0, 30.0]);
+}
+
+#[test]
+fn delay_line_keeps_history_when_delay_changes() {
+        let mut delay = DelayLine::new(1);
+        let mut block = [1.0, 2.0, 3.0, 4.0];
+        delay.process(&mut block);
+        assert_eq!(block, [1.0, 2.0, 3.0, 4.0]);
+        // Growing the delay only moves the read position back into the recorded history
+        delay.set_delay(3);
+        let mut block = [5.0, 6.0];
+        delay.process(&mut block);
+        assert_eq!(block, [2.0, 3.0]);
+        delay.set_delay(1);
+        let mut block = [7.0, 8.0];
+        delay.process(&mut block);
+        assert_eq!(block, [6.0, 7.0]);
+}
+
+// Insert that delays its input and reports the delay as latency
+struct Lookahead {
+        delay: DelayLine,
+}
+
+impl Plugin for Lookahead {
+        fn info(&self) -> PluginInfo {
+                PluginInfo {
+                        name: "Lookahead".to_string(),
+                        vendor: String::new(),
+                        url: String::new(),
+                        plugin_type: PluginType::Native,
+                        unique_id: "test.lookahead".to_string(),
+                        parameters: None,
+                }
+        }
+
+        fn get_parameters(&self) -> Vec<PluginParameter> {
+                Vec::new()
+        }
+
+        fn get_io_config(&self) -> IOConfig {
+                IOConfig::default()
+        }
+
+        fn latency(&self) -> u32 {
+                self.delay.delay_frames() as u32
+        }
+
+        fn process(&mut self, buffer: &mut AudioBuffer, _events: &[PluginEvent], _output: &mut Vec<PluginEvent>) {
+                self.delay.process(buffer.samples);
+        }
+
+        fn get_param(&self, _id: u32) -> f32 {
+                0.0
+        }
+
+        fn set_param(&mut self, _id: u32, _value: f32) {}
+}
+
+#[test]
+fn mixer_aligns_track_with_latent_insert_at_master() {
+        const LATENCY: usize = 24;
+        const FRAMES: usize = 64;
+        let mut mixer = MixerPlugin::new(0);
+        mixer.set_master_safety(SafetySettings {
+                enabled: false,
+                ..Default::default()
+        });
+        for index in 0..3 {
+                mixer.add_track(Uuid::new_v4(), None);
+                mixer.get_track_mut(index).unwrap().set_levels(1.0, 0.0);
+        }
+        let mut delay = DelayLine::new(2);
+        delay.set_delay(LATENCY);
+        let plugin: Box<dyn Plugin> = Box::new(Lookahead { delay });
+        mixer.get_track_mut(1).unwrap().add_insert(InsertSlot {
+                id: Uuid::new_v4(),
+                plugin: Arc::new(Mutex::new(plugin)),
+                bypass: false,
+                post_fader: false,
+        });
+
+        // the insert's latency is read while it processes, so compensation applies from the next block
+        let mut run = |impulse: bool| {
+                for input in 0..2 {
+                        let samples = mixer.aux_input_mut(input, FRAMES * 2).unwrap();
+                        samples.fill(0.0);
+                        if impulse {
+                                samples[0] = 0.25;
+                                samples[FRAMES] = 0.25;
+                        }
+                }
+                let mut samples = vec![0.0; FRAMES * 2];
+                let mut buffer = AudioBuffer {
+                        samples: &mut samples,
+                        channels: 2,
+                        sample_rate: 48000.0,
+                };
+                mixer.process(&mut buffer, &[], &mut Vec::new());
+                samples
+        };
+        run(false);
+        let samples = run(true);
+
+        // both impulses (the uncompensated track delayed to match) land on the same frame of each channel
+        for channel in samples.chunks(FRAMES) {
+                let hits: Vec<(usize, f32)> = channel.iter().copied().enumerate().filter(|(_, s)| *s != 0.0).collect();
+                assert_eq!(hits, vec![(LATENCY, 0.5)]);
+        }
+}
@@ -0,0 +1,81 @@
+use my_daw_lib::audio::plugins::presets::{PluginPreset, PresetParam, PresetStore};
+
+// User preset storage round trip: save, search, rename and delete in a temporary folder.
+
+fn preset(name: &str, tags: &[&str]) -> PluginPreset {
+        PluginPreset {
+                name: name.to_string(),
+                plugin_id: "com.mydaw.gainfader".to_string(),
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                description: String::new(),
+                factory: false,
+                state: Vec::new(),
+                params: vec![PresetParam { id: 0, value: -6.0 }],
+        }
+}
+
+#[test]
+fn save_search_rename_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PresetStore::new(dir.path());
+
+        store.save(&preset("Warm Pad", &["pad", "soft"]), false).unwrap();
+        store.save(&preset("Lead/Bright", &["lead"]), false).unwrap();
+        assert!(store.save(&preset("Warm Pad", &[]), false).is_err());
+
+        let all = store.list("com.mydaw.gainfader");
+        assert_eq!(all.len(), 2);
+        let loaded = store.get("com.mydaw.gainfader", "Lead/Bright").unwrap();
+        assert_eq!(loaded.params, vec![PresetParam { id: 0, value: -6.0 }]);
+
+        let soft: Vec<_> = all.iter().filter(|p| p.matches("", &["SOFT".to_string()])).collect();
+        assert_eq!(soft.len(), 1);
+        assert!(all.iter().any(|p| p.matches("bright", &[])));
+
+        store.rename("com.mydaw.gainfader", "Warm Pad", "Dark Pad").unwrap();
+        assert!(store.get("com.mydaw.gainfader", "Warm Pad").is_none());
+        assert_eq!(
+                store.get("com.mydaw.gainfader", "Dark Pad").unwrap().tags,
+                vec!["pad", "soft"]
+        );
+
+        store.delete("com.mydaw.gainfader", "Dark Pad").unwrap();
+        assert_eq!(store.list("com.mydaw.gainfader").len(), 1);
+}
+
+#[test]
+fn names_that_sanitize_alike_do_not_collide() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PresetStore::new(dir.path());
+
+        store.save(&preset("a/b", &["slash"]), false).unwrap();
+        store.save(&preset("a?b", &["question"]), false).unwrap();
+        store.save(&preset("A/B", &["upper"]), false).unwrap();
+        assert_eq!(store.list("com.mydaw.gainfader").len(), 3);
+        assert_eq!(
+                store.get("com.mydaw.gainfader", "a/b").unwrap().tags,
+                vec!["slash"]
+        );
+        assert_eq!(
+                store.get("com.mydaw.gainfader", "a?b").unwrap().tags,
+                vec!["question"]
+        );
+
+        store.delete("com.mydaw.gainfader", "a?b").unwrap();
+        assert!(store.get("com.mydaw.gainfader", "a/b").is_some());
+
+        // Presets written with the old sanitized file name are still found and migrated on save
+        let legacy = dir.path().join("com.mydaw.gainfader").join("Old_Name.json");
+        std::fs::write(
+                &legacy,
+                serde_json::to_vec(&preset("Old:Name", &[])).unwrap(),
+        )
+        .unwrap();
+        assert!(store.get("com.mydaw.gainfader", "Old:Name").is_some());
+        store.save(&preset("Old:Name", &["new"]), true).unwrap();
+        assert!(!legacy.exists());
+        assert_eq!(
+                store.get("com.mydaw.gainfader", "Old:Name").unwrap().tags,
+                vec!["new"]
+        );
+}
@@ -0,0 +1,79 @@
+use my_daw_lib::audio::core::plugin::{NoteEvent, NoteExpressionKind, PluginEvent};
+use my_daw_lib::audio::sandbox::protocol::{EVENT_RECORD_BYTES, read_events, write_events};
+
+// Binary event records exchanged with sandboxed plugins through shared memory.
+
+// PluginEvent has no PartialEq; compare the debug representation
+fn round_trip(events: &[PluginEvent]) -> (usize, Vec<String>) {
+        let mut area = vec![0u8; 4096];
+        let used = write_events(&mut area, events);
+        let mut decoded = Vec::new();
+        read_events(&area[..used], |event| decoded.push(format!("{:?}", event)));
+        (used, decoded)
+}
+
+#[test]
+fn plugin_events_survive_the_round_trip() {
+        let events = vec![
+                PluginEvent::Midi(NoteEvent::NoteOn {
+                        channel: 3,
+                        note: 60,
+                        velocity: 0.75,
+                        note_id: Some(42),
+                }),
+                PluginEvent::MidiAt {
+                        frame: 17,
+                        event: NoteEvent::NoteExpression {
+                                channel: 1,
+                                note: 64,
+                                note_id: None,
+                                expression: NoteExpressionKind::Brightness,
+                                value: 0.25,
+                        },
+                },
+                PluginEvent::Midi(NoteEvent::PitchBend {
+                        channel: 0,
+                        value: -0.5,
+                }),
+                PluginEvent::Midi(NoteEvent::ProgramChange {
+                        channel: 9,
+                        program: 12,
+                }),
+                PluginEvent::Parameter { id: 7, value: 0.125 },
+                PluginEvent::Transport {
+                        playing: true,
+                        position: Some(12.5),
+                        tempo: None,
+                },
+                PluginEvent::AllNotesOff { panic: true },
+                PluginEvent::TimeSignature {
+                        numerator: 7,
+                        denominator: 8,
+                },
+        ];
+        let (used, decoded) = round_trip(&events);
+        assert_eq!(used, events.len() * EVENT_RECORD_BYTES);
+        let expected: Vec<String> = events.iter().map(|event| format!("{:?}", event)).collect();
+        assert_eq!(decoded, expected);
+}
+
+#[test]
+fn host_internal_events_and_overflow_are_dropped() {
+        let (used, decoded) = round_trip(&[
+                PluginEvent::Custom("ignored".to_string()),
+                PluginEvent::Parameter { id: 1, value: 1.0 },
+        ]);
+        assert_eq!(used, EVENT_RECORD_BYTES);
+        assert_eq!(
+                decoded,
+                vec![format!(
+                        "{:?}",
+                        PluginEvent::Parameter { id: 1, value: 1.0 }
+                )]
+        );
+
+        // an area that fits two records keeps the first two events
+        let mut area = vec![0u8; EVENT_RECORD_BYTES * 2 + 5];
+        let events: Vec<PluginEvent> = (0..4).map(|id| PluginEvent::Parameter { id, value: 0.0 }).collect();
+        assert_eq!(write_events(&mut area, &events), EVENT_RECORD_BYTES * 2);
+}
@@ -0,0 +1,106 @@
+use my_daw_lib::audio::core::clip::{Clip, Note};
+use my_daw_lib::audio::core::plugin::{NoteEvent, PluginEvent};
+use my_daw_lib::audio::midi::mpe::MpeEncoder;
+use my_daw_lib::daw::sequencer::Sequencer;
+use std::collections::HashMap;
+
+// Sequencer note tracking: overlapping same-pitch notes and MPE member channel reuse.
+
+fn note(id: u32, pitch: u8, start: f64, duration: f64) -> Note {
+        Note {
+                id,
+                relative_start: start,
+                duration,
+                note: pitch,
+                velocity: 1.0,
+                channel: 0,
+                expressions: Vec::new(),
+        }
+}
+
+// 1 kHz 下每块 100 帧 = 0.1 秒
+fn sequencer(notes: Vec<Note>, note_ids: bool) -> Sequencer {
+        let mut sequencer = Sequencer::new();
+        sequencer.sample_rate = 1000.0;
+        sequencer.active_notes.set_note_ids(0, note_ids);
+        sequencer.clips.push(Clip {
+                id: "clip".to_string(),
+                name: String::new(),
+                start_time: 0.0,
+                duration: 1.0,
+                instrument_ids: vec![0],
+                instrument_routes: HashMap::new(),
+                notes,
+                controllers: Vec::new(),
+        });
+        sequencer.set_transport(true, Some(0.0), None);
+        sequencer
+}
+
+fn block(sequencer: &mut Sequencer) -> Vec<PluginEvent> {
+        let (mut events, _) = sequencer.process(100);
+        events.remove(&0).unwrap_or_default()
+}
+
+fn note_offs(events: &[PluginEvent]) -> Vec<u32> {
+        events.iter()
+                .filter_map(|e| match e {
+                        PluginEvent::Midi(NoteEvent::NoteOff { note_id, .. }) => *note_id,
+                        _ => None,
+                })
+                .collect()
+}
+
+#[test]
+fn overlapping_notes_release_by_note_id() {
+        let notes = || vec![note(1, 60, 0.0, 0.25), note(2, 60, 0.1, 0.35)];
+
+        // 支持 note_id：第一个音符在自己结束的块内释放
+        let mut with_ids = sequencer(notes(), true);
+        let offs: Vec<Vec<u32>> = (0..5).map(|_| note_offs(&block(&mut with_ids))).collect();
+        assert_eq!(offs, vec![vec![], vec![], vec![1], vec![], vec![2]]);
+        assert!(with_ids.active_notes.is_empty());
+
+        // 不支持 note_id：同音高的 NoteOff 推迟到最后一个音符结束时一并发送
+        let mut coalesced = sequencer(notes(), false);
+        let offs: Vec<Vec<u32>> = (0..5).map(|_| note_offs(&block(&mut coalesced))).collect();
+        assert_eq!(offs.iter().filter(|o| !o.is_empty()).count(), 1);
+        let mut last = offs[4].clone();
+        last.sort();
+        assert_eq!(last, vec![1, 2]);
+        assert!(coalesced.active_notes.is_empty());
+}
+
+#[test]
+fn released_note_frees_its_mpe_channel() {
+        // 15 个同音高音符占满全部成员通道，第一个先结束；随后的新音符应复用它的通道而不是抢占其它音符
+        let mut notes: Vec<Note> = (1..=15).map(|id| note(id, 60, 0.0, 0.9)).collect();
+        notes[0].duration = 0.15;
+        notes.push(note(16, 64, 0.22, 0.5));
+        let mut sequencer = sequencer(notes, true);
+        let mut mpe = MpeEncoder::new();
+
+        let mut blocks = Vec::new();
+        for _ in 0..3 {
+                let mut messages = Vec::new();
+                for event in block(&mut sequencer) {
+                        if let Some((_, midi)) = event.as_midi() {
+                                mpe.encode(midi, |bytes| messages.push(bytes));
+                        }
+                }
+                blocks.push(messages);
+        }
+        let kind = |messages: &[[u8; 3]], status: u8| -> Vec<[u8; 3]> {
+                messages.iter().copied().filter(|m| m[0] & 0xf0 == status).collect()
+        };
+
+        let first_channel = kind(&blocks[0], 0x90)[0][0] & 0x0f;
+        // 第一个音符在它结束的块内释放成员通道
+        assert_eq!(kind(&blocks[1], 0x80), vec![[0x80 | first_channel, 60, 0]]);
+        // 新音符复用该通道，没有抢占其它音符
+        assert!(kind(&blocks[2], 0x80).is_empty());
+        assert_eq!(
+                kind(&blocks[2], 0x90),
+                vec![[0x90 | first_channel, 64, 127]]
+        );
+}
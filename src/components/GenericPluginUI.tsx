@@ -1,7 +1,7 @@
 import { Component, For, createSignal, onMount } from 'solid-js'
 import { listen } from '@tauri-apps/api/event'
 import type { PluginParameter } from '../plugins/api'
-import { getInstanceParameters, setInstanceParameter } from '../plugins/api'
+import { formatInstanceParameter, getInstanceParameters, setInstanceParameter } from '../plugins/api'
 
 interface GenericPluginUIProps {
         uniqueId: string
@@ -12,6 +12,18 @@ interface GenericPluginUIProps {
 export const GenericPluginUI: Component<GenericPluginUIProps> = props => {
         const [params, setParams] = createSignal<PluginParameter[]>([])
         const [values, setValues] = createSignal<number[]>([])
+        const [texts, setTexts] = createSignal<string[]>([])
+
+        const refreshText = async (index: number, id: number, v: number) => {
+                try {
+                        const text = await formatInstanceParameter(props.instanceId, id, v)
+                        const next = [...texts()]
+                        next[index] = text
+                        setTexts(next)
+                } catch (e) {
+                        // keep the previous text
+                }
+        }
 
         onMount(async () => {
                 try {
@@ -19,6 +31,7 @@ export const GenericPluginUI: Component<GenericPluginUIProps> = props => {
                         if (res) {
                                 setParams(res.params)
                                 setValues(res.values)
+                                setTexts(res.texts)
                         }
                 } catch (e) {
                         // ignore
@@ -37,6 +50,7 @@ export const GenericPluginUI: Component<GenericPluginUIProps> = props => {
                                                 const next = [...values()]
                                                 next[idx] = val
                                                 setValues(next)
+                                                refreshText(idx, pId, val)
                                         }
                                 }
                         })
@@ -53,6 +67,7 @@ export const GenericPluginUI: Component<GenericPluginUIProps> = props => {
                         const next = [...values()]
                         next[index] = v
                         setValues(next)
+                        refreshText(index, id, v)
                 } catch (e) {
                         console.error('Failed to set parameter', e)
                 }
@@ -62,13 +77,17 @@ export const GenericPluginUI: Component<GenericPluginUIProps> = props => {
                 <div class='text-on-surface-variant space-y-2 p-2 text-sm'>
                         <For each={params()}>
                                 {(param, i) => (
-                                        <div class='flex items-center gap-3'>
+                                        <div class='flex items-center gap-3' hidden={param.flags?.hidden}>
                                                 <div class='text-on-surface-variant w-28 text-xs'>{param.name}</div>
                                                 <input
                                                         type='range'
                                                         min={param.min_value}
                                                         max={param.max_value}
-                                                        step={(param.max_value - param.min_value) / 100}
+                                                        step={
+                                                                (param.max_value - param.min_value) /
+                                                                (param.step_count > 0 ? param.step_count : 100)
+                                                        }
+                                                        disabled={param.flags?.read_only}
                                                         value={values()[i()] ?? param.default_value}
                                                         onInput={e =>
                                                                 onChange(
@@ -79,8 +98,8 @@ export const GenericPluginUI: Component<GenericPluginUIProps> = props => {
                                                         }
                                                         class='flex-1'
                                                 />
-                                                <div class='w-12 text-right text-xs'>
-                                                        {(values()[i()] ?? param.default_value).toFixed(2)}
+                                                <div class='w-16 text-right text-xs'>
+                                                        {texts()[i()] ?? (values()[i()] ?? param.default_value).toFixed(2)}
                                                 </div>
                                         </div>
                                 )}
@@ -139,7 +139,7 @@ export const MixerPanel: Component = () => {
                                                                                         track.meter_id
                                                                                                 ? meterLevels()[
                                                                                                           track.meter_id
-                                                                                                  ] || 0
+                                                                                                  ]?.level || 0
                                                                                                 : 0
                                                                                 }
                                                                         />
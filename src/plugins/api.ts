@@ -8,6 +8,14 @@ export enum PluginType {
 
 export type ParameterType = 'Float' | 'Int' | 'Bool' | { Enum: string[] }
 
+export type ParameterTaper = 'Linear' | 'Logarithmic' | { Skew: number }
+
+export interface ParameterFlags {
+        automatable: boolean
+        read_only: boolean
+        hidden: boolean
+}
+
 export interface PluginParameter {
         id: number
         name: string
@@ -15,6 +23,11 @@ export interface PluginParameter {
         max_value: number
         default_value: number
         value_type: ParameterType
+        unit: string
+        taper: ParameterTaper
+        step_count: number
+        flags: ParameterFlags
+        group: string
 }
 
 export interface PluginInfo {
@@ -55,13 +68,98 @@ export async function getActivePlugins(): Promise<any[]> {
 
 export async function getInstanceParameters(
         instanceId: string
-): Promise<{ params: PluginParameter[]; values: number[] } | null> {
+): Promise<{ params: PluginParameter[]; values: number[]; texts: string[] } | null> {
         return (await invoke('get_instance_parameters', { instanceId })) as {
                 params: PluginParameter[]
                 values: number[]
+                texts: string[]
         } | null
 }
 
+export async function formatInstanceParameter(instanceId: string, paramId: number, value: number): Promise<string> {
+        return (await invoke('format_instance_parameter', { instanceId, paramId, value })) as string
+}
+
+export async function parseInstanceParameter(instanceId: string, paramId: number, text: string): Promise<number> {
+        return (await invoke('parse_instance_parameter', { instanceId, paramId, text })) as number
+}
+
 export async function setInstanceParameter(instanceId: string, paramId: number, value: number): Promise<void> {
         await invoke('set_instance_parameter', { instanceId, param_id: paramId, value })
 }
+
+export interface PresetInfo {
+        name: string
+        plugin_id: string
+        tags: string[]
+        description: string
+        factory: boolean
+}
+
+export async function listPluginPresets(pluginId: string, query?: string, tags?: string[]): Promise<PresetInfo[]> {
+        return (await invoke('list_plugin_presets', { pluginId, query, tags })) as PresetInfo[]
+}
+
+export async function getPluginPresetTags(pluginId: string): Promise<string[]> {
+        return (await invoke('get_plugin_preset_tags', { pluginId })) as string[]
+}
+
+export async function savePluginPreset(
+        instanceId: string,
+        name: string,
+        options: { tags?: string[]; description?: string; overwrite?: boolean } = {}
+): Promise<PresetInfo> {
+        return (await invoke('save_plugin_preset', { instanceId, name, ...options })) as PresetInfo
+}
+
+export async function loadPluginPreset(instanceId: string, name: string, factory = false): Promise<void> {
+        await invoke('load_plugin_preset', { instanceId, name, factory })
+}
+
+export async function renamePluginPreset(pluginId: string, name: string, newName: string): Promise<void> {
+        await invoke('rename_plugin_preset', { pluginId, name, newName })
+}
+
+export async function deletePluginPreset(pluginId: string, name: string): Promise<void> {
+        await invoke('delete_plugin_preset', { pluginId, name })
+}
+
+export async function setPluginPresetMetadata(
+        pluginId: string,
+        name: string,
+        tags: string[],
+        description?: string
+): Promise<void> {
+        await invoke('set_plugin_preset_metadata', { pluginId, name, tags, description })
+}
+
+export type SnapshotSlot = 'A' | 'B' | string
+
+export async function listInstanceSnapshots(instanceId: string): Promise<SnapshotSlot[]> {
+        return (await invoke('list_instance_snapshots', { instanceId })) as SnapshotSlot[]
+}
+
+export async function storeInstanceSnapshot(instanceId: string, slot: SnapshotSlot): Promise<void> {
+        await invoke('store_instance_snapshot', { instanceId, slot })
+}
+
+export async function recallInstanceSnapshot(instanceId: string, slot: SnapshotSlot): Promise<void> {
+        await invoke('recall_instance_snapshot', { instanceId, slot })
+}
+
+export async function copyInstanceSnapshot(instanceId: string, from: SnapshotSlot, to: SnapshotSlot): Promise<void> {
+        await invoke('copy_instance_snapshot', { instanceId, from, to })
+}
+
+export async function clearInstanceSnapshot(instanceId: string, slot: SnapshotSlot): Promise<void> {
+        await invoke('clear_instance_snapshot', { instanceId, slot })
+}
+
+export async function morphInstanceSnapshots(
+        instanceId: string,
+        from: SnapshotSlot,
+        to: SnapshotSlot,
+        amount: number
+): Promise<void> {
+        await invoke('morph_instance_snapshots', { instanceId, from, to, amount })
+}
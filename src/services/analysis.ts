@@ -0,0 +1,38 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export interface AnalysisSettings {
+        fft_size: number // power of two, 256..16384
+        averaging: number // exponential spectrum smoothing, 0 (none) .. <1
+        bands: number // log-spaced frequency bands
+        min_frequency: number // Hz
+        waveform_points: number // min/max pairs per waveform snapshot
+        waveform_length: number // seconds covered by the snapshot (max 1)
+}
+
+export interface AnalysisFrame {
+        id: string // analysis session
+        node: string // analysed node
+        sample_rate: number
+        frequencies: number[] // band centre frequencies (Hz)
+        spectrum: number[] // band levels (dBFS)
+        waveform: [number, number][] // [min, max] per point
+}
+
+// `node` is a mixer track node_id or an instrument / insert instance_id; returns the session id.
+// Frames arrive as `analysis-frame` events (~30 per second) until the session is stopped.
+export async function startAnalysis(node: string, settings?: AnalysisSettings): Promise<string> {
+        return await invoke('start_analysis', { node, settings })
+}
+
+export async function setAnalysisSettings(id: string, settings: AnalysisSettings): Promise<void> {
+        await invoke('set_analysis_settings', { id, settings })
+}
+
+export async function stopAnalysis(id: string): Promise<void> {
+        await invoke('stop_analysis', { id })
+}
+
+export async function onAnalysisFrame(handler: (frame: AnalysisFrame) => void): Promise<UnlistenFn> {
+        return await listen<AnalysisFrame>('analysis-frame', event => handler(event.payload))
+}
@@ -0,0 +1,99 @@
+import { invoke } from '@tauri-apps/api/core'
+
+// follow: the sequencer follows JACK transport; drive: also acts as JACK timebase master
+export type JackTransportMode = 'off' | 'follow' | 'drive'
+
+export interface JackSettings {
+        client_name: string
+        track_outputs: boolean // stereo post-fader output ports per mixer track
+        track_inputs: boolean // stereo input ports per mixer track, mixed into the track input
+        auto_connect: boolean // connect master to system playback when no saved connections exist
+        transport: JackTransportMode
+}
+
+export type AudioBackend = { type: 'cpal' } | ({ type: 'jack' } & JackSettings)
+
+export async function getAudioBackend(): Promise<AudioBackend> {
+        return await invoke('get_audio_backend')
+}
+
+// Restarts the running engine on the new backend; on failure the previous backend is kept
+export async function setAudioBackend(backend: AudioBackend): Promise<void> {
+        await invoke('set_audio_backend', { backend })
+}
+
+export interface ProcessingSettings {
+        sampleRate: number | null // project sample rate; null follows the device, otherwise resampled at the output
+        doublePrecision: boolean // 64-bit summing on the mixer bus
+}
+
+export async function getProcessingSettings(): Promise<ProcessingSettings> {
+        return await invoke('get_processing_settings')
+}
+
+// Saved with the project; rebuilds the running engine
+export async function setProcessingSettings(settings: ProcessingSettings): Promise<void> {
+        await invoke('set_processing_settings', { settings })
+}
+
+export type ClickSound = 'beep' | 'woodblock' | 'noise'
+
+// dedicated: separate "Click" output port (JACK with track outputs); falls back to master elsewhere
+export type MetronomeOutput = 'master' | 'dedicated'
+
+export interface MetronomeSettings {
+        enabled: boolean
+        sound: ClickSound
+        level: number // linear gain 0..1
+        accent: boolean // higher, louder click on the first beat of each bar
+        count_in_bars: number // bars played before recording starts
+        record_only: boolean // click only while recording (including count-in)
+        output: MetronomeOutput
+}
+
+export interface TimeSignature {
+        numerator: number
+        denominator: number
+}
+
+export async function getMetronome(): Promise<MetronomeSettings> {
+        return await invoke('get_metronome')
+}
+
+export async function setMetronome(settings: MetronomeSettings): Promise<void> {
+        await invoke('set_metronome', { settings })
+}
+
+// Master safety stage: lookahead brickwall limiter (plus optional DC blocker) on the device output.
+// Disable it to pass >0 dBFS peaks unchanged to float outputs such as JACK ports (there is no offline
+// render yet); NaN / Inf protection always stays on.
+export interface SafetySettings {
+        enabled: boolean
+        ceiling: number // dBFS, -24..0
+        lookahead: number // ms, 0.1..10 (adds this much output latency)
+        release: number // ms, 1..2000
+        dc_blocker: boolean
+}
+
+export async function getMasterSafety(): Promise<SafetySettings> {
+        return await invoke('get_master_safety')
+}
+
+export async function setMasterSafety(settings: SafetySettings): Promise<void> {
+        await invoke('set_master_safety', { settings })
+}
+
+// Instruments and inserts that output NaN / Inf are muted and reported through the
+// `plugin-non-finite` event ({ instanceId }); this restores them
+export async function unmutePlugins(): Promise<void> {
+        await invoke('unmute_plugins')
+}
+
+export async function getTimeSignature(): Promise<TimeSignature> {
+        return await invoke('get_time_signature')
+}
+
+// The denominator must be a power of two up to 32
+export async function setTimeSignature(numerator: number, denominator: number): Promise<void> {
+        await invoke('set_time_signature', { numerator, denominator })
+}
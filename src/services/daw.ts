@@ -20,6 +20,7 @@ export const DawService = {
                         trackId: updates.trackId,
                         length: updates.length,
                         notes: updates.notes,
+                        controllers: updates.controllers,
                         instrumentIds: updates.instrumentIds,
                         instrumentRoutes: updates.instrumentRoutes
                 })
@@ -61,6 +62,21 @@ export const DawService = {
                 await invoke('pause')
         },
 
+        // Plays the metronome count-in bars first, then starts the transport from the current position
+        async record(): Promise<void> {
+                await invoke('record')
+        },
+
+        // True while recording, including the count-in
+        async getRecordingState(): Promise<boolean> {
+                return await invoke('get_recording_state')
+        },
+
+        // Release all sounding notes; panic also sends All Sound Off / All Notes Off on every channel
+        async allNotesOff(panic = false): Promise<void> {
+                await invoke('all_notes_off', { panic })
+        },
+
         async getPlaybackState(): Promise<[boolean, number]> {
                 return await invoke('get_playback_state')
         },
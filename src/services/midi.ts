@@ -0,0 +1,125 @@
+import { invoke } from '@tauri-apps/api/core'
+
+export interface MidiInputStatus {
+        id: string
+        name: string
+        open: boolean
+}
+
+export type MidiControlKind =
+        | { type: 'control_change'; controller: number }
+        | { type: 'pitch_bend' }
+        | { type: 'channel_pressure' }
+        | { type: 'poly_pressure'; note: number }
+
+export interface MidiControlSource {
+        port?: string | null // port name; null matches any port
+        channel?: number | null // 0-15; null matches any channel
+        kind: MidiControlKind
+}
+
+export type TransportAction = 'play' | 'pause' | 'stop' | 'toggle_play'
+
+export type MappingTarget =
+        | { type: 'plugin_parameter'; instance_id: string; param_id: number }
+        | { type: 'mixer_volume'; node: string }
+        | { type: 'mixer_pan'; node: string }
+        | { type: 'transport'; action: TransportAction }
+
+export type TakeoverMode = 'jump' | 'pickup' | 'relative'
+export type MappingScope = 'project' | 'global'
+
+export interface MidiMapping {
+        id: string
+        source: MidiControlSource
+        target: MappingTarget
+        min: number // normalized 0-1
+        max: number
+        takeover: TakeoverMode
+        scope: MappingScope
+}
+
+export interface MidiOutputPort {
+        id: string
+        name: string
+}
+
+export type MtcFrameRate = 'fps24' | 'fps25' | 'fps30'
+
+export interface MidiSyncSettings {
+        port: string | null // output port name; null disables sync output
+        clock: boolean // MIDI Clock with Start/Stop/Continue and Song Position Pointer
+        timecode: boolean // MTC quarter frames
+        frame_rate: MtcFrameRate
+        latency_offset_ms: number // positive delays, negative advances
+}
+
+export interface MidiClockInputSettings {
+        enabled: boolean // follow external MIDI clock; local play/pause/stop/seek are rejected while the master runs
+        port: string | null // input port name; null accepts any open input
+}
+
+export interface LearnRequest {
+        target: MappingTarget
+        takeover?: TakeoverMode
+        scope?: MappingScope
+        min?: number
+        max?: number
+}
+
+export async function listMidiInputs(): Promise<MidiInputStatus[]> {
+        return await invoke('list_midi_inputs')
+}
+
+export async function openMidiInput(portId: string): Promise<void> {
+        await invoke('open_midi_input', { portId })
+}
+
+export async function closeMidiInput(portId: string): Promise<void> {
+        await invoke('close_midi_input', { portId })
+}
+
+export async function getMidiMappings(): Promise<MidiMapping[]> {
+        return await invoke('get_midi_mappings')
+}
+
+// Resolves immediately; listen for the `midi-learned` event to receive the new mapping
+export async function startMidiLearn(request: LearnRequest): Promise<void> {
+        await invoke('start_midi_learn', { request })
+}
+
+export async function cancelMidiLearn(): Promise<void> {
+        await invoke('cancel_midi_learn')
+}
+
+export async function addMidiMapping(mapping: Omit<MidiMapping, 'id'>): Promise<string> {
+        return await invoke('add_midi_mapping', { mapping: { ...mapping, id: crypto.randomUUID() } })
+}
+
+export async function updateMidiMapping(mapping: MidiMapping): Promise<void> {
+        await invoke('update_midi_mapping', { mapping })
+}
+
+export async function removeMidiMapping(id: string): Promise<void> {
+        await invoke('remove_midi_mapping', { id })
+}
+
+export async function listMidiOutputs(): Promise<MidiOutputPort[]> {
+        return await invoke('list_midi_outputs')
+}
+
+export async function getMidiSyncSettings(): Promise<MidiSyncSettings> {
+        return await invoke('get_midi_sync_settings')
+}
+
+export async function setMidiSyncSettings(settings: MidiSyncSettings): Promise<void> {
+        await invoke('set_midi_sync_settings', { settings })
+}
+
+export async function getMidiClockInput(): Promise<MidiClockInputSettings> {
+        return await invoke('get_midi_clock_input')
+}
+
+export async function setMidiClockInput(settings: MidiClockInputSettings): Promise<void> {
+        await invoke('set_midi_clock_input', { settings })
+}
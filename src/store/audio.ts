@@ -90,6 +90,14 @@ export const sendParameter = async (paramId: number, value: number) => {
         }
 }
 
+export const sendNodeParameter = async (node: string, paramId: number, value: number) => {
+        try {
+                await invoke('set_node_parameter', { address: { node, paramId }, value })
+        } catch (e) {
+                console.error('Failed to update parameter:', e)
+        }
+}
+
 export const updateInstanceParam = (instanceId: string, paramId: number, value: number) => {
         // Update local state
         setInstances(prev =>
@@ -101,16 +109,6 @@ export const updateInstanceParam = (instanceId: string, paramId: number, value:
                 })
         )
 
-        // Calculate global param ID: 10000 + instanceId * 100 + paramId
-        // Note: This logic needs to be updated if instanceId is UUID.
-        // For now, we need the numeric index for the global ID calculation if the backend expects it.
-        // But wait, the backend `update_parameter` takes `param_id: u32`.
-        // If we are using UUIDs, we can't easily map to a u32 ID unless we look up the index.
-
-        // Let's find the instance to get its index.
-        const inst = instances().find(i => i.id === instanceId)
-        if (inst) {
-                const globalId = 10000 + inst.index * 100 + paramId
-                sendParameter(globalId, value)
-        }
+        // The instance id doubles as the node id of the instrument in the audio graph
+        sendNodeParameter(instanceId, paramId, value)
 }
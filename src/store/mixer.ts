@@ -1,18 +1,57 @@
 import { createSignal } from 'solid-js'
 import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export interface InsertSlotData {
+        instance_id: string
+        name: string
+        label: string
+        bypass: boolean
+        post_fader: boolean
+}
+
+// Channel order follows WAVE/SMPTE: quad = L R Ls Rs, 5.1 = L R C LFE Ls Rs, 7.1 = L R C LFE Lb Rb Ls Rs
+export type ChannelLayout = 'mono' | 'stereo' | 'quad' | '5.1' | '7.1'
 
 export interface MixerTrackData {
         id: number
+        node_id: string
         label: string
         volume: number
         pan: number
+        pan_front: number // surround panner front/rear position (1 front .. -1 rear)
+        layout: ChannelLayout // the master's layout is the output format of every other track
         mute: boolean
         solo: boolean
         meter_id?: string
+        inserts: InsertSlotData[]
+}
+
+// BS.1770 / EBU R128 values in LUFS (range in LU, true_peak in dBTP); null until measurable
+export interface LoudnessReport {
+        momentary: number | null
+        short_term: number | null
+        integrated: number | null
+        range: number | null
+        max_momentary: number | null
+        max_short_term: number | null
+        true_peak: number | null
+}
+
+// Per-channel values are linear gains
+export interface MeterReading {
+        level: number
+        levels: number[] // peak level with fall-back ballistics
+        peak: number[]
+        peak_hold: number[]
+        clip: boolean[] // latched until reset
+        rms: number[]
+        true_peak: number[]
+        loudness: LoudnessReport
 }
 
 export const [mixerTracks, setMixerTracks] = createSignal<MixerTrackData[]>([])
-export const [meterLevels, setMeterLevels] = createSignal<{ [key: string]: number }>({})
+export const [meterLevels, setMeterLevels] = createSignal<{ [key: string]: MeterReading }>({})
 
 export const fetchMixerTracks = async () => {
         try {
@@ -41,20 +80,120 @@ export const removeMixerTrack = async (index: number) => {
         }
 }
 
+// Insert effects: parameters of an insert are accessed through its instance_id
+// (get_instance_parameters / set_instance_parameter), like instruments.
+export const addInsert = async (trackIndex: number, name: string, postFader = false, sandboxed = false) => {
+        try {
+                const id = await invoke<string>('add_mixer_insert', { trackIndex, name, postFader, sandboxed })
+                await fetchMixerTracks()
+                return id
+        } catch (e) {
+                console.error('Failed to add insert:', e)
+        }
+}
+
+export const removeInsert = async (trackIndex: number, slot: number) => {
+        try {
+                await invoke('remove_mixer_insert', { trackIndex, slot })
+                await fetchMixerTracks()
+        } catch (e) {
+                console.error('Failed to remove insert:', e)
+        }
+}
+
+export const moveInsert = async (trackIndex: number, from: number, to: number) => {
+        try {
+                await invoke('move_mixer_insert', { trackIndex, from, to })
+                await fetchMixerTracks()
+        } catch (e) {
+                console.error('Failed to move insert:', e)
+        }
+}
+
+export const setInsertBypass = async (trackIndex: number, slot: number, bypass: boolean) => {
+        try {
+                await invoke('set_mixer_insert_bypass', { trackIndex, slot, bypass })
+                await fetchMixerTracks()
+        } catch (e) {
+                console.error('Failed to set insert bypass:', e)
+        }
+}
+
+export const setInsertPlacement = async (trackIndex: number, slot: number, postFader: boolean) => {
+        try {
+                await invoke('set_mixer_insert_placement', { trackIndex, slot, postFader })
+                await fetchMixerTracks()
+        } catch (e) {
+                console.error('Failed to set insert placement:', e)
+        }
+}
+
 export const setTrackVolume = async (trackId: number, volume: number) => {
         // Update local state immediately for responsiveness
         setMixerTracks(prev => prev.map(t => (t.id === trackId ? { ...t, volume } : t)))
 
-        // Send to backend
-        // Param ID scheme: TrackID * 100 + 0 (Fader Gain is param 0)
-        const paramId = trackId * 100 + 0
+        // Send to backend, addressed by the track's node id (Fader Gain is param 0)
+        const track = mixerTracks().find(t => t.id === trackId)
+        if (!track) return
         try {
-                await invoke('update_parameter', { paramId, value: volume })
+                await invoke('set_node_parameter', { address: { node: track.node_id, paramId: 0 }, value: volume })
         } catch (e) {
                 console.error('Failed to update track volume:', e)
         }
 }
 
+export const setTrackPan = async (trackId: number, pan: number) => {
+        setMixerTracks(prev => prev.map(t => (t.id === trackId ? { ...t, pan } : t)))
+
+        // Pan is param 1 of the track node (-1 left .. 1 right)
+        const track = mixerTracks().find(t => t.id === trackId)
+        if (!track) return
+        try {
+                await invoke('set_node_parameter', { address: { node: track.node_id, paramId: 1 }, value: pan })
+        } catch (e) {
+                console.error('Failed to update track pan:', e)
+        }
+}
+
+// Surround panner front/rear position (param 2); only used when the track feeds a wider surround master
+export const setTrackPanFront = async (trackId: number, panFront: number) => {
+        setMixerTracks(prev => prev.map(t => (t.id === trackId ? { ...t, pan_front: panFront } : t)))
+
+        const track = mixerTracks().find(t => t.id === trackId)
+        if (!track) return
+        try {
+                await invoke('set_node_parameter', { address: { node: track.node_id, paramId: 2 }, value: panFront })
+        } catch (e) {
+                console.error('Failed to update track surround pan:', e)
+        }
+}
+
+// Rebuilds the audio graph; mismatched layouts are up/downmixed, narrower tracks into a surround master are panned
+export const setTrackLayout = async (trackIndex: number, layout: ChannelLayout) => {
+        try {
+                await invoke('set_mixer_track_layout', { trackIndex, layout })
+                await fetchMixerTracks()
+        } catch (e) {
+                console.error('Failed to set track layout:', e)
+        }
+}
+
+export interface SmoothingConfig {
+        mode: 'Linear' | 'Exponential'
+        time_ms: number
+        ramp_plugin_params: boolean
+}
+
+export const getParameterSmoothing = async () => invoke<SmoothingConfig>('get_parameter_smoothing')
+
+export const setParameterSmoothing = async (config: SmoothingConfig) => {
+        try {
+                await invoke('set_parameter_smoothing', { config })
+        } catch (e) {
+                console.error('Failed to update parameter smoothing:', e)
+        }
+}
+
 export const toggleMute = async (trackId: number) => {
         setMixerTracks(prev => prev.map(t => (t.id === trackId ? { ...t, mute: !t.mute } : t)))
         // TODO: Send to backend
@@ -65,24 +204,58 @@ export const toggleSolo = async (trackId: number) => {
         // TODO: Send to backend
 }
 
-// Polling for meter levels
-let pollInterval: number | undefined
+// Meter readings are pushed by the backend as `meter-levels` events (~30 per second)
+let unlistenMeters: UnlistenFn | undefined
+let metering = false
+
+export const startMetering = async () => {
+        if (metering) return
+        metering = true
+        try {
+                const unlisten = await listen<{ [key: string]: MeterReading }>('meter-levels', event => {
+                        setMeterLevels(event.payload)
+                })
+                // stopMetering may have been called while subscribing
+                if (metering) unlistenMeters = unlisten
+                else unlisten()
+        } catch (e) {
+                metering = false
+                console.error('Failed to subscribe to meter levels:', e)
+        }
+}
+
+// Clears peak hold, clip indicators, true peak and integrated loudness on every meter
+export const resetMeters = async () => {
+        try {
+                await invoke('reset_meters_cmd')
+        } catch (e) {
+                console.error('Failed to reset meters:', e)
+        }
+}
+
+// Clears only the latched clip indicators
+export const clearMeterClips = async () => {
+        try {
+                await invoke('clear_meter_clips_cmd')
+        } catch (e) {
+                console.error('Failed to clear meter clips:', e)
+        }
+}
 
-export const startMetering = () => {
-        if (pollInterval) return
-        pollInterval = window.setInterval(async () => {
-                try {
-                        const levels = await invoke<{ [key: string]: number }>('get_meter_levels_cmd')
-                        setMeterLevels(levels)
-                } catch (e) {
-                        console.error('Failed to fetch meter levels:', e)
-                }
-        }, 50) // 20fps
+export interface MeterBallistics {
+        peak_hold: number // seconds; negative holds until reset
+        fall_rate: number // dB per second; 0 drops instantly
 }
 
+export const getMeterBallistics = () => invoke<MeterBallistics>('get_meter_ballistics_cmd')
+
+export const setMeterBallistics = (ballistics: MeterBallistics) =>
+        invoke('set_meter_ballistics_cmd', { ballistics })
+
 export const stopMetering = () => {
-        if (pollInterval) {
-                clearInterval(pollInterval)
-                pollInterval = undefined
+        metering = false
+        if (unlistenMeters) {
+                unlistenMeters()
+                unlistenMeters = undefined
         }
 }
@@ -29,9 +29,36 @@ export interface Note {
         start: Position
         duration: MusicalLength
         velocity: number
+        channel?: number // MIDI channel 0-15
+        expressions?: ExpressionPoint[]
         selected?: boolean
 }
 
+export type NoteExpressionKind = 'pitch' | 'pressure' | 'brightness'
+
+// Per-note expression curve point; time is seconds from the note start.
+// pitch is in semitones, pressure and brightness are 0..1
+export interface ExpressionPoint {
+        kind: NoteExpressionKind
+        time: number
+        value: number
+}
+
+export type ControllerKind =
+        | { type: 'control_change'; controller: number }
+        | { type: 'pitch_bend' }
+        | { type: 'channel_pressure' }
+        | { type: 'poly_pressure'; note: number }
+        | { type: 'program_change' }
+
+export interface ControllerEvent {
+        id: string
+        kind: ControllerKind
+        channel: number
+        time: Position
+        value: number // CC/pressure 0..1, pitch bend -1..1, program number for program change
+}
+
 export type ClipContent = { type: 'Midi' } | { type: 'Audio'; data: { path: string } }
 
 export interface Clip {
@@ -42,6 +69,7 @@ export interface Clip {
         start: Position
         length: MusicalLength
         notes: Note[]
+        controllers?: ControllerEvent[]
         content: ClipContent
         instrumentIds: string[]
         instrumentRoutes: Record<string, number> // InstrumentID -> TrackID